@@ -2,7 +2,7 @@ use std::pin::Pin;
 
 use anyhow::Result;
 use sdk::{
-    flatten_blobs, ContractInput, ContractName, HyleOutput, ProgramId, ProofData,
+    flatten_blobs, BlobEncoding, ContractInput, ContractName, HyleOutput, ProgramId, ProofData,
     RegisterContractAction, StateDigest, Verifier,
 };
 
@@ -22,6 +22,7 @@ pub fn register_hyle_contract(
             verifier,
             program_id,
             state_digest,
+            blob_encoding: BlobEncoding::default(),
         },
         None,
         None,
@@ -44,8 +45,20 @@ pub trait ClientSdkProver {
 
 #[cfg(feature = "risc0")]
 pub mod risc0 {
+    use std::time::{Duration, Instant};
+
+    use anyhow::Context;
+
     use super::*;
 
+    /// Cost of an execution, the only hint of the proving cost before running the prover.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ExecutionStats {
+        /// Cycles of all the segments of the session, padding included
+        pub cycles: u64,
+        pub duration: Duration,
+    }
+
     pub struct Risc0Prover<'a> {
         binary: &'a [u8],
     }
@@ -53,6 +66,38 @@ pub mod risc0 {
         pub fn new(binary: &'a [u8]) -> Self {
             Self { binary }
         }
+
+        /// Runs the program in the zkVM executor, without proving it
+        pub fn execute(&self, contract_input: &ContractInput) -> Result<HyleOutput> {
+            self.execute_with_stats(contract_input)
+                .map(|(hyle_output, _)| hyle_output)
+        }
+
+        pub fn execute_with_stats(
+            &self,
+            contract_input: &ContractInput,
+        ) -> Result<(HyleOutput, ExecutionStats)> {
+            let contract_input = bonsai_runner::as_input_data(contract_input)?;
+            let env = risc0_zkvm::ExecutorEnv::builder()
+                .write_slice(&contract_input)
+                .build()?;
+
+            let start = Instant::now();
+            let session_info = risc0_zkvm::default_executor().execute(env, self.binary)?;
+            let duration = start.elapsed();
+
+            let hyle_output = session_info
+                .journal
+                .decode::<HyleOutput>()
+                .context("Failed to extract HyleOutput from Risc0 journal")?;
+            Ok((
+                hyle_output,
+                ExecutionStats {
+                    cycles: session_info.cycles(),
+                    duration,
+                },
+            ))
+        }
         pub async fn prove(&self, contract_input: ContractInput) -> Result<ProofData> {
             let contract_input = bonsai_runner::as_input_data(&contract_input)?;
 
@@ -151,6 +196,14 @@ pub mod sp1 {
 }
 
 pub mod test {
+    use std::{
+        any::Any,
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    use sdk::{BlobIndex, TxHash};
+
     use super::*;
 
     pub struct TestProver {}
@@ -171,6 +224,82 @@ pub mod test {
         }
     }
 
+    type MockedProof = (TxHash, BlobIndex, HyleOutput);
+
+    /// Executor and prover returning caller-supplied outputs, to test transactions without a zkVM.
+    /// The same instance (or clones of it) must be used as executor and prover of the contract.
+    #[derive(Clone)]
+    pub struct MockProver {
+        outputs: Arc<Mutex<VecDeque<HyleOutput>>>,
+        executed: Arc<Mutex<Vec<MockedProof>>>,
+        state: Arc<dyn Fn() -> Box<dyn Any> + Send + Sync>,
+    }
+
+    impl MockProver {
+        /// Each execution returns the next of these outputs.
+        /// The full state returned by executions is `()`, see `with_state`.
+        pub fn new(outputs: Vec<HyleOutput>) -> Self {
+            MockProver {
+                outputs: Arc::new(Mutex::new(outputs.into())),
+                executed: Arc::new(Mutex::new(vec![])),
+                state: Arc::new(|| Box::new(())),
+            }
+        }
+
+        /// Full state returned by executions, for `StateUpdater`s expecting a given type.
+        pub fn with_state<T: Any + Clone + Send + Sync>(mut self, state: T) -> Self {
+            self.state = Arc::new(move || Box::new(state.clone()));
+            self
+        }
+    }
+
+    impl ClientSdkExecutor for MockProver {
+        fn execute(&self, contract_input: &ContractInput) -> Result<(Box<dyn Any>, HyleOutput)> {
+            let output = self
+                .outputs
+                .lock()
+                .map_err(|_| anyhow::anyhow!("MockProver lock poisoned"))?
+                .pop_front()
+                .ok_or_else(|| anyhow::anyhow!("No more mocked outputs"))?;
+            self.executed
+                .lock()
+                .map_err(|_| anyhow::anyhow!("MockProver lock poisoned"))?
+                .push((
+                    contract_input.tx_hash.clone(),
+                    contract_input.index,
+                    output.clone(),
+                ));
+            Ok(((self.state)(), output))
+        }
+    }
+
+    impl ClientSdkProver for MockProver {
+        /// Proves the output returned by the execution of the same input,
+        /// in the format expected by the `test` verifier.
+        fn prove(
+            &self,
+            contract_input: ContractInput,
+        ) -> Pin<Box<dyn std::future::Future<Output = Result<ProofData>> + Send + '_>> {
+            Box::pin(async move {
+                let output = self
+                    .executed
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("MockProver lock poisoned"))?
+                    .iter()
+                    .find(|(tx_hash, index, _)| {
+                        tx_hash == &contract_input.tx_hash && index == &contract_input.index
+                    })
+                    .map(|(_, _, output)| output.clone())
+                    .ok_or_else(|| anyhow::anyhow!("Input was not executed by MockProver"))?;
+
+                Ok(ProofData(bincode::encode_to_vec(
+                    vec![output],
+                    bincode::config::standard(),
+                )?))
+            })
+        }
+    }
+
     pub fn execute(contract_input: &ContractInput) -> Result<HyleOutput> {
         // FIXME: this is a hack to make the test pass.
         let next_state = contract_input.initial_state.clone();
@@ -202,3 +331,73 @@ fn check_output(output: &HyleOutput) -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use sdk::{identity_provider::IdentityAction, Identity, ProofTransaction};
+
+    use super::test::MockProver;
+    use super::*;
+    use crate::transaction_builder::{StateUpdater, TxExecutorBuilder};
+
+    struct MockStates {}
+
+    impl StateUpdater for MockStates {
+        fn setup(&self, ctx: &mut TxExecutorBuilder<Self>) {
+            let prover = MockProver::new(vec![HyleOutput {
+                next_state: StateDigest(vec![1]),
+                success: true,
+                ..HyleOutput::default()
+            }]);
+            ctx.init_with("c1".into(), StateDigest(vec![0]), prover.clone(), prover);
+        }
+
+        fn update(&mut self, _: &ContractName, new_state: &mut dyn Any) -> Result<()> {
+            anyhow::ensure!(new_state.is::<()>(), "unexpected state");
+            Ok(())
+        }
+
+        fn get(&self, _: &ContractName) -> Result<Box<dyn Any>> {
+            Ok(Box::new(()))
+        }
+    }
+
+    #[test]
+    fn test_mock_prover_builds_and_proves() {
+        let mut executor = TxExecutorBuilder::new(MockStates {}).build();
+
+        let mut tx = ProvableBlobTx::new(Identity::new("test.c1"));
+        let action = IdentityAction::RegisterIdentity {
+            account: "test.c1".into(),
+        };
+        tx.add_action("c1".into(), action.clone(), None, None)
+            .unwrap();
+        let proof_tx = executor.process(tx).unwrap();
+
+        let expected = HyleOutput {
+            next_state: StateDigest(vec![1]),
+            success: true,
+            ..HyleOutput::default()
+        };
+        assert_eq!(proof_tx.outputs, vec![("c1".into(), expected.clone())]);
+
+        let proofs = proof_tx
+            .iter_prove()
+            .map(futures::executor::block_on)
+            .collect::<Result<Vec<ProofTransaction>>>()
+            .unwrap();
+        assert_eq!(
+            proofs.into_iter().map(|p| p.proof).collect::<Vec<_>>(),
+            vec![ProofData(
+                bincode::encode_to_vec(vec![expected], bincode::config::standard()).unwrap()
+            )]
+        );
+
+        // A second execution has no output left
+        let mut tx = ProvableBlobTx::new(Identity::new("test.c1"));
+        tx.add_action("c1".into(), action, None, None).unwrap();
+        assert!(executor.process(tx).is_err());
+    }
+}
@@ -3,4 +3,6 @@ pub mod helpers;
 pub mod rest_client;
 #[cfg(feature = "tcp")]
 pub mod tcp_client;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod transaction_builder;
@@ -1,10 +1,17 @@
-use anyhow::{Context, Result};
-use reqwest::Url;
+use std::{fmt, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use reqwest::{StatusCode, Url};
 
 use sdk::{
     api::*, BlobIndex, BlobTransaction, BlockHash, BlockHeight, ConsensusInfo, Contract,
-    ContractName, ProofTransaction, StateDigest, TxHash, UnsettledBlobTransaction,
+    ContractName, ProgramId, ProofTransaction, SignedBlobTransaction, StateDigest, TxHash,
+    UnsettledBlobTransaction,
 };
+use tracing::warn;
+
+/// Number of times a failed contract registration is retried
+const REGISTER_CONTRACT_RETRIES: usize = 3;
 
 pub struct NodeApiHttpClient {
     pub url: Url,
@@ -16,6 +23,26 @@ pub struct IndexerApiHttpClient {
     pub reqwest_client: reqwest::Client,
 }
 
+/// Returned by [`NodeApiHttpClient::get_contract_state`] when the state digest of a contract
+/// doesn't decode as the requested type.
+#[derive(Debug)]
+pub struct StateDecodeError {
+    pub contract_name: ContractName,
+    pub reason: String,
+}
+
+impl fmt::Display for StateDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Could not decode state of contract {}: {}",
+            self.contract_name, self.reason
+        )
+    }
+}
+
+impl std::error::Error for StateDecodeError {}
+
 impl NodeApiHttpClient {
     pub fn new(url: String) -> Result<Self> {
         Ok(Self {
@@ -29,14 +56,95 @@ impl NodeApiHttpClient {
             .await
     }
 
+    /// Registers a contract, unless a contract with this name is already registered.
+    /// Failed attempts are retried, checking first that the previous one didn't register it.
+    /// Returns the hash of the registration transaction, or None if there was nothing to do.
+    pub async fn register_contract_idempotent(
+        &self,
+        tx: &APIRegisterContract,
+    ) -> Result<Option<TxHash>> {
+        let mut attempt = 0;
+        loop {
+            let result = match self.contract_exists(&tx.contract_name).await {
+                Ok(true) => return Ok(None),
+                Ok(false) => self.register_contract(tx).await.map(Some),
+                Err(e) => Err(e),
+            };
+            match result {
+                Err(e) if attempt < REGISTER_CONTRACT_RETRIES => {
+                    attempt += 1;
+                    warn!(
+                        "Registering contract {} failed, retrying ({attempt}/{REGISTER_CONTRACT_RETRIES}): {e:#}",
+                        tx.contract_name
+                    );
+                    tokio::time::sleep(Duration::from_millis(500 * attempt as u64)).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Registers a contract and polls the node every `poll_interval` until it knows it.
+    /// Fails if the contract isn't registered after `timeout`.
+    pub async fn register_contract_and_wait(
+        &self,
+        tx: &APIRegisterContract,
+        timeout: Duration,
+        poll_interval: Duration,
+    ) -> Result<Contract> {
+        self.register_contract(tx).await?;
+        self.wait_contract(&tx.contract_name, timeout, poll_interval)
+            .await
+    }
+
+    /// Polls the node every `poll_interval` until it knows the contract, for up to `timeout`.
+    pub async fn wait_contract(
+        &self,
+        contract_name: &ContractName,
+        timeout: Duration,
+        poll_interval: Duration,
+    ) -> Result<Contract> {
+        tokio::time::timeout(timeout, async {
+            loop {
+                match self.get_contract(contract_name).await {
+                    Ok(contract) => return contract,
+                    Err(_) => tokio::time::sleep(poll_interval).await,
+                }
+            }
+        })
+        .await
+        .with_context(|| format!("Timeout waiting for contract {contract_name}"))
+    }
+
+    pub async fn register_program(&self, program: &APIRegisterProgram) -> Result<ProgramId> {
+        self.post("v1/program/register", program, "Registering program")
+            .await
+    }
+
     pub async fn send_tx_blob(&self, tx: &BlobTransaction) -> Result<TxHash> {
         self.post("v1/tx/send/blob", tx, "Sending tx blob").await
     }
 
+    /// Sends a blob transaction authorized by a key allowed to submit transactions on the node
+    pub async fn send_tx_blob_signed(&self, tx: &SignedBlobTransaction) -> Result<TxHash> {
+        self.post("v1/tx/send/blob/signed", tx, "Sending signed tx blob")
+            .await
+    }
+
     pub async fn send_tx_proof(&self, tx: &ProofTransaction) -> Result<TxHash> {
         self.post("v1/tx/send/proof", tx, "Sending tx proof").await
     }
 
+    /// Sends a recursive proof, settling every blob proven by the inner proofs
+    pub async fn send_recursive_proof(&self, tx: &ProofTransaction) -> Result<TxHash> {
+        self.post(
+            "v1/tx/send/proof/recursive",
+            tx,
+            "Sending recursive tx proof",
+        )
+        .await
+    }
+
     pub async fn get_consensus_info(&self) -> Result<ConsensusInfo> {
         self.get("v1/consensus/info", "getting consensus info")
             .await
@@ -50,6 +158,11 @@ impl NodeApiHttpClient {
         .await
     }
 
+    pub async fn get_consensus_genesis(&self) -> Result<APIGenesis> {
+        self.get("v1/consensus/genesis", "getting consensus genesis")
+            .await
+    }
+
     pub async fn get_node_info(&self) -> Result<NodeInfo> {
         self.get("v1/info", "getting node info").await
     }
@@ -78,6 +191,55 @@ impl NodeApiHttpClient {
         .await
     }
 
+    /// Fetches a contract and decodes its state digest, failing with a [`StateDecodeError`]
+    /// when the digest isn't a `State`.
+    pub async fn get_contract_state<State>(&self, contract_name: &ContractName) -> Result<State>
+    where
+        State: TryFrom<StateDigest>,
+        State::Error: fmt::Display,
+    {
+        let contract = self.get_contract(contract_name).await?;
+        contract.state.try_into().map_err(|e: State::Error| {
+            StateDecodeError {
+                contract_name: contract_name.clone(),
+                reason: e.to_string(),
+            }
+            .into()
+        })
+    }
+
+    pub async fn get_contract_state_proof(
+        &self,
+        contract_name: &ContractName,
+    ) -> Result<APIContractStateProof> {
+        self.get(
+            &format!("v1/contract/{}/state_proof", contract_name),
+            &format!("getting state proof of contract {}", contract_name),
+        )
+        .await
+    }
+
+    /// Whether the node knows this contract. Only a 404 means it doesn't, other error statuses
+    /// are returned as errors.
+    async fn contract_exists(&self, contract_name: &ContractName) -> Result<bool> {
+        let response = self
+            .reqwest_client
+            .get(format!("{}v1/contract/{}", self.url, contract_name))
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .context(format!("getting contract {} request failed", contract_name))?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => bail!(
+                "getting contract {} failed with status {}",
+                contract_name,
+                status
+            ),
+        }
+    }
+
     pub async fn get_unsettled_tx(
         &self,
         blob_tx_hash: &TxHash,
@@ -89,6 +251,14 @@ impl NodeApiHttpClient {
         .await
     }
 
+    pub async fn get_transaction_status(&self, tx_hash: &TxHash) -> Result<APITransactionStatus> {
+        self.get(
+            &format!("v1/transaction/status/{tx_hash}"),
+            &format!("getting status of tx {}", tx_hash),
+        )
+        .await
+    }
+
     async fn get<T>(&self, endpoint: &str, context_msg: &str) -> Result<T>
     where
         T: serde::de::DeserializeOwned,
@@ -142,6 +312,32 @@ impl IndexerApiHttpClient {
         .await
     }
 
+    /// At most `nb_results` entries from `from_height`: the next page starts after the height
+    /// of the last entry returned.
+    pub async fn get_contract_state_history(
+        &self,
+        contract_name: &ContractName,
+        from_height: Option<BlockHeight>,
+        to_height: Option<BlockHeight>,
+        nb_results: u32,
+    ) -> Result<Vec<APIContractStateHistoryEntry>> {
+        let mut bounds = vec![format!("nb_results={nb_results}")];
+        if let Some(from_height) = from_height {
+            bounds.push(format!("from_height={from_height}"));
+        }
+        if let Some(to_height) = to_height {
+            bounds.push(format!("to_height={to_height}"));
+        }
+        self.get(
+            &format!(
+                "v1/indexer/contract/{contract_name}/history?{}",
+                bounds.join("&")
+            ),
+            &format!("getting state history of contract {contract_name}"),
+        )
+        .await
+    }
+
     pub async fn fetch_current_state<State>(&self, contract_name: &ContractName) -> Result<State>
     where
         State: TryFrom<StateDigest>,
@@ -183,6 +379,53 @@ impl IndexerApiHttpClient {
             .await
     }
 
+    pub async fn get_blocks_page(
+        &self,
+        cursor: Option<&str>,
+        nb_results: u32,
+    ) -> Result<APIBlockPage> {
+        let endpoint = match cursor {
+            Some(cursor) => {
+                format!("v1/indexer/blocks/page?nb_results={nb_results}&cursor={cursor}")
+            }
+            None => format!("v1/indexer/blocks/page?nb_results={nb_results}"),
+        };
+        self.get(&endpoint, "getting blocks page").await
+    }
+
+    pub async fn get_transactions_page(
+        &self,
+        cursor: Option<&str>,
+        nb_results: u32,
+    ) -> Result<APITransactionPage> {
+        let endpoint = match cursor {
+            Some(cursor) => {
+                format!("v1/indexer/transactions/page?nb_results={nb_results}&cursor={cursor}")
+            }
+            None => format!("v1/indexer/transactions/page?nb_results={nb_results}"),
+        };
+        self.get(&endpoint, "getting transactions page").await
+    }
+
+    pub async fn get_contract_actions_page(
+        &self,
+        contract_name: &ContractName,
+        cursor: Option<&str>,
+        nb_results: u32,
+    ) -> Result<APIContractActionPage> {
+        let endpoint = match cursor {
+            Some(cursor) => format!(
+                "v1/indexer/actions/contract/{contract_name}?nb_results={nb_results}&cursor={cursor}"
+            ),
+            None => format!("v1/indexer/actions/contract/{contract_name}?nb_results={nb_results}"),
+        };
+        self.get(
+            &endpoint,
+            &format!("getting actions of contract {contract_name}"),
+        )
+        .await
+    }
+
     pub async fn get_transactions_by_height(
         &self,
         height: &BlockHeight,
@@ -224,6 +467,28 @@ impl IndexerApiHttpClient {
         .await
     }
 
+    pub async fn get_transactions_by_identity(
+        &self,
+        identity: &str,
+        status: Option<TransactionStatus>,
+    ) -> Result<Vec<TransactionWithBlobs>> {
+        let endpoint = match status {
+            Some(status) => {
+                let status = serde_json::to_value(status)?;
+                format!(
+                    "v1/indexer/transactions/identity/{identity}?status={}",
+                    status.as_str().unwrap_or_default()
+                )
+            }
+            None => format!("v1/indexer/transactions/identity/{identity}"),
+        };
+        self.get(
+            &endpoint,
+            &format!("getting transactions of identity {identity}"),
+        )
+        .await
+    }
+
     pub async fn get_blob_by_tx_hash(&self, tx_hash: &TxHash) -> Result<APIBlob> {
         self.get(
             &format!("v1/indexer/blobs/hash/{tx_hash}"),
@@ -232,6 +497,51 @@ impl IndexerApiHttpClient {
         .await
     }
 
+    /// Which proof output settled each blob of a transaction
+    pub async fn get_settled_blob_proofs(
+        &self,
+        tx_hash: &TxHash,
+    ) -> Result<Vec<APISettledBlobProof>> {
+        self.get(
+            &format!("v1/indexer/blobs/hash/{tx_hash}/settled_proofs"),
+            &format!("getting settled blob proofs of transaction {tx_hash}"),
+        )
+        .await
+    }
+
+    pub async fn get_proof(&self, tx_hash: &TxHash) -> Result<APIProof> {
+        self.get(
+            &format!("v1/indexer/proof/{tx_hash}"),
+            &format!("getting proof of transaction {tx_hash}"),
+        )
+        .await
+    }
+
+    pub async fn get_proof_metadata(&self, tx_hash: &TxHash) -> Result<APIProofMetadata> {
+        self.get(
+            &format!("v1/indexer/proof/{tx_hash}/metadata"),
+            &format!("getting proof metadata of transaction {tx_hash}"),
+        )
+        .await
+    }
+
+    /// At most `nb_results` actions, most recent first, from `start_block` down.
+    pub async fn get_staking_actions(
+        &self,
+        identity: &str,
+        start_block: Option<BlockHeight>,
+        nb_results: u32,
+    ) -> Result<Vec<APIStakingAction>> {
+        let endpoint = match start_block {
+            Some(start_block) => format!(
+                "v1/indexer/staking/{identity}?nb_results={nb_results}&start_block={start_block}"
+            ),
+            None => format!("v1/indexer/staking/{identity}?nb_results={nb_results}"),
+        };
+        self.get(&endpoint, &format!("getting staking actions of {identity}"))
+            .await
+    }
+
     pub async fn get_blob(&self, tx_hash: &TxHash, blob_index: BlobIndex) -> Result<APIBlob> {
         self.get(
             &format!("v1/indexer/blob/hash/{tx_hash}/index/{blob_index}"),
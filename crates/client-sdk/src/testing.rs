@@ -0,0 +1,21 @@
+//! Helpers for contract tests.
+
+use sdk::{ContractInput, HyleOutput};
+
+/// Runs a risc0 contract on this input in the zkVM executor, without proving it,
+/// and decodes the HyleOutput it committed. Panics if the execution fails.
+pub fn run_contract_r0(binary: &[u8], input: &ContractInput) -> HyleOutput {
+    let env = risc0_zkvm::ExecutorEnv::builder()
+        .write(input)
+        .expect("Failed to write the contract input")
+        .build()
+        .expect("Failed to build the executor environment");
+    let execute_info = risc0_zkvm::default_executor()
+        .execute(env, binary)
+        .expect("Failed to execute the contract");
+
+    execute_info
+        .journal
+        .decode::<HyleOutput>()
+        .expect("Failed to extract HyleOutput from Risc0 journal")
+}
@@ -6,10 +6,10 @@ use std::{
     sync::{Arc, OnceLock},
 };
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use sdk::{
     Blob, BlobIndex, BlobTransaction, ContractAction, ContractInput, ContractName, Hashable,
-    HyleOutput, Identity, ProofTransaction, StateDigest, TxContext,
+    HyleOutput, Identity, ProofData, ProofTransaction, StateDigest, TxContext,
 };
 
 use crate::helpers::{ClientSdkExecutor, ClientSdkProver};
@@ -60,6 +60,10 @@ impl From<ProvableBlobTx> for BlobTransaction {
         BlobTransaction {
             identity: tx.identity,
             blobs: tx.blobs,
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         }
     }
 }
@@ -109,10 +113,39 @@ impl ProofTxBuilder {
         })
     }
 
+    /// Proves all the transitions concurrently, unlike `iter_prove`.
+    /// Proofs are returned in the order of the blobs, once all of them are done.
+    /// Provers are `Send`, so the returned future can be spawned on a multi-threaded runtime.
+    pub fn prove_all(&self) -> impl Future<Output = Vec<(ContractName, Result<ProofData>)>> + Send {
+        let proofs = self
+            .runners
+            .iter()
+            .map(|runner| {
+                tracing::info!("Proving transition for {}...", runner.contract_name);
+                let contract_name = runner.contract_name.clone();
+                let prover = self.provers.get(&runner.contract_name).cloned();
+                let contract_input = runner.contract_input.get().cloned();
+                async move {
+                    let proof = match (prover, contract_input) {
+                        (Some(prover), Some(contract_input)) => prover.prove(contract_input).await,
+                        (None, _) => Err(anyhow!("no prover defined for {}", contract_name)),
+                        (_, None) => Err(anyhow!("no input for prover of {}", contract_name)),
+                    };
+                    (contract_name, proof)
+                }
+            })
+            .collect::<Vec<_>>();
+        futures::future::join_all(proofs)
+    }
+
     pub fn to_blob_tx(&self) -> BlobTransaction {
         BlobTransaction {
             identity: self.identity.clone(),
             blobs: self.blobs.clone(),
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         }
     }
 }
@@ -332,6 +365,10 @@ impl ContractRunner {
         let tx_hash = BlobTransaction {
             identity: self.identity.clone(),
             blobs: blobs.clone(),
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         }
         .hash();
 
@@ -390,3 +427,85 @@ macro_rules! contract_states {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::Pin,
+        sync::atomic::{AtomicUsize, Ordering},
+        task::Poll,
+    };
+
+    use super::*;
+
+    /// Only returns its proof once all the provers have started
+    struct WaitingProver {
+        proof: Vec<u8>,
+        started: Arc<AtomicUsize>,
+        total: usize,
+    }
+
+    impl ClientSdkProver for WaitingProver {
+        fn prove(
+            &self,
+            _contract_input: ContractInput,
+        ) -> Pin<Box<dyn Future<Output = Result<ProofData>> + Send + '_>> {
+            Box::pin(async move {
+                self.started.fetch_add(1, Ordering::SeqCst);
+                futures::future::poll_fn(|cx| {
+                    if self.started.load(Ordering::SeqCst) < self.total {
+                        cx.waker().wake_by_ref();
+                        Poll::Pending
+                    } else {
+                        Poll::Ready(())
+                    }
+                })
+                .await;
+                Ok(ProofData(self.proof.clone()))
+            })
+        }
+    }
+
+    #[test]
+    fn test_prove_all_proves_concurrently_in_order() {
+        let started = Arc::new(AtomicUsize::new(0));
+        let mut provers: BTreeMap<ContractName, Arc<dyn ClientSdkProver + Sync + Send>> =
+            BTreeMap::new();
+        let mut runners = vec![];
+        // Not in alphabetical order, to check that the order of the blobs is kept
+        for (index, name) in ["c2", "c1"].into_iter().enumerate() {
+            let mut runner =
+                ContractRunner::new(name.into(), "test.c1".into(), BlobIndex(index)).unwrap();
+            runner.build_input(None, vec![], vec![], StateDigest(vec![]));
+            runners.push(runner);
+            provers.insert(
+                name.into(),
+                Arc::new(WaitingProver {
+                    proof: name.as_bytes().to_vec(),
+                    started: started.clone(),
+                    total: 2,
+                }),
+            );
+        }
+        let builder = ProofTxBuilder {
+            identity: "test.c1".into(),
+            blobs: vec![],
+            runners,
+            outputs: vec![],
+            provers,
+        };
+
+        let proofs = futures::executor::block_on(builder.prove_all())
+            .into_iter()
+            .map(|(contract_name, proof)| (contract_name, proof.unwrap()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            proofs,
+            vec![
+                ("c2".into(), ProofData(b"c2".to_vec())),
+                ("c1".into(), ProofData(b"c1".to_vec())),
+            ]
+        );
+    }
+}
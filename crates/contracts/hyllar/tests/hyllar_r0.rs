@@ -1,41 +1,31 @@
 use core::str;
 
+use client_sdk::helpers::risc0::Risc0Prover;
+use client_sdk::testing::run_contract_r0;
 use hyllar::HyllarToken;
 use sdk::{
-    erc20::ERC20Action, BlobIndex, ContractAction, ContractInput, ContractName, Digestable,
-    HyleOutput, TxHash,
+    erc20::ERC20Action, BlobIndex, ContractAction, ContractInput, ContractName, Digestable, TxHash,
 };
 
-fn execute(inputs: ContractInput) -> HyleOutput {
-    let env = risc0_zkvm::ExecutorEnv::builder()
-        .write(&inputs)
-        .unwrap()
-        .build()
-        .unwrap();
-    let prover = risc0_zkvm::default_executor();
-    let execute_info = prover
-        .execute(env, hyllar::client::metadata::HYLLAR_ELF)
-        .unwrap();
-
-    execute_info.journal.decode::<sdk::HyleOutput>().unwrap()
-}
-
 #[test]
 fn execute_transfer_from() {
-    let output = execute(ContractInput {
-        initial_state: HyllarToken::new(1000, "faucet".to_string()).as_digest(),
-        identity: "caller".into(),
-        tx_hash: TxHash::default(),
-        tx_ctx: None,
-        private_input: vec![],
-        blobs: vec![ERC20Action::TransferFrom {
-            sender: "faucet".into(),
-            recipient: "amm".into(),
-            amount: 100,
-        }
-        .as_blob(ContractName::new("hyllar"), None, None)],
-        index: BlobIndex(0),
-    });
+    let output = run_contract_r0(
+        hyllar::client::metadata::HYLLAR_ELF,
+        &ContractInput {
+            initial_state: HyllarToken::new(1000, "faucet".to_string()).as_digest(),
+            identity: "caller".into(),
+            tx_hash: TxHash::default(),
+            tx_ctx: None,
+            private_input: vec![],
+            blobs: vec![ERC20Action::TransferFrom {
+                sender: "faucet".into(),
+                recipient: "amm".into(),
+                amount: 100,
+            }
+            .as_blob(ContractName::new("hyllar"), None, None)],
+            index: BlobIndex(0),
+        },
+    );
 
     assert!(!output.success);
     assert_eq!(
@@ -43,3 +33,25 @@ fn execute_transfer_from() {
         "Allowance exceeded for sender=faucet caller=caller allowance=0"
     );
 }
+
+#[test]
+fn execute_transfer_with_stats() {
+    let (output, stats) = Risc0Prover::new(hyllar::client::metadata::HYLLAR_ELF)
+        .execute_with_stats(&ContractInput {
+            initial_state: HyllarToken::new(1000, "faucet".to_string()).as_digest(),
+            identity: "faucet".into(),
+            tx_hash: TxHash::default(),
+            tx_ctx: None,
+            private_input: vec![],
+            blobs: vec![ERC20Action::Transfer {
+                recipient: "amm".into(),
+                amount: 100,
+            }
+            .as_blob(ContractName::new("hyllar"), None, None)],
+            index: BlobIndex(0),
+        })
+        .unwrap();
+
+    assert!(output.success);
+    assert!(stats.cycles > 0);
+}
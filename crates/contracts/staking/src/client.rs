@@ -55,6 +55,7 @@ impl From<Staking> for APIStaking {
             bonded: val.bonded,
             delegations: val.delegations,
             total_bond: val.total_bond,
+            key_rotations: val.key_rotations,
         }
     }
 }
@@ -67,6 +68,7 @@ impl From<APIStaking> for Staking {
             bonded: val.bonded,
             delegations: val.delegations,
             total_bond: val.total_bond,
+            key_rotations: val.key_rotations,
         }
     }
 }
@@ -106,3 +108,27 @@ pub fn delegate(
         .with_private_input(|state: &Staking| -> anyhow::Result<Vec<u8>> { Ok(state.to_bytes()) });
     Ok(())
 }
+
+pub fn rotate_key(
+    builder: &mut ProvableBlobTx,
+    contract_name: ContractName,
+    old_pubkey: ValidatorPublicKey,
+    new_pubkey: ValidatorPublicKey,
+    nonce: u64,
+    proof: Vec<u8>,
+) -> anyhow::Result<()> {
+    builder
+        .add_action(
+            contract_name,
+            StakingAction::RotateKey {
+                old_pubkey,
+                new_pubkey,
+                nonce,
+                proof,
+            },
+            None,
+            None,
+        )?
+        .with_private_input(|state: &Staking| -> anyhow::Result<Vec<u8>> { Ok(state.to_bytes()) });
+    Ok(())
+}
@@ -84,6 +84,24 @@ impl StakingContract {
                 self.state.delegate_to(self.caller().clone(), validator)
             }
             StakingAction::Distribute { claim: _ } => todo!(),
+            StakingAction::Slash { .. } => Err("Slashing is applied by the consensus".to_string()),
+            StakingAction::RotateKey {
+                old_pubkey,
+                new_pubkey,
+                nonce,
+                proof: _,
+            } => {
+                let caller = self.caller().clone();
+                if !self
+                    .state
+                    .delegations
+                    .get(&old_pubkey)
+                    .is_some_and(|delegators| delegators.contains(&caller))
+                {
+                    return Err(format!("{caller} is not a delegator of {old_pubkey}"));
+                }
+                self.state.rotate_key(&old_pubkey, new_pubkey, nonce)
+            }
         }
     }
 
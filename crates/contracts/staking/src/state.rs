@@ -17,6 +17,8 @@ pub struct Staking {
     /// List of validators that are part of consensus
     pub(crate) bonded: Vec<ValidatorPublicKey>,
     pub(crate) total_bond: u128,
+    /// Number of key rotations applied, which the next rotation has to sign
+    pub(crate) key_rotations: u64,
 }
 
 /// Minimal stake necessary to be part of consensus
@@ -30,6 +32,7 @@ impl Staking {
             rewarded: BTreeMap::new(),
             bonded: Vec::new(),
             total_bond: 0,
+            key_rotations: 0,
         }
     }
 
@@ -40,6 +43,9 @@ impl Staking {
     pub fn total_bond(&self) -> u128 {
         self.total_bond
     }
+    pub fn key_rotations(&self) -> u64 {
+        self.key_rotations
+    }
     pub fn is_bonded(&self, pubkey: &ValidatorPublicKey) -> bool {
         self.bonded.iter().any(|v| v == pubkey)
     }
@@ -113,6 +119,73 @@ impl Staking {
     }
 }
 
+impl Staking {
+    /// Takes up to `amount` from the stakes delegated to the validator, and removes it from
+    /// the bonded validators.
+    pub fn slash(
+        &mut self,
+        validator: &ValidatorPublicKey,
+        amount: u128,
+    ) -> Result<String, String> {
+        let Some(stake) = self.get_stake(validator) else {
+            return Err("Validator has no stake".to_string());
+        };
+        info!("🔪 Slashing {} from validator {}", amount, validator);
+
+        let mut remaining = amount;
+        for delegator in self.delegations.get(validator).into_iter().flatten() {
+            if let Some(delegated) = self.stakes.get_mut(delegator) {
+                let taken = std::cmp::min(*delegated, remaining);
+                *delegated -= taken;
+                remaining -= taken;
+            }
+        }
+
+        if self.is_bonded(validator) {
+            self.bonded.retain(|v| v != validator);
+            self.total_bond = self.total_bond.saturating_sub(stake);
+        }
+        Ok("Slashed".to_string())
+    }
+
+    /// Moves delegations, rewards and bond of a validator to a new key, keeping its stake.
+    pub fn rotate_key(
+        &mut self,
+        old_pubkey: &ValidatorPublicKey,
+        new_pubkey: ValidatorPublicKey,
+        nonce: u64,
+    ) -> Result<String, String> {
+        if nonce != self.key_rotations {
+            return Err(format!(
+                "Key rotation nonce {nonce} does not match {}",
+                self.key_rotations
+            ));
+        }
+        if self.delegations.contains_key(&new_pubkey) || self.is_bonded(&new_pubkey) {
+            return Err("New key is already used by a validator".to_string());
+        }
+        let Some(delegators) = self.delegations.remove(old_pubkey) else {
+            return Err("Validator has no stake".to_string());
+        };
+        info!(
+            "🔑 Rotating key of validator {} to {}",
+            old_pubkey, new_pubkey
+        );
+
+        self.delegations.insert(new_pubkey.clone(), delegators);
+        if let Some(rewarded) = self.rewarded.remove(old_pubkey) {
+            self.rewarded.insert(new_pubkey.clone(), rewarded);
+        }
+        if self.is_bonded(old_pubkey) {
+            self.bonded.retain(|v| v != old_pubkey);
+            self.bonded.push(new_pubkey);
+            self.bonded.sort();
+        }
+        self.key_rotations += 1;
+        Ok("Rotated".to_string())
+    }
+}
+
 impl Default for Staking {
     fn default() -> Self {
         Self::new()
@@ -141,6 +214,7 @@ impl Digestable for Staking {
                 hasher.update(i.0.to_le_bytes());
             }
         }
+        hasher.update(self.key_rotations.to_le_bytes());
         StateDigest(hasher.finalize().to_vec())
     }
 }
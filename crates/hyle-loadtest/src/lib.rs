@@ -6,7 +6,7 @@ use client_sdk::transaction_builder::{ProvableBlobTx, TxExecutorBuilder};
 use hydentity::Hydentity;
 use hyle_contract_sdk::erc20::ERC20;
 use hyle_contract_sdk::BlobTransaction;
-use hyle_contract_sdk::{Blob, BlobData, ContractAction, RegisterContractAction};
+use hyle_contract_sdk::{Blob, BlobData, BlobEncoding, ContractAction, RegisterContractAction};
 use hyle_contract_sdk::{ContractName, Identity};
 use hyle_contract_sdk::{Digestable, TcpServerNetMessage};
 use hyllar::client::transfer;
@@ -47,8 +47,13 @@ pub async fn setup(url: String, users: u32, verifier: String) -> Result<()> {
             verifier: verifier.into(),
             program_id: hyle_contracts::HYLLAR_ID.to_vec().into(),
             state_digest: hyllar_contract.state().as_digest(),
+            blob_encoding: BlobEncoding::default(),
         }
         .as_blob("hyle".into(), None, None)],
+        dependencies: vec![],
+        additional_identities: vec![],
+        nonce: None,
+        fee: 0,
     };
 
     let mut client = NodeTcpClient::new(url).await.unwrap();
@@ -98,7 +103,15 @@ pub async fn generate_blobs_txs(users: u32) -> Result<Vec<Vec<u8>>> {
                 let identity = transaction.identity;
                 let blobs = transaction.blobs;
 
-                let msg: TcpServerNetMessage = BlobTransaction { identity, blobs }.into();
+                let msg: TcpServerNetMessage = BlobTransaction {
+                    identity,
+                    blobs,
+                    dependencies: vec![],
+                    additional_identities: vec![],
+                    nonce: None,
+                    fee: 0,
+                }
+                .into();
                 local_blob_txs.push(msg.to_binary()?);
             }
 
@@ -281,6 +294,10 @@ pub async fn send_massive_blob(url: String) -> Result<()> {
             contract_name: "hydentity".into(),
             data: BlobData(data),
         }],
+        dependencies: vec![],
+        additional_identities: vec![],
+        nonce: None,
+        fee: 0,
     };
     let msg: TcpServerNetMessage = tx.into();
     let encoded_blob_tx = msg.to_binary()?;
@@ -5,8 +5,9 @@ use serde_with::serde_as;
 use utoipa::ToSchema;
 
 use crate::{
-    BlockHeight, ConsensusProposalHash, ContractName, Identity, ProgramId, StateDigest,
-    Transaction, TransactionData, TxHash, ValidatorPublicKey, Verifier,
+    BlobEncoding, BlockHeight, ConsensusProposalHash, ContractName, Identity, ProgramId, ProofData,
+    StakingAction, StateDigest, StateInclusionProof, StateRoot, Transaction, TransactionData,
+    TxHash, ValidatorPublicKey, Verifier,
 };
 
 #[derive(Clone, Serialize, Deserialize, ToSchema)]
@@ -22,6 +23,42 @@ pub struct APIRegisterContract {
     pub program_id: ProgramId,
     pub state_digest: StateDigest,
     pub contract_name: ContractName,
+    #[serde(default)]
+    pub blob_encoding: BlobEncoding,
+}
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct APIRegisterProgram {
+    /// Full program (e.g. verification key), stored by the node and referenced by its hash
+    pub program: Vec<u8>,
+}
+
+/// Proof to verify off-chain, without submitting it as a transaction
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct APIVerifyProof {
+    pub proof: ProofData,
+    pub verifier: Verifier,
+    pub program_id: ProgramId,
+}
+
+/// Current state digest of a contract, with the proof that it's part of the state root
+/// of the block at `block_height`.
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct APIContractStateProof {
+    pub contract_name: ContractName,
+    pub block_height: BlockHeight,
+    pub state_root: StateRoot,
+    pub state_digest: StateDigest,
+    pub proof: StateInclusionProof,
+}
+
+/// Genesis configuration of the network, to check that a node joined the right one.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq, Eq)]
+pub struct APIGenesis {
+    /// Genesis stakers by node name, with their stake
+    pub stakers: BTreeMap<String, u64>,
+    /// Hash of the staker set, identical for all nodes of a network
+    pub genesis_hash: String,
 }
 
 /// Copy from Staking contract
@@ -36,15 +73,19 @@ pub struct APIStaking {
     /// List of validators that are part of consensus
     pub bonded: Vec<ValidatorPublicKey>,
     pub total_bond: u128,
+    /// Number of key rotations applied, which the next rotation has to sign
+    #[serde(default)]
+    pub key_rotations: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct APIBlock {
     // Struct for the blocks table
     pub hash: ConsensusProposalHash,
     pub parent_hash: ConsensusProposalHash,
     pub height: u64,    // Corresponds to BlockHeight
     pub timestamp: i64, // UNIX timestamp
+    pub state_root: Option<StateRoot>,
 }
 
 #[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
@@ -73,6 +114,15 @@ pub enum TransactionStatus {
     TimedOut,
 }
 
+/// What happened to a transaction, as known by the node.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq, Eq)]
+pub struct APITransactionStatus {
+    pub tx_hash: TxHash,
+    pub status: TransactionStatus,
+    /// Block in which the transaction settled, failed or timed out, none while sequenced
+    pub block_height: Option<BlockHeight>,
+}
+
 impl TransactionType {
     pub fn get_type_from_transaction(transaction: &Transaction) -> Self {
         match transaction.transaction_data {
@@ -94,6 +144,87 @@ pub struct APITransaction {
     pub transaction_status: TransactionStatus, // Status of the transaction
 }
 
+/// Position of a transaction in the chain, used as a keyset cursor when paging through
+/// transactions. Clients should treat its encoded form as opaque.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TransactionCursor {
+    pub block_height: BlockHeight,
+    pub index: u32,
+}
+
+impl TransactionCursor {
+    pub fn encode(&self) -> String {
+        hex::encode(format!("{}:{}", self.block_height.0, self.index))
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let bytes = hex::decode(cursor).ok()?;
+        let decoded = String::from_utf8(bytes).ok()?;
+        let (block_height, index) = decoded.split_once(':')?;
+        Some(TransactionCursor {
+            block_height: BlockHeight(block_height.parse().ok()?),
+            index: index.parse().ok()?,
+        })
+    }
+}
+
+/// Height of the last block of a page of blocks, the next page starts below it.
+/// Clients should treat its encoded form as opaque.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BlockCursor {
+    pub block_height: BlockHeight,
+}
+
+impl BlockCursor {
+    pub fn encode(&self) -> String {
+        hex::encode(self.block_height.0.to_string())
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let bytes = hex::decode(cursor).ok()?;
+        let decoded = String::from_utf8(bytes).ok()?;
+        Some(BlockCursor {
+            block_height: BlockHeight(decoded.parse().ok()?),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct APIBlockPage {
+    /// Most recent first
+    pub blocks: Vec<APIBlock>,
+    /// Cursor to pass to get the next page, None if this is the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq, Eq)]
+pub struct APITransactionPage {
+    pub transactions: Vec<APITransaction>,
+    /// Cursor to pass to get the next page, None if this is the last page
+    pub next_cursor: Option<String>,
+}
+
+/// A blob that targeted a contract, as exported to replay the contract's history.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct APIContractAction {
+    pub tx_hash: TxHash,
+    pub block_height: BlockHeight,
+    pub index: u32, // Index of the transaction within the block
+    pub blob_index: u32,
+    pub identity: String,
+    #[serde_as(as = "serde_with::hex::Hex")]
+    pub data: Vec<u8>,
+    pub transaction_status: TransactionStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq, Eq)]
+pub struct APIContractActionPage {
+    pub actions: Vec<APIContractAction>,
+    /// Cursor to pass to get the next page, None if this is the last page
+    pub next_cursor: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq)]
 pub struct TransactionWithBlobs {
     pub tx_hash: TxHash,
@@ -136,6 +267,69 @@ pub struct APIContractState {
     pub state_digest: Vec<u8>,             // The contract state stored in JSON format
 }
 
+/// State digest of a contract after a block that changed it.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct APIContractStateHistoryEntry {
+    pub block_hash: ConsensusProposalHash,
+    pub block_height: BlockHeight,
+    #[serde_as(as = "serde_with::hex::Hex")]
+    pub state_digest: Vec<u8>,
+}
+
+/// A blob of a transaction, with the proof output it was settled with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct APISettledBlobProof {
+    pub blob_index: u32,
+    pub contract_name: String,
+    pub proof_tx_hash: TxHash,
+    /// Index of the output among the proof outputs received for this blob, in arrival order
+    pub blob_proof_output_index: u32,
+    pub hyle_output: APIHyleOutputSummary,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct APIHyleOutputSummary {
+    pub identity: String,
+    #[serde_as(as = "serde_with::hex::Hex")]
+    pub initial_state: Vec<u8>,
+    #[serde_as(as = "serde_with::hex::Hex")]
+    pub next_state: Vec<u8>,
+    pub success: bool,
+}
+
+/// A proof as it was submitted, only kept by the indexer of the node that received it.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct APIProof {
+    pub tx_hash: TxHash,
+    pub contract_name: String,
+    #[serde_as(as = "serde_with::hex::Hex")]
+    pub proof: Vec<u8>,
+}
+
+/// An indexed proof, without the proof itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct APIProofMetadata {
+    pub tx_hash: TxHash,
+    pub contract_name: String,
+    /// Size of the proof, in bytes
+    pub proof_len: u64,
+}
+
+/// A staking action applied by a block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct APIStakingAction {
+    pub block_hash: ConsensusProposalHash,
+    pub block_height: BlockHeight,
+    /// Index of the action within the block
+    pub index: u32,
+    pub identity: String,
+    #[schema(value_type = Object)]
+    pub action: StakingAction,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct APIBlob {
     pub tx_hash: TxHash,       // Corresponds to the transaction hash
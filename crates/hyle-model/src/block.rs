@@ -21,7 +21,13 @@ pub struct Block {
     pub new_bounded_validators: Vec<ValidatorPublicKey>,
     pub staking_actions: Vec<(Identity, StakingAction)>,
     pub registered_contracts: Vec<(TxHash, RegisterContractEffect)>,
+    /// Contracts whose program was replaced, by the settled tx that did it
+    pub updated_programs: Vec<(TxHash, RegisterContractEffect)>,
+    /// Encoding declared by the contracts registered in this block, when not the default
+    pub blob_encodings: BTreeMap<ContractName, BlobEncoding>,
     pub updated_states: BTreeMap<ContractName, StateDigest>,
+    /// Commitment over the state digests of all contracts, once this block is applied.
+    pub state_root: StateRoot,
 }
 
 impl Block {
@@ -30,9 +36,14 @@ impl Block {
     }
 }
 
+/// Blocks are ordered by height, then by hash so that different blocks at the same height
+/// are never considered equal.
 impl Ord for Block {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.block_height.0.cmp(&other.block_height.0)
+        self.block_height
+            .0
+            .cmp(&other.block_height.0)
+            .then_with(|| self.hash.0.cmp(&other.hash.0))
     }
 }
 
@@ -73,9 +84,13 @@ impl Hashable<ConsensusProposalHash> for SignedBlock {
     }
 }
 
+/// Same ordering as `Block`: by height, then by hash.
 impl Ord for SignedBlock {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.height().0.cmp(&other.height().0)
+        self.height()
+            .0
+            .cmp(&other.height().0)
+            .then_with(|| self.hash().0.cmp(&other.hash().0))
     }
 }
 
@@ -105,3 +120,51 @@ impl std::default::Default for SignedBlock {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    #[test]
+    fn test_same_height_blocks_are_not_equal() {
+        let a = Block {
+            block_height: BlockHeight(1),
+            hash: ConsensusProposalHash("aaaa".to_string()),
+            ..Default::default()
+        };
+        let b = Block {
+            block_height: BlockHeight(1),
+            hash: ConsensusProposalHash("bbbb".to_string()),
+            ..Default::default()
+        };
+        let c = Block {
+            block_height: BlockHeight(0),
+            hash: ConsensusProposalHash("cccc".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(a.cmp(&b), Ordering::Less);
+        assert_eq!(c.cmp(&a), Ordering::Less);
+
+        let set = BTreeSet::from([a.clone(), b.clone(), c.clone(), a.clone()]);
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![c, a, b]);
+    }
+
+    #[test]
+    fn test_same_height_signed_blocks_are_not_equal() {
+        let mut a = SignedBlock::default();
+        a.consensus_proposal.slot = 1;
+        a.consensus_proposal.timestamp = 1;
+        let mut b = SignedBlock::default();
+        b.consensus_proposal.slot = 1;
+        b.consensus_proposal.timestamp = 2;
+
+        assert_eq!(a.height(), b.height());
+        assert_ne!(a.cmp(&b), Ordering::Equal);
+        assert_eq!(a.cmp(&b), a.hash().0.cmp(&b.hash().0));
+
+        let set = BTreeSet::from([a.clone(), b.clone(), a]);
+        assert_eq!(set.len(), 2);
+    }
+}
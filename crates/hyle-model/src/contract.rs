@@ -90,6 +90,7 @@ pub struct Identity(pub String);
 #[cfg_attr(feature = "full", derive(utoipa::ToSchema))]
 pub struct TxHash(pub String);
 
+/// Position of a blob in its transaction, which is also its position in the `BlobsHash`.
 #[derive(
     Default, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Encode, Decode, Copy,
 )]
@@ -113,7 +114,7 @@ impl std::fmt::Debug for BlobData {
     }
 }
 
-#[derive(Debug, Encode, Decode)]
+#[derive(Debug, Serialize, Deserialize, Encode, Decode)]
 pub struct StructuredBlobData<Parameters> {
     pub caller: Option<BlobIndex>,
     pub callees: Option<Vec<BlobIndex>>,
@@ -180,6 +181,9 @@ pub trait ContractAction: Send {
     ) -> Blob;
 }
 
+/// Concatenates the contract name and data of each blob, as committed in `HyleOutput::blobs`.
+/// Nothing delimits names and data, so this can't be decoded back into blobs: compare
+/// `BlobsHash::from_concatenated` of the output against the hash of the transaction's blobs.
 pub fn flatten_blobs(blobs: &[Blob]) -> Vec<u8> {
     blobs
         .iter()
@@ -373,13 +377,85 @@ impl Add<BlockHeight> for BlockHeight {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq, Encode, Decode)]
+/// Encoding of the blobs of a contract, declared when registering it.
+#[derive(
+    Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, Hash, Encode, Decode,
+)]
+#[cfg_attr(feature = "full", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum BlobEncoding {
+    /// `bincode::config::standard()`, used by `StructuredBlobData`.
+    #[default]
+    BincodeStandard,
+    /// `bincode::config::legacy()` with fixed int encoding, as used by SP1.
+    BincodeLegacy,
+    Json,
+}
+
+#[cfg(feature = "full")]
+impl BlobEncoding {
+    pub fn decode<T>(&self, data: &BlobData) -> anyhow::Result<T>
+    where
+        T: Decode + serde::de::DeserializeOwned,
+    {
+        let decoded = match self {
+            BlobEncoding::BincodeStandard => {
+                bincode::decode_from_slice(&data.0, bincode::config::standard()).map(|(t, _)| t)?
+            }
+            BlobEncoding::BincodeLegacy => bincode::decode_from_slice(
+                &data.0,
+                bincode::config::legacy().with_fixed_int_encoding(),
+            )
+            .map(|(t, _)| t)?,
+            BlobEncoding::Json => serde_json::from_slice(&data.0)?,
+        };
+        Ok(decoded)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq, Encode)]
 pub struct RegisterContractAction {
     pub verifier: Verifier,
     pub program_id: ProgramId,
     pub state_digest: StateDigest,
     pub contract_name: ContractName,
+    /// Breaking change of the bincode layout: appended after the contract name, so a
+    /// registration encoded with it can only be decoded as the last value of its buffer,
+    /// which is the case of registration blobs. Decoders predating it ignore it and use the
+    /// default encoding.
+    #[serde(default)]
+    pub blob_encoding: BlobEncoding,
+}
+
+/// Bincode layout of the registrations encoded before `blob_encoding` was added.
+#[derive(Decode)]
+struct LegacyRegisterContractAction {
+    verifier: Verifier,
+    program_id: ProgramId,
+    state_digest: StateDigest,
+    contract_name: ContractName,
+}
+
+/// Legacy registrations end after the contract name, they decode with the default encoding.
+impl Decode for RegisterContractAction {
+    fn decode<D: bincode::de::Decoder>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        let legacy = LegacyRegisterContractAction::decode(decoder)?;
+        let blob_encoding = match Decode::decode(decoder) {
+            Err(bincode::error::DecodeError::UnexpectedEnd { .. }) => BlobEncoding::default(),
+            res => res?,
+        };
+        Ok(RegisterContractAction {
+            verifier: legacy.verifier,
+            program_id: legacy.program_id,
+            state_digest: legacy.state_digest,
+            contract_name: legacy.contract_name,
+            blob_encoding,
+        })
+    }
 }
+bincode::impl_borrow_decode!(RegisterContractAction);
 
 #[cfg(feature = "full")]
 impl Hashable<TxHash> for RegisterContractAction {
@@ -391,6 +467,10 @@ impl Hashable<TxHash> for RegisterContractAction {
         hasher.update(self.program_id.0.clone());
         hasher.update(self.state_digest.0.clone());
         hasher.update(self.contract_name.0.clone());
+        // Only hashed when declared, so that the hash of existing registrations is unchanged.
+        if self.blob_encoding != BlobEncoding::default() {
+            hasher.update([self.blob_encoding as u8]);
+        }
         let hash_bytes = hasher.finalize();
         TxHash(hex::encode(hash_bytes))
     }
@@ -417,6 +497,10 @@ impl ContractAction for RegisterContractAction {
 /// Used by the Hylé node to recognize contract registration.
 /// Simply output this struct in your HyleOutput registered_contracts.
 /// See uuid-tld for examples.
+///
+/// An effect naming the proven contract itself replaces that contract's program
+/// and verifier once the tx settles, keeping its name and state: the update is
+/// authorized by the contract's current program. Its `state_digest` is ignored.
 #[derive(Default, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Encode, Decode)]
 #[cfg_attr(feature = "full", derive(utoipa::ToSchema))]
 pub struct RegisterContractEffect {
@@ -440,3 +524,58 @@ impl Hashable<TxHash> for RegisterContractEffect {
         TxHash(hex::encode(hash_bytes))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Encode)]
+    struct LegacyRegistration {
+        verifier: Verifier,
+        program_id: ProgramId,
+        state_digest: StateDigest,
+        contract_name: ContractName,
+    }
+
+    #[test]
+    fn test_legacy_registration_blob_decodes() {
+        let legacy = StructuredBlobData {
+            caller: None,
+            callees: Some(vec![BlobIndex(1)]),
+            parameters: LegacyRegistration {
+                verifier: "risc0".into(),
+                program_id: ProgramId(vec![1, 2, 3]),
+                state_digest: StateDigest(vec![4, 5]),
+                contract_name: "c1".into(),
+            },
+        };
+
+        let decoded =
+            StructuredBlobData::<RegisterContractAction>::try_from(BlobData::from(legacy)).unwrap();
+        assert_eq!(decoded.callees, Some(vec![BlobIndex(1)]));
+        assert_eq!(
+            decoded.parameters,
+            RegisterContractAction {
+                verifier: "risc0".into(),
+                program_id: ProgramId(vec![1, 2, 3]),
+                state_digest: StateDigest(vec![4, 5]),
+                contract_name: "c1".into(),
+                blob_encoding: BlobEncoding::BincodeStandard,
+            }
+        );
+    }
+
+    #[test]
+    fn test_registration_blob_keeps_its_encoding() {
+        let registration = RegisterContractAction {
+            verifier: "sp1".into(),
+            contract_name: "c1".into(),
+            blob_encoding: BlobEncoding::BincodeLegacy,
+            ..RegisterContractAction::default()
+        };
+        let blob = registration.as_blob("hyle".into(), None, None);
+
+        let decoded = StructuredBlobData::<RegisterContractAction>::try_from(blob.data).unwrap();
+        assert_eq!(decoded.parameters, registration);
+    }
+}
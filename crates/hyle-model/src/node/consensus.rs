@@ -102,8 +102,25 @@ pub struct ConsensusProposal {
     pub parent_hash: ConsensusProposalHash,
 }
 
+impl ConsensusProposal {
+    /// Whether `child` directly follows `parent`, i.e. its `parent_hash` is the hash of `parent`.
+    pub fn verify_chain(parent: &ConsensusProposal, child: &ConsensusProposal) -> bool {
+        child.parent_hash == parent.hash()
+    }
+}
+
 /// This is the hash of the proposal, signed by validators
 /// Any consensus-critical data should be hashed here.
+///
+/// It is the hex encoded SHA3-256 of, in order:
+/// - `slot` and `view`, as little endian u64,
+/// - the bytes of `round_leader`,
+/// - for each lane of the `cut`, the bytes of the validator key then the data proposal hash
+///   as a string (lane sizes and signatures aren't hashed),
+/// - for each staking action, the bytes of the bonded candidate's key, or of the slashed
+///   validator's key followed by the amount as little endian u128,
+/// - `timestamp`, as little endian u64,
+/// - `parent_hash` as a string.
 impl Hashable<ConsensusProposalHash> for ConsensusProposal {
     fn hash(&self) -> ConsensusProposalHash {
         let mut hasher = Sha3_256::new();
@@ -116,6 +133,12 @@ impl Hashable<ConsensusProposalHash> for ConsensusProposal {
         });
         self.staking_actions.iter().for_each(|val| match val {
             ConsensusStakingAction::Bond { candidate } => hasher.update(&candidate.pubkey.0),
+            ConsensusStakingAction::Slash {
+                validator, amount, ..
+            } => {
+                hasher.update(&validator.0);
+                hasher.update(amount.to_le_bytes());
+            }
         });
         hasher.update(self.timestamp.to_le_bytes());
         hasher.update(self.parent_hash.0.as_bytes());
@@ -132,7 +155,16 @@ impl std::hash::Hash for ConsensusProposal {
 /// Represents the operations that can be performed by the consensus
 #[derive(Encode, Decode, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub enum ConsensusStakingAction {
-    Bond { candidate: NewValidatorCandidate }, // Bonding a new validator candidate
+    Bond {
+        candidate: NewValidatorCandidate,
+    }, // Bonding a new validator candidate
+    /// Slashing a validator that signed two conflicting messages, which are the evidence
+    Slash {
+        validator: ValidatorPublicKey,
+        amount: u128,
+        first: SignedByValidator<ConsensusNetMessage>,
+        second: SignedByValidator<ConsensusNetMessage>,
+    },
 }
 
 impl From<NewValidatorCandidate> for ConsensusStakingAction {
@@ -276,6 +308,33 @@ mod tests {
         let hash = proposal.hash();
         assert_eq!(hash.0.len(), 64);
     }
+    #[test]
+    fn test_verify_chain() {
+        use super::*;
+        let genesis = ConsensusProposal {
+            slot: 0,
+            parent_hash: ConsensusProposalHash("genesis".to_string()),
+            ..ConsensusProposal::default()
+        };
+        let second = ConsensusProposal {
+            slot: 1,
+            timestamp: 1,
+            parent_hash: genesis.hash(),
+            ..ConsensusProposal::default()
+        };
+        let third = ConsensusProposal {
+            slot: 2,
+            timestamp: 2,
+            parent_hash: second.hash(),
+            ..ConsensusProposal::default()
+        };
+
+        assert!(ConsensusProposal::verify_chain(&genesis, &second));
+        assert!(ConsensusProposal::verify_chain(&second, &third));
+        assert!(!ConsensusProposal::verify_chain(&genesis, &third));
+        assert!(!ConsensusProposal::verify_chain(&third, &second));
+    }
+
     #[test]
     fn test_consensus_proposal_hash_ignored_fields() {
         use super::*;
@@ -13,6 +13,9 @@ pub struct Contract {
     pub program_id: ProgramId,
     pub state: StateDigest,
     pub verifier: Verifier,
+    /// Only declared by contracts registered with a `RegisterContractAction`.
+    #[serde(default)]
+    pub blob_encoding: BlobEncoding,
 }
 
 #[derive(
@@ -30,6 +33,7 @@ pub struct Contract {
 )]
 pub struct UnsettledBlobTransaction {
     pub identity: Identity,
+    pub additional_identities: Vec<Identity>,
     pub hash: TxHash,
     #[schema(value_type=TxContext)]
     pub tx_context: Arc<TxContext>,
@@ -54,6 +58,9 @@ pub struct UnsettledBlobMetadata {
     pub blob: Blob,
     // Each time we receive a proof, we add it to this list
     pub possible_proofs: Vec<(ProgramId, HyleOutput)>,
+    /// Earlier blob that must be proven successful for this one to settle.
+    #[serde(default)]
+    pub depends_on: Option<BlobIndex>,
 }
 
 #[derive(
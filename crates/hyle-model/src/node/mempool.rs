@@ -13,6 +13,22 @@ pub struct DataProposal {
     pub txs: Vec<Transaction>,
 }
 
+/// Authorization of a blob tx, signed by a key allowed to submit transactions
+#[derive(Clone, Debug, Serialize, Deserialize, Encode, Decode, Eq, PartialEq)]
+pub struct BlobTxAuthorization {
+    pub tx_hash: TxHash,
+    /// Timestamp (ms) after which the tx can't be submitted with this authorization,
+    /// so that a signed submission can't be replayed once forgotten by the nodes.
+    pub valid_until: u64,
+}
+
+/// Blob tx submitted along with its authorization
+#[derive(Clone, Debug, Serialize, Deserialize, Encode, Decode, Eq, PartialEq)]
+pub struct SignedBlobTransaction {
+    pub tx: BlobTransaction,
+    pub authorization: SignedByValidator<BlobTxAuthorization>,
+}
+
 impl DataSized for DataProposal {
     fn estimate_size(&self) -> usize {
         self.txs.iter().map(|tx| tx.estimate_size()).sum()
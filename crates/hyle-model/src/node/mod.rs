@@ -2,10 +2,12 @@ mod consensus;
 mod crypto;
 mod data_availability;
 mod mempool;
+mod state_root;
 mod tcp;
 
 pub use consensus::*;
 pub use crypto::*;
 pub use data_availability::*;
 pub use mempool::*;
+pub use state_root::*;
 pub use tcp::*;
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use utoipa::ToSchema;
+
+use crate::{ContractName, StateDigest};
+
+/// Hex-encoded Merkle root over the state digests of all contracts, ordered by name.
+/// Leaves are `sha3(0x00 || len(name) || name || digest)`, inner nodes `sha3(0x01 || left || right)`.
+/// A node without sibling is carried up to the next level as is.
+#[derive(
+    Debug, Default, Clone, Serialize, Deserialize, ToSchema, Encode, Decode, PartialEq, Eq,
+)]
+pub struct StateRoot(pub String);
+
+/// Proof that a contract's state digest is part of a `StateRoot`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct StateInclusionProof {
+    /// Sibling hashes from the leaf up to the root, hex-encoded.
+    pub siblings: Vec<StateProofStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct StateProofStep {
+    pub hash: String,
+    /// Whether the sibling is the left node of the pair.
+    pub is_left: bool,
+}
+
+type Hash = [u8; 32];
+
+fn leaf_hash(contract_name: &ContractName, digest: &StateDigest) -> Hash {
+    let mut hasher = Sha3_256::new();
+    hasher.update([0u8]);
+    hasher.update((contract_name.0.len() as u32).to_be_bytes());
+    hasher.update(contract_name.0.as_bytes());
+    hasher.update(&digest.0);
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha3_256::new();
+    hasher.update([1u8]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+fn next_level(level: &[Hash]) -> Vec<Hash> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+impl StateRoot {
+    pub fn compute(digests: &BTreeMap<ContractName, StateDigest>) -> Self {
+        let mut level = digests
+            .iter()
+            .map(|(name, digest)| leaf_hash(name, digest))
+            .collect::<Vec<_>>();
+        while level.len() > 1 {
+            level = next_level(&level);
+        }
+        StateRoot(hex::encode(level.first().copied().unwrap_or_default()))
+    }
+}
+
+impl StateInclusionProof {
+    /// Builds the proof for `contract_name`, or None if it isn't part of `digests`.
+    pub fn build(
+        digests: &BTreeMap<ContractName, StateDigest>,
+        contract_name: &ContractName,
+    ) -> Option<Self> {
+        let mut index = digests.keys().position(|name| name == contract_name)?;
+        let mut level = digests
+            .iter()
+            .map(|(name, digest)| leaf_hash(name, digest))
+            .collect::<Vec<_>>();
+        let mut siblings = vec![];
+        while level.len() > 1 {
+            let sibling_index = index ^ 1;
+            if let Some(sibling) = level.get(sibling_index) {
+                siblings.push(StateProofStep {
+                    hash: hex::encode(sibling),
+                    is_left: sibling_index < index,
+                });
+            }
+            level = next_level(&level);
+            index /= 2;
+        }
+        Some(StateInclusionProof { siblings })
+    }
+
+    pub fn verify(
+        &self,
+        root: &StateRoot,
+        contract_name: &ContractName,
+        digest: &StateDigest,
+    ) -> bool {
+        let mut hash = leaf_hash(contract_name, digest);
+        for step in &self.siblings {
+            let Some(sibling) = hex::decode(&step.hash)
+                .ok()
+                .and_then(|bytes| Hash::try_from(bytes).ok())
+            else {
+                return false;
+            };
+            hash = if step.is_left {
+                node_hash(&sibling, &hash)
+            } else {
+                node_hash(&hash, &sibling)
+            };
+        }
+        hex::encode(hash) == root.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digests(n: usize) -> BTreeMap<ContractName, StateDigest> {
+        (0..n)
+            .map(|i| (ContractName(format!("c{i}")), StateDigest(vec![i as u8])))
+            .collect()
+    }
+
+    #[test]
+    fn test_inclusion_proofs() {
+        for n in 1..=7 {
+            let digests = digests(n);
+            let root = StateRoot::compute(&digests);
+            for (name, digest) in &digests {
+                let proof = StateInclusionProof::build(&digests, name).unwrap();
+                assert!(proof.verify(&root, name, digest));
+                assert!(!proof.verify(&root, name, &StateDigest(vec![42])));
+            }
+        }
+        assert!(StateInclusionProof::build(&digests(3), &ContractName("c9".into())).is_none());
+    }
+}
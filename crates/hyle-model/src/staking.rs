@@ -14,9 +14,53 @@ pub struct RewardsClaim {
 /// Enum representing the actions that can be performed by the IdentityVerification contract.
 #[derive(Encode, Decode, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub enum StakingAction {
-    Stake { amount: u128 },
-    Delegate { validator: ValidatorPublicKey },
-    Distribute { claim: RewardsClaim },
+    Stake {
+        amount: u128,
+    },
+    Delegate {
+        validator: ValidatorPublicKey,
+    },
+    Distribute {
+        claim: RewardsClaim,
+    },
+    /// Applied by the consensus to a validator caught equivocating, not accepted in blob transactions.
+    Slash {
+        validator: ValidatorPublicKey,
+        amount: u128,
+    },
+    /// Moves the stake delegated to `old_pubkey` to `new_pubkey`. `proof` is the signature by
+    /// `old_pubkey` of the [`KeyRotationMessage`], checked by the node state as contracts can't
+    /// verify it. `nonce` is the number of key rotations already applied by the staking contract.
+    RotateKey {
+        old_pubkey: ValidatorPublicKey,
+        new_pubkey: ValidatorPublicKey,
+        nonce: u64,
+        proof: Vec<u8>,
+    },
+}
+
+/// Domain tag of the [`KeyRotationMessage`], so that no other signed message can rotate a key.
+pub const KEY_ROTATION_DOMAIN: &str = "hyle-staking-rotate-key";
+
+/// Message signed by the old key of a validator to rotate it to a new one.
+/// The nonce keeps a proof from being replayed once the key was rotated back.
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+pub struct KeyRotationMessage {
+    pub domain: String,
+    pub old_pubkey: ValidatorPublicKey,
+    pub new_pubkey: ValidatorPublicKey,
+    pub nonce: u64,
+}
+
+impl KeyRotationMessage {
+    pub fn new(old_pubkey: ValidatorPublicKey, new_pubkey: ValidatorPublicKey, nonce: u64) -> Self {
+        KeyRotationMessage {
+            domain: KEY_ROTATION_DOMAIN.to_string(),
+            old_pubkey,
+            new_pubkey,
+            nonce,
+        }
+    }
 }
 
 impl ContractAction for StakingAction {
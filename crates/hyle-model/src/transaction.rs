@@ -85,6 +85,14 @@ impl Transaction {
             transaction_data: data,
         }
     }
+
+    /// Fee offered by the sender of a blob transaction. Proofs don't carry one.
+    pub fn fee(&self) -> u128 {
+        match &self.transaction_data {
+            TransactionData::Blob(tx) => tx.fee,
+            TransactionData::Proof(_) | TransactionData::VerifiedProof(_) => 0,
+        }
+    }
 }
 
 impl From<TransactionData> for Transaction {
@@ -161,39 +169,157 @@ impl Hashable<ProofDataHash> for ProofData {
     }
 }
 
+impl ProofData {
+    /// Reads a proof written to disk by a prover.
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> std::io::Result<ProofData> {
+        std::fs::read(path).map(ProofData)
+    }
+}
+
+impl ProofDataHash {
+    /// Same as hashing the `ProofData` of this file, without loading it in memory.
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> std::io::Result<ProofDataHash> {
+        let mut hasher = Sha3_256::new();
+        std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+        let hash_bytes = hasher.finalize();
+        Ok(ProofDataHash(hex::encode(hash_bytes)))
+    }
+}
+
 #[derive(
     Debug, Serialize, Deserialize, ToSchema, Default, PartialEq, Eq, Clone, Encode, Decode,
 )]
 pub struct BlobTransaction {
     pub identity: Identity,
     pub blobs: Vec<Blob>,
-    // FIXME: add a nonce or something to prevent BlobTransaction to share the same hash
+    /// Blobs that should only settle if an earlier blob of this transaction succeeded.
+    #[serde(default)]
+    pub dependencies: Vec<BlobDependency>,
+    /// Other identities the transaction acts for, e.g. for a multi-party action.
+    /// Like `identity`, each of them must be proven by a blob of its identity contract.
+    #[serde(default)]
+    pub additional_identities: Vec<Identity>,
+    /// Distinguishes otherwise identical transactions, which would share the same hash.
+    #[serde(default)]
+    pub nonce: Option<u64>,
+    /// Offered to the validators to include the transaction. It is not charged yet, so data
+    /// proposals don't take it into account.
+    #[serde(default)]
+    pub fee: u128,
 }
 impl Hashable<TxHash> for BlobTransaction {
     fn hash(&self) -> TxHash {
         let mut hasher = Sha3_256::new();
         hasher.update(self.identity.0.as_bytes());
         hasher.update(self.blobs_hash().0);
+        // Only hashed when present, so that the hash of unconditional transactions is unchanged.
+        for dependency in &self.dependencies {
+            hasher.update(dependency.blob_index.0.to_le_bytes());
+            hasher.update(dependency.depends_on.0.to_le_bytes());
+        }
+        for identity in &self.additional_identities {
+            hasher.update((identity.0.len() as u64).to_le_bytes());
+            hasher.update(identity.0.as_bytes());
+        }
+        if let Some(nonce) = self.nonce {
+            hasher.update(b"nonce");
+            hasher.update(nonce.to_le_bytes());
+        }
+        if self.fee != 0 {
+            hasher.update(b"fee");
+            hasher.update(self.fee.to_le_bytes());
+        }
         let hash_bytes = hasher.finalize();
         TxHash(hex::encode(hash_bytes))
     }
 }
 
+/// Makes the blob at `blob_index` conditional on the blob at `depends_on`.
+/// If `depends_on` is proven to have failed, this blob is skipped instead of failing the whole
+/// transaction, and the failure of `depends_on` is not fatal either.
+#[derive(
+    Debug, Serialize, Deserialize, ToSchema, Default, PartialEq, Eq, Clone, Encode, Decode,
+)]
+pub struct BlobDependency {
+    pub blob_index: BlobIndex,
+    pub depends_on: BlobIndex,
+}
+
 impl BlobTransaction {
     pub fn blobs_hash(&self) -> BlobsHash {
         BlobsHash::from_vec(&self.blobs)
     }
 
+    /// Returns the blob each blob depends on, if any.
+    pub fn blob_dependencies(&self) -> Result<Vec<Option<BlobIndex>>, anyhow::Error> {
+        let mut dependencies = vec![None; self.blobs.len()];
+        for dependency in &self.dependencies {
+            if dependency.depends_on.0 >= dependency.blob_index.0 {
+                anyhow::bail!(
+                    "Blob {} can only depend on an earlier blob, not {}",
+                    dependency.blob_index,
+                    dependency.depends_on
+                );
+            }
+            // Identities must be proven, so their blobs are not allowed to fail
+            if self
+                .blobs
+                .get(dependency.depends_on.0)
+                .is_some_and(|blob| self.is_identity_contract(&blob.contract_name))
+            {
+                anyhow::bail!(
+                    "Blob {} can't depend on blob {}, which proves an identity",
+                    dependency.blob_index,
+                    dependency.depends_on
+                );
+            }
+            match dependencies.get_mut(dependency.blob_index.0) {
+                Some(slot @ None) => *slot = Some(dependency.depends_on),
+                Some(Some(_)) => {
+                    anyhow::bail!("Blob {} has several dependencies", dependency.blob_index)
+                }
+                None => anyhow::bail!("Blob {} does not exist", dependency.blob_index),
+            }
+        }
+        Ok(dependencies)
+    }
+
+    /// The main identity and the additional ones.
+    pub fn identities(&self) -> impl Iterator<Item = &Identity> {
+        std::iter::once(&self.identity).chain(self.additional_identities.iter())
+    }
+
+    fn is_identity_contract(&self, contract_name: &ContractName) -> bool {
+        self.identities().any(|identity| {
+            identity
+                .0
+                .split_once('.')
+                .is_some_and(|(_, identity_contract_name)| {
+                    identity_contract_name == contract_name.0
+                })
+        })
+    }
+
     pub fn validate_identity(&self) -> Result<(), anyhow::Error> {
+        for (i, identity) in self.identities().enumerate() {
+            if self.identities().take(i).any(|other| other == identity) {
+                anyhow::bail!("Transaction identity {} is declared twice", identity.0);
+            }
+            self.validate_one_identity(identity)?;
+        }
+        Ok(())
+    }
+
+    fn validate_one_identity(&self, identity: &Identity) -> Result<(), anyhow::Error> {
         // Checks that there is a blob that proves the identity
-        let Some((identity, identity_contract_name)) = self.identity.0.split_once('.') else {
-            anyhow::bail!("Transaction identity {} is not correctly formed. It should be in the form <id>.<contract_id_name>", self.identity.0);
+        let Some((name, identity_contract_name)) = identity.0.split_once('.') else {
+            anyhow::bail!("Transaction identity {} is not correctly formed. It should be in the form <id>.<contract_id_name>", identity.0);
         };
 
-        if identity.is_empty() || identity_contract_name.is_empty() {
+        if name.is_empty() || identity_contract_name.is_empty() {
             anyhow::bail!(
                 "Transaction identity {}.{} must not have empty parts",
-                identity,
+                name,
                 identity_contract_name
             );
         }
@@ -213,6 +339,8 @@ impl BlobTransaction {
     }
 }
 
+/// Hash of the blobs of a transaction, flattened in the order of the transaction.
+/// That order must be kept from the mempool to the proofs: each `BlobIndex` is a position in it.
 #[derive(
     Debug,
     Display,
@@ -266,3 +394,75 @@ pub mod base64_field {
         BASE64_STANDARD.decode(&s).map_err(serde::de::Error::custom)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonce_distinguishes_identical_blob_txs() {
+        let tx = BlobTransaction {
+            identity: "bob.c1".into(),
+            blobs: vec![Blob {
+                contract_name: "c1".into(),
+                data: BlobData(vec![1, 2, 3]),
+            }],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let with_nonce = |nonce| BlobTransaction {
+            nonce: Some(nonce),
+            ..tx.clone()
+        };
+
+        assert_eq!(tx.hash(), tx.clone().hash());
+        assert_ne!(tx.hash(), with_nonce(0).hash());
+        assert_ne!(with_nonce(0).hash(), with_nonce(1).hash());
+        assert_eq!(with_nonce(1).hash(), with_nonce(1).hash());
+    }
+
+    #[test]
+    fn test_fee_is_part_of_the_blob_tx_hash() {
+        let tx = BlobTransaction {
+            identity: "bob.c1".into(),
+            blobs: vec![Blob {
+                contract_name: "c1".into(),
+                data: BlobData(vec![1, 2, 3]),
+            }],
+            ..BlobTransaction::default()
+        };
+        let with_fee = |fee| BlobTransaction { fee, ..tx.clone() };
+
+        assert_eq!(tx.hash(), with_fee(0).hash());
+        assert_ne!(tx.hash(), with_fee(1).hash());
+        assert_ne!(with_fee(1).hash(), with_fee(2).hash());
+    }
+
+    #[test]
+    fn test_proof_data_hash_is_sha3_of_the_bytes() {
+        assert_eq!(
+            ProofData(vec![]).hash().0,
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+        );
+        assert_eq!(
+            ProofData(b"abc".to_vec()).hash().0,
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+        );
+    }
+
+    #[test]
+    fn test_proof_file_hash_matches_in_memory_hash() {
+        let proof = ProofData((0..100_000u32).flat_map(|i| i.to_le_bytes()).collect());
+        let path = std::env::temp_dir().join(format!("proof-{}.bin", std::process::id()));
+        std::fs::write(&path, &proof.0).unwrap();
+
+        let file_hash = ProofDataHash::from_file(&path).unwrap();
+        let read_proof = ProofData::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(file_hash, proof.hash());
+        assert_eq!(read_proof, proof);
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::{bail, Context, Error};
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::{Proof, VerifyingKey};
+use hyle_model::HyleOutput;
+
+const FIELD_SIZE: usize = 32;
+const G1_SIZE: usize = 2 * FIELD_SIZE;
+const G2_SIZE: usize = 4 * FIELD_SIZE;
+/// Number of bytes of the encoded HyleOutput carried by each public input.
+/// One less than the field size, so that any chunk is a valid BN254 scalar.
+const BYTES_PER_INPUT: usize = FIELD_SIZE - 1;
+
+/// Cursor over gnark's big-endian encoding of BN254 elements.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            bail!("Unexpected end of data");
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes(bytes.try_into()?))
+    }
+
+    fn fq(&mut self) -> Result<Fq, Error> {
+        let bytes = self.take(FIELD_SIZE)?;
+        let fq = Fq::from_be_bytes_mod_order(bytes);
+        if fq.into_bigint().to_bytes_be() != bytes {
+            bail!("Base field element is not reduced");
+        }
+        Ok(fq)
+    }
+
+    fn fr(&mut self) -> Result<Fr, Error> {
+        let bytes = self.take(FIELD_SIZE)?;
+        let fr = Fr::from_be_bytes_mod_order(bytes);
+        if fr.into_bigint().to_bytes_be() != bytes {
+            bail!("Scalar field element is not reduced");
+        }
+        Ok(fr)
+    }
+
+    /// Uncompressed G1 point: `X || Y`
+    fn g1(&mut self) -> Result<G1Affine, Error> {
+        let point = G1Affine::new_unchecked(self.fq()?, self.fq()?);
+        if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+            bail!("Invalid G1 point");
+        }
+        Ok(point)
+    }
+
+    /// Uncompressed G2 point: `X.A1 || X.A0 || Y.A1 || Y.A0`
+    fn g2(&mut self) -> Result<G2Affine, Error> {
+        let (x1, x0, y1, y0) = (self.fq()?, self.fq()?, self.fq()?, self.fq()?);
+        let point = G2Affine::new_unchecked(Fq2::new(x0, x1), Fq2::new(y0, y1));
+        if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+            bail!("Invalid G2 point");
+        }
+        Ok(point)
+    }
+}
+
+pub fn decode_proof(proof: &[u8]) -> Result<Proof<Bn254>, Error> {
+    if proof.len() != 2 * G1_SIZE + G2_SIZE {
+        bail!(
+            "Expected a {} bytes proof, got {}. Proofs with commitments are not supported",
+            2 * G1_SIZE + G2_SIZE,
+            proof.len()
+        );
+    }
+    let mut reader = Reader(proof);
+    Ok(Proof {
+        a: reader.g1()?,
+        b: reader.g2()?,
+        c: reader.g1()?,
+    })
+}
+
+pub fn decode_verifying_key(vk: &[u8]) -> Result<VerifyingKey<Bn254>, Error> {
+    let mut reader = Reader(vk);
+    let alpha_g1 = reader.g1()?;
+    // [β]1 and [δ]1 are only used by gnark's prover
+    let _beta_g1 = reader.g1()?;
+    let beta_g2 = reader.g2()?;
+    let gamma_g2 = reader.g2()?;
+    let _delta_g1 = reader.g1()?;
+    let delta_g2 = reader.g2()?;
+    let nb_k = reader.u32()? as usize;
+    if nb_k == 0 || reader.0.len() < nb_k * G1_SIZE {
+        bail!("Invalid number of public input points: {}", nb_k);
+    }
+    let gamma_abc_g1 = (0..nb_k)
+        .map(|_| reader.g1())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(VerifyingKey {
+        alpha_g1,
+        beta_g2,
+        gamma_g2,
+        delta_g2,
+        gamma_abc_g1,
+    })
+}
+
+/// gnark public witness binary format:
+/// `nb_public (u32) || nb_secret (u32) || len (u32) || elements (32 bytes each)`,
+/// with `nb_secret == 0` and `len == nb_public`.
+pub fn decode_public_witness(witness: &[u8]) -> Result<Vec<Fr>, Error> {
+    let mut reader = Reader(witness);
+    let nb_public = reader.u32()? as usize;
+    let nb_secret = reader.u32()?;
+    let len = reader.u32()? as usize;
+    if nb_secret != 0 || len != nb_public {
+        bail!("Witness must only contain public inputs");
+    }
+    if reader.0.len() != len * FIELD_SIZE {
+        bail!(
+            "Witness has {} bytes of elements, expected {}",
+            reader.0.len(),
+            len * FIELD_SIZE
+        );
+    }
+    (0..len).map(|_| reader.fr()).collect()
+}
+
+/// Public inputs layout:
+/// - input 0: `N`, the length in bytes of the encoded HyleOutput
+/// - inputs 1..=ceil(N / 31): the encoded HyleOutput, split in chunks of 31 bytes.
+///   Each chunk is read as a big-endian integer, the last one being right-padded with zeros.
+///
+/// The HyleOutput is bincode-encoded with the legacy, fixed-int config,
+/// like SP1's public values.
+pub fn decode_hyle_output(public_inputs: &[Fr]) -> Result<HyleOutput, Error> {
+    let (len, chunks) = public_inputs
+        .split_first()
+        .context("Missing HyleOutput length")?;
+    let len_bytes = len.into_bigint().to_bytes_be();
+    let (high, low) = len_bytes.split_at(FIELD_SIZE - 4);
+    if high.iter().any(|b| *b != 0) {
+        bail!("Invalid HyleOutput length");
+    }
+    let len = u32::from_be_bytes(low.try_into()?) as usize;
+    if chunks.len() != len.div_ceil(BYTES_PER_INPUT) {
+        bail!(
+            "Expected {} chunks for a {} bytes HyleOutput, got {}",
+            len.div_ceil(BYTES_PER_INPUT),
+            len,
+            chunks.len()
+        );
+    }
+
+    let mut encoded = Vec::with_capacity(chunks.len() * BYTES_PER_INPUT);
+    for chunk in chunks {
+        let bytes = chunk.into_bigint().to_bytes_be();
+        let (high, low) = bytes.split_at(FIELD_SIZE - BYTES_PER_INPUT);
+        if high.iter().any(|b| *b != 0) {
+            bail!("HyleOutput chunk is larger than {} bytes", BYTES_PER_INPUT);
+        }
+        encoded.extend_from_slice(low);
+    }
+    if encoded
+        .get(len..)
+        .is_some_and(|padding| padding.iter().any(|b| *b != 0))
+    {
+        bail!("HyleOutput padding must be zeros");
+    }
+    encoded.truncate(len);
+
+    let (hyle_output, read) = bincode::decode_from_slice::<HyleOutput, _>(
+        &encoded,
+        bincode::config::legacy().with_fixed_int_encoding(),
+    )?;
+    if read != len {
+        bail!("Trailing bytes after HyleOutput");
+    }
+    Ok(hyle_output)
+}
@@ -1,31 +1,73 @@
 #![warn(unused_crate_dependencies)]
 
-use std::fmt::Write;
 use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, Context, Error};
 use hyle_model::HyleOutput;
-use rand::Rng;
 use sp1_sdk::{ProverClient, SP1ProofWithPublicValues, SP1VerifyingKey};
 
+mod gnark;
 mod noir_utils;
 
 pub mod risc0 {
-    pub use risc0_zkvm::serde::from_slice;
+    pub use risc0_zkvm::{serde::from_slice, Journal};
+}
+
+/// Context of the errors caused by proof data that can't be decoded, as opposed to
+/// a well-formed proof that doesn't verify. Can be found with `Error::downcast_ref`.
+#[derive(Debug)]
+pub struct DecodeError(pub &'static str);
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
 }
 
 pub fn risc0_proof_verifier(
     encoded_receipt: &[u8],
     image_id: &[u8],
 ) -> Result<risc0_zkvm::Journal, Error> {
+    let image_id = risc0_image_id(image_id)?;
+    let receipt = borsh::from_slice::<risc0_zkvm::Receipt>(encoded_receipt)
+        .context(DecodeError("Error while decoding Risc0 proof's receipt"))?;
+
+    verify_risc0_receipt(receipt, image_id)
+}
+
+/// Same as `risc0_proof_verifier`, but refuses any receipt that isn't a Groth16 one.
+pub fn risc0_groth16_proof_verifier(
+    encoded_receipt: &[u8],
+    image_id: &[u8],
+) -> Result<risc0_zkvm::Journal, Error> {
+    let image_id = risc0_image_id(image_id)?;
     let receipt = borsh::from_slice::<risc0_zkvm::Receipt>(encoded_receipt)
-        .context("Error while decoding Risc0 proof's receipt")?;
+        .context(DecodeError("Error while decoding Risc0 proof's receipt"))?;
 
-    let image_bytes: risc0_zkvm::sha::Digest =
-        image_id.try_into().context("Invalid Risc0 image ID")?;
+    if !matches!(receipt.inner, risc0_zkvm::InnerReceipt::Groth16(_)) {
+        bail!("Risc0 receipt is not a Groth16 receipt");
+    }
 
+    verify_risc0_receipt(receipt, image_id)
+}
+
+/// Checked before decoding the receipt, as a wrong length means a misconfigured program ID.
+fn risc0_image_id(image_id: &[u8]) -> Result<risc0_zkvm::sha::Digest, Error> {
+    if image_id.len() != 32 {
+        bail!("Risc0 image ID must be 32 bytes, got {}", image_id.len());
+    }
+    image_id.try_into().context("Invalid Risc0 image ID")
+}
+
+fn verify_risc0_receipt(
+    receipt: risc0_zkvm::Receipt,
+    image_id: risc0_zkvm::sha::Digest,
+) -> Result<risc0_zkvm::Journal, Error> {
     receipt
-        .verify(image_bytes)
+        .verify(image_id)
         .context("Risc0 proof verification failed")?;
 
     tracing::info!("✅ Risc0 proof verified.");
@@ -35,30 +77,65 @@ pub fn risc0_proof_verifier(
 
 /// At present, we are using binary to facilitate the integration of the Noir verifier.
 /// This is not meant to be a permanent solution.
-pub fn noir_proof_verifier(proof: &[u8], image_id: &[u8]) -> Result<Vec<HyleOutput>, Error> {
-    let mut rng = rand::rng();
-    let salt: [u8; 16] = rng.random();
-    let mut salt_hex = String::with_capacity(salt.len() * 2);
-    for b in &salt {
-        write!(salt_hex, "{:02x}", b).unwrap();
+#[derive(Debug, Clone)]
+pub struct NoirVerifier {
+    /// Path of the `bb` binary, looked up in `PATH` if not absolute.
+    pub bb: PathBuf,
+    /// Each `bb` invocation is killed if it doesn't complete within `timeout`.
+    pub timeout: Duration,
+}
+
+impl Default for NoirVerifier {
+    fn default() -> Self {
+        Self {
+            bb: PathBuf::from("bb"),
+            timeout: Duration::from_secs(60),
+        }
     }
+}
 
-    let proof_path = &format!("/tmp/noir-proof-{salt_hex}");
-    let vk_path = &format!("/tmp/noir-vk-{salt_hex}");
-    let output_path = &format!("/tmp/noir-output-{salt_hex}");
+impl NoirVerifier {
+    pub fn new(bb: Option<PathBuf>, timeout: Duration) -> Self {
+        Self {
+            bb: bb.unwrap_or_else(|| PathBuf::from("bb")),
+            timeout,
+        }
+    }
+
+    pub fn verify(&self, proof: &[u8], image_id: &[u8]) -> Result<Vec<HyleOutput>, Error> {
+        run_noir_verifier(&self.bb, proof, image_id, self.timeout)
+    }
+}
+
+fn run_noir_verifier(
+    bb: &Path,
+    proof: &[u8],
+    image_id: &[u8],
+    timeout: Duration,
+) -> Result<Vec<HyleOutput>, Error> {
+    // Files live in a per-call directory, removed when dropped, whichever way we return.
+    let dir = tempfile::Builder::new()
+        .prefix("noir-")
+        .tempdir()
+        .context("Failed to create temporary directory")?;
+    let proof_path = &dir.path().join("proof");
+    let vk_path = &dir.path().join("vk");
+    let output_path = &dir.path().join("output");
 
     // Write proof and publicKey to files
     std::fs::write(proof_path, proof)?;
     std::fs::write(vk_path, image_id)?;
 
     // Verifying proof
-    let verification_output = std::process::Command::new("bb")
-        .arg("verify")
-        .arg("-p")
-        .arg(proof_path)
-        .arg("-k")
-        .arg(vk_path)
-        .output()?;
+    let verification_output = output_with_timeout(
+        Command::new(bb)
+            .arg("verify")
+            .arg("-p")
+            .arg(proof_path)
+            .arg("-k")
+            .arg(vk_path),
+        timeout,
+    )?;
 
     if !verification_output.status.success() {
         bail!(
@@ -68,15 +145,17 @@ pub fn noir_proof_verifier(proof: &[u8], image_id: &[u8]) -> Result<Vec<HyleOutp
     }
 
     // Extracting outputs
-    let public_outputs_output = std::process::Command::new("bb")
-        .arg("proof_as_fields")
-        .arg("-p")
-        .arg(proof_path)
-        .arg("-k")
-        .arg(vk_path)
-        .arg("-o")
-        .arg(output_path)
-        .output()?;
+    let public_outputs_output = output_with_timeout(
+        Command::new(bb)
+            .arg("proof_as_fields")
+            .arg("-p")
+            .arg(proof_path)
+            .arg("-k")
+            .arg(vk_path)
+            .arg("-o")
+            .arg(output_path),
+        timeout,
+    )?;
 
     if !public_outputs_output.status.success() {
         bail!(
@@ -92,22 +171,80 @@ pub fn noir_proof_verifier(proof: &[u8], image_id: &[u8]) -> Result<Vec<HyleOutp
         .context("Failed to read output file content")?;
 
     let mut public_outputs: Vec<String> = serde_json::from_str(&output_json)?;
-    // TODO: support multi-output proofs.
-    let hyle_output = crate::noir_utils::parse_noir_output(&mut public_outputs)?;
+    crate::noir_utils::parse_noir_outputs(&mut public_outputs)
+}
 
-    // Delete proof_path, vk_path, output_path
-    let _ = std::fs::remove_file(proof_path);
-    let _ = std::fs::remove_file(vk_path);
-    let _ = std::fs::remove_file(output_path);
-    Ok(vec![hyle_output])
+/// Like `Command::output`, but kills the child if it hasn't exited within `timeout`.
+fn output_with_timeout(command: &mut Command, timeout: Duration) -> Result<Output, Error> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Pipes are drained while waiting, so that a verbose child can't block on a full pipe.
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    };
+    let stdout = drain(child.stdout.take().map(|p| Box::new(p) as _));
+    let stderr = drain(child.stderr.take().map(|p| Box::new(p) as _));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("Noir verification timed out after {}s", timeout.as_secs());
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Checks the environment read by `ProverClient::from_env`, which panics on a bad configuration.
+pub fn check_sp1_env() -> Result<(), Error> {
+    check_sp1_prover_config(
+        std::env::var("SP1_PROVER").ok().as_deref(),
+        std::env::var_os("NETWORK_PRIVATE_KEY").is_some_and(|key| !key.is_empty()),
+    )
+}
+
+fn check_sp1_prover_config(prover: Option<&str>, has_network_key: bool) -> Result<(), Error> {
+    match prover {
+        None | Some("mock" | "cpu" | "cuda") => Ok(()),
+        Some("network") if has_network_key => Ok(()),
+        Some("network") => bail!(
+            "SP1_PROVER=network requires NETWORK_PRIVATE_KEY to be set to the private key of your SP1 network account"
+        ),
+        Some(other) => bail!(
+            "Invalid SP1_PROVER value '{}', expected one of: mock, cpu, cuda or network",
+            other
+        ),
+    }
 }
 
 /// The following environment variables are used to configure the prover:
-/// - `SP1_PROVER`: The type of prover to use. Must be one of `mock`, `local`, `cuda`, or `network`.
+/// - `SP1_PROVER`: The type of prover to use. Must be one of `mock`, `cpu` (default), `cuda`, or `network`.
+/// - `NETWORK_PRIVATE_KEY`: Required with the `network` prover.
 pub fn sp1_proof_verifier(
     proof_bin: &[u8],
     verification_key: &[u8],
 ) -> Result<Vec<HyleOutput>, Error> {
+    check_sp1_env().context("Misconfigured SP1 prover")?;
+
     // Setup the prover client.
     let client = ProverClient::from_env();
 
@@ -116,7 +253,7 @@ pub fn sp1_proof_verifier(
             proof_bin,
             bincode::config::legacy().with_fixed_int_encoding(),
         )
-        .context("Error while decoding SP1 proof.")?;
+        .context(DecodeError("Error while decoding SP1 proof."))?;
 
     // Deserialize verification key from JSON
     let vk: SP1VerifyingKey =
@@ -139,13 +276,68 @@ pub fn sp1_proof_verifier(
     Ok(vec![hyle_output])
 }
 
+/// Format of the proof data for the "gnark" verifier.
+#[derive(bincode::Encode, bincode::Decode)]
+pub struct GnarkProof {
+    /// Groth16 proof over BN254, as written by gnark's `Proof.MarshalSolidity`:
+    /// `Ar (G1) || Bs (G2) || Krs (G1)`, uncompressed.
+    pub proof: Vec<u8>,
+    /// Public witness, as written by gnark's `witness.MarshalBinary` (see `gnark::decode_hyle_output`).
+    pub public_witness: Vec<u8>,
+}
+
+/// Verifies a Groth16 proof generated with gnark, `vk` being the verifying key
+/// as written by gnark's `VerifyingKey.WriteRawTo` (uncompressed):
+/// `[α]1 || [β]1 || [β]2 || [γ]2 || [δ]1 || [δ]2 || len (u32) || K[0..len] (G1)`.
+/// Circuits using commitments are not supported: any data after `K` is ignored,
+/// and the pairing check fails for them.
+pub fn gnark_proof_verifier(proof: &[u8], vk: &[u8]) -> Result<Vec<HyleOutput>, Error> {
+    let (proof, _) =
+        bincode::decode_from_slice::<GnarkProof, _>(proof, bincode::config::standard())
+            .context(DecodeError("Error while decoding gnark proof"))?;
+
+    let vk = gnark::decode_verifying_key(vk).context("Invalid gnark verifying key")?;
+    let public_inputs = gnark::decode_public_witness(&proof.public_witness)
+        .context(DecodeError("Invalid gnark public witness"))?;
+    let groth16_proof =
+        gnark::decode_proof(&proof.proof).context(DecodeError("Invalid gnark proof"))?;
+
+    if public_inputs.len() + 1 != vk.gamma_abc_g1.len() {
+        bail!(
+            "gnark public witness has {} elements, verifying key expects {}",
+            public_inputs.len(),
+            vk.gamma_abc_g1.len().saturating_sub(1)
+        );
+    }
+
+    let verified = ark_groth16::Groth16::<ark_bn254::Bn254>::verify_proof(
+        &ark_groth16::prepare_verifying_key(&vk),
+        &groth16_proof,
+        &public_inputs,
+    )
+    .map_err(|e| anyhow::anyhow!("gnark proof verification failed: {:?}", e))?;
+    if !verified {
+        bail!("gnark proof verification failed");
+    }
+
+    // The public witness encodes a single HyleOutput
+    let hyle_output = gnark::decode_hyle_output(&public_inputs)
+        .context("Failed to extract HyleOuput from gnark public witness")?;
+
+    tracing::info!("✅ gnark proof verified.");
+
+    Ok(vec![hyle_output])
+}
+
 #[cfg(test)]
 mod tests {
     use std::{fs::File, io::Read};
 
     use hyle_model::{BlobIndex, HyleOutput, Identity, StateDigest, TxHash};
 
-    use super::noir_proof_verifier;
+    use std::time::Duration;
+
+    use super::{check_sp1_prover_config, risc0_proof_verifier, NoirVerifier};
 
     fn load_file_as_bytes(path: &str) -> Vec<u8> {
         let mut file = File::open(path).expect("Failed to open file");
@@ -197,7 +389,7 @@ mod tests {
         let noir_proof = load_file_as_bytes("./tests/proofs/webauthn.noir.proof");
         let image_id = load_file_as_bytes("./tests/proofs/webauthn.noir.vk");
 
-        let result = noir_proof_verifier(&noir_proof, &image_id);
+        let result = NoirVerifier::default().verify(&noir_proof, &image_id);
         match result {
             Ok(outputs) => {
                 assert_eq!(
@@ -222,4 +414,101 @@ mod tests {
             Err(e) => panic!("Noir verification failed: {:?}", e),
         }
     }
+
+    #[ignore = "manual test"]
+    #[test_log::test]
+    fn test_noir_proof_verifier_concurrently() {
+        let noir_proof = load_file_as_bytes("./tests/proofs/webauthn.noir.proof");
+        let image_id = load_file_as_bytes("./tests/proofs/webauthn.noir.vk");
+
+        let handles = (0..8)
+            .map(|_| {
+                let (noir_proof, image_id) = (noir_proof.clone(), image_id.clone());
+                std::thread::spawn(move || NoirVerifier::default().verify(&noir_proof, &image_id))
+            })
+            .collect::<Vec<_>>();
+
+        let outputs = handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .expect("Verification thread panicked")
+                    .expect("Noir verification failed")
+            })
+            .collect::<Vec<_>>();
+
+        assert!(outputs.iter().all(|output| output == &outputs[0]));
+    }
+
+    /// Writes an executable shell script standing in for `bb`.
+    #[cfg(unix)]
+    fn bb_shim(dir: &tempfile::TempDir, script: &str) -> std::path::PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let bb = dir.path().join("bb");
+        std::fs::write(&bb, format!("#!/bin/sh\n{script}\n")).unwrap();
+        std::fs::set_permissions(&bb, std::fs::Permissions::from_mode(0o755)).unwrap();
+        bb
+    }
+
+    #[cfg(unix)]
+    #[test_log::test]
+    fn test_noir_proof_verifier_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let bb = bb_shim(&dir, "sleep 30");
+
+        let start = std::time::Instant::now();
+        let err = NoirVerifier::new(Some(bb), Duration::from_secs(1))
+            .verify(&[], &[])
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "Noir verification timed out after 1s");
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    #[test_log::test]
+    fn test_noir_proof_verifier_with_custom_bb_path() {
+        let dir = tempfile::tempdir().unwrap();
+        // `verify` succeeds, `proof_as_fields` writes the fields of a HyleOutput for "bob"
+        let bb = bb_shim(
+            &dir,
+            r#"[ "$1" = "proof_as_fields" ] || exit 0
+while [ "$#" -gt 0 ]; do [ "$1" = "-o" ] && out="$2"; shift; done
+echo '["0x1","0x4","0x0","0x0","0x0","0x1","0x4","0x0","0x0","0x0","0x2","0x3","0x62","0x6f","0x62","0x0","0x0","0x2","0x1","0x2","0x1","0x2","0x0","0x0","0x0","0x0","0x0","0x0","0x1"]' > "$out""#,
+        );
+
+        let outputs = NoirVerifier::new(Some(bb), Duration::from_secs(10))
+            .verify(&[], &[])
+            .expect("Noir verification failed");
+
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].identity, Identity("bob".to_owned()));
+        assert_eq!(outputs[0].next_state, StateDigest(vec![0, 0, 0, 2]));
+        assert_eq!(outputs[0].blobs, vec![1, 2]);
+        assert!(outputs[0].success);
+    }
+
+    #[test_log::test]
+    fn test_sp1_prover_config() {
+        assert!(check_sp1_prover_config(None, false).is_ok());
+        assert!(check_sp1_prover_config(Some("mock"), false).is_ok());
+        assert!(check_sp1_prover_config(Some("network"), true).is_ok());
+
+        let err = check_sp1_prover_config(Some("gpu"), false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid SP1_PROVER value 'gpu', expected one of: mock, cpu, cuda or network"
+        );
+        let err = check_sp1_prover_config(Some("network"), false).unwrap_err();
+        assert!(err.to_string().contains("requires NETWORK_PRIVATE_KEY"));
+    }
+
+    #[test_log::test]
+    fn test_risc0_image_id_length_is_checked_first() {
+        // Not a receipt either, but the image ID is reported
+        let err = risc0_proof_verifier(&[1, 2, 3], &[0; 16]).unwrap_err();
+        assert_eq!(err.to_string(), "Risc0 image ID must be 32 bytes, got 16");
+    }
 }
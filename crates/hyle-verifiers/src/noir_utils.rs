@@ -6,16 +6,38 @@ use hyle_model::{
     HYLE_TESTNET_CHAIN_ID,
 };
 
+/// Parses all the HyleOutputs concatenated in the public outputs of a Noir proof.
+pub fn parse_noir_outputs(vector: &mut Vec<String>) -> Result<Vec<HyleOutput>, Error> {
+    if vector.is_empty() {
+        anyhow::bail!("Noir proof has no public outputs");
+    }
+    let mut outputs = vec![];
+    while !vector.is_empty() {
+        let output = parse_noir_output(vector)
+            .with_context(|| format!("Failed to parse HyleOutput #{}", outputs.len()))?;
+        outputs.push(output);
+    }
+    Ok(outputs)
+}
+
 pub fn parse_noir_output(vector: &mut Vec<String>) -> Result<HyleOutput, Error> {
-    let version = u32::from_str_radix(vector.remove(0).strip_prefix("0x").context("parsing")?, 16)?;
+    let version = u32::from_str_radix(
+        pop_field(vector)?.strip_prefix("0x").context("parsing")?,
+        16,
+    )?;
     let initial_state = parse_array(vector)?;
     let next_state = parse_array(vector)?;
     let identity = parse_string(vector)?;
     let tx_hash = parse_string(vector)?;
-    let index = u32::from_str_radix(vector.remove(0).strip_prefix("0x").context("parsing")?, 16)?;
+    let index = u32::from_str_radix(
+        pop_field(vector)?.strip_prefix("0x").context("parsing")?,
+        16,
+    )?;
     let blobs = parse_blobs(vector)?;
-    let success =
-        u32::from_str_radix(vector.remove(0).strip_prefix("0x").context("parsing")?, 16)? == 1;
+    let success = u32::from_str_radix(
+        pop_field(vector)?.strip_prefix("0x").context("parsing")?,
+        16,
+    )? == 1;
 
     Ok(HyleOutput {
         version,
@@ -38,13 +60,24 @@ pub fn parse_noir_output(vector: &mut Vec<String>) -> Result<HyleOutput, Error>
     })
 }
 
+fn pop_field(vector: &mut Vec<String>) -> Result<String, Error> {
+    if vector.is_empty() {
+        anyhow::bail!("Incomplete Noir output: missing fields");
+    }
+    Ok(vector.remove(0))
+}
+
 fn parse_string(vector: &mut Vec<String>) -> Result<String, Error> {
-    let length =
-        usize::from_str_radix(vector.remove(0).strip_prefix("0x").context("parsing")?, 16)?;
+    let length = usize::from_str_radix(
+        pop_field(vector)?.strip_prefix("0x").context("parsing")?,
+        16,
+    )?;
     let mut resp = String::with_capacity(length);
     for _ in 0..length {
-        let code =
-            u32::from_str_radix(vector.remove(0).strip_prefix("0x").context("parsing")?, 16)?;
+        let code = u32::from_str_radix(
+            pop_field(vector)?.strip_prefix("0x").context("parsing")?,
+            16,
+        )?;
         let ch = std::char::from_u32(code)
             .ok_or_else(|| anyhow::anyhow!("Invalid char code: {}", code))?;
         resp.push(ch);
@@ -53,19 +86,26 @@ fn parse_string(vector: &mut Vec<String>) -> Result<String, Error> {
 }
 
 fn parse_array(vector: &mut Vec<String>) -> Result<Vec<u8>, Error> {
-    let length =
-        usize::from_str_radix(vector.remove(0).strip_prefix("0x").context("parsing")?, 16)?;
+    let length = usize::from_str_radix(
+        pop_field(vector)?.strip_prefix("0x").context("parsing")?,
+        16,
+    )?;
     let mut resp = Vec::with_capacity(length);
     for _ in 0..length {
-        let num = u8::from_str_radix(vector.remove(0).strip_prefix("0x").context("parsing")?, 16)?;
+        let num = u8::from_str_radix(
+            pop_field(vector)?.strip_prefix("0x").context("parsing")?,
+            16,
+        )?;
         resp.push(num);
     }
     Ok(resp)
 }
 
 fn parse_blobs(vector: &mut Vec<String>) -> Result<Vec<u8>, Error> {
-    let _blob_len =
-        usize::from_str_radix(vector.remove(0).strip_prefix("0x").context("parsing")?, 16)?;
+    let _blob_len = usize::from_str_radix(
+        pop_field(vector)?.strip_prefix("0x").context("parsing")?,
+        16,
+    )?;
     // Arbitrary value that says that blobs field is size for only 10 elements
     let blob_data: Vec<String> = vector.drain(0..10.min(vector.len())).collect();
     let mut blob_data = VecDeque::from(blob_data);
@@ -103,3 +143,59 @@ fn parse_blobs(vector: &mut Vec<String>) -> Result<Vec<u8>, Error> {
 
     Ok(blobs)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(values: &[usize]) -> Vec<String> {
+        values.iter().map(|v| format!("0x{:x}", v)).collect()
+    }
+
+    /// Public outputs of a HyleOutput for identity "bob", with a single blob [1, 2].
+    fn output_fields(index: usize, next_state: u8) -> Vec<String> {
+        let mut values = vec![1]; // version
+        values.extend([4, 0, 0, 0, 1]); // initial_state
+        values.extend([4, 0, 0, 0, next_state as usize]); // next_state
+        values.push(3);
+        values.extend("bob".chars().map(|c| c as usize)); // identity
+        values.push(0); // tx_hash
+        values.push(index);
+        values.extend([2, 1, 2, 1, 2, 0, 0, 0, 0, 0, 0]); // blobs_len, then 10 blob fields
+        values.push(1); // success
+        fields(&values)
+    }
+
+    #[test]
+    fn parse_multiple_outputs() {
+        let mut public_outputs = output_fields(0, 2);
+        public_outputs.extend(output_fields(1, 3));
+
+        let outputs = parse_noir_outputs(&mut public_outputs).unwrap();
+
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0].index, BlobIndex(0));
+        assert_eq!(outputs[0].next_state, StateDigest(vec![0, 0, 0, 2]));
+        assert_eq!(outputs[1].index, BlobIndex(1));
+        assert_eq!(outputs[1].next_state, StateDigest(vec![0, 0, 0, 3]));
+        for output in outputs {
+            assert_eq!(output.identity.0, "bob");
+            assert_eq!(output.blobs, vec![1, 2]);
+            assert!(output.success);
+        }
+    }
+
+    #[test]
+    fn parse_outputs_with_leftover_fields() {
+        let mut public_outputs = output_fields(0, 2);
+        public_outputs.extend(fields(&[1, 4, 0]));
+
+        let err = parse_noir_outputs(&mut public_outputs).unwrap_err();
+
+        assert_eq!(err.to_string(), "Failed to parse HyleOutput #1");
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Incomplete Noir output: missing fields"
+        );
+    }
+}
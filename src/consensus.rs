@@ -19,7 +19,7 @@ use crate::{
 };
 use anyhow::{anyhow, bail, Context, Error, Result};
 use bincode::{Decode, Encode};
-use hyle_model::utils::get_current_timestamp;
+use equivocation::{Equivocation, EquivocationDetector};
 use hyle_model::utils::get_current_timestamp_ms;
 use metrics::ConsensusMetrics;
 use role_follower::{FollowerRole, FollowerState};
@@ -37,6 +37,7 @@ use tokio::{sync::broadcast, time::sleep};
 use tracing::{debug, info, trace, warn};
 
 pub mod api;
+pub mod equivocation;
 pub mod metrics;
 pub mod module;
 pub mod role_follower;
@@ -63,6 +64,8 @@ pub struct CommittedConsensusProposal {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum ConsensusEvent {
     CommitConsensusProposal(CommittedConsensusProposal),
+    /// A validator signed conflicting messages, this is a slashable fault.
+    ValidatorEquivocation(Equivocation),
 }
 
 #[derive(Clone)]
@@ -131,6 +134,8 @@ pub struct ConsensusStore {
     bft_round_state: BFTRoundState,
     /// Validators that asked to be part of consensus
     validator_candidates: Vec<NewValidatorCandidate>,
+    /// Equivocations to slash in the next round we lead
+    equivocations_to_slash: Vec<Equivocation>,
 }
 
 pub struct Consensus {
@@ -138,6 +143,7 @@ pub struct Consensus {
     bus: ConsensusBusClient,
     file: Option<PathBuf>,
     store: ConsensusStore,
+    equivocations: EquivocationDetector,
     #[allow(dead_code)]
     config: SharedConf,
     crypto: SharedBlstCrypto,
@@ -178,6 +184,12 @@ impl Consensus {
             .clone())
     }
 
+    /// Views only increase through timeout certificates and are reset on commit,
+    /// so the current view is the number of rounds since the last commit.
+    fn rounds_since_last_commit(&self) -> u64 {
+        self.bft_round_state.consensus_proposal.view
+    }
+
     /// Reset bft_round_state for the next round of consensus.
     fn finish_round(&mut self, ticket: Option<Ticket>) -> Result<(), Error> {
         match self.bft_round_state.state_tag {
@@ -227,6 +239,12 @@ impl Consensus {
                                 .bond(candidate.pubkey)
                                 .map_err(|e| anyhow::anyhow!(e))?;
                         }
+                        // Applied once the node state handled the block. The evidence is only
+                        // dropped here, a proposal that doesn't commit must not lose it.
+                        ConsensusStakingAction::Slash { validator, .. } => {
+                            self.equivocations_to_slash
+                                .retain(|equivocation| equivocation.validator != validator);
+                        }
                     }
                 }
             }
@@ -245,6 +263,16 @@ impl Consensus {
             self.bft_round_state.consensus_proposal.view
         );
 
+        let rounds_since_last_commit = self.rounds_since_last_commit();
+        self.metrics
+            .rounds_since_last_commit(rounds_since_last_commit);
+        if rounds_since_last_commit >= TimeoutState::STALLED_ROUNDS_WARNING {
+            warn!(
+                "🐌 No commit for {} rounds, consensus is stalled at slot {}",
+                rounds_since_last_commit, self.bft_round_state.consensus_proposal.slot
+            );
+        }
+
         self.bft_round_state.consensus_proposal.round_leader = self.next_leader()?;
 
         if self.bft_round_state.consensus_proposal.round_leader == *self.crypto.validator_pubkey() {
@@ -252,15 +280,20 @@ impl Consensus {
             debug!("👑 I'm the new leader! 👑")
         } else {
             self.bft_round_state.state_tag = StateTag::Follower;
-            self.bft_round_state
-                .timeout
-                .state
-                .schedule_next(get_current_timestamp());
+            self.schedule_round_timeout();
         }
 
         Ok(())
     }
 
+    /// Followers time out if the current round does not commit within `round_timeout`.
+    fn schedule_round_timeout(&mut self) {
+        self.bft_round_state.timeout.state.schedule_next(
+            get_current_timestamp_ms(),
+            self.config.consensus.round_timeout,
+        );
+    }
+
     /// Verify that quorum certificate includes only validators that are part of the consensus
     fn verify_quorum_signers_part_of_consensus(
         &self,
@@ -281,11 +314,42 @@ impl Consensus {
                 ConsensusStakingAction::Bond { candidate } => {
                     self.verify_new_validators_to_bond(candidate)?;
                 }
+                ConsensusStakingAction::Slash {
+                    validator,
+                    amount,
+                    first,
+                    second,
+                } => {
+                    self.verify_slash(validator, *amount, first, second)?;
+                }
             }
         }
         Ok(())
     }
 
+    /// Verify that the evidence proves the validator equivocated, and that all of its stake is slashed.
+    fn verify_slash(
+        &self,
+        validator: &ValidatorPublicKey,
+        amount: u128,
+        first: &SignedByValidator<ConsensusNetMessage>,
+        second: &SignedByValidator<ConsensusNetMessage>,
+    ) -> Result<()> {
+        if !BlstCrypto::verify(first)? || !BlstCrypto::verify(second)? {
+            bail!("Slashing evidence has an invalid signature");
+        }
+        if !equivocation::proves_equivocation(validator, first, second) {
+            bail!(
+                "Slashing evidence does not prove that {} equivocated",
+                validator
+            );
+        }
+        if self.bft_round_state.staking.get_stake(validator) != Some(amount) {
+            bail!("Slashed amount is not the stake of {}", validator);
+        }
+        Ok(())
+    }
+
     /// Verify that new validators have enough stake
     /// and have a valid signature so can be bonded.
     fn verify_new_validators_to_bond(
@@ -475,6 +539,37 @@ impl Consensus {
             bail!("Invalid signature for message {:?}", &msg);
         }
 
+        if self.equivocations.is_equivocating(&msg.signature.validator) {
+            bail!(
+                "Ignoring message from equivocating validator {}",
+                msg.signature.validator
+            );
+        }
+        self.equivocations
+            .prune(self.bft_round_state.consensus_proposal.slot);
+        // Our own proposal isn't received as a Prepare when we're the leader
+        self.equivocations
+            .note_proposal(&self.bft_round_state.consensus_proposal);
+        if let Some(equivocation) = self.equivocations.record(&msg) {
+            self.metrics.equivocation();
+            warn!(
+                "🚨 Validator {} signed conflicting messages for slot {} view {}",
+                equivocation.validator, equivocation.slot, equivocation.view
+            );
+            let validator = equivocation.validator.clone();
+            if !self
+                .equivocations_to_slash
+                .iter()
+                .any(|pending| pending.validator == validator)
+            {
+                self.equivocations_to_slash.push(equivocation.clone());
+            }
+            self.bus
+                .send(ConsensusEvent::ValidatorEquivocation(equivocation))
+                .context("Sending ValidatorEquivocation event")?;
+            bail!("Validator {} equivocated", validator);
+        }
+
         // TODO: reduce cloning here.
         let SignedByValidator::<ConsensusNetMessage> {
             msg: net_message,
@@ -724,6 +819,35 @@ impl Consensus {
                                 .map_err(|e| anyhow!(e))?;
                         }
                         (_identity, StakingAction::Distribute { claim: _ }) => todo!(),
+                        (_identity, StakingAction::Slash { validator, amount }) => {
+                            warn!(
+                                "🔪 Slashing {} from equivocating validator {}",
+                                amount, validator
+                            );
+                            self.store
+                                .bft_round_state
+                                .staking
+                                .slash(&validator, amount)
+                                .map_err(|e| anyhow!(e))?;
+                        }
+                        (
+                            _identity,
+                            StakingAction::RotateKey {
+                                old_pubkey,
+                                new_pubkey,
+                                nonce,
+                                ..
+                            },
+                        ) => {
+                            if &old_pubkey == self.crypto.validator_pubkey() {
+                                warn!("🔑 Our validator key was rotated to {}", new_pubkey);
+                            }
+                            self.store
+                                .bft_round_state
+                                .staking
+                                .rotate_key(&old_pubkey, new_pubkey, nonce)
+                                .map_err(|e| anyhow!(e))?;
+                        }
                     }
                 }
                 for validator in block.new_bounded_validators.iter() {
@@ -753,7 +877,9 @@ impl Consensus {
     async fn handle_command(&mut self, msg: ConsensusCommand) -> Result<()> {
         match msg {
             ConsensusCommand::TimeoutTick => match &self.bft_round_state.timeout.state {
-                TimeoutState::Scheduled { timestamp } if get_current_timestamp() >= *timestamp => {
+                TimeoutState::Scheduled { timestamp }
+                    if get_current_timestamp_ms() >= *timestamp =>
+                {
                     // Trigger state transition to mutiny
                     info!(
                         "⏰ Trigger timeout for slot {} and view {}",
@@ -761,24 +887,27 @@ impl Consensus {
                         self.bft_round_state.consensus_proposal.view
                     );
 
-                    let timeout_message = ConsensusNetMessage::Timeout(
+                    let (slot, view) = (
                         self.bft_round_state.consensus_proposal.slot,
                         self.bft_round_state.consensus_proposal.view,
                     );
+                    let timeout_message = ConsensusNetMessage::Timeout(slot, view);
 
                     let signed_timeout_message = self
                         .sign_net_message(timeout_message.clone())
                         .context("Signing timeout message")?;
 
-                    self.on_timeout(
-                        signed_timeout_message,
-                        self.bft_round_state.consensus_proposal.slot,
-                        self.bft_round_state.consensus_proposal.view,
-                    )?;
+                    self.on_timeout(signed_timeout_message, slot, view)?;
 
                     self.broadcast_net_message(timeout_message)?;
 
-                    self.bft_round_state.timeout.state.cancel();
+                    // If the timeout got us to the next round, its timeout was already scheduled.
+                    // Otherwise keep retrying until a timeout certificate is formed.
+                    if slot == self.bft_round_state.consensus_proposal.slot
+                        && view == self.bft_round_state.consensus_proposal.view
+                    {
+                        self.schedule_round_timeout();
+                    }
 
                     Ok(())
                 }
@@ -839,6 +968,7 @@ impl Consensus {
                         } else {
                             self.bft_round_state.state_tag = StateTag::Follower;
                             self.bft_round_state.consensus_proposal.slot = 1;
+                            self.schedule_round_timeout();
                             info!(
                                 "💂‍♂️ Starting consensus as follower of leader {}",
                                 self.bft_round_state.consensus_proposal.round_leader
@@ -1023,6 +1153,7 @@ pub mod test {
             let store = ConsensusStore::default();
             let mut conf = Conf::default();
             conf.consensus.slot_duration = 1000;
+            conf.consensus.round_timeout = 5000;
             let bus = ConsensusBusClient::new_from_bus(shared_bus.new_handle()).await;
 
             Consensus {
@@ -1030,6 +1161,7 @@ pub mod test {
                 bus,
                 file: None,
                 store,
+                equivocations: EquivocationDetector::default(),
                 config: Arc::new(conf),
                 crypto: Arc::new(crypto),
             }
@@ -1063,8 +1195,18 @@ pub mod test {
         }
 
         pub fn setup_node(&mut self, index: usize, cryptos: &[BlstCrypto]) {
-            for other_crypto in cryptos.iter() {
-                self.add_trusted_validator(other_crypto.validator_pubkey());
+            self.setup_node_with_stakes(index, cryptos, &vec![100; cryptos.len()]);
+        }
+
+        /// Validators are staked with the amount at their index.
+        pub fn setup_node_with_stakes(
+            &mut self,
+            index: usize,
+            cryptos: &[BlstCrypto],
+            stakes: &[u128],
+        ) {
+            for (other_crypto, stake) in std::iter::zip(cryptos, stakes) {
+                self.add_trusted_validator_with_stake(other_crypto.validator_pubkey(), *stake);
             }
 
             self.consensus.bft_round_state.consensus_proposal.slot = 1;
@@ -1102,13 +1244,27 @@ pub mod test {
             self.consensus.bft_round_state.staking.clone()
         }
 
+        pub fn set_round_timeout(&mut self, round_timeout: u64) {
+            let mut conf = (*self.consensus.config).clone();
+            conf.consensus.round_timeout = round_timeout;
+            self.consensus.config = Arc::new(conf);
+        }
+
+        /// Only times out if the scheduled round timeout is reached.
+        pub async fn timeout_tick(&mut self) {
+            self.consensus
+                .handle_command(ConsensusCommand::TimeoutTick)
+                .await
+                .unwrap_or_else(|err| panic!("Timeout tick for node {}: {:?}", self.name, err));
+        }
+
         pub async fn timeout(nodes: &mut [&mut ConsensusTestCtx]) {
             for n in nodes {
                 n.consensus
                     .bft_round_state
                     .timeout
                     .state
-                    .schedule_next(get_current_timestamp() - 10);
+                    .schedule_next(get_current_timestamp_ms() - 10, 0);
                 n.consensus
                     .handle_command(ConsensusCommand::TimeoutTick)
                     .await
@@ -1117,10 +1273,18 @@ pub mod test {
         }
 
         pub fn add_trusted_validator(&mut self, pubkey: &ValidatorPublicKey) {
+            self.add_trusted_validator_with_stake(pubkey, 100);
+        }
+
+        pub fn add_trusted_validator_with_stake(
+            &mut self,
+            pubkey: &ValidatorPublicKey,
+            stake: u128,
+        ) {
             self.consensus
                 .bft_round_state
                 .staking
-                .stake(hex::encode(pubkey.0.clone()).into(), 100)
+                .stake(hex::encode(pubkey.0.clone()).into(), stake)
                 .unwrap();
 
             self.consensus
@@ -1201,6 +1365,15 @@ pub mod test {
             self.consensus.handle_net_message(msg.clone()).expect(err);
         }
 
+        pub(crate) fn sign(
+            &self,
+            msg: ConsensusNetMessage,
+        ) -> SignedByValidator<ConsensusNetMessage> {
+            self.consensus
+                .sign_net_message(msg)
+                .expect("Error while signing")
+        }
+
         #[track_caller]
         pub(crate) fn handle_msg_err(
             &mut self,
@@ -1554,6 +1727,61 @@ pub mod test {
         );
     }
 
+    #[test_log::test(tokio::test)]
+    async fn prepare_cut_with_duplicated_validator() {
+        let (mut node1, mut node2, mut node3, mut node4): (
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+        ) = build_nodes!(4).await;
+
+        node1.start_round().await;
+
+        // Create prepare with two data proposals for the same validator
+        let prepare_msg = node1
+            .consensus
+            .sign_net_message(ConsensusNetMessage::Prepare(
+                ConsensusProposal {
+                    slot: 1,
+                    view: 0,
+                    round_leader: node1.pubkey(),
+                    timestamp: 123,
+                    cut: vec![
+                        (
+                            node2.pubkey(),
+                            DataProposalHash("test".to_string()),
+                            LaneBytesSize::default(),
+                            AggregateSignature::default(),
+                        ),
+                        (
+                            node2.pubkey(),
+                            DataProposalHash("test2".to_string()),
+                            LaneBytesSize::default(),
+                            AggregateSignature::default(),
+                        ),
+                    ],
+                    staking_actions: vec![],
+                    parent_hash: ConsensusProposalHash("hash".into()),
+                },
+                Ticket::Genesis,
+            ))
+            .expect("Error while signing");
+
+        assert_contains!(
+            node2.handle_msg_err(&prepare_msg).to_string(),
+            "has more than one DataProposal in cut"
+        );
+        assert_contains!(
+            node3.handle_msg_err(&prepare_msg).to_string(),
+            "has more than one DataProposal in cut"
+        );
+        assert_contains!(
+            node4.handle_msg_err(&prepare_msg).to_string(),
+            "has more than one DataProposal in cut"
+        );
+    }
+
     #[test_log::test(tokio::test)]
     async fn prepare_wrong_leader() {
         let (mut node1, mut node2, mut node3, mut node4): (
@@ -1608,6 +1836,53 @@ pub mod test {
         );
     }
 
+    #[test_log::test(tokio::test)]
+    async fn prepare_equivocation_is_detected() {
+        let (mut node1, mut node2): (ConsensusTestCtx, ConsensusTestCtx) = build_nodes!(2).await;
+
+        node1.start_round().await;
+
+        let prepare = broadcast! {
+            description: "Prepare",
+            from: node1, to: [node2],
+            message_matches: ConsensusNetMessage::Prepare(_, _)
+        };
+        let ConsensusNetMessage::Prepare(cp, ticket) = prepare.msg.clone() else {
+            panic!("Expected a Prepare");
+        };
+
+        // Same slot and view, different proposal
+        let conflicting = node1
+            .consensus
+            .sign_net_message(ConsensusNetMessage::Prepare(
+                ConsensusProposal {
+                    timestamp: cp.timestamp + 1,
+                    ..cp
+                },
+                ticket,
+            ))
+            .expect("Error while signing");
+
+        assert_contains!(
+            node2.handle_msg_err(&conflicting).to_string(),
+            "equivocated"
+        );
+        match node2._event_receiver.try_recv() {
+            Ok(ConsensusEvent::ValidatorEquivocation(equivocation)) => {
+                assert_eq!(equivocation.validator, node1.pubkey());
+                assert_eq!(equivocation.first, prepare);
+                assert_eq!(equivocation.second, conflicting);
+            }
+            other => panic!("Expected an equivocation event, got {:?}", other),
+        }
+
+        // Messages from the equivocating validator are now ignored
+        assert_contains!(
+            node2.handle_msg_err(&prepare).to_string(),
+            "equivocating validator"
+        );
+    }
+
     #[test_log::test(tokio::test)]
     async fn prepare_wrong_timestamp_too_old() {
         let (mut node1, mut node2, mut node3, mut node4): (
@@ -1712,6 +1987,240 @@ pub mod test {
         );
     }
 
+    #[test_log::test(tokio::test)]
+    async fn quorum_is_weighted_by_stake() {
+        let cryptos: Vec<BlstCrypto> = AutobahnTestCtx::generate_cryptos(4);
+        let stakes = [100, 100, 100, 1];
+        let mut nodes = vec![];
+        for i in 0..4 {
+            let mut node = ConsensusTestCtx::new(
+                format!("node-{i}").as_ref(),
+                cryptos.get(i).unwrap().clone(),
+            )
+            .await;
+            node.setup_node_with_stakes(i, &cryptos, &stakes);
+            nodes.push(node);
+        }
+        let (mut node1, mut node2, mut node3, mut node4) = build_tuple!(nodes.remove(0), 4);
+
+        node1.start_round().await;
+
+        broadcast! {
+            description: "Leader - Prepare",
+            from: node1, to: [node2, node3, node4],
+            message_matches: ConsensusNetMessage::Prepare(..)
+        };
+
+        // 100 + 100 + 1 out of 301 is not a quorum, even with 3 validators out of 4
+        send! {
+            description: "Follower - PrepareVote",
+            from: [node2, node4], to: node1,
+            message_matches: ConsensusNetMessage::PrepareVote(_)
+        };
+        node1.assert_no_broadcast("Confirm without a quorum");
+
+        send! {
+            description: "Follower - PrepareVote",
+            from: [node3], to: node1,
+            message_matches: ConsensusNetMessage::PrepareVote(_)
+        };
+
+        // The three large holders commit without the tiny one
+        broadcast! {
+            description: "Leader - Confirm",
+            from: node1, to: [node2, node3],
+            message_matches: ConsensusNetMessage::Confirm(_)
+        };
+        send! {
+            description: "Follower - Confirm Ack",
+            from: [node2, node3], to: node1,
+            message_matches: ConsensusNetMessage::ConfirmAck(_)
+        };
+        broadcast! {
+            description: "Leader - Commit",
+            from: node1, to: [node2, node3],
+            message_matches: ConsensusNetMessage::Commit(_, _)
+        };
+
+        assert_eq!(node2.consensus.bft_round_state.consensus_proposal.slot, 2);
+        assert_eq!(node3.consensus.bft_round_state.consensus_proposal.slot, 2);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn equivocating_leader_is_slashed() {
+        let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4).await;
+
+        node1.start_round().await;
+        let prepare = broadcast! {
+            description: "Leader - Prepare",
+            from: node1, to: [node2, node3, node4],
+            message_matches: ConsensusNetMessage::Prepare(..)
+        };
+        let ConsensusNetMessage::Prepare(cp, ticket) = prepare.msg.clone() else {
+            panic!("Expected a Prepare");
+        };
+        let conflicting_prepare = node1.sign(ConsensusNetMessage::Prepare(
+            ConsensusProposal {
+                timestamp: cp.timestamp + 1,
+                ..cp.clone()
+            },
+            ticket,
+        ));
+        node2.handle_msg_err(&conflicting_prepare);
+        assert_eq!(node2.consensus.equivocations_to_slash.len(), 1);
+
+        let stake = node2
+            .consensus
+            .bft_round_state
+            .staking
+            .get_stake(&node1.pubkey())
+            .unwrap();
+        let total_bond = node2.consensus.bft_round_state.staking.total_bond();
+
+        // Evidence has to be two conflicting Prepares signed by the slashed validator
+        assert!(node2
+            .consensus
+            .verify_slash(&node1.pubkey(), stake, &prepare, &prepare)
+            .is_err());
+        assert!(node2
+            .consensus
+            .verify_slash(&node3.pubkey(), stake, &prepare, &conflicting_prepare)
+            .is_err());
+        assert!(node2
+            .consensus
+            .verify_slash(&node1.pubkey(), 1, &prepare, &conflicting_prepare)
+            .is_err());
+        node2
+            .consensus
+            .verify_slash(&node1.pubkey(), stake, &prepare, &conflicting_prepare)
+            .expect("Valid slashing evidence");
+        // Kept until a proposal slashing the validator commits
+        assert_eq!(node2.consensus.equivocations_to_slash.len(), 1);
+
+        node2
+            .consensus
+            .bft_round_state
+            .consensus_proposal
+            .staking_actions = vec![ConsensusStakingAction::Slash {
+            validator: node1.pubkey(),
+            amount: stake,
+            first: prepare.clone(),
+            second: conflicting_prepare.clone(),
+        }];
+        node2
+            .consensus
+            .finish_round(Some(Ticket::TimeoutQC(QuorumCertificate::default())))
+            .expect("Finishing round");
+        assert_eq!(node2.consensus.equivocations_to_slash.len(), 1);
+
+        node2
+            .consensus
+            .bft_round_state
+            .consensus_proposal
+            .staking_actions = vec![ConsensusStakingAction::Slash {
+            validator: node1.pubkey(),
+            amount: stake,
+            first: prepare.clone(),
+            second: conflicting_prepare.clone(),
+        }];
+        node2
+            .consensus
+            .finish_round(Some(Ticket::CommitQC(QuorumCertificate::default())))
+            .expect("Finishing round");
+        assert!(node2.consensus.equivocations_to_slash.is_empty());
+
+        node2
+            .handle_node_state_event(NodeStateEvent::NewBlock(Box::new(Block {
+                staking_actions: vec![(
+                    "consensus".into(),
+                    StakingAction::Slash {
+                        validator: node1.pubkey(),
+                        amount: stake,
+                    },
+                )],
+                ..Default::default()
+            })))
+            .await
+            .expect("Slashing");
+
+        let staking = &node2.consensus.bft_round_state.staking;
+        assert!(!staking.is_bonded(&node1.pubkey()));
+        assert_eq!(staking.get_stake(&node1.pubkey()), Some(0));
+        assert_eq!(staking.total_bond(), total_bond - stake);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn rotated_key_has_to_sign_votes() {
+        let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4).await;
+
+        node1.start_round().await;
+        let prepare = broadcast! {
+            description: "Leader - Prepare",
+            from: node1, to: [node2, node3, node4],
+            message_matches: ConsensusNetMessage::Prepare(..)
+        };
+        let ConsensusNetMessage::Prepare(cp, _) = prepare.msg.clone() else {
+            panic!("Expected a Prepare");
+        };
+
+        let new_crypto = BlstCrypto::new("node-4-rotated".to_string()).unwrap();
+        let old_pubkey = node4.pubkey();
+        let new_pubkey = new_crypto.validator_pubkey().clone();
+        let nonce = node1.consensus.bft_round_state.staking.key_rotations();
+        let proof = node4
+            .consensus
+            .crypto
+            .sign(KeyRotationMessage::new(
+                old_pubkey.clone(),
+                new_pubkey.clone(),
+                nonce,
+            ))
+            .unwrap()
+            .signature
+            .signature
+            .0;
+        let stake = node1
+            .consensus
+            .bft_round_state
+            .staking
+            .get_stake(&old_pubkey)
+            .unwrap();
+        let total_bond = node1.consensus.bft_round_state.staking.total_bond();
+
+        node1
+            .handle_node_state_event(NodeStateEvent::NewBlock(Box::new(Block {
+                staking_actions: vec![(
+                    node4.name.clone().into(),
+                    StakingAction::RotateKey {
+                        old_pubkey: old_pubkey.clone(),
+                        new_pubkey: new_pubkey.clone(),
+                        nonce,
+                        proof,
+                    },
+                )],
+                ..Default::default()
+            })))
+            .await
+            .expect("Rotating key");
+
+        let staking = &node1.consensus.bft_round_state.staking;
+        assert!(!staking.is_bonded(&old_pubkey));
+        assert!(staking.is_bonded(&new_pubkey));
+        assert_eq!(staking.get_stake(&old_pubkey), None);
+        assert_eq!(staking.get_stake(&new_pubkey), Some(stake));
+        assert_eq!(staking.total_bond(), total_bond);
+
+        let old_vote = node4.sign(ConsensusNetMessage::PrepareVote(cp.hash()));
+        assert_contains!(
+            node1.handle_msg_err(&old_vote).to_string(),
+            "not a bonded validator"
+        );
+        let new_vote = new_crypto
+            .sign(ConsensusNetMessage::PrepareVote(cp.hash()))
+            .unwrap();
+        node1.handle_msg(&new_vote, "Vote signed by the rotated key");
+    }
+
     #[test_log::test(tokio::test)]
     async fn timeout_only_one_4() {
         let (mut node1, mut node2, mut node3, mut node4): (
@@ -1968,6 +2477,64 @@ pub mod test {
         assert_eq!(cp.parent_hash, ConsensusProposalHash("genesis".into()));
     }
 
+    #[test_log::test(tokio::test)]
+    async fn test_silent_leader_view_change_recovers() {
+        let (mut node1, mut node2, mut node3, mut node4): (
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+        ) = build_nodes!(4).await;
+
+        node1.start_round().await;
+        // Slot 1 - leader = node1, which then goes silent
+        node1.assert_broadcast("Lost prepare");
+
+        ConsensusTestCtx::timeout(&mut [&mut node3, &mut node4]).await;
+
+        // Without a timeout certificate, the timeout keeps being retried
+        for node in [&node3, &node4] {
+            assert!(matches!(
+                node.consensus.bft_round_state.timeout.state,
+                TimeoutState::Scheduled { .. }
+            ));
+            assert_eq!(node.consensus.rounds_since_last_commit(), 0);
+        }
+
+        broadcast! {
+            description: "Follower - Timeout",
+            from: node3, to: [node2],
+            message_matches: ConsensusNetMessage::Timeout(..)
+        };
+        broadcast! {
+            description: "Follower - Timeout",
+            from: node4, to: [node2],
+            message_matches: ConsensusNetMessage::Timeout(..)
+        };
+
+        // node2 joins the mutiny, builds the timeout certificate and becomes leader of view 1
+        node2.assert_broadcast("Timeout Message 2");
+        assert!(node2.consensus.is_round_leader());
+        assert_eq!(node2.consensus.rounds_since_last_commit(), 1);
+
+        node2.start_round().await;
+
+        let (cp, ticket) = simple_commit_round! {
+          leader: node2,
+          followers: [node3, node4]
+        };
+
+        assert!(matches!(ticket, Ticket::TimeoutQC(_)));
+        assert_eq!(cp.slot, 1);
+        assert_eq!(cp.view, 1);
+        assert_eq!(cp.round_leader, node2.pubkey());
+
+        // The commit resets the counter
+        for node in [&node2, &node3, &node4] {
+            assert_eq!(node.consensus.rounds_since_last_commit(), 0);
+        }
+    }
+
     #[test_log::test(tokio::test)]
     async fn timeout_only_emit_certificate_once() {
         let (mut node1, mut node2, mut node3, mut node4, mut node5): (
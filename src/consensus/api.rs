@@ -1,6 +1,6 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, Result};
 use axum::{debug_handler, extract::State, http::StatusCode, response::IntoResponse, Json, Router};
-use hyle_model::api::APIStaking;
+use hyle_model::api::{APIGenesis, APIStaking};
 use staking::state::Staking;
 use tracing::error;
 use utoipa::OpenApi;
@@ -12,6 +12,7 @@ use crate::{
         command_response::{CmdRespClient, Query},
         metrics::BusMetrics,
     },
+    genesis::{genesis_contracts, genesis_hash},
     model::{CommonRunContext, ConsensusInfo},
     rest::AppError,
 };
@@ -27,26 +28,39 @@ struct RestBusClient {
 
 pub struct RouterState {
     bus: RestBusClient,
+    genesis: APIGenesis,
 }
 
 #[derive(OpenApi)]
 struct ConsensusAPI;
 
-pub async fn api(ctx: &CommonRunContext) -> Router<()> {
+pub async fn api(ctx: &CommonRunContext) -> Result<Router<()>> {
+    let contracts = genesis_contracts(&ctx.config)?;
     let state = RouterState {
         bus: RestBusClient::new_from_bus(ctx.bus.new_handle()).await,
+        genesis: APIGenesis {
+            stakers: ctx
+                .config
+                .consensus
+                .genesis_stakers
+                .clone()
+                .into_iter()
+                .collect(),
+            genesis_hash: genesis_hash(&ctx.config.consensus.genesis_stakers, &contracts)?,
+        },
     };
 
     let (router, api) = OpenApiRouter::with_openapi(ConsensusAPI::openapi())
         .routes(routes!(get_consensus_state))
         .routes(routes!(get_consensus_staking_state))
+        .routes(routes!(get_consensus_genesis))
         .split_for_parts();
 
     if let Ok(mut o) = ctx.openapi.lock() {
         *o = o.clone().nest("/v1", api);
     }
 
-    router.with_state(state)
+    Ok(router.with_state(state))
 }
 
 #[utoipa::path(
@@ -102,6 +116,19 @@ pub async fn get_consensus_staking_state(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/genesis",
+    tag = "Consensus",
+    responses(
+        (status = OK, body = APIGenesis)
+    )
+)]
+#[debug_handler]
+pub async fn get_consensus_genesis(State(state): State<RouterState>) -> Json<APIGenesis> {
+    Json(state.genesis)
+}
+
 impl Clone for RouterState {
     fn clone(&self) -> Self {
         use crate::utils::static_type_map::Pick;
@@ -116,7 +143,8 @@ impl Clone for RouterState {
                     &self.bus,
                 )
                 .clone(),
-            )
+            ),
+            genesis: self.genesis.clone(),
         }
     }
 }
@@ -0,0 +1,218 @@
+//! Detection of validators signing conflicting consensus messages for the same round.
+
+use std::collections::{HashMap, HashSet};
+
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::model::{
+    ConsensusNetMessage, ConsensusProposal, ConsensusProposalHash, Hashable, SignedByValidator,
+    Slot, ValidatorPublicKey, View,
+};
+
+/// Proof that a validator signed two conflicting messages of the same kind for the same round.
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode, PartialEq, Eq)]
+pub struct Equivocation {
+    pub validator: ValidatorPublicKey,
+    pub slot: Slot,
+    pub view: View,
+    /// The message received first
+    pub first: SignedByValidator<ConsensusNetMessage>,
+    /// The message conflicting with it
+    pub second: SignedByValidator<ConsensusNetMessage>,
+}
+
+/// Whether the two messages alone prove that the validator equivocated, for other validators
+/// to check slashing evidence. Only conflicting Prepares do: votes carry the hash of their
+/// proposal, so they can't be attributed to a round without having seen the proposals.
+/// Signatures are not checked here.
+pub fn proves_equivocation(
+    validator: &ValidatorPublicKey,
+    first: &SignedByValidator<ConsensusNetMessage>,
+    second: &SignedByValidator<ConsensusNetMessage>,
+) -> bool {
+    if &first.signature.validator != validator || &second.signature.validator != validator {
+        return false;
+    }
+    match (&first.msg, &second.msg) {
+        (ConsensusNetMessage::Prepare(a, _), ConsensusNetMessage::Prepare(b, _)) => {
+            a.slot == b.slot && a.view == b.view && a.hash() != b.hash()
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum MessageKind {
+    Prepare,
+    PrepareVote,
+    ConfirmAck,
+}
+
+type RoundKey = (ValidatorPublicKey, Slot, View, MessageKind);
+type SignedRoundMessage = (
+    ConsensusProposalHash,
+    SignedByValidator<ConsensusNetMessage>,
+);
+
+#[derive(Default)]
+pub struct EquivocationDetector {
+    /// Round of the proposals we know of. Votes only carry the hash of their proposal,
+    /// so a vote for an unknown proposal can't be attributed to a round.
+    proposals: HashMap<ConsensusProposalHash, (Slot, View)>,
+    signed: HashMap<RoundKey, SignedRoundMessage>,
+    equivocating: HashSet<ValidatorPublicKey>,
+}
+
+impl EquivocationDetector {
+    pub fn note_proposal(&mut self, consensus_proposal: &ConsensusProposal) {
+        self.proposals.insert(
+            consensus_proposal.hash(),
+            (consensus_proposal.slot, consensus_proposal.view),
+        );
+    }
+
+    /// Records a signed message, and returns the equivocation if its validator
+    /// already signed a different message of the same kind for the same round.
+    pub fn record(&mut self, msg: &SignedByValidator<ConsensusNetMessage>) -> Option<Equivocation> {
+        let (kind, proposal_hash) = match &msg.msg {
+            ConsensusNetMessage::Prepare(consensus_proposal, _) => {
+                self.note_proposal(consensus_proposal);
+                (MessageKind::Prepare, consensus_proposal.hash())
+            }
+            ConsensusNetMessage::PrepareVote(hash) => (MessageKind::PrepareVote, hash.clone()),
+            ConsensusNetMessage::ConfirmAck(hash) => (MessageKind::ConfirmAck, hash.clone()),
+            _ => return None,
+        };
+        let (slot, view) = *self.proposals.get(&proposal_hash)?;
+        let validator = msg.signature.validator.clone();
+
+        let key = (validator.clone(), slot, view, kind);
+        match self.signed.get(&key) {
+            Some((signed_hash, _)) if signed_hash == &proposal_hash => None,
+            Some((_, first)) => {
+                self.equivocating.insert(validator.clone());
+                Some(Equivocation {
+                    validator,
+                    slot,
+                    view,
+                    first: first.clone(),
+                    second: msg.clone(),
+                })
+            }
+            None => {
+                self.signed.insert(key, (proposal_hash, msg.clone()));
+                None
+            }
+        }
+    }
+
+    /// Whether this validator was caught equivocating. Its messages are then ignored,
+    /// which excludes it from quorums.
+    pub fn is_equivocating(&self, validator: &ValidatorPublicKey) -> bool {
+        self.equivocating.contains(validator)
+    }
+
+    /// Forgets the messages of the rounds before the previous slot.
+    pub fn prune(&mut self, current_slot: Slot) {
+        let oldest = current_slot.saturating_sub(1);
+        self.proposals.retain(|_, (slot, _)| *slot >= oldest);
+        self.signed.retain(|(_, slot, _, _), _| *slot >= oldest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::crypto::BlstCrypto;
+
+    fn proposal(slot: Slot, timestamp: u64) -> ConsensusProposal {
+        ConsensusProposal {
+            slot,
+            view: 0,
+            timestamp,
+            ..ConsensusProposal::default()
+        }
+    }
+
+    #[test]
+    fn test_conflicting_votes_are_detected() {
+        let voter = BlstCrypto::new("voter".into()).unwrap();
+        let mut detector = EquivocationDetector::default();
+        let cp_a = proposal(3, 1);
+        let cp_b = proposal(3, 2);
+        detector.note_proposal(&cp_a);
+        detector.note_proposal(&cp_b);
+
+        let vote_a = voter
+            .sign(ConsensusNetMessage::PrepareVote(cp_a.hash()))
+            .unwrap();
+        let vote_b = voter
+            .sign(ConsensusNetMessage::PrepareVote(cp_b.hash()))
+            .unwrap();
+
+        assert_eq!(detector.record(&vote_a), None);
+        // Receiving the same vote again is fine
+        assert_eq!(detector.record(&vote_a), None);
+        // A confirm ack isn't compared to prepare votes
+        let ack_b = voter
+            .sign(ConsensusNetMessage::ConfirmAck(cp_b.hash()))
+            .unwrap();
+        assert_eq!(detector.record(&ack_b), None);
+        assert!(!detector.is_equivocating(voter.validator_pubkey()));
+
+        assert_eq!(
+            detector.record(&vote_b),
+            Some(Equivocation {
+                validator: voter.validator_pubkey().clone(),
+                slot: 3,
+                view: 0,
+                first: vote_a,
+                second: vote_b,
+            })
+        );
+        assert!(detector.is_equivocating(voter.validator_pubkey()));
+    }
+
+    #[test]
+    fn test_votes_of_other_rounds_are_not_conflicting() {
+        let voter = BlstCrypto::new("voter".into()).unwrap();
+        let mut detector = EquivocationDetector::default();
+        let cp_a = proposal(3, 1);
+        let cp_b = proposal(4, 1);
+        let unknown = proposal(3, 5);
+        detector.note_proposal(&cp_a);
+        detector.note_proposal(&cp_b);
+
+        for cp in [&cp_a, &cp_b, &unknown] {
+            let vote = voter
+                .sign(ConsensusNetMessage::PrepareVote(cp.hash()))
+                .unwrap();
+            assert_eq!(detector.record(&vote), None);
+        }
+        assert!(!detector.is_equivocating(voter.validator_pubkey()));
+    }
+
+    #[test]
+    fn test_conflicting_prepares_are_detected() {
+        let leader = BlstCrypto::new("leader".into()).unwrap();
+        let mut detector = EquivocationDetector::default();
+
+        let prepare_a = leader
+            .sign(ConsensusNetMessage::Prepare(
+                proposal(3, 1),
+                crate::model::Ticket::Genesis,
+            ))
+            .unwrap();
+        let prepare_b = leader
+            .sign(ConsensusNetMessage::Prepare(
+                proposal(3, 2),
+                crate::model::Ticket::Genesis,
+            ))
+            .unwrap();
+
+        assert_eq!(detector.record(&prepare_a), None);
+        assert!(detector.record(&prepare_b).is_some());
+        assert!(detector.is_equivocating(leader.validator_pubkey()));
+    }
+}
@@ -21,6 +21,8 @@ pub struct ConsensusMetrics {
     confirmed_ack_gauge: Gauge<u64>,
     prepare_votes_gauge: Gauge<u64>,
     prepare_votes_aggregation: Counter<u64>,
+    rounds_since_last_commit: Gauge<u64>,
+    equivocation: Counter<u64>,
 }
 
 impl ConsensusMetrics {
@@ -48,6 +50,8 @@ impl ConsensusMetrics {
             confirmed_ack_gauge: my_meter.u64_gauge("confirmed_ack_gauge").build(),
             prepare_votes_gauge: my_meter.u64_gauge("prepare_votes_gauge").build(),
             prepare_votes_aggregation: my_meter.u64_counter("prepare_votes_aggregation").build(),
+            rounds_since_last_commit: my_meter.u64_gauge("rounds_since_last_commit").build(),
+            equivocation: my_meter.u64_counter("equivocation").build(),
         }
     }
 
@@ -105,10 +109,18 @@ impl ConsensusMetrics {
         self.prepare_votes_gauge.record(nb, &[])
     }
 
+    pub fn rounds_since_last_commit(&self, nb: u64) {
+        self.rounds_since_last_commit.record(nb, &[])
+    }
+
     pub fn commit(&self) {
         self.commit.add(1, &[]);
     }
     pub fn commit_error(&self, kind: &'static str) {
         self.commit_error.add(1, &[KeyValue::new("kind", kind)]);
     }
+
+    pub fn equivocation(&self) {
+        self.equivocation.add(1, &[]);
+    }
 }
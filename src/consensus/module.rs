@@ -17,11 +17,11 @@ impl Module for Consensus {
             .data_directory
             .clone()
             .join("consensus.bin");
-        let store: ConsensusStore = Self::load_from_disk_or_default(file.as_path());
+        let store: ConsensusStore = Self::load_from_disk_or_default(file.as_path())?;
         let metrics = ConsensusMetrics::global(ctx.common.config.id.clone());
         let bus = ConsensusBusClient::new_from_bus(ctx.common.bus.new_handle()).await;
 
-        let api = api::api(&ctx.common).await;
+        let api = api::api(&ctx.common).await?;
         if let Ok(mut guard) = ctx.common.router.lock() {
             if let Some(router) = guard.take() {
                 guard.replace(router.nest("/v1/consensus", api));
@@ -33,6 +33,7 @@ impl Module for Consensus {
             bus,
             file: Some(file),
             store,
+            equivocations: Default::default(),
             config: ctx.common.config.clone(),
             crypto: ctx.node.crypto.clone(),
         })
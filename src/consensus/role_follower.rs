@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use bincode::{Decode, Encode};
 use tracing::{debug, info, trace, warn};
 
@@ -198,6 +200,7 @@ impl FollowerRole for Consensus {
     /// Verifies that the proposed cut in the consensus proposal is valid.
     ///
     /// For the cut to be considered valid:
+    /// - Each validator must be a staker, with at most one DataProposal in the cut.
     /// - Each DataProposal associated with a validator must have received sufficient signatures.
     /// - The aggregated signatures for each DataProposal must be valid.
     fn verify_poda(&mut self, consensus_proposal: &ConsensusProposal) -> Result<()> {
@@ -208,6 +211,18 @@ impl FollowerRole for Consensus {
             self.bft_round_state.staking
         );
 
+        let mut seen_validators = HashSet::new();
+        if let Some((validator, ..)) = consensus_proposal
+            .cut
+            .iter()
+            .find(|(validator, ..)| !seen_validators.insert(validator))
+        {
+            bail!(
+                "Validator {} has more than one DataProposal in cut",
+                validator
+            );
+        }
+
         let accepted_validators = self.bft_round_state.staking.bonded();
         for (validator, data_proposal_hash, lane_size, poda_sig) in &consensus_proposal.cut {
             let voting_power = self
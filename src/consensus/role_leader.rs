@@ -5,8 +5,8 @@ use crate::{
     consensus::StateTag,
     mempool::QueryNewCut,
     model::{
-        ConsensusNetMessage, ConsensusProposalHash, Hashable, SignedByValidator, Ticket,
-        ValidatorPublicKey,
+        ConsensusNetMessage, ConsensusProposalHash, ConsensusStakingAction, Hashable,
+        SignedByValidator, Ticket, ValidatorPublicKey,
     },
 };
 use anyhow::{anyhow, bail, Result};
@@ -14,7 +14,7 @@ use bincode::{Decode, Encode};
 use staking::state::MIN_STAKE;
 use tracing::{debug, error, trace};
 
-use super::Consensus;
+use super::{equivocation::proves_equivocation, Consensus};
 
 #[derive(Encode, Decode, Default, Debug)]
 pub enum Step {
@@ -114,9 +114,39 @@ impl LeaderRole for Consensus {
 
         self.bft_round_state.leader.step = Step::PrepareVote;
 
+        // Only evidence other validators can check is proposed, and all of the stake is slashed.
+        // It is kept until the proposal commits, see finish_round.
+        let equivocations_to_slash = self.equivocations_to_slash.clone();
+        let slashes = equivocations_to_slash
+            .into_iter()
+            .filter_map(|equivocation| {
+                if !proves_equivocation(
+                    &equivocation.validator,
+                    &equivocation.first,
+                    &equivocation.second,
+                ) || !self
+                    .bft_round_state
+                    .staking
+                    .is_bonded(&equivocation.validator)
+                {
+                    return None;
+                }
+                let amount = self
+                    .bft_round_state
+                    .staking
+                    .get_stake(&equivocation.validator)?;
+                Some(ConsensusStakingAction::Slash {
+                    validator: equivocation.validator,
+                    amount,
+                    first: equivocation.first,
+                    second: equivocation.second,
+                })
+            });
+
         let staking_actions = new_validators_to_bond
             .into_iter()
             .map(|v| v.into())
+            .chain(slashes)
             .collect();
 
         // Start Consensus with following cut
@@ -167,6 +197,17 @@ impl LeaderRole for Consensus {
             self.metrics.prepare_vote_error("invalid_proposal_hash");
             bail!("PrepareVote has not received valid consensus proposal hash");
         }
+        if !self
+            .bft_round_state
+            .staking
+            .is_bonded(&msg.signature.validator)
+        {
+            self.metrics.prepare_vote_error("not_bonded");
+            bail!(
+                "PrepareVote from {} which is not a bonded validator",
+                msg.signature.validator
+            );
+        }
 
         // Save vote message
         self.store.bft_round_state.leader.prepare_votes.insert(msg);
@@ -261,6 +302,17 @@ impl LeaderRole for Consensus {
             );
             bail!("ConfirmAck got invalid consensus proposal hash");
         }
+        if !self
+            .bft_round_state
+            .staking
+            .is_bonded(&msg.signature.validator)
+        {
+            self.metrics.confirm_ack_error("not_bonded");
+            bail!(
+                "ConfirmAck from {} which is not a bonded validator",
+                msg.signature.validator
+            );
+        }
 
         // Save ConfirmAck. Ends if the message already has been processed
         if !self.store.bft_round_state.leader.confirm_ack.insert(msg) {
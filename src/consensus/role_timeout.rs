@@ -5,8 +5,8 @@ use tracing::{debug, info, trace, warn};
 
 use super::Consensus;
 use crate::model::{
-    utils::get_current_timestamp, ConsensusNetMessage, QuorumCertificate, SignedByValidator, Slot,
-    Ticket, ValidatorPublicKey, View,
+    ConsensusNetMessage, QuorumCertificate, SignedByValidator, Slot, Ticket, ValidatorPublicKey,
+    View,
 };
 use anyhow::{bail, Context, Result};
 
@@ -17,14 +17,16 @@ pub(super) enum TimeoutState {
     Inactive,
     // A new slot was created, and its timeout is scheduled
     Scheduled {
+        /// In milliseconds
         timestamp: u64,
     },
     CertificateEmitted,
 }
 
 impl TimeoutState {
-    pub const TIMEOUT_SECS: u64 = 5;
-    pub fn schedule_next(&mut self, timestamp: u64) {
+    /// Number of rounds without a commit after which we warn about consensus liveness.
+    pub const STALLED_ROUNDS_WARNING: u64 = 3;
+    pub fn schedule_next(&mut self, timestamp: u64, round_timeout: u64) {
         match self {
             TimeoutState::Inactive => {
                 trace!("⏲️ Scheduling timeout");
@@ -37,24 +39,9 @@ impl TimeoutState {
             }
         }
         *self = TimeoutState::Scheduled {
-            timestamp: timestamp + TimeoutState::TIMEOUT_SECS,
+            timestamp: timestamp + round_timeout,
         };
     }
-    pub fn cancel(&mut self) {
-        match self {
-            TimeoutState::CertificateEmitted => {
-                info!("⏲️ Cancelling timeout after it was emitted");
-            }
-            TimeoutState::Scheduled { timestamp } => {
-                info!("⏲️ Cancelling timeout set to trigger to {}", timestamp);
-            }
-            TimeoutState::Inactive => {
-                info!("⏲️ Cancelling inactive timeout");
-            }
-        }
-        *self = TimeoutState::Inactive;
-    }
-
     pub fn certificate_emitted(&mut self) {
         match self {
             TimeoutState::CertificateEmitted => {
@@ -220,10 +207,7 @@ impl TimeoutRole for Consensus {
             len += 1;
             voting_power += self.get_own_voting_power();
 
-            self.bft_round_state
-                .timeout
-                .state
-                .schedule_next(get_current_timestamp());
+            self.schedule_round_timeout();
         }
 
         // Create TC if applicable
@@ -248,10 +232,7 @@ impl TimeoutRole for Consensus {
 
             let timeout_certificate = timeout_signed_aggregation.signature;
 
-            self.bft_round_state
-                .timeout
-                .state
-                .schedule_next(get_current_timestamp());
+            self.schedule_round_timeout();
 
             if &self.next_leader()? == self.crypto.validator_pubkey() {
                 self.carry_on_with_ticket(Ticket::TimeoutQC(timeout_certificate))?;
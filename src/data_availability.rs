@@ -37,6 +37,7 @@ use futures::{
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 use std::collections::HashMap;
+use std::collections::VecDeque;
 use tokio::{
     net::{TcpListener, TcpStream},
     task::{JoinHandle, JoinSet},
@@ -73,6 +74,9 @@ struct BlockStreamPeer {
     sender: SplitSink<Framed<TcpStream, DataAvailabilityServerCodec>, SignedBlock>,
     /// Handle to abort the receiving side of the stream
     keepalive_abort: JoinHandle<()>,
+    /// Blocks still to be replayed to the peer, in order.
+    /// New blocks are queued here until the replay is done, so they're not streamed out of order.
+    catchup_queue: VecDeque<ConsensusProposalHash>,
 }
 
 #[derive(Debug)]
@@ -218,22 +222,26 @@ impl DataAvailability {
 
             // Send one block to a peer as part of "catchup",
             // once we have sent all blocks the peer is presumably synchronised.
-            Some((mut block_hashes, peer_ip)) = catchup_receiver.recv() => {
-                let hash = block_hashes.pop();
+            Some(peer_ip) = catchup_receiver.recv() => {
+                let Some(peer) = self.stream_peer_metadata.get_mut(&peer_ip) else {
+                    continue;
+                };
+                let hash = peer.catchup_queue.pop_front();
 
                 trace!("📡  Sending block {:?} to peer {}", &hash, &peer_ip);
                 if let Some(hash) = hash {
-                    if let Ok(Some(signed_block)) = self.blocks.get(&hash)
-                    {
-                        // Errors will be handled when sending new blocks, ignore here.
-                        if self.stream_peer_metadata
-                            .get_mut(&peer_ip)
-                            .context("peer not found")?
-                            .sender
-                            .send(signed_block)
-                            .await.is_ok() {
-                            let _ = catchup_sender.send((block_hashes, peer_ip)).await;
+                    match self.blocks.get(&hash) {
+                        Ok(Some(signed_block)) => {
+                            // Errors will be handled when sending new blocks, ignore here.
+                            if peer.sender.send(signed_block).await.is_err() {
+                                peer.catchup_queue.clear();
+                                continue;
+                            }
                         }
+                        _ => error!("Block {} to stream to peer {} not found", &hash, &peer_ip),
+                    }
+                    if !peer.catchup_queue.is_empty() {
+                        let _ = catchup_sender.send(peer_ip).await;
                     }
                 }
             }
@@ -253,6 +261,7 @@ impl DataAvailability {
             MempoolEvent::BuiltSignedBlock(signed_block) => {
                 self.handle_signed_block(signed_block).await;
             }
+            MempoolEvent::PendingTxEvicted(_) => {}
             MempoolEvent::StartedBuildingBlocks(height) => {
                 self.catchup_height = Some(height - 1);
                 if let Some(handle) = self.catchup_task.as_ref() {
@@ -367,6 +376,9 @@ impl DataAvailability {
                 info!("peer {} timed out", &peer_id);
                 peer.keepalive_abort.abort();
                 to_remove.push(peer_id.clone());
+            } else if !peer.catchup_queue.is_empty() {
+                // Still replaying past blocks, this one will be sent after them.
+                peer.catchup_queue.push_back(block.hash());
             } else {
                 info!("streaming block {} to peer {}", block.hash(), &peer_id);
                 match peer.sender.send(block.clone()).await {
@@ -397,7 +409,7 @@ impl DataAvailability {
         &mut self,
         start_height: BlockHeight,
         ping_sender: tokio::sync::mpsc::Sender<String>,
-        catchup_sender: tokio::sync::mpsc::Sender<(Vec<ConsensusProposalHash>, String)>,
+        catchup_sender: tokio::sync::mpsc::Sender<String>,
         sender: SplitSink<Framed<TcpStream, DataAvailabilityServerCodec>, SignedBlock>,
         mut receiver: SplitStream<Framed<TcpStream, DataAvailabilityServerCodec>>,
         peer_ip: &String,
@@ -415,22 +427,11 @@ impl DataAvailability {
                 }
             })?;
 
-        // Then store data so we can send new blocks as they come.
-        self.stream_peer_metadata.insert(
-            peer_ip.to_string(),
-            BlockStreamPeer {
-                last_ping: get_current_timestamp(),
-                sender,
-                keepalive_abort,
-            },
-        );
-
-        // Finally, stream past blocks as required.
-        // We'll create a copy of the range so we don't stream everything.
-        // We will safely stream everything as any new block will be sent
-        // because we registered in the struct beforehand.
+        // Stream past blocks from the requested height up to the current tip.
+        // Blocks received in the meantime are queued after those,
+        // so the peer gets everything in order with no gap nor duplicate.
         // Like pings, this just sends a message processed in the main select! loop.
-        let mut processed_block_hashes: Vec<_> = self
+        let catchup_queue: VecDeque<_> = self
             .blocks
             .range(
                 start_height,
@@ -441,11 +442,22 @@ impl DataAvailability {
             )
             .filter_map(|block| block.map(|b| b.hash()).ok())
             .collect();
-        processed_block_hashes.reverse();
+        let needs_catchup = !catchup_queue.is_empty();
 
-        catchup_sender
-            .send((processed_block_hashes, peer_ip.clone()))
-            .await?;
+        // Then store data so we can send new blocks as they come.
+        self.stream_peer_metadata.insert(
+            peer_ip.to_string(),
+            BlockStreamPeer {
+                last_ping: get_current_timestamp(),
+                sender,
+                keepalive_abort,
+                catchup_queue,
+            },
+        );
+
+        if needs_catchup {
+            catchup_sender.send(peer_ip.clone()).await?;
+        }
 
         Ok(())
     }
@@ -734,6 +746,80 @@ pub mod tests {
 
         assert_eq!(heights_received, (0..18).collect::<Vec<u64>>());
     }
+
+    #[test_log::test(tokio::test)]
+    async fn test_da_streaming_since_height() {
+        let tmpdir = tempfile::tempdir().unwrap().into_path();
+        let blocks = Blocks::new(&tmpdir).unwrap();
+
+        let global_bus = crate::bus::SharedMessageBus::new(
+            crate::bus::metrics::BusMetrics::global("global".to_string()),
+        );
+        let bus = super::DABusClient::new_from_bus(global_bus.new_handle()).await;
+        let mut block_sender = TestBusClient::new_from_bus(global_bus).await;
+
+        let mut config: Conf = Conf::new(None, None, None).unwrap();
+        config.da_address = format!("127.0.0.1:{}", find_available_port().await);
+        let mut da = super::DataAvailability {
+            config: config.clone().into(),
+            bus,
+            blocks,
+            buffered_signed_blocks: Default::default(),
+            stream_peer_metadata: Default::default(),
+            need_catchup: false,
+            catchup_task: None,
+            catchup_height: None,
+        };
+
+        let mut block = SignedBlock::default();
+        for i in 1..15 {
+            da.handle_signed_block(block.clone()).await;
+            block.consensus_proposal.parent_hash = block.hash();
+            block.consensus_proposal.slot = i;
+        }
+
+        tokio::spawn(async move {
+            da.start().await.unwrap();
+        });
+
+        // wait until it's up
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let mut stream = tokio::net::TcpStream::connect(config.da_address.clone())
+            .await
+            .unwrap();
+
+        // Resume from height 5, while the tip is at 13
+        stream.write_u32(8).await.unwrap();
+        stream.write_u64(5).await.unwrap();
+
+        // New blocks keep coming while the backlog is being replayed
+        for i in 15..20 {
+            block_sender
+                .send(MempoolEvent::BuiltSignedBlock(block.clone()))
+                .unwrap();
+            block.consensus_proposal.parent_hash = block.hash();
+            block.consensus_proposal.slot = i;
+        }
+
+        let mut da_stream = Framed::new(stream, LengthDelimitedCodec::new());
+
+        let mut heights_received = vec![];
+        while let Some(Ok(bytes)) = da_stream.next().await {
+            let block: SignedBlock =
+                bincode::decode_from_slice(&bytes, bincode::config::standard())
+                    .unwrap()
+                    .0;
+            heights_received.push(block.height().0);
+            if heights_received.len() == 14 {
+                break;
+            }
+        }
+
+        // Backlog then live blocks, in order, without gaps nor duplicates
+        assert_eq!(heights_received, (5..19).collect::<Vec<u64>>());
+    }
+
     #[test_log::test(tokio::test)]
     async fn test_da_catchup() {
         let sender_global_bus = crate::bus::SharedMessageBus::new(
@@ -5,9 +5,13 @@ use crate::{
     handle_messages,
     model::*,
     p2p::network::PeerEvent,
-    utils::{conf::SharedConf, crypto::SharedBlstCrypto, modules::Module},
+    utils::{
+        conf::{Conf, SharedConf},
+        crypto::SharedBlstCrypto,
+        modules::Module,
+    },
 };
-use anyhow::{Error, Result};
+use anyhow::{bail, Error, Result};
 use client_sdk::{
     contract_states,
     helpers::register_hyle_contract,
@@ -21,6 +25,7 @@ use hyle_contract_sdk::{identity_provider::IdentityVerification, Identity, State
 use hyle_contract_sdk::{ContractName, Digestable, ProgramId};
 use hyllar::{client::transfer, HyllarToken};
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
 use staking::{
     client::{delegate, stake},
     state::Staking,
@@ -28,6 +33,22 @@ use staking::{
 use tracing::{debug, error, info};
 use verifiers::NativeVerifiers;
 
+mod spec;
+
+/// Contracts registered by every genesis block, with the 'hyle' TLD the node starts with.
+pub const BUILTIN_CONTRACTS: [&str; 8] = [
+    "hyle",
+    "blst",
+    "sha3_256",
+    "webauthn",
+    "staking",
+    "hyllar",
+    "hydentity",
+    "risc0-recursion",
+];
+
+pub use spec::GenesisSpec;
+
 #[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
 pub enum GenesisEvent {
     NoGenesis,
@@ -44,22 +65,58 @@ struct GenesisBusClient {
 
 type PeerPublicKeyMap = BTreeMap<String, ValidatorPublicKey>;
 
+/// Hex-encoded hash of the genesis spec: the stakers, ordered by name, then
+/// the genesis contracts in registration order.
+/// Each staker is hashed as `len(name) || name || stake`, and each contract as
+/// `len(action) || action` over the bincode-encoded registration.
+pub fn genesis_hash(
+    genesis_stakers: &HashMap<String, u64>,
+    contracts: &[RegisterContractAction],
+) -> Result<String> {
+    let mut hasher = Sha3_256::new();
+    hasher.update((genesis_stakers.len() as u32).to_be_bytes());
+    for (name, stake) in genesis_stakers.iter().collect::<BTreeMap<_, _>>() {
+        hasher.update((name.len() as u32).to_be_bytes());
+        hasher.update(name.as_bytes());
+        hasher.update(stake.to_be_bytes());
+    }
+    hasher.update((contracts.len() as u32).to_be_bytes());
+    for contract in contracts {
+        let encoded = bincode::encode_to_vec(contract, bincode::config::standard())?;
+        hasher.update((encoded.len() as u32).to_be_bytes());
+        hasher.update(encoded);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Contracts of the genesis file, if there is one.
+pub fn genesis_contracts(config: &Conf) -> Result<Vec<RegisterContractAction>> {
+    match &config.genesis_file {
+        Some(genesis_file) => Ok(GenesisSpec::load(genesis_file)?.contracts),
+        None => Ok(vec![]),
+    }
+}
+
 pub struct Genesis {
     config: SharedConf,
     bus: GenesisBusClient,
     peer_pubkey: PeerPublicKeyMap,
     crypto: SharedBlstCrypto,
+    /// Contracts of the genesis file, registered on top of the builtin ones
+    contracts: Vec<RegisterContractAction>,
 }
 
 impl Module for Genesis {
     type Context = SharedRunContext;
     async fn build(ctx: Self::Context) -> Result<Self> {
         let bus = GenesisBusClient::new_from_bus(ctx.common.bus.new_handle()).await;
+        let contracts = genesis_contracts(&ctx.common.config)?;
         Ok(Genesis {
             config: ctx.common.config.clone(),
             bus,
             peer_pubkey: BTreeMap::new(),
             crypto: ctx.node.crypto.clone(),
+            contracts,
         })
     }
 
@@ -81,7 +138,7 @@ contract_states!(
 impl Genesis {
     pub async fn start(&mut self) -> Result<(), Error> {
         let file = self.config.data_directory.clone().join("genesis.bin");
-        let already_handled_genesis: bool = Self::load_from_disk_or_default(&file);
+        let already_handled_genesis: bool = Self::load_from_disk_or_default(&file)?;
         if already_handled_genesis {
             debug!("🌿 Genesis block already handled, skipping");
             // TODO: do we need a different message?
@@ -156,6 +213,7 @@ impl Genesis {
         let genesis_txs = match Self::generate_genesis_txs(
             &self.peer_pubkey,
             &self.config.consensus.genesis_stakers,
+            &self.contracts,
         )
         .await
         {
@@ -177,10 +235,26 @@ impl Genesis {
     pub async fn generate_genesis_txs(
         peer_pubkey: &PeerPublicKeyMap,
         genesis_stake: &HashMap<String, u64>,
+        contracts: &[RegisterContractAction],
     ) -> Result<Vec<Transaction>> {
         let (contract_program_ids, mut genesis_txs, mut tx_executor) =
             Self::genesis_contracts_txs();
 
+        if !contracts.is_empty() {
+            let mut register_tx = ProvableBlobTx::new("hyle.hyle".into());
+            for contract in contracts {
+                if contract_program_ids.contains_key(&contract.contract_name) {
+                    bail!(
+                        "Genesis contract {} is already a builtin contract",
+                        contract.contract_name
+                    );
+                }
+                info!("🌱  Registering contract {}", contract.contract_name);
+                register_tx.add_action("hyle".into(), contract.clone(), None, None)?;
+            }
+            genesis_txs.push(BlobTransaction::from(register_tx).into());
+        }
+
         let register_txs = Self::generate_register_txs(peer_pubkey, &mut tx_executor).await?;
 
         let faucet_txs =
@@ -205,7 +279,14 @@ impl Genesis {
             // dissemination. We can create the same VerifiedProofTransaction on each genesis
             // validator, and assume it's the same.
 
-            let tx = BlobTransaction { identity, blobs };
+            let tx = BlobTransaction {
+                identity,
+                blobs,
+                dependencies: vec![],
+                additional_identities: vec![],
+                nonce: None,
+                fee: 0,
+            };
             let blob_tx_hash = tx.hash();
 
             genesis_txs.push(tx.into());
@@ -380,6 +461,7 @@ impl Genesis {
         let mut map = BTreeMap::default();
         map.insert("blst".into(), NativeVerifiers::Blst.into());
         map.insert("sha3_256".into(), NativeVerifiers::Sha3_256.into());
+        map.insert("webauthn".into(), NativeVerifiers::WebAuthn.into());
         map.insert("hyllar".into(), ProgramId(hyllar_program_id.clone()));
         map.insert("hydentity".into(), ProgramId(hydentity_program_id.clone()));
         map.insert("staking".into(), ProgramId(staking_program_id.clone()));
@@ -408,6 +490,15 @@ impl Genesis {
         )
         .expect("register sha3_256");
 
+        register_hyle_contract(
+            &mut register_tx,
+            "webauthn".into(),
+            "webauthn".into(),
+            NativeVerifiers::WebAuthn.into(),
+            StateDigest::default(),
+        )
+        .expect("register webauthn");
+
         register_hyle_contract(
             &mut register_tx,
             "staking".into(),
@@ -534,17 +625,37 @@ mod tests {
         let bus = GenesisBusClient::new_from_bus(shared_bus.new_handle()).await;
         let test_bus = TestGenesisBusClient::new_from_bus(shared_bus.new_handle()).await;
         let crypto = Arc::new(BlstCrypto::new(config.id.clone()).unwrap());
+        let contracts = config
+            .genesis_file
+            .as_ref()
+            .map(|genesis_file| GenesisSpec::load(genesis_file).unwrap().contracts)
+            .unwrap_or_default();
         (
             Genesis {
                 config: Arc::new(config),
                 bus,
                 peer_pubkey: BTreeMap::new(),
                 crypto,
+                contracts,
             },
             test_bus,
         )
     }
 
+    #[test]
+    fn test_builtin_contracts_are_the_genesis_ones() {
+        let (program_ids, _, _) = Genesis::genesis_contracts_txs();
+        let mut builtin = program_ids
+            .keys()
+            .map(|name| name.0.as_str())
+            .chain(["hyle"])
+            .collect::<Vec<_>>();
+        builtin.sort();
+        let mut expected = BUILTIN_CONTRACTS.to_vec();
+        expected.sort();
+        assert_eq!(builtin, expected);
+    }
+
     #[test_log::test(tokio::test)]
     async fn test_not_part_of_genesis() {
         let tmpdir = tempfile::Builder::new().tempdir().unwrap();
@@ -595,6 +706,57 @@ mod tests {
         }
     }
 
+    #[test_log::test(tokio::test)]
+    async fn test_genesis_from_file() {
+        let tmpdir = tempfile::Builder::new().tempdir().unwrap();
+        let genesis_file = tmpdir.path().join("genesis.json");
+        std::fs::write(
+            &genesis_file,
+            r#"{
+                "stakers": { "single-node": 100 },
+                "contracts": [{
+                    "contract_name": "launched",
+                    "verifier": "test",
+                    "program_id": [1, 2, 3],
+                    "state_digest": [4, 5, 6]
+                }]
+            }"#,
+        )
+        .unwrap();
+        let mut config = Conf {
+            id: "single-node".to_string(),
+            single_node: Some(true),
+            data_directory: tmpdir.path().to_path_buf(),
+            genesis_file: Some(genesis_file),
+            ..Default::default()
+        };
+        config.apply_genesis_file().unwrap();
+        let (mut genesis, mut bus) = new(config).await;
+        let validator = genesis.crypto.validator_pubkey().clone();
+
+        genesis.start().await.unwrap();
+
+        let GenesisEvent::GenesisBlock(signed_block) = bus.try_recv().expect("recv") else {
+            panic!("Expected a genesis block");
+        };
+        let mut node_state = crate::node_state::NodeState::default();
+        let block = node_state.handle_signed_block(&signed_block);
+        assert_eq!(block.block_height, BlockHeight(0));
+
+        let contract = node_state
+            .contracts
+            .get(&ContractName::new("launched"))
+            .unwrap();
+        assert_eq!(contract.program_id, ProgramId(vec![1, 2, 3]));
+        assert_eq!(contract.state, StateDigest(vec![4, 5, 6]));
+
+        assert_eq!(block.new_bounded_validators, vec![validator.clone()]);
+        assert!(block.staking_actions.contains(&(
+            Identity(format!("{validator}.hydentity")),
+            StakingAction::Stake { amount: 100 }
+        )));
+    }
+
     #[test_log::test(tokio::test)]
     async fn test_genesis_as_leader() {
         let tmpdir = tempfile::Builder::new().tempdir().unwrap();
@@ -758,4 +920,48 @@ mod tests {
 
         assert_eq!(rec1, rec2);
     }
+
+    #[test]
+    fn test_genesis_hash_depends_on_stakers() {
+        let stakers = |list: &[(&str, u64)]| {
+            list.iter()
+                .map(|(name, stake)| (name.to_string(), *stake))
+                .collect::<HashMap<_, _>>()
+        };
+        let hash = |list: &[(&str, u64)]| genesis_hash(&stakers(list), &[]).unwrap();
+        let reference = hash(&[("node-1", 100), ("node-2", 100)]);
+
+        assert_eq!(reference, hash(&[("node-2", 100), ("node-1", 100)]));
+        assert_ne!(reference, hash(&[("node-1", 100), ("node-2", 101)]));
+        assert_ne!(reference, hash(&[("node-1", 100), ("node-3", 100)]));
+        assert_ne!(reference, hash(&[("node-1", 100)]));
+    }
+
+    #[test]
+    fn test_genesis_hash_depends_on_contracts() {
+        let stakers = HashMap::from([("node-1".to_string(), 100)]);
+        let contract = |name: &str| RegisterContractAction {
+            contract_name: name.into(),
+            ..Default::default()
+        };
+        let hash =
+            |contracts: &[RegisterContractAction]| genesis_hash(&stakers, contracts).unwrap();
+        let reference = hash(&[contract("a"), contract("b")]);
+
+        assert_eq!(reference, hash(&[contract("a"), contract("b")]));
+        assert_ne!(reference, hash(&[]));
+        assert_ne!(reference, hash(&[contract("a")]));
+        assert_ne!(reference, hash(&[contract("b"), contract("a")]));
+        assert_ne!(
+            reference,
+            hash(&[
+                contract("a"),
+                RegisterContractAction {
+                    contract_name: "b".into(),
+                    state_digest: StateDigest(vec![1]),
+                    ..Default::default()
+                }
+            ])
+        );
+    }
 }
@@ -0,0 +1,127 @@
+//! Description of a genesis block in a file, to launch a network reproducibly.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::BUILTIN_CONTRACTS;
+use crate::model::{
+    contract_registration::validate_contract_registration, ContractName, RegisterContractAction,
+};
+
+/// JSON contents of the `genesis_file` of the configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GenesisSpec {
+    /// Stake of each genesis validator, by node id.
+    pub stakers: HashMap<String, u64>,
+    /// Contracts registered by the genesis block, besides the builtin ones.
+    #[serde(default)]
+    pub contracts: Vec<RegisterContractAction>,
+}
+
+impl GenesisSpec {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read(path)
+            .with_context(|| format!("Reading genesis file {}", path.display()))?;
+        let spec: Self = serde_json::from_slice(&content)
+            .with_context(|| format!("Parsing genesis file {}", path.display()))?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.stakers.is_empty() {
+            bail!("Genesis file must list at least one staker");
+        }
+        let mut contract_names = HashSet::new();
+        for contract in &self.contracts {
+            if BUILTIN_CONTRACTS.contains(&contract.contract_name.0.as_str()) {
+                bail!(
+                    "Genesis contract {} is already a builtin contract",
+                    contract.contract_name
+                );
+            }
+            // Registered by the 'hyle' TLD, as any other contract
+            validate_contract_registration(&ContractName::new("hyle"), &contract.contract_name)
+                .with_context(|| {
+                    format!("Registering genesis contract {}", contract.contract_name)
+                })?;
+            if !contract_names.insert(&contract.contract_name) {
+                bail!(
+                    "Contract {} is registered more than once in the genesis file",
+                    contract.contract_name
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contract(name: &str) -> RegisterContractAction {
+        RegisterContractAction {
+            contract_name: ContractName::new(name),
+            verifier: "test".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_genesis_spec() {
+        let spec = GenesisSpec {
+            stakers: HashMap::from([("node-1".to_string(), 100)]),
+            contracts: vec![contract("c1"), contract("c2")],
+        };
+        assert!(spec.validate().is_ok());
+
+        let no_stakers = GenesisSpec {
+            stakers: HashMap::new(),
+            ..spec.clone()
+        };
+        assert!(no_stakers.validate().is_err());
+
+        let duplicate = GenesisSpec {
+            contracts: vec![contract("c1"), contract("c1")],
+            ..spec
+        };
+        assert!(duplicate
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("c1 is registered more than once"));
+    }
+
+    #[test]
+    fn test_genesis_spec_rejects_builtin_contracts() {
+        for name in ["hyle", "staking", "hydentity"] {
+            let spec = GenesisSpec {
+                stakers: HashMap::from([("node-1".to_string(), 100)]),
+                contracts: vec![contract(name)],
+            };
+            assert!(spec
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("is already a builtin contract"));
+        }
+    }
+
+    #[test]
+    fn test_genesis_spec_rejects_invalid_registrations() {
+        for name in ["", "c1.hydentity", "c1.c2"] {
+            let spec = GenesisSpec {
+                stakers: HashMap::from([("node-1".to_string(), 100)]),
+                contracts: vec![contract(name)],
+            };
+            let err = spec.validate().unwrap_err();
+            assert!(format!("{err:#}").contains("Invalid"), "{name}: {err:#}");
+        }
+    }
+}
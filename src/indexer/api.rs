@@ -2,8 +2,10 @@ use crate::utils::logger::LogMe;
 
 use super::IndexerApiState;
 use api::{
-    APIBlob, APIBlock, APIContract, APIContractState, APITransaction, BlobWithStatus,
-    TransactionStatus, TransactionType, TransactionWithBlobs,
+    APIBlob, APIBlock, APIBlockPage, APIContract, APIContractAction, APIContractActionPage,
+    APIContractState, APIContractStateHistoryEntry, APIProof, APIProofMetadata,
+    APISettledBlobProof, APIStakingAction, APITransaction, APITransactionPage, BlobWithStatus,
+    BlockCursor, TransactionCursor, TransactionStatus, TransactionType, TransactionWithBlobs,
 };
 use axum::{
     extract::{Path, Query, State},
@@ -21,6 +23,31 @@ pub struct BlockPagination {
     pub nb_results: Option<i64>,
 }
 
+#[derive(Debug, serde::Deserialize)]
+pub struct CursorPagination {
+    pub cursor: Option<String>,
+    pub nb_results: Option<i64>,
+}
+
+/// Largest page returned by the paginated routes
+const MAX_PAGE_SIZE: i64 = 100;
+
+fn page_size(nb_results: Option<i64>) -> i64 {
+    nb_results.unwrap_or(10).clamp(0, MAX_PAGE_SIZE)
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct HeightRange {
+    pub from_height: Option<i64>,
+    pub to_height: Option<i64>,
+    pub nb_results: Option<i64>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct TransactionStatusFilter {
+    pub status: Option<TransactionStatus>,
+}
+
 #[derive(OpenApi)]
 #[openapi(paths(get_blocks))]
 pub(super) struct IndexerAPI;
@@ -55,6 +82,62 @@ pub async fn get_blocks(
     Ok(Json(blocks))
 }
 
+#[utoipa::path(
+    get,
+    tag = "Indexer",
+    params(
+        ("cursor" = Option<String>, Query, description = "Cursor returned by the previous page"),
+        ("nb_results" = Option<i64>, Query, description = "Maximum number of blocks to return, at most 100"),
+    ),
+    path = "/blocks/page",
+    responses(
+        (status = OK, body = APIBlockPage)
+    )
+)]
+pub async fn get_blocks_page(
+    Query(pagination): Query<CursorPagination>,
+    State(state): State<IndexerApiState>,
+) -> Result<Json<APIBlockPage>, StatusCode> {
+    let nb_results = page_size(pagination.nb_results);
+    let below_height = match pagination.cursor {
+        Some(cursor) => {
+            let cursor = BlockCursor::decode(&cursor).ok_or(StatusCode::BAD_REQUEST)?;
+            i64::try_from(cursor.block_height.0).map_err(|_| StatusCode::BAD_REQUEST)?
+        }
+        None => i64::MAX,
+    };
+
+    let blocks = sqlx::query_as::<_, BlockDb>(
+        "SELECT * FROM blocks WHERE height < $1 ORDER BY height DESC LIMIT $2",
+    )
+    .bind(below_height)
+    .bind(nb_results)
+    .fetch_all(&state.db)
+    .await
+    .map(|db| {
+        db.into_iter()
+            .map(Into::<APIBlock>::into)
+            .collect::<Vec<_>>()
+    })
+    .log_error("Failed to fetch blocks page")
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let next_cursor = match blocks.last() {
+        Some(last) if blocks.len() as i64 == nb_results => Some(
+            BlockCursor {
+                block_height: BlockHeight(last.height),
+            }
+            .encode(),
+        ),
+        _ => None,
+    };
+
+    Ok(Json(APIBlockPage {
+        blocks,
+        next_cursor,
+    }))
+}
+
 #[utoipa::path(
     get,
     tag = "Indexer",
@@ -179,6 +262,149 @@ pub async fn get_transactions(
     Ok(Json(transactions))
 }
 
+/// Indexed transactions, latest first.
+#[utoipa::path(
+    get,
+    tag = "Indexer",
+    params(
+        ("cursor" = Option<String>, Query, description = "Cursor returned by the previous page"),
+        ("nb_results" = Option<i64>, Query, description = "Maximum number of transactions to return, at most 100"),
+    ),
+    path = "/transactions/page",
+    responses(
+        (status = OK, body = APITransactionPage)
+    )
+)]
+pub async fn get_transactions_page(
+    Query(pagination): Query<CursorPagination>,
+    State(state): State<IndexerApiState>,
+) -> Result<Json<APITransactionPage>, StatusCode> {
+    let nb_results = page_size(pagination.nb_results);
+    let (height, index) = cursor_bounds(pagination.cursor, (i64::MAX, i64::MAX))?;
+
+    // Keyset pagination, latest transactions first: new rows are only appended above the first
+    // page, so concurrent inserts never cause skipped or duplicated transactions.
+    let rows = sqlx::query_as::<_, TransactionWithHeightDb>(
+        r#"
+        SELECT t.*, b.height
+        FROM transactions t
+        JOIN blocks b ON t.block_hash = b.hash
+        WHERE (b.height, t.index) < ($1, $2)
+        ORDER BY b.height DESC, t.index DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(height)
+    .bind(index)
+    .bind(nb_results)
+    .fetch_all(&state.db)
+    .await
+    .log_error("Failed to fetch transactions page")
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let next_cursor = match rows.last() {
+        Some(last) if rows.len() as i64 == nb_results => Some(last.cursor().encode()),
+        _ => None,
+    };
+
+    Ok(Json(APITransactionPage {
+        transactions: rows
+            .into_iter()
+            .map(|row| Into::<APITransaction>::into(row.transaction))
+            .collect(),
+        next_cursor,
+    }))
+}
+
+/// Decodes an optional cursor into the (height, index) pair to continue from,
+/// `start` when there is none.
+fn cursor_bounds(cursor: Option<String>, start: (i64, i64)) -> Result<(i64, i64), StatusCode> {
+    match cursor {
+        Some(cursor) => {
+            let cursor = TransactionCursor::decode(&cursor).ok_or(StatusCode::BAD_REQUEST)?;
+            Ok((
+                i64::try_from(cursor.block_height.0).map_err(|_| StatusCode::BAD_REQUEST)?,
+                i64::from(cursor.index),
+            ))
+        }
+        None => Ok(start),
+    }
+}
+
+/// Every blob that targeted the contract, in chain order, along with the status of its transaction.
+/// Meant to replay the whole history of a contract, page by page.
+#[utoipa::path(
+    get,
+    tag = "Indexer",
+    params(
+        ("contract_name" = String, Path, description = "Contract name"),
+        ("cursor" = Option<String>, Query, description = "Cursor returned by the previous page"),
+        ("nb_results" = Option<i64>, Query, description = "Maximum number of transactions to return, at most 100"),
+    ),
+    path = "/actions/contract/{contract_name}",
+    responses(
+        (status = OK, body = APIContractActionPage)
+    )
+)]
+pub async fn get_contract_actions_page(
+    Path(contract_name): Path<String>,
+    Query(pagination): Query<CursorPagination>,
+    State(state): State<IndexerApiState>,
+) -> Result<Json<APIContractActionPage>, StatusCode> {
+    let nb_results = page_size(pagination.nb_results);
+    // Without a cursor, start before the first possible (height, index) pair.
+    let (height, index) = cursor_bounds(pagination.cursor, (-1, -1))?;
+
+    // Pages are made of whole transactions, so that the cursor never splits a transaction's blobs.
+    let rows = sqlx::query_as::<_, ContractActionDb>(
+        r#"
+        WITH txs AS (
+            SELECT t.*, b.height
+            FROM transactions t
+            JOIN blocks b ON t.block_hash = b.hash
+            WHERE (b.height, t.index) > ($2, $3)
+            AND EXISTS (SELECT 1 FROM blobs WHERE blobs.tx_hash = t.tx_hash AND blobs.contract_name = $1)
+            ORDER BY b.height ASC, t.index ASC
+            LIMIT $4
+        )
+        SELECT txs.*, blobs.blob_index, blobs.identity, blobs.data
+        FROM txs
+        JOIN blobs ON blobs.tx_hash = txs.tx_hash AND blobs.contract_name = $1
+        ORDER BY txs.height ASC, txs.index ASC, blobs.blob_index ASC
+        "#,
+    )
+    .bind(contract_name)
+    .bind(height)
+    .bind(index)
+    .bind(nb_results)
+    .fetch_all(&state.db)
+    .await
+    .log_error("Failed to fetch contract actions page")
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut nb_txs = 0;
+    let mut last_cursor = None;
+    for row in &rows {
+        let cursor = row.transaction.cursor();
+        if last_cursor != Some(cursor) {
+            nb_txs += 1;
+            last_cursor = Some(cursor);
+        }
+    }
+    let next_cursor = match last_cursor {
+        Some(last) if nb_txs == nb_results => Some(last.encode()),
+        _ => None,
+    };
+
+    Ok(Json(APIContractActionPage {
+        actions: rows
+            .into_iter()
+            .map(Into::<APIContractAction>::into)
+            .collect(),
+        next_cursor,
+    }))
+}
+
 #[utoipa::path(
     get,
     tag = "Indexer",
@@ -354,42 +580,8 @@ pub async fn get_blob_transactions_by_contract(
     .log_error("Failed to fetch transactions with blobs")
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let transactions: Result<Vec<TransactionWithBlobs>, anyhow::Error> = rows
-        .into_iter()
-        .map(|row| {
-            let tx_hash: TxHashDb = row.try_get("tx_hash")?;
-            let block_hash: ConsensusProposalHash = row.try_get("block_hash")?;
-            let index: i32 = row.try_get("index")?;
-            let version: i32 = row.try_get("version")?;
-            let transaction_type: TransactionType = row.try_get("transaction_type")?;
-            let transaction_status: TransactionStatus = row.try_get("transaction_status")?;
-            let identity: String = row.try_get("identity")?;
-            let blobs: Vec<(String, Vec<u8>, Vec<serde_json::Value>)> = row.try_get("blobs")?;
-
-            let index: u32 = index.try_into()?;
-            let version: u32 = version.try_into()?;
-
-            let blobs = blobs
-                .into_iter()
-                .map(|(contract_name, data, proof_outputs)| BlobWithStatus {
-                    contract_name,
-                    data,
-                    proof_outputs,
-                })
-                .collect();
-
-            Ok(TransactionWithBlobs {
-                tx_hash: tx_hash.0,
-                block_hash,
-                index,
-                version,
-                transaction_type,
-                transaction_status,
-                identity,
-                blobs,
-            })
-        })
-        .collect();
+    let transactions: Result<Vec<TransactionWithBlobs>, anyhow::Error> =
+        rows.into_iter().map(transaction_with_blobs).collect();
     match transactions {
         Ok(transactions) => Ok(Json(transactions)),
         Err(e) => {
@@ -399,6 +591,125 @@ pub async fn get_blob_transactions_by_contract(
     }
 }
 
+/// Transactions with a blob sent by the identity, most recent first.
+#[utoipa::path(
+    get,
+    tag = "Indexer",
+    params(
+        ("identity" = String, Path, description = "Identity"),
+        ("status" = Option<TransactionStatus>, Query, description = "Only transactions with this status"),
+        ("start_block" = Option<i64>, Query, description = "Highest block height to return transactions from"),
+        ("nb_results" = Option<i64>, Query, description = "Maximum number of transactions to return, at most 100"),
+    ),
+    path = "/transactions/identity/{identity}",
+    responses(
+        (status = OK, body = [TransactionWithBlobs])
+    )
+)]
+pub async fn get_transactions_by_identity(
+    Path(identity): Path<String>,
+    Query(pagination): Query<BlockPagination>,
+    Query(filter): Query<TransactionStatusFilter>,
+    State(state): State<IndexerApiState>,
+) -> Result<Json<Vec<TransactionWithBlobs>>, StatusCode> {
+    let rows = sqlx::query(
+        r#"
+        WITH txs AS (
+            SELECT t.*, bl.height
+            FROM transactions t
+            JOIN blocks bl ON t.block_hash = bl.hash
+            WHERE EXISTS (SELECT 1 FROM blobs WHERE blobs.tx_hash = t.tx_hash AND blobs.identity = $1)
+            AND ($2::transaction_status IS NULL OR t.transaction_status = $2)
+            AND ($3::bigint IS NULL OR bl.height <= $3)
+            ORDER BY bl.height DESC, t.index ASC
+            LIMIT $4
+        ),
+        tx_blobs AS (
+            SELECT blobs.*, array_remove(ARRAY_AGG(blob_proof_outputs.hyle_output), NULL) AS proof_outputs
+            FROM blobs
+            JOIN txs ON blobs.tx_hash = txs.tx_hash
+            LEFT JOIN blob_proof_outputs ON blobs.tx_hash = blob_proof_outputs.blob_tx_hash AND blobs.blob_index = blob_proof_outputs.blob_index
+            GROUP BY blobs.tx_hash, blobs.blob_index, blobs.identity
+        )
+        SELECT
+            t.tx_hash,
+            t.block_hash,
+            t.index,
+            t.version,
+            t.transaction_type,
+            t.transaction_status,
+            b.identity,
+            array_agg(ROW(b.contract_name, b.data, b.proof_outputs) ORDER BY b.blob_index) AS blobs
+        FROM txs t
+        JOIN tx_blobs b ON t.tx_hash = b.tx_hash
+        GROUP BY
+            t.tx_hash,
+            t.block_hash,
+            t.index,
+            t.version,
+            t.transaction_type,
+            t.transaction_status,
+            t.height,
+            b.identity
+        ORDER BY t.height DESC, t.index ASC
+        "#,
+    )
+    .bind(identity)
+    .bind(filter.status)
+    .bind(pagination.start_block)
+    .bind(page_size(pagination.nb_results))
+    .fetch_all(&state.db)
+    .await
+    .log_error("Failed to fetch transactions of identity")
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let transactions: Result<Vec<TransactionWithBlobs>, anyhow::Error> =
+        rows.into_iter().map(transaction_with_blobs).collect();
+    match transactions {
+        Ok(transactions) => Ok(Json(transactions)),
+        Err(e) => {
+            tracing::warn!("Failed to parse transactions with blobs: {:?}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn transaction_with_blobs(
+    row: sqlx::postgres::PgRow,
+) -> Result<TransactionWithBlobs, anyhow::Error> {
+    let tx_hash: TxHashDb = row.try_get("tx_hash")?;
+    let block_hash: ConsensusProposalHash = row.try_get("block_hash")?;
+    let index: i32 = row.try_get("index")?;
+    let version: i32 = row.try_get("version")?;
+    let transaction_type: TransactionType = row.try_get("transaction_type")?;
+    let transaction_status: TransactionStatus = row.try_get("transaction_status")?;
+    let identity: String = row.try_get("identity")?;
+    let blobs: Vec<(String, Vec<u8>, Vec<serde_json::Value>)> = row.try_get("blobs")?;
+
+    let index: u32 = index.try_into()?;
+    let version: u32 = version.try_into()?;
+
+    let blobs = blobs
+        .into_iter()
+        .map(|(contract_name, data, proof_outputs)| BlobWithStatus {
+            contract_name,
+            data,
+            proof_outputs,
+        })
+        .collect();
+
+    Ok(TransactionWithBlobs {
+        tx_hash: tx_hash.0,
+        block_hash,
+        index,
+        version,
+        transaction_type,
+        transaction_status,
+        identity,
+        blobs,
+    })
+}
+
 #[utoipa::path(
     get,
     tag = "Indexer",
@@ -427,6 +738,42 @@ pub async fn get_blobs_by_tx_hash(
     Ok(Json(blobs))
 }
 
+#[utoipa::path(
+    get,
+    tag = "Indexer",
+    params(
+        ("tx_hash" = String, Path, description = "Tx hash"),
+    ),
+    path = "/blobs/hash/{tx_hash}/settled_proofs",
+    responses(
+        (status = OK, body = [APISettledBlobProof])
+    )
+)]
+pub async fn get_settled_blob_proofs_by_tx_hash(
+    Path(tx_hash): Path<String>,
+    State(state): State<IndexerApiState>,
+) -> Result<Json<Vec<APISettledBlobProof>>, StatusCode> {
+    let settled_proofs = sqlx::query_as::<_, SettledBlobProofDb>(
+        "SELECT blob_index, contract_name, proof_tx_hash, blob_proof_output_index, hyle_output
+        FROM blob_proof_outputs
+        WHERE blob_tx_hash = $1 AND settled = true
+        ORDER BY blob_index",
+    )
+    .bind(tx_hash)
+    .fetch_all(&state.db)
+    .await
+    .log_error("Failed to fetch settled blob proofs")
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    settled_proofs
+        .into_iter()
+        .map(APISettledBlobProof::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .map(Json)
+        .log_error("Failed to parse settled blob proofs")
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
 #[utoipa::path(
     get,
     tag = "Indexer",
@@ -458,6 +805,110 @@ pub async fn get_blob(
     }
 }
 
+#[utoipa::path(
+    get,
+    tag = "Indexer",
+    params(
+        ("tx_hash" = String, Path, description = "Proof tx hash"),
+    ),
+    path = "/proof/{tx_hash}",
+    responses(
+        (status = OK, body = APIProof)
+    )
+)]
+pub async fn get_proof(
+    Path(tx_hash): Path<String>,
+    State(state): State<IndexerApiState>,
+) -> Result<Json<APIProof>, StatusCode> {
+    let proof = sqlx::query_as::<_, ProofTransactionDb>(
+        "SELECT tx_hash, contract_name, proof FROM proofs WHERE tx_hash = $1",
+    )
+    .bind(tx_hash)
+    .fetch_optional(&state.db)
+    .await
+    .log_error("Failed to fetch proof")
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    match proof {
+        Some(proof) => Ok(Json(proof.into())),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexer",
+    params(
+        ("tx_hash" = String, Path, description = "Proof tx hash"),
+    ),
+    path = "/proof/{tx_hash}/metadata",
+    responses(
+        (status = OK, body = APIProofMetadata)
+    )
+)]
+pub async fn get_proof_metadata(
+    Path(tx_hash): Path<String>,
+    State(state): State<IndexerApiState>,
+) -> Result<Json<APIProofMetadata>, StatusCode> {
+    // Proofs can be large, only their length is read
+    let metadata = sqlx::query_as::<_, ProofMetadataDb>(
+        "SELECT tx_hash, contract_name, octet_length(proof) as proof_len
+        FROM proofs WHERE tx_hash = $1",
+    )
+    .bind(tx_hash)
+    .fetch_optional(&state.db)
+    .await
+    .log_error("Failed to fetch proof metadata")
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    match metadata {
+        Some(metadata) => Ok(Json(metadata.into())),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+/// Staking actions of the identity, most recent first.
+#[utoipa::path(
+    get,
+    tag = "Indexer",
+    params(
+        ("identity" = String, Path, description = "Identity"),
+        ("start_block" = Option<i64>, Query, description = "Highest block height to return actions from"),
+        ("nb_results" = Option<i64>, Query, description = "Maximum number of actions to return, at most 100"),
+    ),
+    path = "/staking/{identity}",
+    responses(
+        (status = OK, body = [APIStakingAction])
+    )
+)]
+pub async fn get_staking_actions_by_identity(
+    Path(identity): Path<String>,
+    Query(pagination): Query<BlockPagination>,
+    State(state): State<IndexerApiState>,
+) -> Result<Json<Vec<APIStakingAction>>, StatusCode> {
+    let actions = sqlx::query_as::<_, StakingActionDb>(
+        "SELECT * FROM staking_actions
+        WHERE identity = $1 AND ($2::bigint IS NULL OR block_height <= $2)
+        ORDER BY block_height DESC, index ASC
+        LIMIT $3",
+    )
+    .bind(identity)
+    .bind(pagination.start_block)
+    .bind(page_size(pagination.nb_results))
+    .fetch_all(&state.db)
+    .await
+    .log_error("Failed to fetch staking actions")
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    actions
+        .into_iter()
+        .map(APIStakingAction::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .map(Json)
+        .log_error("Failed to parse staking actions")
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
 #[utoipa::path(
     get,
     tag = "Indexer",
@@ -507,6 +958,47 @@ pub async fn get_contract(
     }
 }
 
+#[utoipa::path(
+    get,
+    tag = "Indexer",
+    params(
+        ("contract_name" = String, Path, description = "Contract name"),
+        ("from_height" = Option<i64>, Query, description = "Lowest block height, inclusive"),
+        ("to_height" = Option<i64>, Query, description = "Highest block height, inclusive"),
+        ("nb_results" = Option<i64>, Query, description = "Maximum number of entries to return, at most 100. The next page starts after the height of the last entry"),
+    ),
+    path = "/contract/{contract_name}/history",
+    responses(
+        (status = OK, body = [APIContractStateHistoryEntry])
+    )
+)]
+pub async fn get_contract_state_history(
+    Path(contract_name): Path<String>,
+    Query(range): Query<HeightRange>,
+    State(state): State<IndexerApiState>,
+) -> Result<Json<Vec<APIContractStateHistoryEntry>>, StatusCode> {
+    let history = sqlx::query_as::<_, ContractStateHistoryDb>(
+        r#"
+        SELECT cs.block_hash, b.height, cs.state_digest
+        FROM contract_state cs
+        JOIN blocks b ON cs.block_hash = b.hash
+        WHERE contract_name = $1 AND b.height >= $2 AND b.height <= $3
+        ORDER BY b.height
+        LIMIT $4"#,
+    )
+    .bind(contract_name)
+    .bind(range.from_height.unwrap_or(0))
+    .bind(range.to_height.unwrap_or(i64::MAX))
+    .bind(page_size(range.nb_results))
+    .fetch_all(&state.db)
+    .await
+    .log_error("Failed to fetch contract state history")
+    .map(|db| db.into_iter().map(Into::into).collect())
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(history))
+}
+
 #[utoipa::path(
     get,
     tag = "Indexer",
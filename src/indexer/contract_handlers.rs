@@ -4,15 +4,19 @@ use super::contract_state_indexer::Store;
 use crate::model::BlobTransaction;
 use crate::rest::AppError;
 use anyhow::{anyhow, Context, Result};
+use axum::body::{Body, Bytes};
 use axum::extract::Path;
+use axum::http::header;
 use axum::Router;
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use bincode::Encode;
 use hydentity::{AccountInfo, Hydentity};
 use hyle_contract_sdk::identity_provider::{self, IdentityAction, IdentityVerification};
 use hyle_contract_sdk::{
     erc20::{self, ERC20Action, ERC20},
-    Blob, BlobIndex, Identity, StructuredBlobData,
+    Blob, BlobIndex, BlockHeight, Digestable, Identity, StructuredBlobData,
 };
+use hyle_model::BlobEncoding;
 use hyllar::{HyllarToken, HyllarTokenContract};
 use serde::Serialize;
 use tokio::sync::RwLock;
@@ -30,28 +34,53 @@ where
         store: Arc<RwLock<Store<Self>>>,
     ) -> impl std::future::Future<Output = (Router<()>, OpenApi)> + std::marker::Send;
 
-    fn handle(tx: &BlobTransaction, index: BlobIndex, state: Self) -> Result<Self>;
+    /// Applies the blob at `index`, decoded with the encoding the contract declared.
+    fn handle(
+        tx: &BlobTransaction,
+        index: BlobIndex,
+        state: Self,
+        blob_encoding: BlobEncoding,
+    ) -> Result<Self>;
+}
+
+/// Routes mounted for every indexed contract, next to the ones of its handler.
+pub fn default_api<S>(store: Arc<RwLock<Store<S>>>) -> (Router<()>, OpenApi)
+where
+    S: Serialize + Clone + Digestable + Send + Sync + 'static,
+{
+    let (router, api) = OpenApiRouter::default()
+        .routes(routes!(get_state))
+        .routes(routes!(get_state_digest))
+        .split_for_parts();
+
+    (router.with_state(store), api)
 }
 
 impl ContractHandler for Hydentity {
     async fn api(store: Arc<RwLock<Store<Self>>>) -> (Router<()>, OpenApi) {
         let (router, api) = OpenApiRouter::default()
-            .routes(routes!(get_state))
+            .routes(routes!(get_state_stream))
+            .routes(routes!(get_checksum))
             .routes(routes!(get_nonce))
             .split_for_parts();
 
         (router.with_state(store), api)
     }
 
-    fn handle(tx: &BlobTransaction, index: BlobIndex, state: Self) -> Result<Self> {
+    fn handle(
+        tx: &BlobTransaction,
+        index: BlobIndex,
+        state: Self,
+        blob_encoding: BlobEncoding,
+    ) -> Result<Self> {
         let Blob {
             data,
             contract_name,
         } = tx.blobs.get(index.0).context("Failed to get blob")?;
 
-        let (action, _): (IdentityAction, _) =
-            bincode::decode_from_slice(data.0.as_slice(), bincode::config::standard())
-                .context("Failed to decode payload")?;
+        let action: IdentityAction = blob_encoding
+            .decode(data)
+            .context("Failed to decode payload")?;
 
         let res =
             identity_provider::execute_action(state, action, "").map_err(|e| anyhow::anyhow!(e))?;
@@ -63,7 +92,8 @@ impl ContractHandler for Hydentity {
 impl ContractHandler for HyllarToken {
     async fn api(store: Arc<RwLock<Store<HyllarToken>>>) -> (Router<()>, OpenApi) {
         let (router, api) = OpenApiRouter::default()
-            .routes(routes!(get_state))
+            .routes(routes!(get_state_stream))
+            .routes(routes!(get_checksum))
             .routes(routes!(get_balance))
             .routes(routes!(get_allowance))
             .split_for_parts();
@@ -71,13 +101,18 @@ impl ContractHandler for HyllarToken {
         (router.with_state(store), api)
     }
 
-    fn handle(tx: &BlobTransaction, index: BlobIndex, state: HyllarToken) -> Result<HyllarToken> {
+    fn handle(
+        tx: &BlobTransaction,
+        index: BlobIndex,
+        state: HyllarToken,
+        blob_encoding: BlobEncoding,
+    ) -> Result<HyllarToken> {
         let Blob {
             contract_name,
             data,
         } = tx.blobs.get(index.0).context("Failed to get blob")?;
 
-        let data: StructuredBlobData<ERC20Action> = data.clone().try_into()?;
+        let data: StructuredBlobData<ERC20Action> = blob_encoding.decode(data)?;
 
         let caller: Identity = data
             .caller
@@ -110,6 +145,158 @@ pub async fn get_state<S: Serialize + Clone + 'static>(
     ))
 }
 
+#[derive(Serialize, ToSchema)]
+struct StateDigestResponse {
+    /// Hex-encoded
+    state_digest: String,
+    block_height: BlockHeight,
+}
+
+#[utoipa::path(
+    get,
+    path = "/state/digest",
+    tag = "Contract",
+    responses(
+        (status = OK, description = "Get the digest of the contract state, and the block height it corresponds to", body = StateDigestResponse)
+    )
+)]
+pub async fn get_state_digest<S: Digestable + 'static>(
+    State(state): State<Arc<RwLock<Store<S>>>>,
+) -> Result<impl IntoResponse, AppError> {
+    let store = state.read().await;
+    let digest = store
+        .state
+        .as_ref()
+        .map(Digestable::as_digest)
+        .ok_or(AppError(
+            StatusCode::NOT_FOUND,
+            anyhow!("No state found for contract '{}'", store.contract_name),
+        ))?;
+
+    Ok(Json(StateDigestResponse {
+        state_digest: hex::encode(digest.0),
+        block_height: store.block_height,
+    }))
+}
+
+/// Size of the chunks of a streamed state
+const STATE_STREAM_CHUNK_SIZE: usize = 64 * 1024;
+/// Number of encoded chunks waiting to be sent before the encoder blocks
+const STATE_STREAM_BUFFERED_CHUNKS: usize = 8;
+
+/// Bincode writer sending the encoded bytes to the response body chunk by chunk,
+/// so that the encoded state is never held in one buffer.
+struct ChunkWriter {
+    sender: tokio::sync::mpsc::Sender<Result<Bytes, std::io::Error>>,
+    buffer: Vec<u8>,
+}
+
+impl ChunkWriter {
+    fn flush(&mut self) -> Result<(), bincode::error::EncodeError> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(
+            &mut self.buffer,
+            Vec::with_capacity(STATE_STREAM_CHUNK_SIZE),
+        );
+        self.sender
+            .blocking_send(Ok(Bytes::from(chunk)))
+            .map_err(|_| bincode::error::EncodeError::Other("state stream closed"))
+    }
+}
+
+impl bincode::enc::write::Writer for ChunkWriter {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), bincode::error::EncodeError> {
+        let mut bytes = bytes;
+        while !bytes.is_empty() {
+            let len = std::cmp::min(STATE_STREAM_CHUNK_SIZE - self.buffer.len(), bytes.len());
+            let (head, tail) = bytes.split_at(len);
+            self.buffer.extend_from_slice(head);
+            bytes = tail;
+            if self.buffer.len() == STATE_STREAM_CHUNK_SIZE {
+                self.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/state/stream",
+    tag = "Contract",
+    responses(
+        (status = OK, description = "Stream the bincode-encoded state of contract, in chunks, for states too large for a json response")
+    )
+)]
+pub async fn get_state_stream<S: Encode + Clone + Send + 'static>(
+    State(state): State<Arc<RwLock<Store<S>>>>,
+) -> Result<impl IntoResponse, AppError> {
+    // Cloning releases the lock right away, a slow client must not hold back the indexer.
+    let state = {
+        let store = state.read().await;
+        store.state.clone().ok_or(AppError(
+            StatusCode::NOT_FOUND,
+            anyhow!("No state found for contract '{}'", store.contract_name),
+        ))?
+    };
+
+    let (sender, receiver) = tokio::sync::mpsc::channel(STATE_STREAM_BUFFERED_CHUNKS);
+    tokio::task::spawn_blocking(move || {
+        let writer = ChunkWriter {
+            sender,
+            buffer: Vec::with_capacity(STATE_STREAM_CHUNK_SIZE),
+        };
+        let mut encoder = bincode::enc::EncoderImpl::new(writer, bincode::config::standard());
+        let encoded = state.encode(&mut encoder);
+        let mut writer = encoder.into_writer();
+        if let Err(err) = encoded.and_then(|_| writer.flush()) {
+            // Aborts the response, the client must not mistake a truncated state for a full one.
+            let _ = writer
+                .sender
+                .blocking_send(Err(std::io::Error::other(err.to_string())));
+        }
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/octet-stream")],
+        Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(receiver)),
+    ))
+}
+
+#[derive(Serialize, ToSchema)]
+struct ChecksumResponse {
+    checksum: String,
+    block_height: BlockHeight,
+}
+
+#[utoipa::path(
+    get,
+    path = "/checksum",
+    tag = "Contract",
+    responses(
+        (status = OK, description = "Get checksum of the contract state, and the block height it corresponds to", body = ChecksumResponse)
+    )
+)]
+pub async fn get_checksum<S: Encode + 'static>(
+    State(state): State<Arc<RwLock<Store<S>>>>,
+) -> Result<impl IntoResponse, AppError> {
+    let store = state.read().await;
+    let checksum = store
+        .state_checksum()
+        .map_err(|err| AppError(StatusCode::INTERNAL_SERVER_ERROR, err))?
+        .ok_or(AppError(
+            StatusCode::NOT_FOUND,
+            anyhow!("No state found for contract '{}'", store.contract_name),
+        ))?;
+
+    Ok(Json(ChecksumResponse {
+        checksum,
+        block_height: store.block_height,
+    }))
+}
+
 #[derive(Serialize, ToSchema)]
 struct NonceResponse {
     account: String,
@@ -1,14 +1,21 @@
-use anyhow::{anyhow, Error, Result};
+use anyhow::{anyhow, Context, Error, Result};
 use bincode::{Decode, Encode};
-use hyle_contract_sdk::{BlobIndex, ContractName, TxHash};
-use hyle_model::RegisterContractEffect;
+use hyle_contract_sdk::{BlobIndex, ContractName, Digestable, StateDigest, TxHash};
+use hyle_model::{BlobEncoding, BlockHeight, RegisterContractEffect};
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, ops::Deref, path::PathBuf, sync::Arc};
+use sha3::{Digest, Sha3_256};
+use std::{
+    collections::BTreeMap,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 use tokio::sync::RwLock;
-use tracing::debug;
+use tracing::{debug, error, warn};
 
 use crate::{
-    bus::BusMessage,
+    bus::{BusClientSender, BusMessage},
     model::{
         Blob, BlobTransaction, Block, CommonRunContext, Hashable, Transaction, TransactionData,
     },
@@ -17,7 +24,10 @@ use crate::{
     utils::{conf::Conf, logger::LogMe, modules::Module},
 };
 
-use super::{contract_handlers::ContractHandler, indexer_bus_client::IndexerBusClient};
+use super::{
+    contract_handlers::{default_api, ContractHandler},
+    indexer_bus_client::IndexerBusClient,
+};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum ProverEvent {
@@ -25,11 +35,29 @@ pub enum ProverEvent {
 }
 impl BusMessage for ProverEvent {}
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum ContractStateIndexerEvent {
+    /// The state computed by the indexer does not match the digest recorded by the node.
+    StateDivergence {
+        contract_name: ContractName,
+        block_height: BlockHeight,
+        node_digest: StateDigest,
+        indexer_digest: StateDigest,
+    },
+}
+impl BusMessage for ContractStateIndexerEvent {}
+
 #[derive(Encode, Decode)]
 pub struct Store<State> {
     pub state: Option<State>,
     pub contract_name: ContractName,
     pub unsettled_blobs: BTreeMap<TxHash, BlobTransaction>,
+    /// Height of the last block processed by the indexer
+    pub block_height: BlockHeight,
+    /// Digest of the contract state recorded by the node, as of the last block that updated it
+    pub node_digest: Option<StateDigest>,
+    /// Declared by the contract when registered, its blobs are decoded with it
+    pub blob_encoding: BlobEncoding,
 }
 
 impl<State> Default for Store<State> {
@@ -38,16 +66,29 @@ impl<State> Default for Store<State> {
             state: None,
             contract_name: Default::default(),
             unsettled_blobs: BTreeMap::new(),
+            block_height: BlockHeight(0),
+            node_digest: None,
+            blob_encoding: BlobEncoding::default(),
         }
     }
 }
 
+impl<State: Encode> Store<State> {
+    /// Hex-encoded sha3 of the encoded state, to compare states computed by different indexers.
+    pub fn state_checksum(&self) -> Result<Option<String>> {
+        let Some(state) = &self.state else {
+            return Ok(None);
+        };
+        let encoded = bincode::encode_to_vec(state, bincode::config::standard())?;
+        Ok(Some(hex::encode(Sha3_256::digest(encoded))))
+    }
+}
+
 pub struct ContractStateIndexer<State> {
     bus: IndexerBusClient,
     store: Arc<RwLock<Store<State>>>,
     contract_name: ContractName,
     file: PathBuf,
-    #[allow(dead_code)]
     config: Arc<Conf>,
 }
 
@@ -64,6 +105,7 @@ where
         + Sync
         + Send
         + ContractHandler
+        + Digestable
         + Encode
         + Decode
         + 'static,
@@ -78,11 +120,14 @@ where
             .data_directory
             .join(format!("state_indexer_{}.bin", ctx.contract_name).as_str());
 
-        let mut store = Self::load_from_disk_or_default::<Store<State>>(file.as_path());
+        let mut store = Self::load_store(file.as_path())?;
         store.contract_name = ctx.contract_name.clone();
         let store = Arc::new(RwLock::new(store));
 
         let (nested, mut api) = State::api(Arc::clone(&store)).await;
+        let (default_nested, default_api) = default_api(Arc::clone(&store));
+        let nested = nested.merge(default_nested);
+        api.merge(default_api);
         if let Ok(mut o) = ctx.common.openapi.lock() {
             // Deduplicate operation ids
             for p in api.paths.paths.iter_mut() {
@@ -132,11 +177,19 @@ where
         + Sync
         + Send
         + ContractHandler
+        + Digestable
         + Encode
         + Decode
         + 'static,
 {
     pub async fn start(&mut self) -> Result<(), Error> {
+        let reconciliation_interval = self.config.indexer_reconciliation_interval;
+        let mut reconciliation_ticker =
+            tokio::time::interval(Duration::from_secs(reconciliation_interval.max(1)));
+        let checkpoint_interval = self.config.indexer_checkpoint_interval;
+        let mut checkpoint_ticker =
+            tokio::time::interval(Duration::from_secs(checkpoint_interval.max(1)));
+
         module_handle_messages! {
             on_bus self.bus,
             listen<NodeStateEvent> event => {
@@ -144,71 +197,152 @@ where
                     .await
                     .log_error("Handling node state event")
             }
+            _ = reconciliation_ticker.tick() => {
+                if reconciliation_interval > 0 {
+                    _ = self.reconcile().await.log_error("Reconciling contract state");
+                }
+            }
+            _ = checkpoint_ticker.tick() => {
+                if checkpoint_interval > 0 {
+                    _ = self.save_store().await.log_error("Checkpointing contract state indexer");
+                }
+            }
         };
 
-        if let Err(e) =
-            Self::save_on_disk::<Store<State>>(self.file.as_path(), self.store.read().await.deref())
-        {
-            tracing::warn!(cn = %self.contract_name, "Failed to save contract state indexer on disk: {}", e);
+        if let Err(e) = self.save_store().await {
+            warn!(cn = %self.contract_name, "Failed to save contract state indexer on disk: {}", e);
         }
         Ok(())
     }
 
-    /// Note: Each copy of the contract state indexer does the same handle_block on each data event
-    /// coming from node state.
+    /// Previous save of the store, loaded if the last one can't be decoded.
+    fn backup_path(file: &Path) -> PathBuf {
+        file.with_extension("bin.bak")
+    }
+
+    /// Saves the store, keeping the previous save as a backup.
+    async fn save_store(&self) -> Result<()> {
+        if self.file.exists() {
+            std::fs::rename(&self.file, Self::backup_path(&self.file))
+                .context("Backing up contract state indexer store")?;
+        }
+        Self::save_on_disk::<Store<State>>(self.file.as_path(), self.store.read().await.deref())
+    }
+
+    fn load_store(file: &Path) -> Result<Store<State>> {
+        let backup = Self::backup_path(file);
+        match Self::load_from_disk::<Store<State>>(file) {
+            Ok(Some(store)) => Ok(store),
+            // Interrupted between the backup and the save
+            Ok(None) if backup.exists() => Self::load_from_disk_or_default(&backup),
+            Ok(None) => Ok(Store::default()),
+            Err(e) if backup.exists() => {
+                warn!("{:#}, falling back to {}", e, backup.to_string_lossy());
+                Self::load_from_disk_or_default(&backup)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Blocks are handled once by the node state of the DA listener, every contract state
+    /// indexer consumes the same handled block.
     async fn handle_node_state_event(&mut self, event: NodeStateEvent) -> Result<(), Error> {
         let NodeStateEvent::NewBlock(block) = event;
-        self.handle_processed_block(*block).await?;
+        self.handle_processed_block(&block).await?;
 
         Ok(())
     }
 
-    async fn handle_processed_block(&mut self, block: Block) -> Result<()> {
-        for (_, contract) in block.registered_contracts {
+    /// Only the parts of the block touching this contract are copied into the store.
+    async fn handle_processed_block(&mut self, block: &Block) -> Result<()> {
+        for (_, contract) in &block.registered_contracts {
             if self.contract_name == contract.contract_name {
-                self.handle_register_contract(contract).await?;
+                let blob_encoding = block
+                    .blob_encodings
+                    .get(&contract.contract_name)
+                    .copied()
+                    .unwrap_or_default();
+                self.handle_register_contract(contract.clone(), blob_encoding)
+                    .await?;
             }
         }
 
-        for tx in block.txs {
-            if let TransactionData::Blob(tx) = tx.transaction_data {
+        for tx in &block.txs {
+            if let TransactionData::Blob(tx) = &tx.transaction_data {
                 self.handle_blob(tx).await?;
             }
         }
 
-        for s_tx in block.successful_txs {
-            self.settle_tx(s_tx).await?;
+        for s_tx in &block.successful_txs {
+            self.settle_tx(s_tx.clone()).await?;
+        }
+
+        let mut store = self.store.write().await;
+        if let Some(digest) = block.updated_states.get(&self.contract_name) {
+            store.node_digest = Some(digest.clone());
         }
+        store.block_height = block.block_height;
         Ok(())
     }
 
-    async fn handle_blob(&mut self, tx: BlobTransaction) -> Result<()> {
-        let tx_hash = tx.hash();
-        let mut found_supported_blob = false;
-
-        for b in &tx.blobs {
-            if self.contract_name == b.contract_name {
-                found_supported_blob = true;
-                break;
+    /// Compares the indexed state to the digest recorded by the node, and reports any divergence.
+    async fn reconcile(&mut self) -> Result<()> {
+        let divergence = {
+            let store = self.store.read().await;
+            let (Some(state), Some(node_digest)) = (&store.state, &store.node_digest) else {
+                return Ok(());
+            };
+            let indexer_digest = state.as_digest();
+            if indexer_digest == *node_digest {
+                return Ok(());
             }
-        }
+            error!(
+                cn = %self.contract_name,
+                "State diverged from the node at block {}: node digest {:?}, indexer digest {:?}",
+                store.block_height,
+                node_digest,
+                indexer_digest
+            );
+            ContractStateIndexerEvent::StateDivergence {
+                contract_name: self.contract_name.clone(),
+                block_height: store.block_height,
+                node_digest: node_digest.clone(),
+                indexer_digest,
+            }
+        };
+        self.bus.send(divergence)?;
+        Ok(())
+    }
+
+    async fn handle_blob(&mut self, tx: &BlobTransaction) -> Result<()> {
+        let found_supported_blob = tx
+            .blobs
+            .iter()
+            .any(|b| self.contract_name == b.contract_name);
 
         if found_supported_blob {
+            let tx_hash = tx.hash();
             debug!(cn = %self.contract_name, "⚒️  Found supported blob in transaction: {}", tx_hash);
             self.store
                 .write()
                 .await
                 .unsettled_blobs
-                .insert(tx_hash.clone(), tx);
+                .insert(tx_hash, tx.clone());
         }
 
         Ok(())
     }
 
-    async fn handle_register_contract(&self, contract: RegisterContractEffect) -> Result<()> {
+    async fn handle_register_contract(
+        &self,
+        contract: RegisterContractEffect,
+        blob_encoding: BlobEncoding,
+    ) -> Result<()> {
         debug!(cn = %self.contract_name, "📝 Registering supported contract '{}'", contract.contract_name);
-        let state = contract.state_digest.try_into()?;
-        self.store.write().await.state = Some(state);
+        let mut store = self.store.write().await;
+        store.blob_encoding = blob_encoding;
+        store.node_digest = Some(contract.state_digest.clone());
+        store.state = Some(contract.state_digest.try_into()?);
         Ok(())
     }
 
@@ -231,7 +365,7 @@ where
                 .clone()
                 .ok_or(anyhow!("No state found for {contract_name}"))?;
 
-            let new_state = State::handle(&tx, BlobIndex(index), state)?;
+            let new_state = State::handle(&tx, BlobIndex(index), state, store.blob_encoding)?;
 
             debug!(cn = %self.contract_name, "📈 Updated state for {contract_name}");
 
@@ -257,6 +391,12 @@ mod tests {
     #[derive(Clone, Debug, Default, Encode, Decode, Serialize, Deserialize)]
     struct MockState(Vec<u8>);
 
+    impl Digestable for MockState {
+        fn as_digest(&self) -> StateDigest {
+            StateDigest(self.0.clone())
+        }
+    }
+
     impl TryFrom<StateDigest> for MockState {
         type Error = Error;
 
@@ -266,7 +406,12 @@ mod tests {
     }
 
     impl ContractHandler for MockState {
-        fn handle(tx: &BlobTransaction, index: BlobIndex, mut state: Self) -> Result<Self> {
+        fn handle(
+            tx: &BlobTransaction,
+            index: BlobIndex,
+            mut state: Self,
+            _blob_encoding: BlobEncoding,
+        ) -> Result<Self> {
             state.0 = tx.blobs.get(index.0).unwrap().data.0.clone();
             Ok(state)
         }
@@ -277,8 +422,19 @@ mod tests {
     }
 
     async fn build_indexer(contract_name: ContractName) -> ContractStateIndexer<MockState> {
+        build_indexer_on_bus(
+            contract_name,
+            SharedMessageBus::new(BusMetrics::global("global".to_string())),
+        )
+        .await
+    }
+
+    async fn build_indexer_on_bus(
+        contract_name: ContractName,
+        bus: SharedMessageBus,
+    ) -> ContractStateIndexer<MockState> {
         let common = Arc::new(CommonRunContext {
-            bus: SharedMessageBus::new(BusMetrics::global("global".to_string())),
+            bus,
             config: Arc::new(Conf::default()),
             router: Default::default(),
             openapi: Default::default(),
@@ -300,7 +456,10 @@ mod tests {
             verifier: "test".into(),
             program_id: ProgramId(vec![]),
         };
-        indexer.handle_register_contract(rce).await.unwrap();
+        indexer
+            .handle_register_contract(rce, BlobEncoding::default())
+            .await
+            .unwrap();
     }
 
     #[test_log::test(tokio::test)]
@@ -312,6 +471,32 @@ mod tests {
 
         let store = indexer.store.read().await;
         assert!(store.state.is_some());
+        assert_eq!(store.blob_encoding, BlobEncoding::BincodeStandard);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_registered_blob_encoding_is_kept() {
+        let contract_name = ContractName::from("test_contract");
+        let mut indexer = build_indexer(contract_name.clone()).await;
+
+        let block = Block {
+            block_height: BlockHeight(1),
+            registered_contracts: vec![(
+                TxHash::new("register"),
+                RegisterContractEffect {
+                    contract_name: contract_name.clone(),
+                    state_digest: StateDigest::default(),
+                    verifier: "test".into(),
+                    program_id: ProgramId(vec![]),
+                },
+            )],
+            blob_encodings: BTreeMap::from([(contract_name, BlobEncoding::Json)]),
+            ..Block::default()
+        };
+        indexer.handle_processed_block(&block).await.unwrap();
+
+        let store = indexer.store.read().await;
+        assert_eq!(store.blob_encoding, BlobEncoding::Json);
     }
 
     #[test_log::test(tokio::test)]
@@ -324,12 +509,16 @@ mod tests {
         let tx = BlobTransaction {
             blobs: vec![blob],
             identity: "test".into(),
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let tx_hash = tx.hash();
 
         let mut indexer = build_indexer(contract_name.clone()).await;
         register_contract(&mut indexer).await;
-        indexer.handle_blob(tx).await.unwrap();
+        indexer.handle_blob(&tx).await.unwrap();
 
         let store = indexer.store.read().await;
         assert!(store.unsettled_blobs.contains_key(&tx_hash));
@@ -346,6 +535,10 @@ mod tests {
         let tx = BlobTransaction {
             blobs: vec![blob],
             identity: "test".into(),
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let tx_hash = tx.hash();
 
@@ -363,6 +556,44 @@ mod tests {
         assert_eq!(store.state.clone().unwrap().0, vec![1, 2, 3]);
     }
 
+    #[test_log::test(tokio::test)]
+    async fn test_state_checksum() {
+        let contract_name = ContractName::from("test_contract");
+        let tx = |data: Vec<u8>| BlobTransaction {
+            blobs: vec![Blob {
+                contract_name: contract_name.clone(),
+                data: BlobData(data),
+            }],
+            identity: "test".into(),
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+
+        let mut indexer1 = build_indexer(contract_name.clone()).await;
+        let mut indexer2 = build_indexer(contract_name.clone()).await;
+        assert_eq!(indexer1.store.read().await.state_checksum().unwrap(), None);
+
+        for indexer in [&mut indexer1, &mut indexer2] {
+            register_contract(indexer).await;
+            indexer.handle_blob(&tx(vec![1, 2, 3])).await.unwrap();
+            indexer.settle_tx(tx(vec![1, 2, 3]).hash()).await.unwrap();
+        }
+
+        let checksum1 = indexer1.store.read().await.state_checksum().unwrap();
+        let checksum2 = indexer2.store.read().await.state_checksum().unwrap();
+        assert!(checksum1.is_some());
+        assert_eq!(checksum1, checksum2);
+
+        // Diverging states give different checksums
+        indexer2.handle_blob(&tx(vec![4, 5, 6])).await.unwrap();
+        indexer2.settle_tx(tx(vec![4, 5, 6]).hash()).await.unwrap();
+
+        let checksum2 = indexer2.store.read().await.state_checksum().unwrap();
+        assert_ne!(checksum1, checksum2);
+    }
+
     #[tokio::test]
     async fn test_handle_node_state_event() {
         let contract_name = ContractName::from("test_contract");
@@ -377,4 +608,200 @@ mod tests {
         indexer.handle_node_state_event(event).await.unwrap();
         // Add assertions based on the expected state changes
     }
+
+    #[test_log::test(tokio::test)]
+    async fn test_indexers_share_handled_block() {
+        let contracts: Vec<ContractName> = ["c1", "c2", "c3"].map(ContractName::from).into();
+        let tx = BlobTransaction {
+            blobs: vec![
+                Blob {
+                    contract_name: "c1".into(),
+                    data: BlobData(vec![1]),
+                },
+                Blob {
+                    contract_name: "c2".into(),
+                    data: BlobData(vec![2]),
+                },
+            ],
+            identity: "test".into(),
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let tx_hash = tx.hash();
+
+        // Handled once for all the indexers
+        let block = Block {
+            block_height: BlockHeight(1),
+            registered_contracts: contracts
+                .iter()
+                .map(|contract_name| {
+                    (
+                        TxHash::new("register"),
+                        RegisterContractEffect {
+                            contract_name: contract_name.clone(),
+                            state_digest: StateDigest::default(),
+                            verifier: "test".into(),
+                            program_id: ProgramId(vec![]),
+                        },
+                    )
+                })
+                .collect(),
+            txs: vec![tx.into()],
+            successful_txs: vec![tx_hash.clone()],
+            ..Block::default()
+        };
+
+        let mut indexers = vec![];
+        for contract_name in &contracts {
+            let mut indexer = build_indexer(contract_name.clone()).await;
+            indexer.handle_processed_block(&block).await.unwrap();
+            indexers.push(indexer);
+        }
+
+        let mut states = vec![];
+        for indexer in &indexers {
+            let store = indexer.store.read().await;
+            assert!(!store.unsettled_blobs.contains_key(&tx_hash));
+            assert_eq!(store.block_height, BlockHeight(1));
+            states.push(store.state.clone().unwrap().0);
+        }
+        assert_eq!(states, vec![vec![1], vec![2], vec![]]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_default_state_routes() {
+        use axum_test::TestServer;
+
+        let contract_name = ContractName::from("test_contract");
+        let mut indexer = build_indexer(contract_name.clone()).await;
+        let server = TestServer::new(default_api(indexer.store.clone()).0).unwrap();
+
+        // Not registered yet
+        server.get("/state").await.assert_status_not_found();
+        server.get("/state/digest").await.assert_status_not_found();
+
+        register_contract(&mut indexer).await;
+        let tx = BlobTransaction {
+            blobs: vec![Blob {
+                contract_name,
+                data: BlobData(vec![1, 2, 3]),
+            }],
+            identity: "test".into(),
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        indexer.handle_blob(&tx).await.unwrap();
+        indexer.settle_tx(tx.hash()).await.unwrap();
+
+        let state = server.get("/state").await;
+        state.assert_status_ok();
+        assert_eq!(state.json::<Vec<u8>>(), vec![1, 2, 3]);
+
+        let digest = server.get("/state/digest").await;
+        digest.assert_status_ok();
+        assert_eq!(
+            digest.json::<serde_json::Value>().get("state_digest"),
+            Some(&serde_json::json!("010203"))
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_truncated_store_falls_back_to_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut indexer = build_indexer(ContractName::from("test_contract")).await;
+        indexer.file = dir.path().join("state_indexer_test_contract.bin");
+
+        indexer.store.write().await.state = Some(MockState(vec![1, 2, 3]));
+        indexer.save_store().await.unwrap();
+        indexer.store.write().await.state = Some(MockState(vec![4, 5, 6]));
+        indexer.save_store().await.unwrap();
+
+        let loaded = ContractStateIndexer::<MockState>::load_store(&indexer.file).unwrap();
+        assert_eq!(loaded.state.unwrap().0, vec![4, 5, 6]);
+
+        // Crashed while writing the last save
+        let content = std::fs::read(&indexer.file).unwrap();
+        std::fs::write(&indexer.file, content.get(..content.len() / 2).unwrap()).unwrap();
+        let loaded = ContractStateIndexer::<MockState>::load_store(&indexer.file).unwrap();
+        assert_eq!(loaded.state.unwrap().0, vec![1, 2, 3]);
+
+        // Without a backup, the store isn't silently reset
+        std::fs::remove_file(ContractStateIndexer::<MockState>::backup_path(
+            &indexer.file,
+        ))
+        .unwrap();
+        assert!(ContractStateIndexer::<MockState>::load_store(&indexer.file).is_err());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_stream_large_state() {
+        use crate::indexer::contract_handlers::get_state_stream;
+        use axum::routing::get;
+        use axum_test::TestServer;
+
+        let state = MockState((0..3_000_000).map(|i| (i % 251) as u8).collect());
+        let store = Arc::new(RwLock::new(Store::<MockState>::default()));
+        let server = TestServer::new(
+            axum::Router::new()
+                .route("/state/stream", get(get_state_stream::<MockState>))
+                .with_state(store.clone()),
+        )
+        .unwrap();
+
+        server.get("/state/stream").await.assert_status_not_found();
+
+        store.write().await.state = Some(state.clone());
+        let response = server.get("/state/stream").await;
+        response.assert_status_ok();
+
+        let (received, _): (MockState, _) =
+            bincode::decode_from_slice(response.as_bytes(), bincode::config::standard()).unwrap();
+        assert_eq!(received.0, state.0);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_reconcile_detects_divergence() {
+        let contract_name = ContractName::from("test_contract");
+        let bus = SharedMessageBus::new(BusMetrics::global("global".to_string()));
+        let mut alerts =
+            crate::bus::dont_use_this::get_receiver::<ContractStateIndexerEvent>(&bus).await;
+        let mut indexer = build_indexer_on_bus(contract_name.clone(), bus).await;
+        register_contract(&mut indexer).await;
+
+        let block = |height: u64, digest: Vec<u8>| Block {
+            block_height: BlockHeight(height),
+            updated_states: BTreeMap::from([(contract_name.clone(), StateDigest(digest))]),
+            ..Block::default()
+        };
+
+        // Same state as the node
+        indexer.store.write().await.state = Some(MockState(vec![1, 2, 3]));
+        indexer
+            .handle_processed_block(&block(1, vec![1, 2, 3]))
+            .await
+            .unwrap();
+        indexer.reconcile().await.unwrap();
+        assert!(alerts.try_recv().is_err());
+
+        // The node recorded another state
+        indexer
+            .handle_processed_block(&block(2, vec![4, 5, 6]))
+            .await
+            .unwrap();
+        indexer.reconcile().await.unwrap();
+        let ContractStateIndexerEvent::StateDivergence {
+            contract_name: diverged,
+            block_height,
+            node_digest,
+            indexer_digest,
+        } = alerts.try_recv().unwrap();
+        assert_eq!(diverged, contract_name);
+        assert_eq!(block_height, BlockHeight(2));
+        assert_eq!(node_digest, StateDigest(vec![4, 5, 6]));
+        assert_eq!(indexer_digest, StateDigest(vec![1, 2, 3]));
+    }
 }
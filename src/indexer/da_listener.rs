@@ -73,7 +73,7 @@ impl Module for DAListener {
                 .data_directory
                 .join("da_listener_node_state.bin")
                 .as_path(),
-        );
+        )?;
 
         for name in node_state.contracts.keys() {
             info!("📝 Loaded contract state for {}", name);
@@ -0,0 +1,2 @@
+-- State root over all contract digests, once the block is applied
+ALTER TABLE blocks ADD COLUMN state_root TEXT;
@@ -0,0 +1,5 @@
+-- Contract the proof was submitted for, backfilled from the outputs it verified
+ALTER TABLE proofs ADD COLUMN contract_name TEXT NOT NULL DEFAULT '';
+UPDATE proofs SET contract_name = bpo.contract_name
+FROM blob_proof_outputs bpo
+WHERE bpo.proof_tx_hash = proofs.tx_hash;
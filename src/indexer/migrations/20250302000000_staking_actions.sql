@@ -0,0 +1,11 @@
+-- Staking actions applied by each block, in the order they were applied
+CREATE TABLE staking_actions (
+    block_hash TEXT NOT NULL REFERENCES blocks(hash) ON DELETE CASCADE,
+    block_height BIGINT NOT NULL,
+    index INT NOT NULL,                -- Index of the action within the block
+    identity TEXT NOT NULL,            -- Identity that performed the action, "consensus" for slashes
+    action JSONB NOT NULL,
+    PRIMARY KEY (block_hash, index)
+);
+
+CREATE INDEX staking_actions_identity ON staking_actions (identity, block_height);
@@ -0,0 +1,9 @@
+-- Program of a contract before each block that updated it, to revert the update on a reorg
+CREATE TABLE contract_program_updates (
+    contract_name TEXT NOT NULL,
+    block_hash TEXT NOT NULL REFERENCES blocks(hash) ON DELETE CASCADE,
+    block_height BIGINT NOT NULL,
+    previous_verifier TEXT NOT NULL,
+    previous_program_id BYTEA NOT NULL,
+    PRIMARY KEY (contract_name, block_hash)
+);
@@ -5,10 +5,11 @@ use crate::{
     consensus::{CommittedConsensusProposal, ConsensusEvent},
     genesis::GenesisEvent,
     mempool::storage::Storage,
+    model::utils::get_current_timestamp_ms,
     model::*,
     module_handle_messages,
-    node_state::module::NodeStateEvent,
-    p2p::network::OutboundMessage,
+    node_state::{max_tx_version_at, module::NodeStateEvent},
+    p2p::network::{OutboundMessage, PeerEvent},
     tcp_server::TcpServerMessage,
     utils::{
         conf::SharedConf,
@@ -24,6 +25,7 @@ use api::RestApiMessage;
 use bincode::{Decode, Encode};
 use hyle_contract_sdk::{ContractName, ProgramId, Verifier};
 use metrics::MempoolMetrics;
+use seen_txs::SeenTxs;
 use serde::{Deserialize, Serialize};
 use staking::state::Staking;
 use std::{
@@ -37,10 +39,11 @@ use storage::{DataProposalVerdict, LaneBytesSize, LaneEntry};
 use strum_macros::IntoStaticStr;
 use tracing::{debug, error, info, trace, warn};
 
-use verifiers::{verify_proof, verify_recursive_proof};
+use verifiers::{verify_recursive_proof, ProgramStore, VerifierBackends, VerifierRegistry};
 
 pub mod api;
 pub mod metrics;
+pub mod seen_txs;
 pub mod storage;
 pub mod verifiers;
 
@@ -70,6 +73,14 @@ impl KnownContracts {
             (verifier.clone(), program_id.clone()),
         );
     }
+
+    /// Whether a contract is registered with this program, directly or referenced by hash.
+    fn is_registered_program(&self, program_id: &ProgramId) -> bool {
+        let hash_reference = ProgramStore::hash_reference(&program_id.0);
+        self.0
+            .values()
+            .any(|(_, registered)| registered == program_id || registered == &hash_reference)
+    }
 }
 
 module_bus_client! {
@@ -84,6 +95,7 @@ struct MempoolBusClient {
     receiver(ConsensusEvent),
     receiver(GenesisEvent),
     receiver(NodeStateEvent),
+    receiver(PeerEvent),
     receiver(Query<QueryNewCut, Cut>),
 }
 }
@@ -93,19 +105,40 @@ pub struct MempoolStore {
     storage: Storage,
     buffered_proposals: BTreeMap<ValidatorPublicKey, Vec<DataProposal>>,
     pending_txs: Vec<Transaction>,
+    /// Slot at which each pending tx was received, to evict stale ones
+    pending_txs_slot: BTreeMap<TxHash, Slot>,
+    seen_txs: SeenTxs,
     last_ccp: Option<CommittedConsensusProposal>,
     blocks_under_contruction: VecDeque<BlockUnderConstruction>,
     buc_build_start_height: Option<u64>,
     staking: Staking,
     known_contracts: Arc<std::sync::RwLock<KnownContracts>>,
+    programs: Arc<std::sync::RwLock<ProgramStore>>,
+    /// Expiry of the authorizations received, they are rejected if submitted again until then
+    authorized_txs: BTreeMap<TxHash, u64>,
+    /// Height of the last block handled by the node state
+    block_height: BlockHeight,
 }
 
+/// Maximum size in bytes of the transactions of a data proposal. Validators refuse
+/// larger data proposals from their peers, so it is the same for the whole network.
+pub const MAX_DATA_PROPOSAL_SIZE: usize = 10_485_760; // 10 MB
+
+/// Longest validity accepted for a blob tx authorization, which bounds how long it is remembered.
+pub const MAX_BLOB_TX_AUTHORIZATION_VALIDITY_MS: u64 = 3_600_000; // 1 hour
+
 pub struct Mempool {
     bus: MempoolBusClient,
     file: Option<PathBuf>,
     conf: SharedConf,
     crypto: SharedBlstCrypto,
     metrics: MempoolMetrics,
+    /// Protocol verifiers, peers' data proposals are verified with them
+    verifiers: Arc<VerifierRegistry>,
+    /// `verifiers` with the configured remappings, for proofs submitted to this node
+    local_verifiers: Arc<VerifierRegistry>,
+    /// [`MAX_DATA_PROPOSAL_SIZE`], only lowered by tests
+    max_data_proposal_size: usize,
     inner: MempoolStore,
 }
 
@@ -130,6 +163,10 @@ pub enum MempoolNetMessage {
     PoDAUpdate(DataProposalHash, Vec<SignedByValidator<MempoolNetMessage>>),
     SyncRequest(Option<DataProposalHash>, Option<DataProposalHash>),
     SyncReply(Vec<LaneEntry>),
+    /// A program referenced by hash, sent to the validators that requested it
+    Program(Vec<u8>),
+    /// Asks for the program of a registered contract, referenced by hash
+    ProgramRequest(ProgramId),
 }
 
 impl Display for MempoolNetMessage {
@@ -145,6 +182,8 @@ impl BusMessage for MempoolNetMessage {}
 pub enum MempoolEvent {
     BuiltSignedBlock(SignedBlock),
     StartedBuildingBlocks(BlockHeight),
+    /// The tx waited more than `pending_tx_ttl` slots for a data proposal
+    PendingTxEvicted(TxHash),
 }
 impl BusMessage for MempoolEvent {}
 
@@ -175,7 +214,7 @@ impl Module for Mempool {
                 .data_directory
                 .join("mempool_lanes_tip.bin")
                 .as_path(),
-        )
+        )?
         .unwrap_or_default();
 
         let attributes = Self::load_from_disk::<MempoolStore>(
@@ -184,7 +223,7 @@ impl Module for Mempool {
                 .data_directory
                 .join("mempool.bin")
                 .as_path(),
-        )
+        )?
         .unwrap_or(MempoolStore {
             storage: Storage::new(ctx.node.crypto.validator_pubkey().clone(), lanes_tip),
             ..MempoolStore::default()
@@ -200,12 +239,19 @@ impl Module for Mempool {
             .entry("hyle".into())
             .or_insert_with(|| (Verifier("hyle".to_owned()), ProgramId(vec![])));
 
+        let noir = ctx.common.config.noir_verifier();
+        let verifiers = VerifierRegistry::new(&noir);
+        let local_verifiers = verifiers.with_local_backends(&ctx.common.config.verifiers, &noir);
+
         Ok(Mempool {
             bus,
             file: Some(ctx.common.config.data_directory.clone()),
             conf: ctx.common.config.clone(),
             metrics,
             crypto: Arc::clone(&ctx.node.crypto),
+            verifiers: Arc::new(verifiers),
+            local_verifiers: Arc::new(local_verifiers),
+            max_data_proposal_size: MAX_DATA_PROPOSAL_SIZE,
             inner: attributes,
         })
     }
@@ -247,10 +293,11 @@ impl Mempool {
                     .log_error("Handling ConsensusEvent in Mempool");
             }
             listen<NodeStateEvent> cmd => {
-                let NodeStateEvent::NewBlock(block) = cmd;
-                for (_, contract) in block.registered_contracts {
-                    self.handle_contract_registration(contract);
-                }
+                self.handle_node_state_event(cmd);
+            }
+            listen<PeerEvent> PeerEvent::NewPeer { pubkey, .. } => {
+                let _ = self.on_new_peer(pubkey)
+                    .log_error("Requesting missing programs to new peer");
             }
             command_response<QueryNewCut, Cut> staking => {
                 Ok(self.handle_querynewcut(staking))
@@ -276,14 +323,44 @@ impl Mempool {
         Ok(())
     }
 
+    fn handle_node_state_event(&mut self, event: NodeStateEvent) {
+        let NodeStateEvent::NewBlock(block) = event;
+        self.block_height = block.block_height;
+        // Txs included in a block can't be submitted again
+        for tx in &block.txs {
+            self.seen_txs.insert(tx.hash(), self.conf.max_seen_txs);
+        }
+        for (_, contract) in block.registered_contracts {
+            self.handle_contract_registration(contract);
+        }
+        // Upgrades replace the program and verifier proofs are checked against
+        for (_, update) in block.updated_programs {
+            self.handle_contract_registration(update);
+        }
+    }
+
     fn handle_contract_registration(&mut self, effect: RegisterContractEffect) {
         #[allow(clippy::expect_used, reason = "not held across await")]
-        let mut known_contracts = self.known_contracts.write().expect("logic issue");
-        known_contracts.register_contract(
-            &effect.contract_name,
-            &effect.verifier,
-            &effect.program_id,
-        );
+        self.known_contracts
+            .write()
+            .expect("logic issue")
+            .register_contract(&effect.contract_name, &effect.verifier, &effect.program_id);
+        self.on_registered_program(&effect.program_id);
+    }
+
+    /// Fetches the program of a registered contract from the peers when we don't have it.
+    fn on_registered_program(&mut self, program_id: &ProgramId) {
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        let missing = self
+            .programs
+            .write()
+            .expect("logic issue")
+            .on_registered_program(program_id);
+        if missing {
+            let _ = self
+                .broadcast_net_message(MempoolNetMessage::ProgramRequest(program_id.clone()))
+                .log_error("Requesting program");
+        }
     }
 
     // Optimistically parse Hyle tx blobs
@@ -311,20 +388,155 @@ impl Mempool {
 
     fn handle_api_message(&mut self, command: RestApiMessage) -> Result<()> {
         match command {
-            RestApiMessage::NewTx(tx) => self
-                .on_new_tx(tx)
-                .context("Received invalid transaction. Won't process it"),
+            RestApiMessage::NewTx(tx) => {
+                self.check_tx_version(&tx)?;
+                self.check_unsigned_tx_allowed(&tx)?;
+                self.on_new_tx(tx)
+                    .context("Received invalid transaction. Won't process it")
+            }
+            RestApiMessage::NewSignedBlobTx(signed_tx) => {
+                self.on_new_signed_blob_tx(signed_tx, get_current_timestamp_ms())
+            }
+            RestApiMessage::NewRecursiveProofTx(tx) => {
+                self.on_new_proof_tx(TransactionData::Proof(tx).into(), true);
+                Ok(())
+            }
+            RestApiMessage::RegisterProgram(program) => {
+                #[allow(clippy::expect_used, reason = "not held across await")]
+                let program_id = self
+                    .programs
+                    .write()
+                    .expect("logic issue")
+                    .insert(program)?;
+                info!("📦 Stored program {}", hex::encode(&program_id.0));
+                Ok(())
+            }
         }
     }
 
     fn handle_tcp_server_message(&mut self, command: TcpServerMessage) -> Result<()> {
         match command {
-            TcpServerMessage::NewTx(tx) => self
-                .on_new_tx(tx)
-                .context("Received invalid transaction. Won't process it"),
+            TcpServerMessage::NewTx(tx) => {
+                self.check_tx_version(&tx)?;
+                self.check_unsigned_tx_allowed(&tx)?;
+                self.on_new_tx(tx)
+                    .context("Received invalid transaction. Won't process it")
+            }
         }
     }
 
+    /// Transactions from a future version would otherwise be processed with the current logic.
+    /// Versions must be supported by this node and active on the network for the next block.
+    fn check_tx_version(&self, tx: &Transaction) -> Result<()> {
+        if tx.version > self.conf.max_supported_tx_version {
+            bail!(
+                "Tx {} has version {}, but this node supports versions up to {}. Won't process it",
+                tx.hash(),
+                tx.version,
+                self.conf.max_supported_tx_version
+            );
+        }
+        let next_height = self.block_height + 1;
+        if tx.version > max_tx_version_at(next_height) {
+            bail!(
+                "Tx {} has version {}, which is not active at height {}. Won't process it",
+                tx.hash(),
+                tx.version,
+                next_height
+            );
+        }
+        Ok(())
+    }
+
+    /// When an allowlist is configured, blob transactions can only be submitted signed.
+    fn check_unsigned_tx_allowed(&self, tx: &Transaction) -> Result<()> {
+        if !self.conf.blob_tx_allowlist.is_empty()
+            && matches!(tx.transaction_data, TransactionData::Blob(_))
+        {
+            bail!(
+                "Blob tx {} must be signed by an allowlisted key. Won't process it",
+                tx.hash()
+            );
+        }
+        Ok(())
+    }
+
+    fn on_new_signed_blob_tx(&mut self, signed_tx: SignedBlobTransaction, now: u64) -> Result<()> {
+        let SignedBlobTransaction { tx, authorization } = signed_tx;
+        let tx_hash = tx.hash();
+        if authorization.msg.tx_hash != tx_hash {
+            bail!(
+                "Authorization is for blob tx {}, not {}. Won't process it",
+                authorization.msg.tx_hash,
+                tx_hash
+            );
+        }
+        self.verify_blob_tx_authorization(&authorization)?;
+        self.check_blob_tx_authorization_not_replayed(&authorization, now)?;
+        self.on_new_tx(TransactionData::Blob(tx).into())
+            .context("Received invalid transaction. Won't process it")?;
+        self.authorized_txs
+            .insert(tx_hash, authorization.msg.valid_until);
+        Ok(())
+    }
+
+    fn verify_blob_tx_authorization(
+        &self,
+        authorization: &SignedByValidator<BlobTxAuthorization>,
+    ) -> Result<()> {
+        let signer = &authorization.signature.validator;
+        if !BlstCrypto::verify(authorization)? {
+            bail!(
+                "Invalid signature from {} for blob tx {}. Won't process it",
+                signer,
+                authorization.msg.tx_hash
+            );
+        }
+        if !self.conf.blob_tx_allowlist.is_empty() && !self.conf.blob_tx_allowlist.contains(signer)
+        {
+            bail!(
+                "{} is not allowed to submit blob tx {}. Won't process it",
+                signer,
+                authorization.msg.tx_hash
+            );
+        }
+        Ok(())
+    }
+
+    /// Authorizations are remembered until they expire, seen_txs alone would forget them earlier.
+    fn check_blob_tx_authorization_not_replayed(
+        &mut self,
+        authorization: &SignedByValidator<BlobTxAuthorization>,
+        now: u64,
+    ) -> Result<()> {
+        self.authorized_txs
+            .retain(|_, valid_until| *valid_until >= now);
+        let BlobTxAuthorization {
+            tx_hash,
+            valid_until,
+        } = &authorization.msg;
+        if *valid_until < now {
+            bail!(
+                "Authorization of blob tx {} has expired. Won't process it",
+                tx_hash
+            );
+        }
+        if *valid_until > now + MAX_BLOB_TX_AUTHORIZATION_VALIDITY_MS {
+            bail!(
+                "Authorization of blob tx {} is valid for more than {}ms. Won't process it",
+                tx_hash,
+                MAX_BLOB_TX_AUTHORIZATION_VALIDITY_MS
+            );
+        }
+        if self.authorized_txs.contains_key(tx_hash) {
+            bail!(
+                "Blob tx {} was already submitted with an authorization. Won't process it",
+                tx_hash
+            );
+        }
+        Ok(())
+    }
+
     fn handle_internal_event(&mut self, event: InternalMempoolEvent) -> Result<()> {
         match event {
             InternalMempoolEvent::OnProcessedNewTx(tx) => {
@@ -337,11 +549,139 @@ impl Mempool {
         }
     }
 
+    /// Pending txs for the next data proposal, up to `MAX_DATA_PROPOSAL_SIZE` bytes.
+    /// Over the limit, proofs are taken first, as they settle txs that were already proposed,
+    /// then blob txs in arrival order. Fees are not charged by the node state, so they can't
+    /// be trusted to order txs. The others are kept for a later proposal, along with the
+    /// proofs of the blob txs kept. Taken txs keep their arrival order.
+    fn take_pending_txs(&mut self) -> Vec<Transaction> {
+        let max_size = self.max_data_proposal_size;
+        let sizes: Vec<usize> = self
+            .pending_txs
+            .iter()
+            .map(|tx| tx.estimate_size())
+            .collect();
+        if sizes.iter().sum::<usize>() <= max_size {
+            self.pending_txs_slot.clear();
+            return std::mem::take(&mut self.pending_txs);
+        }
+
+        // (index, is_proof, size)
+        let mut by_priority: Vec<(usize, bool, usize)> = self
+            .pending_txs
+            .iter()
+            .zip(sizes)
+            .enumerate()
+            .map(|(i, (tx, size))| {
+                let is_proof = !matches!(tx.transaction_data, TransactionData::Blob(_));
+                (i, is_proof, size)
+            })
+            .collect();
+        // Stable, so txs of a kind keep their arrival order
+        by_priority.sort_by_key(|(_, is_proof, _)| !is_proof);
+
+        let mut taken = vec![false; self.pending_txs.len()];
+        let mut size = 0;
+        for (i, _, tx_size) in by_priority {
+            if size + tx_size <= max_size {
+                size += tx_size;
+                if let Some(is_taken) = taken.get_mut(i) {
+                    *is_taken = true;
+                }
+            }
+        }
+
+        // A proof can't be proposed before the blob tx it settles
+        let deferred_blob_txs: HashSet<TxHash> = self
+            .pending_txs
+            .iter()
+            .zip(&taken)
+            .filter(|(tx, taken)| {
+                !**taken && matches!(tx.transaction_data, TransactionData::Blob(_))
+            })
+            .map(|(tx, _)| tx.hash())
+            .collect();
+        for (tx, is_taken) in self.pending_txs.iter().zip(taken.iter_mut()) {
+            if let TransactionData::VerifiedProof(proof_tx) = &tx.transaction_data {
+                if proof_tx
+                    .proven_blobs
+                    .iter()
+                    .any(|blob| deferred_blob_txs.contains(&blob.blob_tx_hash))
+                {
+                    *is_taken = false;
+                }
+            }
+        }
+
+        let (new_txs, deferred): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending_txs)
+            .into_iter()
+            .zip(taken)
+            .partition(|(_, taken)| *taken);
+        self.pending_txs = deferred.into_iter().map(|(tx, _)| tx).collect();
+        for (tx, _) in &new_txs {
+            self.pending_txs_slot.remove(&tx.hash());
+        }
+        self.metrics.snapshot_pending_tx(self.pending_txs.len());
+        if !self.pending_txs.is_empty() {
+            debug!(
+                "🚗 {} txs deferred to a later data proposal",
+                self.pending_txs.len()
+            );
+        }
+        new_txs.into_iter().map(|(tx, _)| tx).collect()
+    }
+
+    /// Drops the pending txs received more than `pending_tx_ttl` slots ago.
+    /// Txs are only taken out of `pending_txs` by the mempool itself, so a tx is either
+    /// proposed or evicted.
+    /// Pending txs are proposed on every tick, so this only evicts the txs deferred by the
+    /// data proposal size limit. Proposed txs that are never proven are not pending anymore:
+    /// they wait in the node state, which times them out after its `BLOB_TIMEOUT` blocks.
+    fn evict_stale_pending_txs(&mut self) -> Result<()> {
+        let ttl = self.conf.pending_tx_ttl;
+        let Some(slot) = self
+            .last_ccp
+            .as_ref()
+            .map(|ccp| ccp.consensus_proposal.slot)
+        else {
+            return Ok(());
+        };
+        if ttl == 0 || self.pending_txs.is_empty() {
+            return Ok(());
+        }
+
+        let (stale, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending_txs)
+            .into_iter()
+            .partition(|tx| {
+                self.pending_txs_slot
+                    .get(&tx.hash())
+                    .is_some_and(|received| received + ttl < slot)
+            });
+        self.pending_txs = pending;
+        for tx in stale {
+            let tx_hash = tx.hash();
+            warn!(
+                "🗑️ Evicting tx {} not proposed after {} slots",
+                tx_hash, ttl
+            );
+            self.pending_txs_slot.remove(&tx_hash);
+            // It may be submitted again
+            self.seen_txs.remove(&tx_hash);
+            self.authorized_txs.remove(&tx_hash);
+            self.metrics.add_evicted_tx(&tx);
+            self.bus
+                .send(MempoolEvent::PendingTxEvicted(tx_hash))
+                .context("Sending PendingTxEvicted event")?;
+        }
+        self.metrics.snapshot_pending_tx(self.pending_txs.len());
+        Ok(())
+    }
+
     fn handle_data_proposal_management(&mut self) -> Result<()> {
         trace!("🌝 Handling DataProposal management");
         // Create new DataProposal with pending txs
         let crypto = self.crypto.clone();
-        let new_txs = std::mem::take(&mut self.pending_txs);
+        let new_txs = self.take_pending_txs();
         self.storage.new_data_proposal(&crypto, new_txs); // TODO: copy crypto in storage
 
         // Check for each pending DataProposal if it has enough signatures
@@ -548,6 +888,8 @@ impl Mempool {
 
                 self.try_create_block_under_construction(cpp);
 
+                self.evict_stale_pending_txs()?;
+
                 self.try_to_send_full_signed_blocks()?;
 
                 // Fetch in advance data proposals
@@ -560,6 +902,7 @@ impl Mempool {
 
                 Ok(())
             }
+            ConsensusEvent::ValidatorEquivocation(_) => Ok(()),
         }
     }
 
@@ -625,6 +968,45 @@ impl Mempool {
             MempoolNetMessage::SyncReply(lane_entries) => {
                 self.on_sync_reply(validator, lane_entries)?;
             }
+            MempoolNetMessage::Program(program) => {
+                // Stored by hash, a peer can't make it resolve a program id to another program
+                #[allow(clippy::expect_used, reason = "not held across await")]
+                self.programs
+                    .write()
+                    .expect("logic issue")
+                    .insert_wanted(program)
+                    .with_context(|| format!("Refusing program from {}", validator))?;
+            }
+            MempoolNetMessage::ProgramRequest(program_id) => {
+                #[allow(clippy::expect_used, reason = "not held across await")]
+                let program = self
+                    .programs
+                    .read()
+                    .expect("logic issue")
+                    .get(&program_id)
+                    .cloned();
+                if let Some(program) = program {
+                    self.send_net_message(validator.clone(), MempoolNetMessage::Program(program))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Requests the programs we are still missing to a new peer, which may have them
+    fn on_new_peer(&mut self, pubkey: ValidatorPublicKey) -> Result<()> {
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        let wanted: Vec<ProgramId> = self
+            .programs
+            .read()
+            .expect("logic issue")
+            .wanted()
+            .collect();
+        for program_id in wanted {
+            self.send_net_message(
+                pubkey.clone(),
+                MempoolNetMessage::ProgramRequest(program_id),
+            )?;
         }
         Ok(())
     }
@@ -783,6 +1165,17 @@ impl Mempool {
             data_proposal.estimate_size()
         );
         let data_proposal_hash = data_proposal.hash();
+        let max_size = self.max_data_proposal_size;
+        let txs_size: usize = data_proposal.txs.iter().map(|tx| tx.estimate_size()).sum();
+        if txs_size > max_size {
+            error!(
+                "Refusing DataProposal {} from {}: {} bytes of txs, max {}",
+                data_proposal_hash, validator, txs_size, max_size
+            );
+            self.drop_buffered_children(validator, data_proposal_hash);
+            return Ok(());
+        }
+        // Txs of peers' data proposals never went through our ingress checks
         let (verdict, lane_size) = self.storage.on_data_proposal(validator, &data_proposal);
         match verdict {
             DataProposalVerdict::Empty => {
@@ -800,11 +1193,18 @@ impl Mempool {
             DataProposalVerdict::Process => {
                 trace!("Further processing for DataProposal");
                 let kc = self.known_contracts.clone();
+                let programs = self.programs.clone();
+                let verifiers = self.verifiers.clone();
                 let sender: &tokio::sync::broadcast::Sender<InternalMempoolEvent> = self.bus.get();
                 let sender = sender.clone();
                 let validator = validator.clone();
                 tokio::task::spawn_blocking(move || {
-                    let decision = Storage::process_data_proposal(&mut data_proposal, kc);
+                    let decision = Storage::process_data_proposal(
+                        &mut data_proposal,
+                        kc,
+                        programs,
+                        &verifiers,
+                    );
                     sender.send(InternalMempoolEvent::OnProcessedDataProposal((
                         validator,
                         decision,
@@ -836,11 +1236,38 @@ impl Mempool {
             }
             DataProposalVerdict::Refuse => {
                 debug!("Refuse vote for DataProposal");
+                self.drop_buffered_children(validator, data_proposal_hash);
             }
         }
         Ok(())
     }
 
+    /// Drops the buffered data proposals built on a refused one, we won't vote for them either.
+    fn drop_buffered_children(
+        &mut self,
+        validator: &ValidatorPublicKey,
+        refused: DataProposalHash,
+    ) {
+        let Some(buffered) = self.buffered_proposals.get_mut(validator) else {
+            return;
+        };
+        let mut refused = vec![refused];
+        while let Some(parent) = refused.pop() {
+            buffered.retain(|data_proposal| {
+                if data_proposal.parent_data_proposal_hash.as_ref() != Some(&parent) {
+                    return true;
+                }
+                let hash = data_proposal.hash();
+                warn!(
+                    "Dropping buffered DataProposal {} from {}: its parent {} was refused",
+                    hash, validator, parent
+                );
+                refused.push(hash);
+                false
+            });
+        }
+    }
+
     fn on_processed_data_proposal(
         &mut self,
         validator: ValidatorPublicKey,
@@ -874,6 +1301,7 @@ impl Mempool {
             }
             DataProposalVerdict::Refuse => {
                 debug!("Refuse vote for DataProposal");
+                self.drop_buffered_children(&validator, data_proposal_hash);
             }
         }
         Ok(())
@@ -882,6 +1310,23 @@ impl Mempool {
     fn on_new_tx(&mut self, tx: Transaction) -> Result<()> {
         // TODO: Verify fees ?
 
+        let max_size = self.max_data_proposal_size;
+        if tx.estimate_size() > max_size {
+            bail!(
+                "Transaction {} does not fit in a data proposal ({} bytes, max {})",
+                tx.hash(),
+                tx.estimate_size(),
+                max_size
+            );
+        }
+        if self.seen_txs.contains(&tx.hash()) {
+            self.metrics.add_duplicate_tx(&tx);
+            bail!(
+                "Duplicate transaction {}, already pending or included in a block",
+                tx.hash()
+            );
+        }
+
         match tx.transaction_data {
             TransactionData::Blob(ref blob_tx) => {
                 debug!("Got new blob tx {}", tx.hash());
@@ -892,20 +1337,13 @@ impl Mempool {
                 // TODO: would be good to not need to clone here.
                 self.handle_hyle_contract_registration(blob_tx);
             }
-            TransactionData::Proof(_) => {
-                let kc = self.known_contracts.clone();
-                let sender: &tokio::sync::broadcast::Sender<InternalMempoolEvent> = self.bus.get();
-                let sender = sender.clone();
-                tokio::task::spawn_blocking(move || {
-                    let tx =
-                        Self::process_proof_tx(kc, tx).log_error("Error processing proof tx")?;
-                    sender
-                        .send(InternalMempoolEvent::OnProcessedNewTx(tx))
-                        .log_warn("sending processed TX")
-                });
+            TransactionData::Proof(ref proof_tx) => {
+                self.check_contract_not_paused(&proof_tx.contract_name)?;
+                self.on_new_proof_tx(tx, false);
                 return Ok(());
             }
             TransactionData::VerifiedProof(ref proof_tx) => {
+                self.check_contract_not_paused(&proof_tx.contract_name)?;
                 debug!(
                     "Got verified proof tx {} for {}",
                     tx.hash(),
@@ -917,15 +1355,62 @@ impl Mempool {
         let tx_type: &'static str = (&tx.transaction_data).into();
 
         self.metrics.add_api_tx(tx_type);
+        let slot = self
+            .last_ccp
+            .as_ref()
+            .map_or(0, |ccp| ccp.consensus_proposal.slot);
+        self.pending_txs_slot.entry(tx.hash()).or_insert(slot);
+        // Proofs are only remembered once verified, their verification may depend on a pending registration
+        self.seen_txs.insert(tx.hash(), self.conf.max_seen_txs);
         self.pending_txs.push(tx);
         self.metrics.snapshot_pending_tx(self.pending_txs.len());
 
         Ok(())
     }
 
+    fn check_contract_not_paused(&self, contract_name: &ContractName) -> Result<()> {
+        if self.conf.paused_contracts.contains(contract_name) {
+            bail!(
+                "Contract {} is paused, its proofs are rejected",
+                contract_name
+            );
+        }
+        Ok(())
+    }
+
+    /// Verifies the proof on the blocking pool, the verified tx is then handled as a new tx.
+    /// `recursive` requires the proof to be verified as a recursive proof.
+    fn on_new_proof_tx(&mut self, tx: Transaction, recursive: bool) {
+        let kc = self.known_contracts.clone();
+        let programs = self.programs.clone();
+        let conf = self.conf.clone();
+        let verifiers = self.local_verifiers.clone();
+        let sender: &tokio::sync::broadcast::Sender<InternalMempoolEvent> = self.bus.get();
+        let sender = sender.clone();
+        tokio::task::spawn_blocking(move || {
+            let tx =
+                Self::process_proof_tx(kc, programs, &conf.verifiers, &verifiers, tx, recursive)
+                    .log_error("Error processing proof tx")?;
+            Self::check_proof_freshness(
+                &tx,
+                get_current_timestamp_ms() as u128,
+                conf.max_proof_age as u128 * 1000,
+                conf.max_clock_skew as u128 * 1000,
+            )
+            .log_error("Rejecting proof tx")?;
+            sender
+                .send(InternalMempoolEvent::OnProcessedNewTx(tx))
+                .log_warn("sending processed TX")
+        });
+    }
+
     fn process_proof_tx(
         known_contracts: Arc<std::sync::RwLock<KnownContracts>>,
+        programs: Arc<std::sync::RwLock<ProgramStore>>,
+        backends: &VerifierBackends,
+        verifiers: &VerifierRegistry,
         mut tx: Transaction,
+        recursive: bool,
     ) -> Result<Transaction> {
         let TransactionData::Proof(proof_transaction) = tx.transaction_data else {
             bail!("Can only process ProofTx");
@@ -939,16 +1424,41 @@ impl Mempool {
             .get(&proof_transaction.contract_name)
             .context("Contract unknown")?
             .clone();
+        // Only verification uses the full program, proven blobs keep the registered program_id.
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        let resolved_program_id = programs.read().expect("logic error").resolve(&program_id)?;
 
         let is_recursive = proof_transaction.contract_name.0 == "risc0-recursion";
+        if recursive && !is_recursive {
+            bail!(
+                "Contract {} can't verify recursive proofs",
+                proof_transaction.contract_name
+            );
+        }
 
         let (hyle_outputs, program_ids) = if is_recursive {
-            let (program_ids, hyle_outputs) =
-                verify_recursive_proof(&proof_transaction.proof, &verifier, &program_id)
-                    .context("verify_rec_proof")?;
+            let (program_ids, hyle_outputs) = verify_recursive_proof(
+                &proof_transaction.proof,
+                &verifier,
+                &resolved_program_id,
+                backends,
+            )
+            .context("verify_rec_proof")?;
+            #[allow(clippy::expect_used, reason = "not held across await")]
+            let known_contracts = known_contracts.read().expect("logic error");
+            if let Some(program_id) = program_ids
+                .iter()
+                .find(|program_id| !known_contracts.is_registered_program(program_id))
+            {
+                bail!(
+                    "Recursive proof verifies unregistered program {}",
+                    hex::encode(&program_id.0)
+                );
+            }
             (hyle_outputs, program_ids)
         } else {
-            let hyle_outputs = verify_proof(&proof_transaction.proof, &verifier, &program_id)
+            let hyle_outputs = verifiers
+                .verify(&proof_transaction.proof, &verifier, &resolved_program_id)
                 .context("verify_proof")?;
             (hyle_outputs, vec![program_id.clone()])
         };
@@ -993,6 +1503,44 @@ impl Mempool {
         Ok(tx)
     }
 
+    /// Rejects verified proofs generated against a blob tx whose block timestamp (ms), from the
+    /// `tx_ctx` of the output, is older than `max_age` or ahead of `now` by more than `max_skew`.
+    /// No verifier exposes a timestamp of the proof itself, outputs without a `tx_ctx` are not
+    /// checked. A `max_age` of 0 disables the check.
+    fn check_proof_freshness(
+        tx: &Transaction,
+        now: u128,
+        max_age: u128,
+        max_skew: u128,
+    ) -> Result<()> {
+        let TransactionData::VerifiedProof(proof_tx) = &tx.transaction_data else {
+            bail!("Can only check verified proofs");
+        };
+        if max_age == 0 {
+            return Ok(());
+        }
+        for blob in proof_tx.proven_blobs.iter() {
+            let Some(tx_ctx) = &blob.hyle_output.tx_ctx else {
+                continue;
+            };
+            if tx_ctx.timestamp > now + max_skew {
+                bail!(
+                    "Proof for blob tx {} has a block timestamp {}ms in the future",
+                    blob.blob_tx_hash,
+                    tx_ctx.timestamp - now
+                );
+            }
+            if now.saturating_sub(tx_ctx.timestamp) > max_age {
+                bail!(
+                    "Proof for blob tx {} has a block timestamp too old ({}ms)",
+                    blob.blob_tx_hash,
+                    now - tx_ctx.timestamp
+                );
+            }
+        }
+        Ok(())
+    }
+
     fn send_vote(
         &mut self,
         validator: &ValidatorPublicKey,
@@ -1117,6 +1665,7 @@ pub mod test {
     use crate::model;
     use crate::p2p::network::NetMessage;
     use crate::tests::autobahn_testing::assert_chanmsg_matches;
+    use crate::utils::conf::Conf;
     use anyhow::Result;
     use assertables::assert_ok;
     use hyle_contract_sdk::StateDigest;
@@ -1140,9 +1689,15 @@ pub mod test {
             Mempool {
                 bus,
                 file: None,
-                conf: SharedConf::default(),
+                conf: Arc::new(Conf {
+                    max_supported_tx_version: 1,
+                    ..Conf::default()
+                }),
                 crypto: Arc::new(crypto),
                 metrics: MempoolMetrics::global("id".to_string()),
+                verifiers: Arc::new(VerifierRegistry::default_for_tests()),
+                local_verifiers: Arc::new(VerifierRegistry::default_for_tests()),
+                max_data_proposal_size: MAX_DATA_PROPOSAL_SIZE,
                 inner: MempoolStore {
                     storage,
                     ..MempoolStore::default()
@@ -1474,8 +2029,13 @@ pub mod test {
                 program_id: ProgramId(vec![]),
                 state_digest: StateDigest(vec![0, 1, 2, 3]),
                 contract_name: name,
+                blob_encoding: BlobEncoding::default(),
             }
             .as_blob("hyle".into(), None, None)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         }
         .into()
     }
@@ -1601,6 +2161,165 @@ pub mod test {
         Ok(())
     }
 
+    #[test_log::test(tokio::test)]
+    async fn test_pending_txs_are_sent_in_one_data_proposal() -> Result<()> {
+        let mut sender = MempoolTestCtx::new("sender").await;
+        let mut receiver = MempoolTestCtx::new("receiver").await;
+        let cryptos = [
+            (*sender.mempool.crypto).clone(),
+            (*receiver.mempool.crypto).clone(),
+        ];
+        sender.setup_node(&cryptos);
+        receiver.setup_node(&cryptos);
+
+        // Transactions submitted between two ticks are batched together
+        let txs = (0..5)
+            .map(|i| make_register_contract_tx(ContractName::new(format!("test{i}"))))
+            .collect::<Vec<_>>();
+        for tx in &txs {
+            sender.submit_tx(tx);
+        }
+        sender.make_data_proposal_with_pending_txs()?;
+
+        let signed_msg = sender.assert_broadcast("DataProposal");
+        assert!(sender.out_receiver.try_recv().is_err());
+        let MempoolNetMessage::DataProposal(data_proposal) = &signed_msg.msg else {
+            panic!("Expected DataProposal message");
+        };
+        assert_eq!(data_proposal.txs, txs);
+
+        receiver.mempool.handle_net_message(signed_msg.clone())?;
+        receiver.handle_processed_data_proposals().await;
+        let lane = receiver
+            .mempool
+            .storage
+            .lanes
+            .get(sender.validator_pubkey())
+            .unwrap();
+        assert_eq!(
+            lane.data_proposals.first().unwrap().1.data_proposal.txs,
+            txs
+        );
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_data_proposal_is_split_at_max_size() -> Result<()> {
+        let mut sender = MempoolTestCtx::new("sender").await;
+        let mut receiver = MempoolTestCtx::new("receiver").await;
+        let cryptos = [
+            (*sender.mempool.crypto).clone(),
+            (*receiver.mempool.crypto).clone(),
+        ];
+        sender.setup_node(&cryptos);
+        receiver.setup_node(&cryptos);
+
+        let txs = (0..10)
+            .map(|i| make_register_contract_tx(ContractName::new(format!("test{i}"))))
+            .collect::<Vec<_>>();
+        let tx_size = txs.first().unwrap().estimate_size();
+        sender.mempool.max_data_proposal_size = 4 * tx_size;
+        for tx in &txs {
+            sender.submit_tx(tx);
+        }
+
+        // A tx that can never fit in a data proposal is refused
+        let large_tx = make_register_contract_tx(ContractName::new("a".repeat(4 * tx_size)));
+        assert!(sender.mempool.on_new_tx(large_tx).is_err());
+
+        sender.make_data_proposal_with_pending_txs()?;
+        let signed_msg = sender.assert_broadcast("DataProposal");
+        let MempoolNetMessage::DataProposal(data_proposal) = &signed_msg.msg else {
+            panic!("Expected DataProposal message");
+        };
+        assert_eq!(data_proposal.txs.len(), 4);
+        assert_eq!(sender.mempool.pending_txs.len(), 6);
+        assert!(data_proposal
+            .txs
+            .iter()
+            .all(|tx| !sender.mempool.pending_txs.contains(tx)));
+
+        // Data proposals over the limit are refused by peers
+        receiver.mempool.max_data_proposal_size = 3 * tx_size;
+        receiver.mempool.handle_net_message(signed_msg.clone())?;
+        assert!(receiver.out_receiver.try_recv().is_err());
+        assert!(receiver
+            .mempool
+            .storage
+            .lanes
+            .get(sender.validator_pubkey())
+            .is_none_or(|lane| lane.data_proposals.is_empty()));
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_data_proposal_defers_txs_beyond_its_size_limit() -> Result<()> {
+        let mut ctx = MempoolTestCtx::new("mempool").await;
+        let crypto = (*ctx.mempool.crypto).clone();
+        ctx.setup_node(&[crypto, BlstCrypto::new("validator1".into()).unwrap()]);
+
+        let first_tx = make_register_contract_tx(ContractName::new("test1"));
+        // A fee is not charged, so it doesn't get a tx ahead of the others
+        let mut second_tx = make_register_contract_tx(ContractName::new("test2"));
+        if let TransactionData::Blob(blob_tx) = &mut second_tx.transaction_data {
+            blob_tx.fee = 10;
+        }
+        // Room for a single tx
+        ctx.mempool.max_data_proposal_size = second_tx.estimate_size();
+
+        ctx.submit_tx(&first_tx);
+        ctx.submit_tx(&second_tx);
+        ctx.make_data_proposal_with_pending_txs()?;
+
+        match ctx.assert_broadcast("DataProposal").msg {
+            MempoolNetMessage::DataProposal(dp) => assert_eq!(dp.txs, vec![first_tx]),
+            _ => panic!("Expected DataProposal message"),
+        };
+        assert_eq!(ctx.mempool.pending_txs, vec![second_tx.clone()]);
+
+        // The deferred tx goes in the next data proposal
+        ctx.make_data_proposal_with_pending_txs()?;
+        assert!(ctx.mempool.pending_txs.is_empty());
+        let lane = ctx
+            .mempool
+            .storage
+            .lanes
+            .get(ctx.validator_pubkey())
+            .unwrap();
+        assert_eq!(
+            lane.data_proposals.last().unwrap().1.data_proposal.txs,
+            vec![second_tx]
+        );
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_proof_is_deferred_with_its_blob_tx() -> Result<()> {
+        let mut ctx = MempoolTestCtx::new("mempool").await;
+
+        let first_tx = make_register_contract_tx(ContractName::new("test1"));
+        let second_tx = make_register_contract_tx(ContractName::new("test2"));
+        let proof_tx: Transaction = VerifiedProofTransaction {
+            contract_name: "test2".into(),
+            proof: None,
+            proof_hash: ProofDataHash::default(),
+            proven_blobs: vec![BlobProofOutput {
+                blob_tx_hash: second_tx.hash(),
+                ..BlobProofOutput::default()
+            }],
+            is_recursive: false,
+        }
+        .into();
+        // Room for the proof and a single blob tx
+        ctx.mempool.max_data_proposal_size = first_tx.estimate_size() + proof_tx.estimate_size();
+        ctx.mempool.pending_txs = vec![first_tx.clone(), second_tx.clone(), proof_tx.clone()];
+
+        let new_txs = ctx.mempool.take_pending_txs();
+        assert_eq!(new_txs, vec![first_tx]);
+        assert_eq!(ctx.mempool.pending_txs, vec![second_tx, proof_tx]);
+        Ok(())
+    }
+
     #[test_log::test(tokio::test)]
     async fn test_receiving_data_proposal() -> Result<()> {
         let mut ctx = MempoolTestCtx::new("mempool").await;
@@ -2000,6 +2719,153 @@ pub mod test {
         Ok(())
     }
 
+    #[test_log::test(tokio::test)]
+    async fn test_duplicate_txs_are_rejected() -> Result<()> {
+        let mut ctx = MempoolTestCtx::new("mempool").await;
+        ctx.mempool.conf = Arc::new(Conf {
+            max_seen_txs: 10,
+            ..(*ctx.mempool.conf).clone()
+        });
+
+        let tx = make_register_contract_tx(ContractName::new("test1"));
+        ctx.submit_tx(&tx);
+        let err = ctx
+            .mempool
+            .handle_api_message(RestApiMessage::NewTx(tx.clone()))
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("Duplicate transaction"));
+        assert_eq!(ctx.mempool.pending_txs, vec![tx]);
+
+        // Txs from other lanes are known once included in a block
+        let other_tx = make_register_contract_tx(ContractName::new("test2"));
+        ctx.mempool
+            .handle_node_state_event(NodeStateEvent::NewBlock(Box::new(Block {
+                txs: vec![other_tx.clone()],
+                ..Default::default()
+            })));
+        assert!(ctx
+            .mempool
+            .handle_api_message(RestApiMessage::NewTx(other_tx))
+            .is_err());
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_proof_for_upgraded_program() -> Result<()> {
+        let mut ctx = MempoolTestCtx::new("mempool").await;
+        let contract = |verifier: &str, program_id: &[u8]| RegisterContractEffect {
+            verifier: verifier.into(),
+            program_id: ProgramId(program_id.to_vec()),
+            state_digest: StateDigest(vec![]),
+            contract_name: "c1".into(),
+        };
+        ctx.mempool
+            .handle_node_state_event(NodeStateEvent::NewBlock(Box::new(Block {
+                registered_contracts: vec![(
+                    TxHash::new("register"),
+                    contract("test-panic", b"old"),
+                )],
+                ..Default::default()
+            })));
+        ctx.mempool
+            .handle_node_state_event(NodeStateEvent::NewBlock(Box::new(Block {
+                updated_programs: vec![(TxHash::new("upgrade"), contract("test", b"new"))],
+                ..Default::default()
+            })));
+        assert_eq!(
+            ctx.mempool
+                .known_contracts
+                .read()
+                .unwrap()
+                .0
+                .get(&"c1".into()),
+            Some(&(Verifier("test".to_owned()), ProgramId(b"new".to_vec())))
+        );
+
+        // The proof is verified with the new verifier, against the new program
+        ctx.submit_tx(
+            &ProofTransaction {
+                contract_name: "c1".into(),
+                proof: ProofData(bincode::encode_to_vec(
+                    vec![HyleOutput::default()],
+                    bincode::config::standard(),
+                )?),
+            }
+            .into(),
+        );
+        ctx.handle_processed_data_proposals().await;
+        let [pending] = ctx.mempool.pending_txs.as_slice() else {
+            panic!("Expected the verified proof to be pending");
+        };
+        let TransactionData::VerifiedProof(verified) = &pending.transaction_data else {
+            panic!("Expected a verified proof transaction");
+        };
+        assert_eq!(
+            verified.proven_blobs.first().unwrap().program_id,
+            ProgramId(b"new".to_vec())
+        );
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_stale_pending_txs_are_evicted() -> Result<()> {
+        let mut ctx = MempoolTestCtx::new("mempool").await;
+        ctx.mempool.conf = Arc::new(Conf {
+            pending_tx_ttl: 2,
+            ..(*ctx.mempool.conf).clone()
+        });
+
+        let commit = |ctx: &mut MempoolTestCtx, slot| {
+            ctx.mempool
+                .handle_consensus_event(ConsensusEvent::CommitConsensusProposal(
+                    CommittedConsensusProposal {
+                        staking: ctx.mempool.staking.clone(),
+                        consensus_proposal: model::ConsensusProposal {
+                            slot,
+                            view: 0,
+                            round_leader: ctx.validator_pubkey().clone(),
+                            cut: vec![],
+                            staking_actions: vec![],
+                            timestamp: 777,
+                            parent_hash: ConsensusProposalHash("test".to_string()),
+                        },
+                        certificate: AggregateSignature::default(),
+                    },
+                ))
+        };
+        let evicted = |ctx: &mut MempoolTestCtx| {
+            std::iter::from_fn(|| ctx.mempool_event_receiver.try_recv().ok())
+                .filter_map(|event| match event {
+                    MempoolEvent::PendingTxEvicted(tx_hash) => Some(tx_hash),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        commit(&mut ctx, 10)?;
+        let tx = make_register_contract_tx(ContractName::new("test1"));
+        ctx.submit_tx(&tx);
+
+        commit(&mut ctx, 12)?;
+        assert_eq!(ctx.mempool.pending_txs, vec![tx.clone()]);
+        assert!(evicted(&mut ctx).is_empty());
+
+        commit(&mut ctx, 13)?;
+        assert!(ctx.mempool.pending_txs.is_empty());
+        assert!(ctx.mempool.pending_txs_slot.is_empty());
+        assert_eq!(evicted(&mut ctx), vec![tx.hash()]);
+
+        // Nothing left to propose
+        ctx.make_data_proposal_with_pending_txs()?;
+        assert!(ctx
+            .mempool
+            .storage
+            .lanes
+            .get(ctx.validator_pubkey())
+            .is_none_or(|lane| lane.data_proposals.is_empty()));
+        Ok(())
+    }
+
     #[test_log::test(tokio::test)]
     async fn test_signed_block_start_building_later() -> Result<()> {
         let mut ctx = MempoolTestCtx::new("mempool").await;
@@ -2279,11 +3145,587 @@ pub mod test {
 
         assert!(Mempool::load_from_disk::<MempoolStore>(
             ctx.mempool.file.unwrap().join("test-mempool.bin").as_path(),
-        )
+        )?
         .is_some());
 
         std::fs::remove_file("./test-mempool.bin").expect("Failed to delete test-mempool.bin");
 
         Ok(())
     }
+
+    #[test_log::test(tokio::test)]
+    async fn test_blob_tx_allowlist() -> Result<()> {
+        let mut ctx = MempoolTestCtx::new("mempool").await;
+        let allowed = BlstCrypto::new("allowed".into())?;
+        let other = BlstCrypto::new("other".into())?;
+        ctx.mempool.conf = Arc::new(Conf {
+            blob_tx_allowlist: vec![allowed.validator_pubkey().clone()],
+            ..(*ctx.mempool.conf).clone()
+        });
+
+        let tx = make_register_contract_tx(ContractName::new("test1"));
+        let TransactionData::Blob(blob_tx) = tx.transaction_data.clone() else {
+            panic!("Expected a blob transaction");
+        };
+        let now = 1_000_000;
+        let authorize = |crypto: &BlstCrypto, tx_hash: TxHash, valid_until: u64| {
+            crypto.sign(BlobTxAuthorization {
+                tx_hash,
+                valid_until,
+            })
+        };
+        let signed = |authorization| SignedBlobTransaction {
+            tx: blob_tx.clone(),
+            authorization,
+        };
+
+        // Unsigned submission
+        assert!(ctx
+            .mempool
+            .handle_api_message(RestApiMessage::NewTx(tx.clone()))
+            .is_err());
+
+        // Signed by a key that is not in the allowlist
+        assert!(ctx
+            .mempool
+            .on_new_signed_blob_tx(signed(authorize(&other, tx.hash(), now + 1)?), now)
+            .is_err());
+
+        // Authorization of the allowlisted key for another transaction
+        assert!(ctx
+            .mempool
+            .on_new_signed_blob_tx(
+                signed(authorize(&allowed, TxHash::new("other"), now + 1)?),
+                now
+            )
+            .is_err());
+
+        // Signature of the allowlisted key over another authorization
+        let mut wrongly_signed = authorize(&allowed, tx.hash(), now + 1)?;
+        wrongly_signed.msg.valid_until = now + 2;
+        assert!(ctx
+            .mempool
+            .on_new_signed_blob_tx(signed(wrongly_signed), now)
+            .is_err());
+
+        // Expired, or valid for too long
+        assert!(ctx
+            .mempool
+            .on_new_signed_blob_tx(signed(authorize(&allowed, tx.hash(), now - 1)?), now)
+            .is_err());
+        assert!(ctx
+            .mempool
+            .on_new_signed_blob_tx(
+                signed(authorize(
+                    &allowed,
+                    tx.hash(),
+                    now + MAX_BLOB_TX_AUTHORIZATION_VALIDITY_MS + 1
+                )?),
+                now
+            )
+            .is_err());
+
+        assert!(ctx.mempool.pending_txs.is_empty());
+
+        let authorization = authorize(&allowed, tx.hash(), now + 1)?;
+        ctx.mempool
+            .on_new_signed_blob_tx(signed(authorization.clone()), now)?;
+        assert_eq!(ctx.mempool.pending_txs, vec![tx.clone()]);
+        ctx.make_data_proposal_with_pending_txs()?;
+
+        // Replaying the submission is rejected, even once seen_txs forgot the tx
+        ctx.mempool.seen_txs = SeenTxs::default();
+        assert!(ctx
+            .mempool
+            .on_new_signed_blob_tx(signed(authorization), now)
+            .is_err());
+        assert!(ctx.mempool.pending_txs.is_empty());
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_blob_tx_allowlist_is_only_enforced_at_ingress() -> Result<()> {
+        let mut sender = MempoolTestCtx::new("sender").await;
+        let mut receiver = MempoolTestCtx::new("receiver").await;
+        let cryptos = [
+            (*sender.mempool.crypto).clone(),
+            (*receiver.mempool.crypto).clone(),
+        ];
+        sender.setup_node(&cryptos);
+        receiver.setup_node(&cryptos);
+        let allowed = BlstCrypto::new("allowed".into())?;
+        receiver.mempool.conf = Arc::new(Conf {
+            blob_tx_allowlist: vec![allowed.validator_pubkey().clone()],
+            ..(*receiver.mempool.conf).clone()
+        });
+
+        // The sender has no allowlist: the receiver's own allowlist doesn't decide its vote
+        let tx = make_register_contract_tx(ContractName::new("test1"));
+        sender.submit_tx(&tx);
+        sender.make_data_proposal_with_pending_txs()?;
+        let signed_msg = sender.assert_broadcast("DataProposal");
+
+        receiver.mempool.handle_net_message(signed_msg)?;
+        let vote = receiver.assert_send(sender.validator_pubkey(), "DataVote");
+        assert!(matches!(vote.msg, MempoolNetMessage::DataVote(..)));
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_children_of_refused_data_proposal_are_dropped() -> Result<()> {
+        let mut sender = MempoolTestCtx::new("sender").await;
+        let mut receiver = MempoolTestCtx::new("receiver").await;
+        let cryptos = [
+            (*sender.mempool.crypto).clone(),
+            (*receiver.mempool.crypto).clone(),
+        ];
+        sender.setup_node(&cryptos);
+        receiver.setup_node(&cryptos);
+
+        let tx = |name: &str| make_register_contract_tx(ContractName::new(name));
+        receiver.mempool.max_data_proposal_size = tx("test1").estimate_size();
+        let parent = DataProposal {
+            id: 0,
+            parent_data_proposal_hash: None,
+            txs: vec![tx("test1"), tx("test2")],
+        };
+        let child = DataProposal {
+            id: 1,
+            parent_data_proposal_hash: Some(parent.hash()),
+            txs: vec![tx("test3")],
+        };
+        let grandchild = DataProposal {
+            id: 2,
+            parent_data_proposal_hash: Some(child.hash()),
+            txs: vec![tx("test4")],
+        };
+
+        // The descendants arrive first and wait for their parent
+        for data_proposal in [child, grandchild] {
+            receiver.mempool.handle_net_message(
+                sender
+                    .mempool
+                    .sign_net_message(MempoolNetMessage::DataProposal(data_proposal))?,
+            )?;
+            receiver.assert_send(sender.validator_pubkey(), "SyncRequest");
+        }
+        assert_eq!(
+            receiver
+                .mempool
+                .buffered_proposals
+                .get(sender.validator_pubkey())
+                .map(|buffered| buffered.len()),
+            Some(2)
+        );
+
+        // The parent is too large: nothing will ever be voted for in this branch
+        receiver.mempool.handle_net_message(
+            sender
+                .mempool
+                .sign_net_message(MempoolNetMessage::DataProposal(parent))?,
+        )?;
+        assert!(receiver.out_receiver.try_recv().is_err());
+        assert!(receiver
+            .mempool
+            .buffered_proposals
+            .get(sender.validator_pubkey())
+            .is_some_and(|buffered| buffered.is_empty()));
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_unsupported_tx_version_is_rejected() -> Result<()> {
+        let mut ctx = MempoolTestCtx::new("mempool").await;
+
+        let tx = make_register_contract_tx(ContractName::new("test1"));
+        let future_tx = Transaction {
+            version: 2,
+            ..make_register_contract_tx(ContractName::new("test2"))
+        };
+
+        let err = ctx
+            .mempool
+            .handle_api_message(RestApiMessage::NewTx(future_tx.clone()))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("this node supports versions up to 1"));
+
+        ctx.mempool
+            .handle_api_message(RestApiMessage::NewTx(tx.clone()))?;
+        assert_eq!(ctx.mempool.pending_txs, vec![tx]);
+
+        // Supported, but only active from height 10
+        ctx.mempool.conf = Arc::new(Conf {
+            max_supported_tx_version: 2,
+            ..(*ctx.mempool.conf).clone()
+        });
+        let err = ctx
+            .mempool
+            .handle_api_message(RestApiMessage::NewTx(future_tx.clone()))
+            .unwrap_err();
+        assert!(err.to_string().contains("not active at height 1"));
+
+        ctx.mempool
+            .handle_node_state_event(NodeStateEvent::NewBlock(Box::new(Block {
+                block_height: BlockHeight(9),
+                ..Default::default()
+            })));
+        ctx.mempool
+            .handle_api_message(RestApiMessage::NewTx(future_tx.clone()))?;
+        assert_eq!(ctx.mempool.pending_txs.len(), 2);
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_data_proposal_with_unsupported_tx_version_is_voted() -> Result<()> {
+        let mut sender = MempoolTestCtx::new("sender").await;
+        let mut receiver = MempoolTestCtx::new("receiver").await;
+        let cryptos = [
+            (*sender.mempool.crypto).clone(),
+            (*receiver.mempool.crypto).clone(),
+        ];
+        sender.setup_node(&cryptos);
+        receiver.setup_node(&cryptos);
+
+        // The sender was upgraded first, the receiver only supports version 1
+        sender.mempool.conf = Arc::new(Conf {
+            max_supported_tx_version: 2,
+            ..(*sender.mempool.conf).clone()
+        });
+        sender.mempool.block_height = BlockHeight(9);
+        sender.submit_tx(&Transaction {
+            version: 2,
+            ..make_register_contract_tx(ContractName::new("test1"))
+        });
+        sender.make_data_proposal_with_pending_txs()?;
+        let signed_msg = sender.assert_broadcast("DataProposal");
+
+        // Versions are enforced by the node state, the lane isn't stalled by the upgrade
+        receiver.mempool.handle_net_message(signed_msg)?;
+        let vote = receiver.assert_send(sender.validator_pubkey(), "DataVote");
+        assert!(matches!(vote.msg, MempoolNetMessage::DataVote(..)));
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_proof_with_program_referenced_by_hash() -> Result<()> {
+        let program = b"a very large verification key".to_vec();
+        let mut store = ProgramStore::default();
+        let program_id = store.insert(program.clone())?;
+
+        assert_ne!(program_id.0, program);
+        assert_eq!(program_id, ProgramStore::hash_reference(&program));
+        assert_eq!(store.resolve(&program_id)?, ProgramId(program));
+
+        let mut known_contracts = KnownContracts::default();
+        known_contracts.register_contract(&"c1".into(), &"test".into(), &program_id);
+        let known_contracts = Arc::new(std::sync::RwLock::new(known_contracts));
+
+        let proof_tx: Transaction = ProofTransaction {
+            contract_name: "c1".into(),
+            proof: ProofData(bincode::encode_to_vec(
+                vec![HyleOutput::default()],
+                bincode::config::standard(),
+            )?),
+        }
+        .into();
+
+        // Unknown program: the proof can't be verified
+        assert!(Mempool::process_proof_tx(
+            known_contracts.clone(),
+            Arc::new(std::sync::RwLock::new(ProgramStore::default())),
+            &VerifierBackends::default(),
+            &VerifierRegistry::default_for_tests(),
+            proof_tx.clone(),
+            false,
+        )
+        .is_err());
+
+        let verified = Mempool::process_proof_tx(
+            known_contracts,
+            Arc::new(std::sync::RwLock::new(store)),
+            &VerifierBackends::default(),
+            &VerifierRegistry::default_for_tests(),
+            proof_tx,
+            false,
+        )?;
+        let TransactionData::VerifiedProof(verified) = verified.transaction_data else {
+            panic!("Expected a verified proof transaction");
+        };
+        // The registered hash reference is kept in the proven blobs
+        assert_eq!(verified.proven_blobs.len(), 1);
+        assert_eq!(
+            verified.proven_blobs.first().unwrap().program_id,
+            program_id
+        );
+
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_registered_program_is_fetched_on_demand() -> Result<()> {
+        let mut ctx1 = MempoolTestCtx::new("mempool1").await;
+        let mut ctx2 = MempoolTestCtx::new("mempool2").await;
+        let program = b"a very large verification key".to_vec();
+        let program_id = ProgramStore::hash_reference(&program);
+
+        // Uploads are kept locally, not gossiped
+        ctx1.mempool
+            .handle_api_message(RestApiMessage::RegisterProgram(program.clone()))?;
+        assert!(ctx1.out_receiver.try_recv().is_err());
+        assert!(ctx1
+            .mempool
+            .handle_api_message(RestApiMessage::RegisterProgram(vec![
+                0;
+                verifiers::MAX_PROGRAM_SIZE
+                    + 1
+            ]))
+            .is_err());
+
+        // Programs nobody asked for are refused
+        let unsolicited = ctx1
+            .mempool
+            .sign_net_message(MempoolNetMessage::Program(program.clone()))?;
+        assert!(ctx2.mempool.handle_net_message(unsolicited).is_err());
+        assert!(ctx2
+            .mempool
+            .programs
+            .read()
+            .unwrap()
+            .resolve(&program_id)
+            .is_err());
+
+        // Once a contract registers it, it's requested to the peers
+        ctx2.mempool
+            .handle_contract_registration(RegisterContractEffect {
+                verifier: "test".into(),
+                program_id: program_id.clone(),
+                state_digest: StateDigest(vec![]),
+                contract_name: "c1".into(),
+            });
+        let request = ctx2.assert_broadcast("ProgramRequest");
+        assert_eq!(
+            request.msg,
+            MempoolNetMessage::ProgramRequest(program_id.clone())
+        );
+
+        // A validator connecting later is asked too, until we get it
+        let pubkey1 = ctx1.validator_pubkey().clone();
+        ctx2.mempool.on_new_peer(pubkey1.clone())?;
+        ctx2.assert_send(&pubkey1, "ProgramRequest");
+
+        let pubkey2 = ctx2.validator_pubkey().clone();
+        ctx1.handle_msg(&request, "ProgramRequest");
+        let reply = ctx1.assert_send(&pubkey2, "Program");
+        assert_eq!(reply.msg, MempoolNetMessage::Program(program.clone()));
+
+        ctx2.handle_msg(&reply, "Program");
+        assert_eq!(
+            ctx2.mempool.programs.read().unwrap().resolve(&program_id)?,
+            ProgramId(program.clone())
+        );
+        ctx2.mempool.on_new_peer(pubkey1)?;
+        assert!(ctx2.out_receiver.try_recv().is_err());
+
+        Ok(())
+    }
+
+    #[test_log::test]
+    fn test_unregistered_programs_are_bounded() -> Result<()> {
+        let mut store = ProgramStore::default();
+        let program = |i: usize| i.to_le_bytes().to_vec();
+        let registered = store.insert(program(0))?;
+        assert!(!store.on_registered_program(&registered));
+        let evicted = store.insert(program(1))?;
+        for i in 2..verifiers::MAX_UNREGISTERED_PROGRAMS + 2 {
+            store.insert(program(i))?;
+        }
+
+        assert!(store.resolve(&evicted).is_err());
+        assert_eq!(store.resolve(&registered)?, ProgramId(program(0)));
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_proof_with_registered_custom_verifier() -> Result<()> {
+        use anyhow::Context;
+
+        /// Accepts proofs that are the program id followed by the identity to prove
+        struct PrivateVerifier;
+        impl verifiers::ProofVerifier for PrivateVerifier {
+            fn verify(&self, proof: &[u8], program_id: &ProgramId) -> Result<Vec<HyleOutput>> {
+                let identity = proof
+                    .strip_prefix(program_id.0.as_slice())
+                    .context("Proof is not for this program")?;
+                Ok(vec![HyleOutput {
+                    identity: String::from_utf8(identity.to_vec())?.into(),
+                    success: true,
+                    ..HyleOutput::default()
+                }])
+            }
+        }
+        verifiers::register_verifier("private-prover", Arc::new(PrivateVerifier))?;
+        // Built-in names are part of the protocol
+        assert!(verifiers::register_verifier("risc0", Arc::new(PrivateVerifier)).is_err());
+
+        let mut known_contracts = KnownContracts::default();
+        known_contracts.register_contract(
+            &"c1".into(),
+            &"private-prover".into(),
+            &ProgramId(b"program".to_vec()),
+        );
+        let known_contracts = Arc::new(std::sync::RwLock::new(known_contracts));
+        let proof_tx = |proof: &[u8]| -> Transaction {
+            ProofTransaction {
+                contract_name: "c1".into(),
+                proof: ProofData(proof.to_vec()),
+            }
+            .into()
+        };
+
+        assert!(Mempool::process_proof_tx(
+            known_contracts.clone(),
+            Arc::new(std::sync::RwLock::new(ProgramStore::default())),
+            &VerifierBackends::default(),
+            &VerifierRegistry::default_for_tests(),
+            proof_tx(b"other program, bob.c1"),
+            false,
+        )
+        .is_err());
+
+        let verified = Mempool::process_proof_tx(
+            known_contracts,
+            Arc::new(std::sync::RwLock::new(ProgramStore::default())),
+            &VerifierBackends::default(),
+            &VerifierRegistry::default_for_tests(),
+            proof_tx(b"programbob.c1"),
+            false,
+        )?;
+        let TransactionData::VerifiedProof(verified) = verified.transaction_data else {
+            panic!("Expected a verified proof transaction");
+        };
+        assert_eq!(verified.proven_blobs.len(), 1);
+        let output = &verified.proven_blobs.first().unwrap().hyle_output;
+        assert_eq!(output.identity, "bob.c1".into());
+        assert!(output.success);
+
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_proof_freshness_window() -> Result<()> {
+        let mut known_contracts = KnownContracts::default();
+        known_contracts.register_contract(&"c1".into(), &"test".into(), &ProgramId(vec![]));
+        let known_contracts = Arc::new(std::sync::RwLock::new(known_contracts));
+        let verified_at = |timestamp: u128| -> Result<Transaction> {
+            let output = HyleOutput {
+                tx_ctx: Some(TxContext {
+                    timestamp,
+                    ..TxContext::default()
+                }),
+                ..HyleOutput::default()
+            };
+            Mempool::process_proof_tx(
+                known_contracts.clone(),
+                Arc::new(std::sync::RwLock::new(ProgramStore::default())),
+                &VerifierBackends::default(),
+                &VerifierRegistry::default_for_tests(),
+                ProofTransaction {
+                    contract_name: "c1".into(),
+                    proof: ProofData(bincode::encode_to_vec(
+                        vec![output],
+                        bincode::config::standard(),
+                    )?),
+                }
+                .into(),
+                false,
+            )
+        };
+
+        let now = 1_000_000;
+        let max_age = 60_000;
+        let max_skew = 1_000;
+
+        // Just inside the window
+        Mempool::check_proof_freshness(&verified_at(now - max_age)?, now, max_age, max_skew)?;
+        Mempool::check_proof_freshness(&verified_at(now + max_skew)?, now, max_age, max_skew)?;
+
+        // Just outside
+        assert!(Mempool::check_proof_freshness(
+            &verified_at(now - max_age - 1)?,
+            now,
+            max_age,
+            max_skew
+        )
+        .is_err());
+        assert!(Mempool::check_proof_freshness(
+            &verified_at(now + max_skew + 1)?,
+            now,
+            max_age,
+            max_skew
+        )
+        .is_err());
+
+        // Disabled, or no timestamp in the proof
+        Mempool::check_proof_freshness(&verified_at(0)?, now, 0, max_skew)?;
+        let no_timestamp = Mempool::process_proof_tx(
+            known_contracts.clone(),
+            Arc::new(std::sync::RwLock::new(ProgramStore::default())),
+            &VerifierBackends::default(),
+            &VerifierRegistry::default_for_tests(),
+            ProofTransaction {
+                contract_name: "c1".into(),
+                proof: ProofData(bincode::encode_to_vec(
+                    vec![HyleOutput::default()],
+                    bincode::config::standard(),
+                )?),
+            }
+            .into(),
+            false,
+        )?;
+        Mempool::check_proof_freshness(&no_timestamp, now, max_age, max_skew)?;
+
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_proofs_for_paused_contract_are_rejected() -> Result<()> {
+        let mut ctx = MempoolTestCtx::new("mempool").await;
+        let proof_tx: Transaction = VerifiedProofTransaction {
+            contract_name: "c1".into(),
+            proof: None,
+            proof_hash: ProofDataHash::default(),
+            proven_blobs: vec![],
+            is_recursive: false,
+        }
+        .into();
+        let conf = (*ctx.mempool.conf).clone();
+
+        ctx.mempool.conf = Arc::new(Conf {
+            paused_contracts: vec!["c1".into()],
+            ..conf.clone()
+        });
+        let err = ctx.mempool.on_new_tx(proof_tx.clone()).unwrap_err();
+        assert!(err.to_string().contains("Contract c1 is paused"));
+        let err = ctx
+            .mempool
+            .on_new_tx(
+                ProofTransaction {
+                    contract_name: "c1".into(),
+                    proof: ProofData::default(),
+                }
+                .into(),
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("Contract c1 is paused"));
+        assert!(ctx.mempool.pending_txs.is_empty());
+
+        // Unpaused
+        ctx.mempool.conf = Arc::new(conf);
+        ctx.mempool.on_new_tx(proof_tx.clone())?;
+        assert_eq!(ctx.mempool.pending_txs, vec![proof_tx]);
+
+        Ok(())
+    }
 }
@@ -1,9 +1,22 @@
 use anyhow::anyhow;
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json, Router};
+use axum::{
+    extract::{ConnectInfo, DefaultBodyLimit, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json, Router,
+};
 use bincode::{Decode, Encode};
 use hyle_contract_sdk::TxHash;
-use hyle_model::{api::APIRegisterContract, ContractAction, RegisterContractAction};
+use hyle_model::{
+    api::{APIRegisterContract, APIRegisterProgram, APIVerifyProof},
+    ContractAction, HyleOutput, ProgramId, RegisterContractAction,
+};
 use serde::{Deserialize, Serialize};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
 use tracing::info;
 use utoipa::OpenApi;
 use utoipa_axum::{router::OpenApiRouter, routes};
@@ -11,16 +24,24 @@ use utoipa_axum::{router::OpenApiRouter, routes};
 use crate::{
     bus::{bus_client, metrics::BusMetrics, BusClientSender, BusMessage},
     model::{
-        BlobTransaction, CommonRunContext, Hashable, ProofTransaction, Transaction, TransactionData,
+        BlobTransaction, CommonRunContext, Hashable, ProofTransaction, SignedBlobTransaction,
+        Transaction, TransactionData,
     },
     rest::AppError,
+    utils::{conf::SharedConf, rate_limiter::RateLimiter},
 };
 
-use super::contract_registration::validate_contract_registration;
+use super::{
+    contract_registration::validate_contract_registration,
+    verifiers::{ProgramStore, VerifierRegistry, MAX_PROGRAM_SIZE},
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone, Encode, Decode)]
 pub enum RestApiMessage {
     NewTx(Transaction),
+    NewSignedBlobTx(SignedBlobTransaction),
+    NewRecursiveProofTx(ProofTransaction),
+    RegisterProgram(Vec<u8>),
 }
 impl BusMessage for RestApiMessage {}
 
@@ -32,6 +53,9 @@ struct RestBusClient {
 
 pub struct RouterState {
     bus: RestBusClient,
+    conf: SharedConf,
+    verify_rate_limiter: Arc<RateLimiter<IpAddr>>,
+    verifiers: Arc<VerifierRegistry>,
 }
 
 #[derive(OpenApi)]
@@ -40,12 +64,30 @@ struct MempoolAPI;
 pub async fn api(ctx: &CommonRunContext) -> Router<()> {
     let state = RouterState {
         bus: RestBusClient::new_from_bus(ctx.bus.new_handle()).await,
+        conf: ctx.config.clone(),
+        verify_rate_limiter: Arc::new(RateLimiter::new(
+            ctx.config.verify_api_rate_limit,
+            Duration::from_secs(1),
+        )),
+        verifiers: Arc::new(
+            VerifierRegistry::new(&ctx.config.noir_verifier())
+                .with_local_backends(&ctx.config.verifiers, &ctx.config.noir_verifier()),
+        ),
     };
 
+    let verify_body_limit = verify_body_limit(ctx.config.verify_api_max_proof_size);
     let (router, api) = OpenApiRouter::with_openapi(MempoolAPI::openapi())
         .routes(routes!(register_contract))
+        .routes(routes!(register_program))
         .routes(routes!(send_blob_transaction))
+        .routes(routes!(send_signed_blob_transaction))
         .routes(routes!(send_proof_transaction))
+        .routes(routes!(send_recursive_proof_transaction))
+        .merge(
+            OpenApiRouter::new()
+                .routes(routes!(verify))
+                .layer(DefaultBodyLimit::max(verify_body_limit)),
+        )
         .split_for_parts();
 
     if let Ok(mut o) = ctx.openapi.lock() {
@@ -84,6 +126,30 @@ pub async fn send_blob_transaction(
     handle_send(state, TransactionData::Blob(payload)).await
 }
 
+#[utoipa::path(
+    post,
+    path = "/tx/send/blob/signed",
+    tag = "Mempool",
+    responses(
+        (status = OK, description = "Send blob transaction signed by an allowlisted key", body = TxHash)
+    )
+)]
+pub async fn send_signed_blob_transaction(
+    State(state): State<RouterState>,
+    Json(payload): Json<SignedBlobTransaction>,
+) -> Result<impl IntoResponse, AppError> {
+    let tx_hash = payload.tx.hash();
+    info!(
+        "Got blob transaction {} signed by {}",
+        tx_hash, payload.authorization.signature.validator
+    );
+    state
+        .bus
+        .send(RestApiMessage::NewSignedBlobTx(payload))
+        .map(|_| Json(tx_hash))
+        .map_err(|err| AppError(StatusCode::INTERNAL_SERVER_ERROR, anyhow!(err)))
+}
+
 #[utoipa::path(
     post,
     path = "/tx/send/proof",
@@ -100,6 +166,27 @@ pub async fn send_proof_transaction(
     handle_send(state, TransactionData::Proof(payload)).await
 }
 
+#[utoipa::path(
+    post,
+    path = "/tx/send/proof/recursive",
+    tag = "Mempool",
+    responses(
+        (status = OK, description = "Send recursive proof transaction, settling all the blobs it proves", body = TxHash)
+    )
+)]
+pub async fn send_recursive_proof_transaction(
+    State(state): State<RouterState>,
+    Json(payload): Json<ProofTransaction>,
+) -> Result<impl IntoResponse, AppError> {
+    let tx_hash = payload.hash();
+    info!("Got recursive proof transaction {}", tx_hash);
+    state
+        .bus
+        .send(RestApiMessage::NewRecursiveProofTx(payload))
+        .map(|_| Json(tx_hash))
+        .map_err(|err| AppError(StatusCode::INTERNAL_SERVER_ERROR, anyhow!(err)))
+}
+
 #[utoipa::path(
     post,
     path = "/contract/register",
@@ -121,13 +208,99 @@ pub async fn register_contract(
             program_id: payload.program_id,
             state_digest: payload.state_digest,
             contract_name: payload.contract_name,
+            blob_encoding: payload.blob_encoding,
         }
         .as_blob(owner, None, None)],
+        dependencies: vec![],
+        additional_identities: vec![],
+        nonce: None,
+        fee: 0,
     };
 
     handle_send(state, TransactionData::Blob(tx)).await
 }
 
+#[utoipa::path(
+    post,
+    path = "/program/register",
+    tag = "Mempool",
+    responses(
+        (status = OK, description = "Store a program until a contract registers it, the other validators then fetch it. Returns the program id referencing it by hash", body = ProgramId)
+    )
+)]
+pub async fn register_program(
+    State(state): State<RouterState>,
+    Json(payload): Json<APIRegisterProgram>,
+) -> Result<impl IntoResponse, AppError> {
+    if payload.program.len() > MAX_PROGRAM_SIZE {
+        return Err(AppError(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            anyhow!(
+                "Program is {} bytes, at most {} are accepted",
+                payload.program.len(),
+                MAX_PROGRAM_SIZE
+            ),
+        ));
+    }
+    let program_id = ProgramStore::hash_reference(&payload.program);
+    info!("Got program {}", hex::encode(&program_id.0));
+    state
+        .bus
+        .send(RestApiMessage::RegisterProgram(payload.program))
+        .map(|_| Json(program_id))
+        .map_err(|err| AppError(StatusCode::INTERNAL_SERVER_ERROR, anyhow!(err)))
+}
+
+/// Size of the largest JSON body of a `/verify` request with a proof of at most
+/// `max_proof_size` bytes: proofs are base64 encoded, the rest is a few fields.
+fn verify_body_limit(max_proof_size: usize) -> usize {
+    max_proof_size.div_ceil(3) * 4 + 16 * 1024
+}
+
+#[utoipa::path(
+    post,
+    path = "/verify",
+    tag = "Mempool",
+    responses(
+        (status = OK, description = "Verify a proof without submitting it, returns its outputs", body = [HyleOutput])
+    )
+)]
+pub async fn verify(
+    State(state): State<RouterState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    Json(payload): Json<APIVerifyProof>,
+) -> Result<impl IntoResponse, AppError> {
+    if payload.proof.0.len() > state.conf.verify_api_max_proof_size {
+        return Err(AppError(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            anyhow!(
+                "Proof is {} bytes, at most {} are accepted",
+                payload.proof.0.len(),
+                state.conf.verify_api_max_proof_size
+            ),
+        ));
+    }
+    if !state.verify_rate_limiter.try_acquire(client.ip()) {
+        return Err(AppError(
+            StatusCode::TOO_MANY_REQUESTS,
+            anyhow!("Too many verification requests, retry later"),
+        ));
+    }
+
+    let verifiers = state.verifiers.clone();
+    tokio::task::spawn_blocking(move || {
+        verifiers.verify(&payload.proof, &payload.verifier, &payload.program_id)
+    })
+    .await?
+    .map(Json)
+    .map_err(|err| {
+        AppError(
+            StatusCode::BAD_REQUEST,
+            anyhow!("Proof verification failed: {:#}", err),
+        )
+    })
+}
+
 impl Clone for RouterState {
     fn clone(&self) -> Self {
         use crate::utils::static_type_map::Pick;
@@ -136,6 +309,121 @@ impl Clone for RouterState {
                 Pick::<BusMetrics>::get(&self.bus).clone(),
                 Pick::<tokio::sync::broadcast::Sender<RestApiMessage>>::get(&self.bus).clone(),
             ),
+            conf: self.conf.clone(),
+            verify_rate_limiter: self.verify_rate_limiter.clone(),
+            verifiers: self.verifiers.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::routing::post;
+    use axum_test::TestServer;
+    use hyle_model::{BlobIndex, ProofData, Verifier};
+
+    use super::*;
+    use crate::{bus::SharedMessageBus, utils::conf::Conf};
+
+    async fn setup_test_server(conf: Conf) -> TestServer {
+        let state = RouterState {
+            bus: RestBusClient::new_from_bus(SharedMessageBus::default().new_handle()).await,
+            verify_rate_limiter: Arc::new(RateLimiter::new(
+                conf.verify_api_rate_limit,
+                Duration::from_secs(60),
+            )),
+            verifiers: Arc::new(
+                VerifierRegistry::new(&conf.noir_verifier())
+                    .with_local_backends(&conf.verifiers, &conf.noir_verifier()),
+            ),
+            conf: Arc::new(conf),
+        };
+        let body_limit = verify_body_limit(state.conf.verify_api_max_proof_size);
+        TestServer::new(
+            Router::new()
+                .route("/verify", post(verify))
+                .layer(DefaultBodyLimit::max(body_limit))
+                .with_state(state)
+                .into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .unwrap()
+    }
+
+    fn test_payload(proof: Vec<u8>) -> APIVerifyProof {
+        APIVerifyProof {
+            proof: ProofData(proof),
+            verifier: Verifier("test".to_owned()),
+            program_id: ProgramId(vec![]),
         }
     }
+
+    #[test_log::test(tokio::test)]
+    async fn test_verify_valid_and_tampered_proofs() {
+        let server = setup_test_server(Conf {
+            verify_api_max_proof_size: 1_000_000,
+            verify_api_rate_limit: 10,
+            ..Conf::default()
+        })
+        .await;
+        let proof = bincode::encode_to_vec(
+            vec![HyleOutput {
+                identity: "bob.c1".into(),
+                ..HyleOutput::default()
+            }],
+            bincode::config::standard(),
+        )
+        .unwrap();
+
+        let response = server
+            .post("/verify")
+            .json(&test_payload(proof.clone()))
+            .await;
+        response.assert_status_ok();
+        let outputs = response.json::<Vec<HyleOutput>>();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].identity.0, "bob.c1");
+        assert_eq!(outputs[0].index, BlobIndex(0));
+
+        let mut tampered = proof;
+        tampered.pop();
+        let response = server.post("/verify").json(&test_payload(tampered)).await;
+        response.assert_status_bad_request();
+        assert!(response.text().starts_with("Proof verification failed"));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_verify_limits() {
+        let server = setup_test_server(Conf {
+            verify_api_max_proof_size: 4,
+            verify_api_rate_limit: 1,
+            ..Conf::default()
+        })
+        .await;
+
+        let response = server
+            .post("/verify")
+            .json(&test_payload(vec![0xff; 5]))
+            .await;
+        response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(response.text().starts_with("Proof is 5 bytes"));
+
+        // Refused before the body is parsed
+        let response = server
+            .post("/verify")
+            .json(&test_payload(vec![0xff; 100_000]))
+            .await;
+        response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(!response.text().starts_with("Proof is"));
+
+        server
+            .post("/verify")
+            .json(&test_payload(vec![0xff; 4]))
+            .await
+            .assert_status_bad_request();
+        server
+            .post("/verify")
+            .json(&test_payload(vec![0xff; 4]))
+            .await
+            .assert_status(StatusCode::TOO_MANY_REQUESTS);
+    }
 }
@@ -1,9 +1,13 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use hyle_verifiers::DecodeError;
 use opentelemetry::{
-    metrics::{Counter, Gauge},
+    metrics::{Counter, Gauge, Histogram},
     InstrumentationScope, KeyValue,
 };
 
-use crate::model::{DataProposal, ValidatorPublicKey};
+use crate::model::{DataProposal, HyleOutput, Transaction, ValidatorPublicKey, Verifier};
 
 use super::QueryNewCut;
 
@@ -16,6 +20,8 @@ pub struct MempoolMetrics {
     sync_request: Counter<u64>,
     sync_reply: Counter<u64>,
     pending_tx: Gauge<u64>,
+    evicted_tx: Counter<u64>,
+    duplicate_tx: Counter<u64>,
     new_cut: Counter<u64>,
 }
 
@@ -45,6 +51,12 @@ impl MempoolMetrics {
                 .u64_counter(format!("{mempool}_sync_reply"))
                 .build(),
             pending_tx: my_meter.u64_gauge(format!("{mempool}_pending_tx")).build(),
+            evicted_tx: my_meter
+                .u64_counter(format!("{mempool}_evicted_tx"))
+                .build(),
+            duplicate_tx: my_meter
+                .u64_counter(format!("{mempool}_duplicate_tx"))
+                .build(),
             new_cut: my_meter.u64_counter(format!("{mempool}_new_cut")).build(),
         }
     }
@@ -70,6 +82,16 @@ impl MempoolMetrics {
         )
     }
 
+    pub fn add_evicted_tx(&self, tx: &Transaction) {
+        let tx_type: &'static str = (&tx.transaction_data).into();
+        self.evicted_tx.add(1, &[KeyValue::new("kind", tx_type)]);
+    }
+
+    pub fn add_duplicate_tx(&self, tx: &Transaction) {
+        let tx_type: &'static str = (&tx.transaction_data).into();
+        self.duplicate_tx.add(1, &[KeyValue::new("kind", tx_type)]);
+    }
+
     pub fn add_proposed_txs(&self, dp: &DataProposal) {
         for tx in dp.txs.iter() {
             let tx_type: &'static str = (&tx.transaction_data).into();
@@ -116,3 +138,45 @@ impl MempoolMetrics {
         )
     }
 }
+
+/// Outcome and duration of proof verifications, labeled by verifier name.
+pub struct VerifierMetrics {
+    success: Counter<u64>,
+    decode_failure: Counter<u64>,
+    failure: Counter<u64>,
+    duration: Histogram<f64>,
+}
+
+impl VerifierMetrics {
+    pub fn global() -> VerifierMetrics {
+        let scope = InstrumentationScope::builder("hyle_verifiers").build();
+        let my_meter = opentelemetry::global::meter_with_scope(scope);
+
+        let verifier = "verifier";
+
+        VerifierMetrics {
+            success: my_meter.u64_counter(format!("{verifier}_success")).build(),
+            decode_failure: my_meter
+                .u64_counter(format!("{verifier}_decode_failure"))
+                .build(),
+            failure: my_meter.u64_counter(format!("{verifier}_failure")).build(),
+            duration: my_meter
+                .f64_histogram(format!("{verifier}_duration"))
+                .with_unit("s")
+                .build(),
+        }
+    }
+
+    /// Proofs that couldn't be decoded are counted apart from the ones that don't verify.
+    pub fn record(&self, verifier: &Verifier, elapsed: Duration, result: &Result<Vec<HyleOutput>>) {
+        let labels = [KeyValue::new("verifier", verifier.0.clone())];
+        self.duration.record(elapsed.as_secs_f64(), &labels);
+        match result {
+            Ok(_) => self.success.add(1, &labels),
+            Err(e) if e.downcast_ref::<DecodeError>().is_some() => {
+                self.decode_failure.add(1, &labels)
+            }
+            Err(_) => self.failure.add(1, &labels),
+        }
+    }
+}
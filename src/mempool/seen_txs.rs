@@ -0,0 +1,60 @@
+use std::collections::{HashSet, VecDeque};
+
+use bincode::{Decode, Encode};
+use hyle_model::TxHash;
+
+/// Hashes of the txs accepted by the mempool or included in a block, to refuse duplicates.
+/// Bounded in size, the oldest hashes are forgotten first.
+#[derive(Debug, Default, Encode, Decode)]
+pub struct SeenTxs {
+    hashes: HashSet<TxHash>,
+    order: VecDeque<TxHash>,
+}
+
+impl SeenTxs {
+    pub fn contains(&self, tx_hash: &TxHash) -> bool {
+        self.hashes.contains(tx_hash)
+    }
+
+    /// A capacity of 0 disables the tracking.
+    pub fn insert(&mut self, tx_hash: TxHash, capacity: usize) {
+        if capacity == 0 || self.hashes.contains(&tx_hash) {
+            return;
+        }
+        while self.order.len() >= capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.hashes.remove(&oldest);
+            }
+        }
+        self.hashes.insert(tx_hash.clone());
+        self.order.push_back(tx_hash);
+    }
+
+    pub fn remove(&mut self, tx_hash: &TxHash) {
+        if self.hashes.remove(tx_hash) {
+            self.order.retain(|hash| hash != tx_hash);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oldest_txs_are_forgotten() {
+        let mut seen = SeenTxs::default();
+        for tx in ["a", "b", "c"] {
+            seen.insert(TxHash::new(tx), 2);
+        }
+
+        assert!(!seen.contains(&TxHash::new("a")));
+        assert!(seen.contains(&TxHash::new("b")));
+        assert!(seen.contains(&TxHash::new("c")));
+
+        seen.remove(&TxHash::new("b"));
+        assert!(!seen.contains(&TxHash::new("b")));
+        seen.insert(TxHash::new("d"), 2);
+        assert!(seen.contains(&TxHash::new("c")));
+    }
+}
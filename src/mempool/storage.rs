@@ -18,7 +18,7 @@ use crate::{
     utils::crypto::BlstCrypto,
 };
 
-use super::verifiers::{verify_proof, verify_recursive_proof};
+use super::verifiers::{verify_recursive_proof, ProgramStore, VerifierBackends, VerifierRegistry};
 use super::{KnownContracts, MempoolNetMessage};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -296,7 +296,11 @@ impl Storage {
     pub fn process_data_proposal(
         data_proposal: &mut DataProposal,
         known_contracts: Arc<std::sync::RwLock<KnownContracts>>,
+        programs: Arc<std::sync::RwLock<ProgramStore>>,
+        verifiers: &VerifierRegistry,
     ) -> DataProposalVerdict {
+        // Proofs that are not recursive are independent of each other and verified in one batch.
+        let mut batch = vec![];
         for tx in &data_proposal.txs {
             match &tx.transaction_data {
                 TransactionData::Blob(blob_tx) => {
@@ -343,11 +347,25 @@ impl Storage {
                             }
                         }
                     };
+                    #[allow(clippy::expect_used, reason = "not held across await")]
+                    let program_id =
+                        match programs.read().expect("logic error").resolve(&program_id) {
+                            Ok(program_id) => program_id,
+                            Err(e) => {
+                                warn!("Refusing DataProposal: {}", e);
+                                return DataProposalVerdict::Refuse;
+                            }
+                        };
                     // TODO: figure out how to generalize this
                     let is_recursive = proof_tx.contract_name.0 == "risc0-recursion";
 
                     if is_recursive {
-                        match verify_recursive_proof(proof, &verifier, &program_id) {
+                        match verify_recursive_proof(
+                            proof,
+                            &verifier,
+                            &program_id,
+                            &VerifierBackends::default(),
+                        ) {
                             Ok((local_program_ids, local_hyle_outputs)) => {
                                 let data_matches = local_program_ids
                                     .iter()
@@ -372,33 +390,49 @@ impl Storage {
                                     warn!("Refusing DataProposal: incorrect HyleOutput in proof transaction");
                                     return DataProposalVerdict::Refuse;
                                 }
-                            }
-                            Err(e) => {
-                                warn!("Refusing DataProposal: invalid recursive proof transaction: {}", e);
-                                return DataProposalVerdict::Refuse;
-                            }
-                        }
-                    } else {
-                        match verify_proof(proof, &verifier, &program_id) {
-                            Ok(outputs) => {
-                                // TODO: we could check the blob hash here too.
-                                if outputs.len() != proof_tx.proven_blobs.len()
-                                    && std::iter::zip(outputs.iter(), proof_tx.proven_blobs.iter())
-                                        .any(|(output, BlobProofOutput { hyle_output, .. })| {
-                                            output != hyle_output
-                                        })
-                                {
-                                    warn!("Refusing DataProposal: incorrect HyleOutput in proof transaction");
+                                #[allow(clippy::expect_used, reason = "not held across await")]
+                                let known_contracts = known_contracts.read().expect("logic error");
+                                if !local_program_ids.iter().all(|program_id| {
+                                    known_contracts.is_registered_program(program_id)
+                                }) {
+                                    warn!("Refusing DataProposal: recursive proof verifies an unregistered program");
                                     return DataProposalVerdict::Refuse;
                                 }
                             }
                             Err(e) => {
-                                warn!("Refusing DataProposal: invalid proof transaction: {}", e);
+                                warn!("Refusing DataProposal: invalid recursive proof transaction: {}", e);
                                 return DataProposalVerdict::Refuse;
                             }
                         }
+                    } else {
+                        batch.push((proof, verifier, program_id, &proof_tx.proven_blobs));
+                    }
+                }
+            }
+        }
+
+        let inputs = batch
+            .iter()
+            .map(|(proof, verifier, program_id, _)| (*proof, verifier, program_id))
+            .collect::<Vec<_>>();
+        let results = verifiers.verify_batch(&inputs);
+        for (result, (_, _, _, proven_blobs)) in results.into_iter().zip(batch.iter()) {
+            match result {
+                Ok(outputs) => {
+                    // TODO: we could check the blob hash here too.
+                    if outputs.len() != proven_blobs.len()
+                        && std::iter::zip(outputs.iter(), proven_blobs.iter()).any(
+                            |(output, BlobProofOutput { hyle_output, .. })| output != hyle_output,
+                        )
+                    {
+                        warn!("Refusing DataProposal: incorrect HyleOutput in proof transaction");
+                        return DataProposalVerdict::Refuse;
                     }
                 }
+                Err(e) => {
+                    warn!("Refusing DataProposal: invalid proof transaction: {}", e);
+                    return DataProposalVerdict::Refuse;
+                }
             }
         }
 
@@ -853,6 +887,7 @@ mod tests {
         mempool::{
             storage::{DataProposalHash, DataProposalVerdict, LaneEntry, Storage},
             test::make_register_contract_tx,
+            verifiers::{ProgramStore, VerifierRegistry},
             KnownContracts, MempoolNetMessage,
         },
         model::{
@@ -955,6 +990,10 @@ mod tests {
                     contract_name: ContractName::new("c1"),
                     data: BlobData(inner_tx.as_bytes().to_vec()),
                 }],
+                dependencies: vec![],
+                additional_identities: vec![],
+                nonce: None,
+                fee: 0,
             }),
         }
     }
@@ -968,9 +1007,12 @@ mod tests {
     ) -> (DataProposalVerdict, Option<LaneBytesSize>) {
         let (verdict, size) = store.on_data_proposal(pubkey, &data_proposal);
         let verdict = match verdict {
-            DataProposalVerdict::Process => {
-                Storage::process_data_proposal(&mut data_proposal, known_contracts)
-            }
+            DataProposalVerdict::Process => Storage::process_data_proposal(
+                &mut data_proposal,
+                known_contracts,
+                Arc::new(RwLock::new(ProgramStore::default())),
+                &VerifierRegistry::default_for_tests(),
+            ),
             verdict => verdict,
         };
         match verdict {
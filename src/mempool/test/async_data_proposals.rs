@@ -64,8 +64,13 @@ async fn impl_test_mempool_isnt_blocked_by_proof_verification() -> Result<()> {
                         program_id: ProgramId(vec![]),
                         state_digest: StateDigest(vec![0, 1, 2, 3]),
                         contract_name: contract_name.clone(),
+                        blob_encoding: BlobEncoding::default(),
                     }
                     .as_blob("hyle".into(), None, None)],
+                    dependencies: vec![],
+                    additional_identities: vec![],
+                    nonce: None,
+                    fee: 0,
                 }
                 .into()],
             }],
@@ -100,6 +105,10 @@ async fn impl_test_mempool_isnt_blocked_by_proof_verification() -> Result<()> {
             contract_name: contract_name.clone(),
             data: BlobData(vec![]),
         }],
+        dependencies: vec![],
+        additional_identities: vec![],
+        nonce: None,
+        fee: 0,
     };
     let blob_tx_hash = blob_tx.hash();
     let proof = ProofData(
@@ -143,6 +152,7 @@ async fn impl_test_mempool_isnt_blocked_by_proof_verification() -> Result<()> {
                     data_prop_hash = cut.1.clone();
                 }
             }
+            ConsensusEvent::ValidatorEquivocation(_) => continue,
         }
         let evt: NodeStateEvent = node_client.recv().await?;
         match evt {
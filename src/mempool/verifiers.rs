@@ -1,82 +1,518 @@
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, LazyLock, RwLock,
+    },
+};
+
 use anyhow::{Context, Result};
-use hyle_model::{Identity, ProofData, Signed, ValidatorSignature};
+use bincode::{Decode, Encode};
+use hyle_model::{BlobTransaction, Identity, ProofData, Signed, ValidatorSignature};
+use serde::{Deserialize, Serialize};
 use sha3::Digest;
 
-use hyle_contract_sdk::{Blob, BlobIndex, HyleOutput, ProgramId, StateDigest, TxHash, Verifier};
+use hyle_contract_sdk::{BlobIndex, HyleOutput, ProgramId, StateDigest, TxHash, Verifier};
 
-use hyle_verifiers::{noir_proof_verifier, risc0_proof_verifier, sp1_proof_verifier};
+use hyle_verifiers::{
+    gnark_proof_verifier, risc0_groth16_proof_verifier, risc0_proof_verifier, sp1_proof_verifier,
+    DecodeError, NoirVerifier,
+};
 
+use super::metrics::VerifierMetrics;
 use crate::{
-    model::verifiers::{BlstSignatureBlob, NativeVerifiers, ShaBlob},
+    model::verifiers::{BlstSignatureBlob, NativeVerifiers, ShaBlob, WebAuthnBlob},
     utils::crypto::BlstCrypto,
 };
 
-pub fn verify_proof(
-    proof: &ProofData,
-    verifier: &Verifier,
-    #[allow(unused_variables)] program_id: &ProgramId,
-) -> Result<Vec<HyleOutput>> {
-    let hyle_outputs = match verifier.0.as_str() {
-        // TODO: add #[cfg(test)]
-        "test" => {
-            let (output, _) = bincode::decode_from_slice::<Vec<HyleOutput>, _>(
-                &proof.0,
-                bincode::config::standard(),
-            )?;
-            Ok(output)
+/// Prefix of a `ProgramId` that references a program by the sha3-256 hash of its content,
+/// instead of embedding it (useful for large verification keys, e.g. SP1 vks).
+pub const PROGRAM_ID_HASH_PREFIX: &[u8] = b"sha3-256:";
+
+/// Largest program accepted in the program store.
+pub const MAX_PROGRAM_SIZE: usize = 1_048_576; // 1 MB
+
+/// Programs kept while no registered contract references them, the oldest are dropped first.
+pub const MAX_UNREGISTERED_PROGRAMS: usize = 64;
+
+/// Content-addressed store of programs, used to resolve hash-referenced `ProgramId`s
+/// into the full program at verification time.
+///
+/// Programs uploaded to this node are kept in a bounded cache until a contract registers them.
+/// Programs of registered contracts that we don't have are fetched from the peers, and only
+/// those are accepted from them.
+#[derive(Debug, Default, Clone, Encode, Decode)]
+pub struct ProgramStore {
+    programs: HashMap<Vec<u8>, Vec<u8>>,
+    /// Stored programs not referenced by a registered contract yet, oldest first
+    unregistered: VecDeque<Vec<u8>>,
+    /// Programs of registered contracts that are missing from the store
+    wanted: BTreeSet<Vec<u8>>,
+}
+
+impl ProgramStore {
+    /// Returns the `ProgramId` referencing this program by hash.
+    pub fn hash_reference(program: &[u8]) -> ProgramId {
+        let mut hasher = sha3::Sha3_256::new();
+        hasher.update(program);
+        ProgramId([PROGRAM_ID_HASH_PREFIX, hasher.finalize().as_slice()].concat())
+    }
+
+    fn check_size(program: &[u8]) -> Result<()> {
+        if program.len() > MAX_PROGRAM_SIZE {
+            anyhow::bail!(
+                "Program is {} bytes, at most {} are accepted",
+                program.len(),
+                MAX_PROGRAM_SIZE
+            );
+        }
+        Ok(())
+    }
+
+    /// Stores a program uploaded to this node and returns the `ProgramId` referencing it.
+    /// It's dropped after [`MAX_UNREGISTERED_PROGRAMS`] newer uploads if no contract registers it.
+    pub fn insert(&mut self, program: Vec<u8>) -> Result<ProgramId> {
+        Self::check_size(&program)?;
+        let program_id = Self::hash_reference(&program);
+        if self.programs.contains_key(&program_id.0) {
+            return Ok(program_id);
+        }
+        if self.wanted.remove(&program_id.0) {
+            tracing::debug!("📦 Storing program {}", hex::encode(&program_id.0));
+            self.programs.insert(program_id.0.clone(), program);
+            return Ok(program_id);
+        }
+        tracing::debug!(
+            "📦 Storing unregistered program {}",
+            hex::encode(&program_id.0)
+        );
+        self.programs.insert(program_id.0.clone(), program);
+        self.unregistered.push_back(program_id.0.clone());
+        while self.unregistered.len() > MAX_UNREGISTERED_PROGRAMS {
+            if let Some(evicted) = self.unregistered.pop_front() {
+                self.programs.remove(&evicted);
+            }
+        }
+        Ok(program_id)
+    }
+
+    /// Stores a program received from a peer, if it's one we asked for.
+    pub fn insert_wanted(&mut self, program: Vec<u8>) -> Result<ProgramId> {
+        Self::check_size(&program)?;
+        let program_id = Self::hash_reference(&program);
+        if !self.wanted.remove(&program_id.0) {
+            anyhow::bail!("Program {} was not requested", hex::encode(&program_id.0));
+        }
+        tracing::debug!("📦 Storing program {}", hex::encode(&program_id.0));
+        self.programs.insert(program_id.0.clone(), program);
+        Ok(program_id)
+    }
+
+    /// Keeps the program of a registered contract for good. Returns true when it's
+    /// hash-referenced and missing from the store, it then has to be fetched from the peers.
+    pub fn on_registered_program(&mut self, program_id: &ProgramId) -> bool {
+        if !program_id.0.starts_with(PROGRAM_ID_HASH_PREFIX) {
+            return false;
+        }
+        self.unregistered.retain(|hash| hash != &program_id.0);
+        if self.programs.contains_key(&program_id.0) {
+            return false;
+        }
+        self.wanted.insert(program_id.0.clone())
+    }
+
+    /// Programs of registered contracts that still have to be fetched.
+    pub fn wanted(&self) -> impl Iterator<Item = ProgramId> + '_ {
+        self.wanted.iter().cloned().map(ProgramId)
+    }
+
+    /// The stored program referenced by this `ProgramId`.
+    pub fn get(&self, program_id: &ProgramId) -> Option<&Vec<u8>> {
+        self.programs.get(&program_id.0)
+    }
+
+    /// Resolves a hash-referenced `ProgramId` to the full program.
+    /// Other `ProgramId`s are returned as is.
+    pub fn resolve(&self, program_id: &ProgramId) -> Result<ProgramId> {
+        if !program_id.0.starts_with(PROGRAM_ID_HASH_PREFIX) {
+            return Ok(program_id.clone());
+        }
+        self.programs
+            .get(&program_id.0)
+            .map(|program| ProgramId(program.clone()))
+            .with_context(|| {
+                format!(
+                    "Program {} not found in program store",
+                    hex::encode(&program_id.0)
+                )
+            })
+    }
+}
+
+/// Backend implementation a verifier name can be dispatched to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifierBackend {
+    /// Decodes bincode-encoded HyleOutputs, without any actual verification.
+    Test,
+    Risc0,
+    /// Risc0, only accepting Groth16 receipts.
+    Risc0Groth16,
+    Noir,
+    Sp1,
+    /// Groth16 over BN254, proofs generated with gnark.
+    Gnark,
+}
+
+/// Verifier names every node dispatches to the same backend.
+pub const BUILTIN_VERIFIERS: [&str; 5] = ["test", "risc0", "noir", "sp1", "gnark"];
+
+impl VerifierBackend {
+    /// Backend of a built-in verifier name, which is part of the protocol.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            // TODO: add #[cfg(test)]
+            "test" => Some(VerifierBackend::Test),
+            "risc0" => Some(VerifierBackend::Risc0),
+            "noir" => Some(VerifierBackend::Noir),
+            "sp1" => Some(VerifierBackend::Sp1),
+            "gnark" => Some(VerifierBackend::Gnark),
+            _ => None,
+        }
+    }
+}
+
+/// Mapping of verifier names to backends, on top of the default mapping.
+/// It's local to the node: it only applies to proofs submitted to it, peers' data proposals
+/// are always verified with the built-in mapping.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(transparent)]
+pub struct VerifierBackends(pub HashMap<String, VerifierBackend>);
+
+impl VerifierBackends {
+    pub fn resolve(&self, verifier: &Verifier) -> Option<VerifierBackend> {
+        self.0
+            .get(&verifier.0)
+            .copied()
+            .or_else(|| VerifierBackend::from_name(&verifier.0))
+    }
+}
+
+#[derive(Debug)]
+pub enum VerifyError {
+    /// The verifier backend panicked, usually on malformed input.
+    BackendPanic(String),
+}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::BackendPanic(payload) => {
+                write!(f, "Verifier backend panicked: {}", payload)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Runs a verifier backend, turning a panic into a `VerifyError::BackendPanic`
+/// so that a malicious proof can't take the node down.
+fn catch_backend_panic<T>(verifier: &Verifier, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let payload = payload
+            .downcast_ref::<&str>()
+            .map(|p| p.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic payload".to_string());
+        tracing::error!("💥 {} verifier panicked: {}", verifier, payload);
+        Err(VerifyError::BackendPanic(payload).into())
+    })
+}
+
+/// A proving system able to verify proofs and extract their HyleOutputs.
+pub trait ProofVerifier: Send + Sync {
+    fn verify(&self, proof: &[u8], program_id: &ProgramId) -> Result<Vec<HyleOutput>>;
+}
+
+impl<F> ProofVerifier for F
+where
+    F: Fn(&[u8], &ProgramId) -> Result<Vec<HyleOutput>> + Send + Sync,
+{
+    fn verify(&self, proof: &[u8], program_id: &ProgramId) -> Result<Vec<HyleOutput>> {
+        self(proof, program_id)
+    }
+}
+
+/// Verifiers registered with `register_verifier`, available to every `VerifierRegistry`.
+static REGISTERED_VERIFIERS: LazyLock<RwLock<HashMap<String, Arc<dyn ProofVerifier>>>> =
+    LazyLock::new(Default::default);
+
+/// Makes a verifier available under `name`, e.g. for a proving system maintained out of tree.
+/// Built-in names and names already registered are refused. Registries are built when
+/// the node starts, so verifiers have to be registered before.
+pub fn register_verifier(name: impl Into<String>, verifier: Arc<dyn ProofVerifier>) -> Result<()> {
+    let name = name.into();
+    if BUILTIN_VERIFIERS.contains(&name.as_str()) {
+        anyhow::bail!("{} is a built-in verifier, it can't be replaced", name);
+    }
+    #[allow(clippy::expect_used, reason = "not held across await")]
+    let mut registered = REGISTERED_VERIFIERS.write().expect("logic issue");
+    if registered.contains_key(&name) {
+        anyhow::bail!("A verifier is already registered as {}", name);
+    }
+    registered.insert(name, verifier);
+    Ok(())
+}
+
+static VERIFIER_METRICS: LazyLock<VerifierMetrics> = LazyLock::new(VerifierMetrics::global);
+
+/// Verifiers keyed by their `Verifier` name.
+#[derive(Clone, Default)]
+pub struct VerifierRegistry(HashMap<String, Arc<dyn ProofVerifier>>);
+
+impl VerifierRegistry {
+    /// Built-in verifiers under their default names, then the verifiers registered with
+    /// `register_verifier`. This is the registry peers' data proposals are verified with.
+    pub fn new(noir: &NoirVerifier) -> Self {
+        let mut registry = Self::default();
+        for name in BUILTIN_VERIFIERS {
+            if let Some(backend) = VerifierBackend::from_name(name) {
+                registry.register(name, backend.verifier(noir));
+            }
         }
         #[cfg(test)]
-        "test-slow" => {
-            tracing::info!("Sleeping for 2 seconds to simulate a slow verifier");
-            std::thread::sleep(std::time::Duration::from_secs(2));
-            tracing::info!("Woke up from sleep");
-            Ok(serde_json::from_slice(&proof.0)?)
-        }
-        "risc0" => {
-            let journal = risc0_proof_verifier(&proof.0, &program_id.0)?;
-            // First try to decode it as a single HyleOutput
-            Ok(match journal.decode::<HyleOutput>() {
-                Ok(ho) => vec![ho],
-                Err(_) => {
-                    let hyle_output = journal
-                        .decode::<Vec<Vec<u8>>>()
-                        .context("Failed to extract HyleOuput from Risc0's journal")?;
-
-                    // Doesn't actually work to just deserialize in one go.
-                    hyle_output
-                        .iter()
-                        .map(|o| hyle_verifiers::risc0::from_slice::<HyleOutput, _>(o))
-                        .collect::<Result<Vec<_>, _>>()
-                        .context("Failed to decode HyleOutput")?
+        {
+            registry.register(
+                "test-slow",
+                Arc::new(|proof: &[u8], _: &ProgramId| -> Result<Vec<HyleOutput>> {
+                    tracing::info!("Sleeping for 2 seconds to simulate a slow verifier");
+                    std::thread::sleep(std::time::Duration::from_secs(2));
+                    tracing::info!("Woke up from sleep");
+                    Ok(serde_json::from_slice(proof)?)
+                }),
+            );
+            registry.register(
+                "test-cpu",
+                Arc::new(
+                    |proof: &[u8], program_id: &ProgramId| -> Result<Vec<HyleOutput>> {
+                        let mut digest = sha3::Sha3_256::digest(proof);
+                        for _ in 0..2_000_000 {
+                            digest = sha3::Sha3_256::digest(digest);
+                        }
+                        verify_test_proof(proof, program_id)
+                    },
+                ),
+            );
+            registry.register(
+                "test-panic",
+                Arc::new(|_: &[u8], _: &ProgramId| -> Result<Vec<HyleOutput>> {
+                    panic!("Simulating a verifier panic")
+                }),
+            );
+        }
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        for (name, verifier) in REGISTERED_VERIFIERS.read().expect("logic issue").iter() {
+            registry.register(name.clone(), verifier.clone());
+        }
+        registry
+    }
+
+    /// This registry with the configured remappings, for proofs submitted to this node.
+    pub fn with_local_backends(&self, backends: &VerifierBackends, noir: &NoirVerifier) -> Self {
+        let mut registry = self.clone();
+        for (name, backend) in &backends.0 {
+            registry.register(name.clone(), backend.verifier(noir));
+        }
+        registry
+    }
+
+    /// Registry of the default configuration.
+    #[cfg(test)]
+    pub fn default_for_tests() -> Self {
+        Self::new(&NoirVerifier::default())
+    }
+
+    fn register(&mut self, name: impl Into<String>, verifier: Arc<dyn ProofVerifier>) {
+        self.0.insert(name.into(), verifier);
+    }
+
+    pub fn verify(
+        &self,
+        proof: &ProofData,
+        verifier: &Verifier,
+        program_id: &ProgramId,
+    ) -> Result<Vec<HyleOutput>> {
+        let proof_verifier = self
+            .0
+            .get(&verifier.0)
+            .with_context(|| format!("{} verifier not implemented yet", verifier))?;
+        let span = tracing::info_span!(
+            "verify",
+            verifier = %verifier,
+            program_id = %truncated_hex(&program_id.0),
+            proof_len = proof.0.len(),
+            success = tracing::field::Empty,
+            error = tracing::field::Empty,
+        );
+        let _enter = span.enter();
+        let start = std::time::Instant::now();
+        let result = catch_backend_panic(verifier, || proof_verifier.verify(&proof.0, program_id));
+        VERIFIER_METRICS.record(verifier, start.elapsed(), &result);
+        match &result {
+            Ok(_) => {
+                span.record("success", true);
+            }
+            Err(e) => {
+                span.record("success", false);
+                span.record("error", tracing::field::display(format!("{e:#}")));
+            }
+        }
+        tracing::debug!("🔎 Verification done in {:?}", start.elapsed());
+        let hyle_outputs = result?;
+        hyle_outputs.iter().for_each(|hyle_output| {
+            tracing::debug!(
+                "🔎 {}",
+                std::str::from_utf8(&hyle_output.program_outputs)
+                    .map(|o| format!("Program outputs: {o}"))
+                    .unwrap_or("Invalid UTF-8".to_string())
+            );
+        });
+
+        Ok(hyle_outputs)
+    }
+
+    /// Verifies independent proofs concurrently, on at most one thread per available core.
+    /// Results are returned in the order of `inputs`.
+    pub fn verify_batch(
+        &self,
+        inputs: &[(&ProofData, &Verifier, &ProgramId)],
+    ) -> Vec<Result<Vec<HyleOutput>>> {
+        let threads = std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(inputs.len());
+        if threads <= 1 {
+            return inputs
+                .iter()
+                .map(|(proof, verifier, program_id)| self.verify(proof, verifier, program_id))
+                .collect();
+        }
+        let next = AtomicUsize::new(0);
+
+        let mut results = inputs.iter().map(|_| None).collect::<Vec<_>>();
+        std::thread::scope(|scope| {
+            let workers = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut verified = vec![];
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            let Some((proof, verifier, program_id)) = inputs.get(i) else {
+                                break verified;
+                            };
+                            verified.push((i, self.verify(proof, verifier, program_id)));
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            for worker in workers {
+                for (i, result) in worker.join().unwrap_or_default() {
+                    if let Some(slot) = results.get_mut(i) {
+                        *slot = Some(result);
+                    }
                 }
+            }
+        });
+
+        results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| Err(anyhow::anyhow!("Verification thread panicked")))
             })
+            .collect()
+    }
+}
+
+/// Hex of the first bytes of a program ID, enough to tell them apart in logs.
+fn truncated_hex(program_id: &[u8]) -> String {
+    hex::encode(program_id.get(..16).unwrap_or(program_id))
+}
+
+impl VerifierBackend {
+    fn verifier(self, noir: &NoirVerifier) -> Arc<dyn ProofVerifier> {
+        match self {
+            VerifierBackend::Test => Arc::new(verify_test_proof),
+            VerifierBackend::Risc0 => Arc::new(verify_risc0_proof),
+            VerifierBackend::Risc0Groth16 => Arc::new(verify_risc0_groth16_proof),
+            VerifierBackend::Noir => {
+                let noir = noir.clone();
+                Arc::new(move |proof: &[u8], program_id: &ProgramId| {
+                    noir.verify(proof, &program_id.0)
+                })
+            }
+            VerifierBackend::Sp1 => Arc::new(verify_sp1_proof),
+            VerifierBackend::Gnark => Arc::new(verify_gnark_proof),
         }
-        "noir" => noir_proof_verifier(&proof.0, &program_id.0),
-        "sp1" => sp1_proof_verifier(&proof.0, &program_id.0),
-        _ => Err(anyhow::anyhow!("{} verifier not implemented yet", verifier)),
-    }?;
-    hyle_outputs.iter().for_each(|hyle_output| {
-        tracing::debug!(
-            "🔎 {}",
-            std::str::from_utf8(&hyle_output.program_outputs)
-                .map(|o| format!("Program outputs: {o}"))
-                .unwrap_or("Invalid UTF-8".to_string())
-        );
-    });
+    }
+}
+
+fn verify_test_proof(proof: &[u8], _: &ProgramId) -> Result<Vec<HyleOutput>> {
+    let (output, _) =
+        bincode::decode_from_slice::<Vec<HyleOutput>, _>(proof, bincode::config::standard())
+            .context(DecodeError("Failed to decode test proof"))?;
+    Ok(output)
+}
+
+fn verify_risc0_proof(proof: &[u8], program_id: &ProgramId) -> Result<Vec<HyleOutput>> {
+    decode_risc0_hyle_outputs(risc0_proof_verifier(proof, &program_id.0)?)
+}
+
+fn verify_risc0_groth16_proof(proof: &[u8], program_id: &ProgramId) -> Result<Vec<HyleOutput>> {
+    decode_risc0_hyle_outputs(risc0_groth16_proof_verifier(proof, &program_id.0)?)
+}
+
+fn verify_sp1_proof(proof: &[u8], program_id: &ProgramId) -> Result<Vec<HyleOutput>> {
+    sp1_proof_verifier(proof, &program_id.0)
+}
+
+fn verify_gnark_proof(proof: &[u8], program_id: &ProgramId) -> Result<Vec<HyleOutput>> {
+    gnark_proof_verifier(proof, &program_id.0)
+}
 
-    Ok(hyle_outputs)
+fn decode_risc0_hyle_outputs(journal: hyle_verifiers::risc0::Journal) -> Result<Vec<HyleOutput>> {
+    // First try to decode it as a single HyleOutput
+    Ok(match journal.decode::<HyleOutput>() {
+        Ok(ho) => vec![ho],
+        Err(_) => {
+            let hyle_output = journal.decode::<Vec<Vec<u8>>>().context(DecodeError(
+                "Failed to extract HyleOuput from Risc0's journal",
+            ))?;
+
+            // Doesn't actually work to just deserialize in one go.
+            hyle_output
+                .iter()
+                .map(|o| hyle_verifiers::risc0::from_slice::<HyleOutput, _>(o))
+                .collect::<Result<Vec<_>, _>>()
+                .context(DecodeError("Failed to decode HyleOutput"))?
+        }
+    })
 }
 
 pub fn verify_recursive_proof(
     proof: &ProofData,
     verifier: &Verifier,
     program_id: &ProgramId,
+    backends: &VerifierBackends,
 ) -> Result<(Vec<ProgramId>, Vec<HyleOutput>)> {
     use risc0_recursion::{Risc0Journal, Risc0ProgramId};
 
-    let outputs = match verifier.0.as_str() {
-        "risc0" => {
-            let journal = risc0_proof_verifier(&proof.0, &program_id.0)?;
+    let outputs = catch_backend_panic(verifier, || match backends.resolve(verifier) {
+        Some(backend @ (VerifierBackend::Risc0 | VerifierBackend::Risc0Groth16)) => {
+            let journal = if backend == VerifierBackend::Risc0Groth16 {
+                risc0_groth16_proof_verifier(&proof.0, &program_id.0)?
+            } else {
+                risc0_proof_verifier(&proof.0, &program_id.0)?
+            };
             let mut output = journal
                 .decode::<Vec<(Risc0ProgramId, Risc0Journal)>>()
                 .context("Failed to extract HyleOuput from Risc0's journal")?;
@@ -95,7 +531,7 @@ pub fn verify_recursive_proof(
             "{} recursive verifier not implemented yet",
             verifier
         )),
-    }?;
+    })?;
     outputs.1.iter().for_each(|hyle_output| {
         tracing::debug!(
             "🔎 {}",
@@ -111,14 +547,12 @@ pub fn verify_recursive_proof(
 pub fn verify_native(
     tx_hash: TxHash,
     index: BlobIndex,
-    blobs: &[Blob],
+    tx: &BlobTransaction,
     verifier: NativeVerifiers,
 ) -> HyleOutput {
-    #[allow(clippy::expect_used, reason = "Logic error in the code")]
-    let blob = blobs.get(index.0).expect("Invalid blob index");
-    let blobs = hyle_contract_sdk::flatten_blobs(blobs);
+    let blobs = hyle_contract_sdk::flatten_blobs(&tx.blobs);
 
-    let (identity, success) = match verify_native_impl(blob, verifier) {
+    let (identity, success) = match verify_native_impl(tx, index, verifier) {
         Ok((identity, success)) => (identity, success),
         Err(e) => {
             tracing::trace!("Native blob verification failed: {:?}", e);
@@ -148,9 +582,11 @@ pub fn verify_native(
 }
 
 pub fn verify_native_impl(
-    blob: &Blob,
+    tx: &BlobTransaction,
+    index: BlobIndex,
     verifier: NativeVerifiers,
 ) -> anyhow::Result<(Identity, bool)> {
+    let blob = tx.blobs.get(index.0).context("Invalid blob index")?;
     match verifier {
         NativeVerifiers::Blst => {
             let (blob, _) = bincode::decode_from_slice::<BlstSignatureBlob, _>(
@@ -181,5 +617,306 @@ pub fn verify_native_impl(
 
             Ok((blob.identity, res == blob.sha))
         }
+        NativeVerifiers::WebAuthn => {
+            let (blob, _) = bincode::decode_from_slice::<WebAuthnBlob, _>(
+                &blob.data.0,
+                bincode::config::standard(),
+            )?;
+            let challenge = WebAuthnBlob::challenge(tx, index);
+            let verified = verify_webauthn_assertion(&blob, &challenge)?;
+            Ok((blob.identity, verified))
+        }
+    }
+}
+
+/// Checks that a WebAuthn assertion was made by a present user for this challenge,
+/// by the passkey of the blob identity, and that its signature is valid.
+fn verify_webauthn_assertion(blob: &WebAuthnBlob, challenge: &TxHash) -> Result<bool> {
+    use base64::Engine;
+    use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+
+    #[derive(Deserialize)]
+    struct ClientData {
+        #[serde(rename = "type")]
+        kind: String,
+        challenge: String,
+    }
+
+    let client_data: ClientData =
+        serde_json::from_slice(&blob.client_data_json).context("Failed to parse clientDataJSON")?;
+    if client_data.kind != "webauthn.get" {
+        anyhow::bail!("Unexpected WebAuthn client data type {}", client_data.kind);
+    }
+    let expected_challenge = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .encode(hex::decode(&challenge.0).context("Invalid challenge")?);
+    if client_data.challenge != expected_challenge {
+        anyhow::bail!(
+            "WebAuthn challenge {} does not match {}",
+            client_data.challenge,
+            expected_challenge
+        );
+    }
+
+    // Flags come after the 32 bytes of the RP ID hash, then 4 bytes of signature counter.
+    let flags = blob
+        .authenticator_data
+        .get(32)
+        .filter(|_| blob.authenticator_data.len() >= 37)
+        .context("authenticatorData is too short")?;
+    if flags & 0x01 == 0 {
+        anyhow::bail!("WebAuthn assertion was made without user presence");
+    }
+
+    let identity = WebAuthnBlob::identity(&blob.public_key)?;
+    if blob.identity != identity {
+        anyhow::bail!(
+            "WebAuthn identity {} is not the identity {} of the passkey",
+            blob.identity,
+            identity
+        );
+    }
+
+    let public_key =
+        VerifyingKey::from_sec1_bytes(&blob.public_key).context("Invalid secp256r1 public key")?;
+    let signature = Signature::from_der(&blob.signature).context("Invalid signature")?;
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(&blob.client_data_json);
+    let message = [blob.authenticator_data.as_slice(), &hasher.finalize()].concat();
+
+    Ok(public_key.verify(&message, &signature).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::indexing_slicing)]
+    use super::*;
+
+    #[test_log::test]
+    fn test_verifier_panic_is_caught() {
+        let res = VerifierRegistry::new(&NoirVerifier::default()).verify(
+            &ProofData(vec![1, 2, 3]),
+            &Verifier("test-panic".to_owned()),
+            &ProgramId(vec![]),
+        );
+        let err = res.expect_err("a panicking verifier should return an error");
+        assert!(matches!(
+            err.downcast_ref::<VerifyError>(),
+            Some(VerifyError::BackendPanic(payload)) if payload == "Simulating a verifier panic"
+        ));
+
+        // We survived the panic and can keep verifying proofs
+        let proof = ProofData(
+            bincode::encode_to_vec(vec![HyleOutput::default()], bincode::config::standard())
+                .unwrap(),
+        );
+        assert_eq!(
+            VerifierRegistry::new(&NoirVerifier::default())
+                .verify(&proof, &Verifier("test".to_owned()), &ProgramId(vec![]),)
+                .unwrap(),
+            vec![HyleOutput::default()]
+        );
+    }
+
+    #[test_log::test]
+    fn test_verifier_backend_remapping() {
+        let proof = ProofData(
+            bincode::encode_to_vec(vec![HyleOutput::default()], bincode::config::standard())
+                .unwrap(),
+        );
+        let custom = Verifier("custom".to_owned());
+
+        // Unknown verifier name without a mapping
+        assert!(VerifierRegistry::new(&NoirVerifier::default())
+            .verify(&proof, &custom, &ProgramId(vec![]),)
+            .is_err());
+
+        let backends = VerifierBackends(HashMap::from([
+            ("custom".to_owned(), VerifierBackend::Test),
+            ("test".to_owned(), VerifierBackend::Risc0Groth16),
+        ]));
+        assert_eq!(backends.resolve(&custom), Some(VerifierBackend::Test));
+        assert_eq!(
+            backends.resolve(&Verifier("risc0".to_owned())),
+            Some(VerifierBackend::Risc0)
+        );
+
+        let protocol = VerifierRegistry::default_for_tests();
+        let local = protocol.with_local_backends(&backends, &NoirVerifier::default());
+        assert_eq!(
+            local.verify(&proof, &custom, &ProgramId(vec![])).unwrap(),
+            vec![HyleOutput::default()]
+        );
+        // "test" now dispatches to risc0, which rejects the empty program ID
+        let err = local
+            .verify(&proof, &Verifier("test".to_owned()), &ProgramId(vec![]))
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("Risc0"));
+
+        // The protocol registry, used for peers' data proposals, ignores the remapping
+        assert!(protocol
+            .verify(&proof, &custom, &ProgramId(vec![]))
+            .is_err());
+        assert_eq!(
+            protocol
+                .verify(&proof, &Verifier("test".to_owned()), &ProgramId(vec![]))
+                .unwrap(),
+            vec![HyleOutput::default()]
+        );
+    }
+
+    #[test_log::test]
+    fn test_malformed_receipt_is_a_decode_error() {
+        let err = VerifierRegistry::new(&NoirVerifier::default())
+            .verify(
+                &ProofData(vec![1, 2, 3]),
+                &Verifier("risc0".to_owned()),
+                &ProgramId(vec![0; 32]),
+            )
+            .unwrap_err();
+        assert!(err.downcast_ref::<DecodeError>().is_some());
+    }
+
+    #[test_log::test]
+    fn test_verify_batch_preserves_order() {
+        let proof = |index: usize| {
+            bincode::encode_to_vec(
+                vec![HyleOutput {
+                    index: BlobIndex(index),
+                    ..HyleOutput::default()
+                }],
+                bincode::config::standard(),
+            )
+            .unwrap()
+        };
+        let mut inputs = (0..16)
+            .map(|i| {
+                (
+                    ProofData(proof(i)),
+                    Verifier("test".to_owned()),
+                    ProgramId(vec![]),
+                )
+            })
+            .collect::<Vec<_>>();
+        inputs[5].0 = ProofData(vec![0xff]);
+        inputs[9].1 = Verifier("test-panic".to_owned());
+        let inputs = inputs
+            .iter()
+            .map(|(proof, verifier, program_id)| (proof, verifier, program_id))
+            .collect::<Vec<_>>();
+
+        let results = VerifierRegistry::new(&NoirVerifier::default()).verify_batch(&inputs);
+
+        assert_eq!(results.len(), 16);
+        for (i, result) in results.into_iter().enumerate() {
+            match i {
+                5 | 9 => assert!(result.is_err()),
+                _ => assert_eq!(result.unwrap()[0].index, BlobIndex(i)),
+            }
+        }
+    }
+
+    /// Records the fields of the spans it sees
+    #[derive(Clone, Default)]
+    struct SpanFields(Arc<std::sync::Mutex<HashMap<String, String>>>);
+
+    impl tracing::field::Visit for SpanFields {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for SpanFields {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _: &tracing::span::Id,
+            _: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            attrs.record(&mut self.clone());
+        }
+
+        fn on_record(
+            &self,
+            _: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            _: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            values.record(&mut self.clone());
+        }
+    }
+
+    fn verify_with_span_fields(proof: &[u8], verifier: &str) -> HashMap<String, String> {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let fields = SpanFields::default();
+        let subscriber = tracing_subscriber::registry().with(fields.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            let _ = VerifierRegistry::new(&NoirVerifier::default()).verify(
+                &ProofData(proof.to_vec()),
+                &Verifier(verifier.to_owned()),
+                &ProgramId(vec![0xab; 40]),
+            );
+        });
+        let fields = fields.0.lock().unwrap().clone();
+        fields
+    }
+
+    #[test]
+    fn test_verification_span_fields() {
+        let proof =
+            bincode::encode_to_vec(vec![HyleOutput::default()], bincode::config::standard())
+                .unwrap();
+        let fields = verify_with_span_fields(&proof, "test");
+        assert_eq!(fields.get("verifier").unwrap(), "test");
+        assert_eq!(fields.get("program_id").unwrap(), &"ab".repeat(16));
+        assert_eq!(fields.get("proof_len").unwrap(), &proof.len().to_string());
+        assert_eq!(fields.get("success").unwrap(), "true");
+        assert!(!fields.contains_key("error"));
+
+        let fields = verify_with_span_fields(&[0xff], "test");
+        assert_eq!(fields.get("proof_len").unwrap(), "1");
+        assert_eq!(fields.get("success").unwrap(), "false");
+        assert!(fields
+            .get("error")
+            .unwrap()
+            .contains("Failed to decode test proof"));
+    }
+
+    /// No risc0 proof fixture is checked in, so the "test-cpu" verifier stands in for a
+    /// CPU-bound backend by hashing each proof two million times.
+    #[ignore = "manual test"]
+    #[test_log::test]
+    fn bench_verify_batch() {
+        let registry = VerifierRegistry::new(&NoirVerifier::default());
+        let proof = ProofData(
+            bincode::encode_to_vec(vec![HyleOutput::default()], bincode::config::standard())
+                .unwrap(),
+        );
+        let verifier = Verifier("test-cpu".to_owned());
+        let program_id = ProgramId(vec![]);
+        let inputs = vec![(&proof, &verifier, &program_id); 16];
+
+        let start = std::time::Instant::now();
+        for (proof, verifier, program_id) in &inputs {
+            registry.verify(proof, verifier, program_id).unwrap();
+        }
+        let sequential = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let results = registry.verify_batch(&inputs);
+        let batched = start.elapsed();
+
+        assert!(results.iter().all(|result| result.is_ok()));
+        tracing::info!(
+            "Verified 16 proofs in {:?} sequentially, {:?} in batch",
+            sequential,
+            batched
+        );
+        if std::thread::available_parallelism().map_or(1, |n| n.get()) > 1 {
+            assert!(batched < sequential);
+        }
     }
 }
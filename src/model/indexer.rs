@@ -1,14 +1,16 @@
 use hyle_model::api::{
-    APIBlob, APIBlock, APIContract, APIContractState, APITransaction, TransactionStatus,
+    APIBlob, APIBlock, APIContract, APIContractAction, APIContractState,
+    APIContractStateHistoryEntry, APIHyleOutputSummary, APIProof, APIProofMetadata,
+    APISettledBlobProof, APIStakingAction, APITransaction, TransactionCursor, TransactionStatus,
     TransactionType,
 };
-use hyle_model::ConsensusProposalHash;
+use hyle_model::{BlockHeight, ConsensusProposalHash, StateRoot};
 use serde::{Deserialize, Serialize};
 
 use sqlx::types::chrono::NaiveDateTime;
 use sqlx::{prelude::Type, Postgres};
 
-use hyle_contract_sdk::TxHash;
+use hyle_contract_sdk::{HyleOutput, TxHash};
 
 #[derive(sqlx::FromRow, Debug)]
 pub struct BlockDb {
@@ -17,7 +19,8 @@ pub struct BlockDb {
     pub parent_hash: ConsensusProposalHash,
     #[sqlx(try_from = "i64")]
     pub height: u64, // Corresponds to BlockHeight
-    pub timestamp: NaiveDateTime, // UNIX timestamp
+    pub timestamp: NaiveDateTime,   // UNIX timestamp
+    pub state_root: Option<String>, // Absent for blocks indexed before state roots
 }
 
 impl From<BlockDb> for APIBlock {
@@ -27,6 +30,7 @@ impl From<BlockDb> for APIBlock {
             parent_hash: value.parent_hash,
             height: value.height,
             timestamp: value.timestamp.and_utc().timestamp(),
+            state_root: value.state_root.map(StateRoot),
         }
     }
 }
@@ -57,6 +61,23 @@ impl From<TransactionDb> for APITransaction {
     }
 }
 
+#[derive(sqlx::FromRow, Debug)]
+pub struct TransactionWithHeightDb {
+    #[sqlx(flatten)]
+    pub transaction: TransactionDb,
+    #[sqlx(try_from = "i64")]
+    pub height: u64, // Height of the block containing the transaction
+}
+
+impl TransactionWithHeightDb {
+    pub fn cursor(&self) -> TransactionCursor {
+        TransactionCursor {
+            block_height: BlockHeight(self.height),
+            index: self.transaction.index,
+        }
+    }
+}
+
 #[derive(sqlx::FromRow, Debug)]
 pub struct BlobDb {
     pub tx_hash: TxHashDb, // Corresponds to the transaction hash
@@ -81,6 +102,88 @@ impl From<BlobDb> for APIBlob {
     }
 }
 
+#[derive(sqlx::FromRow, Debug)]
+pub struct SettledBlobProofDb {
+    // Struct for the settled lines of the blob_proof_outputs table
+    #[sqlx(try_from = "i32")]
+    pub blob_index: u32,
+    pub contract_name: String,
+    pub proof_tx_hash: TxHashDb,
+    #[sqlx(try_from = "i32")]
+    pub blob_proof_output_index: u32,
+    pub hyle_output: serde_json::Value,
+}
+
+impl TryFrom<SettledBlobProofDb> for APISettledBlobProof {
+    type Error = serde_json::Error;
+
+    fn try_from(value: SettledBlobProofDb) -> Result<Self, Self::Error> {
+        let hyle_output: HyleOutput = serde_json::from_value(value.hyle_output)?;
+        Ok(APISettledBlobProof {
+            blob_index: value.blob_index,
+            contract_name: value.contract_name,
+            proof_tx_hash: value.proof_tx_hash.0,
+            blob_proof_output_index: value.blob_proof_output_index,
+            hyle_output: APIHyleOutputSummary {
+                identity: hyle_output.identity.0,
+                initial_state: hyle_output.initial_state.0,
+                next_state: hyle_output.next_state.0,
+                success: hyle_output.success,
+            },
+        })
+    }
+}
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct StakingActionDb {
+    // Struct for the staking_actions table
+    pub block_hash: ConsensusProposalHash,
+    #[sqlx(try_from = "i64")]
+    pub block_height: u64,
+    #[sqlx(try_from = "i32")]
+    pub index: u32, // Index of the action within the block
+    pub identity: String,
+    pub action: serde_json::Value,
+}
+
+impl TryFrom<StakingActionDb> for APIStakingAction {
+    type Error = serde_json::Error;
+
+    fn try_from(value: StakingActionDb) -> Result<Self, Self::Error> {
+        Ok(APIStakingAction {
+            block_hash: value.block_hash,
+            block_height: BlockHeight(value.block_height),
+            index: value.index,
+            identity: value.identity,
+            action: serde_json::from_value(value.action)?,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct ContractActionDb {
+    #[sqlx(flatten)]
+    pub transaction: TransactionWithHeightDb,
+    #[sqlx(try_from = "i32")]
+    pub blob_index: u32, // Index of the blob within the transaction
+    pub identity: String, // Identity of the blob
+    pub data: Vec<u8>,    // Actual blob data
+}
+
+impl From<ContractActionDb> for APIContractAction {
+    fn from(value: ContractActionDb) -> Self {
+        APIContractAction {
+            tx_hash: value.transaction.transaction.tx_hash.0,
+            block_height: BlockHeight(value.transaction.height),
+            index: value.transaction.transaction.index,
+            blob_index: value.blob_index,
+            identity: value.identity,
+            data: value.data,
+            transaction_status: value.transaction.transaction.transaction_status,
+        }
+    }
+}
+
 #[derive(sqlx::FromRow, Debug)]
 pub struct ProofTransactionDb {
     // Struct for the proof_transactions table
@@ -89,6 +192,34 @@ pub struct ProofTransactionDb {
     pub proof: Vec<u8>,        // Proof associated with the transaction
 }
 
+impl From<ProofTransactionDb> for APIProof {
+    fn from(value: ProofTransactionDb) -> Self {
+        APIProof {
+            tx_hash: value.tx_hash.0,
+            contract_name: value.contract_name,
+            proof: value.proof,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct ProofMetadataDb {
+    pub tx_hash: TxHashDb,
+    pub contract_name: String,
+    #[sqlx(try_from = "i32")]
+    pub proof_len: u64,
+}
+
+impl From<ProofMetadataDb> for APIProofMetadata {
+    fn from(value: ProofMetadataDb) -> Self {
+        APIProofMetadata {
+            tx_hash: value.tx_hash.0,
+            contract_name: value.contract_name,
+            proof_len: value.proof_len,
+        }
+    }
+}
+
 #[derive(sqlx::FromRow, Debug)]
 pub struct ContractDb {
     // Struct for the contracts table
@@ -129,6 +260,24 @@ impl From<ContractStateDb> for APIContractState {
     }
 }
 
+#[derive(sqlx::FromRow, Debug)]
+pub struct ContractStateHistoryDb {
+    pub block_hash: ConsensusProposalHash,
+    #[sqlx(try_from = "i64")]
+    pub height: u64,
+    pub state_digest: Vec<u8>,
+}
+
+impl From<ContractStateHistoryDb> for APIContractStateHistoryEntry {
+    fn from(value: ContractStateHistoryDb) -> Self {
+        APIContractStateHistoryEntry {
+            block_hash: value.block_hash,
+            block_height: BlockHeight(value.height),
+            state_digest: value.state_digest,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct TxHashDb(pub TxHash);
 
@@ -1,11 +1,13 @@
 use hyle_contract_sdk::{
-    Blob, BlobData, BlobIndex, ContractAction, ContractName, Identity, ProgramId, Verifier,
+    Blob, BlobData, BlobIndex, ContractAction, ContractName, Identity, ProgramId, TxHash, Verifier,
 };
+use hyle_model::{BlobTransaction, Hashable};
 
 #[derive(Debug, Copy, Clone)]
 pub enum NativeVerifiers {
     Blst,
     Sha3_256,
+    WebAuthn,
 }
 
 impl From<NativeVerifiers> for ProgramId {
@@ -13,6 +15,7 @@ impl From<NativeVerifiers> for ProgramId {
         match value {
             NativeVerifiers::Blst => ProgramId("blst".as_bytes().to_vec()),
             NativeVerifiers::Sha3_256 => ProgramId("sha3_256".as_bytes().to_vec()),
+            NativeVerifiers::WebAuthn => ProgramId("webauthn".as_bytes().to_vec()),
         }
     }
 }
@@ -23,11 +26,54 @@ impl TryFrom<&Verifier> for NativeVerifiers {
         match value.0.as_str() {
             "blst" => Ok(Self::Blst),
             "sha3_256" => Ok(Self::Sha3_256),
+            "webauthn" => Ok(Self::WebAuthn),
             _ => Err(format!("Unknown native verifier: {}", value)),
         }
     }
 }
 
+/// Signature scheme an identity has to be proven with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SignatureScheme {
+    Bls12_381,
+    EcdsaSecp256k1,
+    EcdsaSecp256r1,
+    Ed25519,
+}
+
+impl SignatureScheme {
+    /// Scheme required for this identity, from its identity contract (i.e. `ecdsa_secp256r1`
+    /// in `bob.ecdsa_secp256r1`). Settlement depends on it, so it is a protocol constant.
+    pub fn for_identity(identity: &Identity) -> Option<SignatureScheme> {
+        let (_, identity_contract_name) = identity.0.split_once('.')?;
+        match identity_contract_name {
+            "blst" => Some(SignatureScheme::Bls12_381),
+            "ecdsa_secp256k1" => Some(SignatureScheme::EcdsaSecp256k1),
+            "ecdsa_secp256r1" => Some(SignatureScheme::EcdsaSecp256r1),
+            "ed25519" => Some(SignatureScheme::Ed25519),
+            _ => None,
+        }
+    }
+
+    /// Programs known to check signatures of this scheme, as (verifier, program ID).
+    /// A verifier alone proves nothing about the scheme, as e.g. a Noir circuit can prove
+    /// anything: no circuit is listed yet for ECDSA & EdDSA.
+    pub fn programs(&self) -> &'static [(&'static str, &'static [u8])] {
+        match self {
+            SignatureScheme::Bls12_381 => &[("blst", b"blst" as &[u8])],
+            SignatureScheme::EcdsaSecp256k1 => &[],
+            SignatureScheme::EcdsaSecp256r1 => &[],
+            SignatureScheme::Ed25519 => &[],
+        }
+    }
+
+    pub fn accepts(&self, verifier: &Verifier, program_id: &ProgramId) -> bool {
+        self.programs()
+            .iter()
+            .any(|(v, p)| verifier.0 == *v && program_id.0 == *p)
+    }
+}
+
 /// Format of the BlobData for native contract "blst"
 #[derive(Debug, bincode::Encode, bincode::Decode)]
 pub struct BlstSignatureBlob {
@@ -93,3 +139,62 @@ impl ContractAction for ShaBlob {
         }
     }
 }
+
+/// Format of the BlobData for native contract "webauthn": a WebAuthn assertion,
+/// as returned by `navigator.credentials.get()` for a passkey.
+#[derive(Debug, bincode::Encode, bincode::Decode)]
+pub struct WebAuthnBlob {
+    pub identity: Identity,
+    pub authenticator_data: Vec<u8>,
+    pub client_data_json: Vec<u8>,
+    /// DER encoded secp256r1 signature of authenticator_data + sha256(client_data_json)
+    pub signature: Vec<u8>,
+    /// SEC1 encoded secp256r1 public key of the passkey, which `identity` must be derived from
+    pub public_key: Vec<u8>,
+}
+
+impl WebAuthnBlob {
+    pub fn as_blob(&self) -> Blob {
+        <Self as ContractAction>::as_blob(self, "webauthn".into(), None, None)
+    }
+
+    /// Identity of the passkey with this SEC1 encoded public key: the hex-encoded sha256
+    /// of its uncompressed encoding, so that both encodings give the same identity.
+    pub fn identity(public_key: &[u8]) -> anyhow::Result<Identity> {
+        use sha2::Digest;
+
+        let public_key = p256::ecdsa::VerifyingKey::from_sec1_bytes(public_key)
+            .map_err(|_| anyhow::anyhow!("Invalid secp256r1 public key"))?;
+        let hash = sha2::Sha256::digest(public_key.to_encoded_point(false).as_bytes());
+        Ok(Identity(format!("{}.webauthn", hex::encode(hash))))
+    }
+
+    /// Challenge the assertion must sign for the blob at `index`.
+    /// The transaction hash covers the assertion itself, so this is the hash of the
+    /// transaction with the data of this blob left empty.
+    pub fn challenge(tx: &BlobTransaction, index: BlobIndex) -> TxHash {
+        let mut unsigned = tx.clone();
+        if let Some(blob) = unsigned.blobs.get_mut(index.0) {
+            blob.data = BlobData(vec![]);
+        }
+        unsigned.hash()
+    }
+}
+
+impl ContractAction for WebAuthnBlob {
+    fn as_blob(
+        &self,
+        contract_name: ContractName,
+        _caller: Option<BlobIndex>,
+        _callees: Option<Vec<BlobIndex>>,
+    ) -> Blob {
+        #[allow(clippy::expect_used)]
+        Blob {
+            contract_name,
+            data: BlobData(
+                bincode::encode_to_vec(self, bincode::config::standard())
+                    .expect("failed to encode WebAuthnBlob"),
+            ),
+        }
+    }
+}
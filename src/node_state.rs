@@ -1,36 +1,65 @@
 //! State required for participation in consensus by the node.
 
 use crate::mempool::verifiers;
-use crate::model::verifiers::NativeVerifiers;
+use crate::model::verifiers::{NativeVerifiers, SignatureScheme};
 use crate::model::*;
+use crate::utils::crypto::BlstCrypto;
 use anyhow::{bail, Error, Result};
+use audit::{VerificationAudit, VerificationAuditEntry, VerificationResult};
 use bincode::{Decode, Encode};
 use contract_registration::validate_contract_registration;
-use hyle_contract_sdk::{utils::parse_structured_blob, BlobIndex, HyleOutput, TxHash};
+use hyle_contract_sdk::{BlobIndex, HyleOutput, TxHash};
 use ordered_tx_map::OrderedTxMap;
+use orphan_proofs::OrphanProofs;
+use staking::state::Staking;
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     sync::Arc,
 };
 use timeouts::Timeouts;
-use tracing::{debug, error, info, trace};
+use tracing::{debug, error, info, trace, warn};
 
 mod api;
+pub mod audit;
+mod metrics;
 pub mod module;
 mod ordered_tx_map;
+mod orphan_proofs;
+pub mod snapshot;
 mod timeouts;
+mod tx_statuses;
 
 pub struct SettledTxOutput {
     // Original blob transaction, now settled.
     pub tx: UnsettledBlobTransaction,
     /// This is the index of the blob proof output used in the blob settlement, for each blob.
-    pub blob_proof_output_indices: Vec<usize>,
+    /// Blobs skipped because the blob they depend on failed have none.
+    pub blob_proof_output_indices: Vec<Option<usize>>,
     /// New data for contracts modified by the settled TX.
     pub updated_contracts: BTreeMap<ContractName, Contract>,
     /// Whether the transaction is settled as a success or a failure.
     pub success: bool,
 }
 
+/// Height from which each transaction version is accepted in blocks, part of the protocol
+/// so that all nodes handle the same txs. Versions not listed are never accepted.
+#[cfg(not(test))]
+pub const TX_VERSION_ACTIVATIONS: &[(u32, BlockHeight)] = &[(1, BlockHeight(0))];
+/// Tests activate a version 2 at height 10.
+#[cfg(test)]
+pub const TX_VERSION_ACTIVATIONS: &[(u32, BlockHeight)] =
+    &[(1, BlockHeight(0)), (2, BlockHeight(10))];
+
+/// Highest transaction version accepted in the block at this height.
+pub fn max_tx_version_at(height: BlockHeight) -> u32 {
+    TX_VERSION_ACTIVATIONS
+        .iter()
+        .filter(|(_, activation)| activation.0 <= height.0)
+        .map(|(version, _)| *version)
+        .max()
+        .unwrap_or(0)
+}
+
 /// NodeState manages the flattened, up-to-date state of the chain.
 /// It processes raw transactions and outputs more structured data for indexers.
 /// See also: NodeStateModule for the actual module implementation.
@@ -38,9 +67,16 @@ pub struct SettledTxOutput {
 pub struct NodeState {
     timeouts: Timeouts,
     current_height: BlockHeight,
+    /// Height of the last block fully handled, none before the genesis block
+    last_handled_height: Option<BlockHeight>,
     // This field is public for testing purposes
     pub contracts: HashMap<ContractName, Contract>,
     unsettled_transactions: OrderedTxMap,
+    pub orphan_proofs: OrphanProofs,
+    pub verification_audit: VerificationAudit,
+    /// Replica of the staking contract state, from the staking actions of settled txs,
+    /// to apply the slashes decided by the consensus to its on-chain state.
+    staking: Staking,
 }
 
 // TODO: we should register the 'hyle' TLD in the genesis block.
@@ -49,8 +85,12 @@ impl Default for NodeState {
         let mut ret = Self {
             timeouts: Timeouts::default(),
             current_height: BlockHeight(0),
+            last_handled_height: None,
             contracts: HashMap::new(),
             unsettled_transactions: OrderedTxMap::default(),
+            orphan_proofs: OrphanProofs::default(),
+            verification_audit: VerificationAudit::default(),
+            staking: Staking::default(),
         };
         // Insert a default hyle-TLD contract
         ret.contracts.insert(
@@ -60,6 +100,7 @@ impl Default for NodeState {
                 program_id: ProgramId(vec![]),
                 state: StateDigest(vec![0]),
                 verifier: Verifier("hyle".to_owned()),
+                blob_encoding: BlobEncoding::default(),
             },
         );
         ret
@@ -67,6 +108,24 @@ impl Default for NodeState {
 }
 
 impl NodeState {
+    /// Handles a block unless it was already handled, which happens when blocks are
+    /// delivered again after a restart. Blocks are applied entirely in memory, and the
+    /// state is persisted in between blocks, so a block is never partially applied.
+    pub fn handle_new_signed_block(&mut self, signed_block: &SignedBlock) -> Option<Block> {
+        if self
+            .last_handled_height
+            .is_some_and(|last| signed_block.height() <= last)
+        {
+            warn!(
+                "Block {} {} already handled, skipping it",
+                signed_block.height(),
+                signed_block.hash()
+            );
+            return None;
+        }
+        Some(self.handle_signed_block(signed_block))
+    }
+
     pub fn handle_signed_block(&mut self, signed_block: &SignedBlock) -> Block {
         self.current_height = signed_block.height();
 
@@ -80,18 +139,39 @@ impl NodeState {
             blob_proof_outputs: vec![],
             successful_txs: vec![],
             verified_blobs: vec![],
-            staking_actions: vec![],
+            // Slashes decided by the consensus, staking actions of settled txs are added below
+            staking_actions: signed_block
+                .consensus_proposal
+                .staking_actions
+                .iter()
+                .filter_map(|v| match v {
+                    ConsensusStakingAction::Slash {
+                        validator, amount, ..
+                    } => Some((
+                        "consensus".into(),
+                        StakingAction::Slash {
+                            validator: validator.clone(),
+                            amount: *amount,
+                        },
+                    )),
+                    ConsensusStakingAction::Bond { .. } => None,
+                })
+                .collect(),
             new_bounded_validators: signed_block
                 .consensus_proposal
                 .staking_actions
                 .iter()
-                .map(|v| match v {
-                    ConsensusStakingAction::Bond { candidate } => candidate.pubkey.clone(),
+                .filter_map(|v| match v {
+                    ConsensusStakingAction::Bond { candidate } => Some(candidate.pubkey.clone()),
+                    ConsensusStakingAction::Slash { .. } => None,
                 })
                 .collect(),
             timed_out_txs: vec![], // Added below as it needs the block
             registered_contracts: vec![],
+            updated_programs: vec![],
+            blob_encodings: BTreeMap::new(),
             updated_states: BTreeMap::new(),
+            state_root: StateRoot::default(), // Computed once all transactions are handled
         };
 
         // We'll need to remember some data to validate transactions proofs.
@@ -103,24 +183,66 @@ impl NodeState {
         });
 
         self.clear_timeouts(&mut block_under_construction);
+        self.orphan_proofs.drop_expired(self.current_height);
+
+        // Slashes are applied before the txs of the block, as by the consensus
+        let slashes: Vec<(ValidatorPublicKey, u128)> = block_under_construction
+            .staking_actions
+            .iter()
+            .filter_map(|(_, action)| match action {
+                StakingAction::Slash { validator, amount } => Some((validator.clone(), *amount)),
+                _ => None,
+            })
+            .collect();
+        for (validator, amount) in slashes {
+            if let Some(state) = self.slash_staking_contract(&validator, amount) {
+                block_under_construction
+                    .updated_states
+                    .insert("staking".into(), state);
+            }
+        }
 
         let txs = signed_block.txs();
+        let lanes = signed_block.data_proposals.iter().flat_map(|(lane, dps)| {
+            dps.iter()
+                .flat_map(move |dp| dp.txs.iter().map(move |tx| (lane, tx)))
+        });
+        let max_tx_version = max_tx_version_at(self.current_height);
         // Handle all transactions
-        for tx in txs.iter() {
+        for (lane, tx) in lanes {
+            if tx.version > max_tx_version {
+                warn!(
+                    "Ignoring tx {} with version {}, only versions up to {} are active at height {}",
+                    tx.hash(),
+                    tx.version,
+                    max_tx_version,
+                    self.current_height
+                );
+                if let TransactionData::Blob(_) = tx.transaction_data {
+                    block_under_construction.failed_txs.push(tx.hash());
+                }
+                continue;
+            }
             match &tx.transaction_data {
                 TransactionData::Blob(blob_transaction) => {
                     match self.handle_blob_tx(blob_transaction, tx_context.clone()) {
-                        Ok(Some(tx_hash)) => {
-                            let mut blob_tx_to_try_and_settle = BTreeSet::new();
-                            blob_tx_to_try_and_settle.insert(tx_hash);
+                        Ok(maybe_tx_hash) => {
                             // In case of a BlobTransaction with only native verifies, we need to trigger the
                             // settlement here as we will never get a ProofTransaction
-                            self.settle_txs_until_done(
-                                &mut block_under_construction,
-                                blob_tx_to_try_and_settle,
-                            );
+                            let mut blob_tx_to_try_and_settle =
+                                maybe_tx_hash.into_iter().collect::<BTreeSet<_>>();
+                            // Proofs may also have arrived before the transaction itself
+                            blob_tx_to_try_and_settle.extend(self.handle_orphan_proofs(
+                                &blob_transaction.hash(),
+                                &mut block_under_construction.blob_proof_outputs,
+                            ));
+                            if !blob_tx_to_try_and_settle.is_empty() {
+                                self.settle_txs_until_done(
+                                    &mut block_under_construction,
+                                    blob_tx_to_try_and_settle,
+                                );
+                            }
                         }
-                        Ok(None) => {}
                         Err(e) => {
                             error!("Failed to handle blob transaction: {:?}", e);
                             block_under_construction.failed_txs.push(tx.hash());
@@ -134,17 +256,95 @@ impl NodeState {
                     // First, store the proofs and check if we can settle the transaction
                     // NB: if some of the blob proof outputs are bad, we just ignore those
                     // but we don't actually fail the transaction.
+                    let (timestamp, block_height) = (
+                        block_under_construction.block_timestamp,
+                        block_under_construction.block_height,
+                    );
+                    let verifier = self
+                        .contracts
+                        .get(&proof_tx.contract_name)
+                        .map(|contract| contract.verifier.clone())
+                        .unwrap_or_default();
+                    // A recursive proof is rejected as a whole if any of its programs is wrong
+                    let recursive_proof_error = match proof_tx.is_recursive {
+                        true => self.check_recursive_program_ids(proof_tx).err(),
+                        false => None,
+                    };
                     let blob_tx_to_try_and_settle = proof_tx
                         .proven_blobs
                         .iter()
                         .filter_map(|blob_proof_data| {
+                            let audit_entry = |result| VerificationAuditEntry {
+                                timestamp,
+                                block_height,
+                                submitted_by: lane.clone(),
+                                proof_tx_hash: proof_tx.hash(),
+                                proof_hash: proof_tx.proof_hash.clone(),
+                                contract_name: proof_tx.contract_name.clone(),
+                                verifier: verifier.clone(),
+                                program_id: hex::encode(&blob_proof_data.program_id.0),
+                                blob_tx_hash: blob_proof_data.blob_tx_hash.clone(),
+                                blob_index: blob_proof_data.hyle_output.index,
+                                result,
+                            };
+                            if let Some(err) = &recursive_proof_error {
+                                self.verification_audit.record(audit_entry(
+                                    VerificationResult::Invalid(format!("{err:#}")),
+                                ));
+                                info!(
+                                    "Rejecting blob #{} in recursive proof transaction {:?}: {err}",
+                                    blob_proof_data.hyle_output.index, proof_tx.hash(),
+                                );
+                                return None;
+                            }
+                            if self
+                                .orphan_proofs
+                                .is_finished(&blob_proof_data.blob_tx_hash)
+                            {
+                                self.verification_audit.record(audit_entry(
+                                    VerificationResult::Invalid(
+                                        "BlobTx already settled or timed out".into(),
+                                    ),
+                                ));
+                                info!(
+                                    "Ignoring proof for BlobTx {} that already went through",
+                                    blob_proof_data.blob_tx_hash
+                                );
+                                return None;
+                            }
+                            if self
+                                .unsettled_transactions
+                                .get(&blob_proof_data.blob_tx_hash)
+                                .is_none()
+                            {
+                                self.verification_audit
+                                    .record(audit_entry(VerificationResult::Buffered));
+                                debug!(
+                                    "Buffering proof for unknown BlobTx {}",
+                                    blob_proof_data.blob_tx_hash
+                                );
+                                self.orphan_proofs.push(
+                                    lane,
+                                    proof_tx.hash(),
+                                    blob_proof_data.clone(),
+                                    self.current_height,
+                                );
+                                return None;
+                            }
                             match self.handle_blob_proof(
                                 proof_tx.hash(),
                                 &mut block_under_construction.blob_proof_outputs,
                                 blob_proof_data,
                             ) {
-                                Ok(maybe_tx_hash) => maybe_tx_hash,
+                                Ok(maybe_tx_hash) => {
+                                    self.verification_audit
+                                        .record(audit_entry(VerificationResult::Valid));
+                                    maybe_tx_hash
+                                }
                                 Err(err) => {
+                                    self.verification_audit.record(audit_entry(
+                                        VerificationResult::Invalid(format!("{err:#}")),
+                                    ));
                                     info!(
                                         "Failed to handle blob #{} in verified proof transaction {:?}: {err}",
                                         blob_proof_data.hyle_output.index, proof_tx.hash(),
@@ -162,10 +362,38 @@ impl NodeState {
                 }
             }
         }
+        for tx_hash in (block_under_construction.successful_txs.iter())
+            .chain(block_under_construction.failed_txs.iter())
+            .chain(block_under_construction.timed_out_txs.iter())
+        {
+            self.orphan_proofs.on_finished(tx_hash);
+        }
         block_under_construction.txs = txs;
+        block_under_construction.state_root = StateRoot::compute(&self.contract_digests());
+        self.last_handled_height = Some(block_under_construction.block_height);
         block_under_construction
     }
 
+    /// Iterates over all registered contracts, in no particular order.
+    pub fn contracts(&self) -> impl Iterator<Item = (&ContractName, &Contract)> {
+        self.contracts.iter()
+    }
+
+    /// Snapshot of the current state digest of every registered contract.
+    pub fn contract_digests(&self) -> BTreeMap<ContractName, StateDigest> {
+        self.contracts()
+            .map(|(name, contract)| (name.clone(), contract.state.clone()))
+            .collect()
+    }
+
+    /// Proof that the current state digest of a contract is part of the latest state root.
+    pub fn state_inclusion_proof(
+        &self,
+        contract_name: &ContractName,
+    ) -> Option<StateInclusionProof> {
+        StateInclusionProof::build(&self.contract_digests(), contract_name)
+    }
+
     pub fn handle_register_contract_effect(&mut self, tx: &RegisterContractEffect) {
         info!("📝 Registering contract {}", tx.contract_name);
         self.contracts.insert(
@@ -175,6 +403,7 @@ impl NodeState {
                 program_id: tx.program_id.clone(),
                 state: tx.state_digest.clone(),
                 verifier: tx.verifier.clone(),
+                blob_encoding: BlobEncoding::default(),
             },
         );
     }
@@ -194,6 +423,8 @@ impl NodeState {
             bail!("Blob Transaction must have at least one blob");
         }
 
+        let dependencies = tx.blob_dependencies()?;
+
         let (blob_tx_hash, blobs_hash) = (tx.hash(), tx.blobs_hash());
 
         let mut should_try_and_settle = true;
@@ -203,6 +434,7 @@ impl NodeState {
             .iter()
             .enumerate()
             .map(|(index, blob)| {
+                let depends_on = dependencies.get(index).copied().flatten();
                 if let Some(Ok(verifier)) = self
                     .contracts
                     .get(&blob.contract_name)
@@ -211,12 +443,13 @@ impl NodeState {
                     let hyle_output = verifiers::verify_native(
                         blob_tx_hash.clone(),
                         BlobIndex(index),
-                        &tx.blobs,
+                        tx,
                         verifier,
                     );
                     return UnsettledBlobMetadata {
                         blob: blob.clone(),
                         possible_proofs: vec![(verifier.into(), hyle_output)],
+                        depends_on,
                     };
                 } else if blob.contract_name.0 == "hyle" {
                     // Special case for 'hyle' - we generate a fake proof like for native verifiers
@@ -238,6 +471,7 @@ impl NodeState {
                         return UnsettledBlobMetadata {
                             blob: blob.clone(),
                             possible_proofs: vec![(ProgramId(vec![]), synthetic_output)],
+                            depends_on,
                         };
                     }
                 } else {
@@ -246,13 +480,20 @@ impl NodeState {
                 UnsettledBlobMetadata {
                     blob: blob.clone(),
                     possible_proofs: vec![],
+                    depends_on,
                 }
             })
             .collect();
+        // Proofs refer to blobs by their position in the hashed transaction
+        debug_assert_eq!(
+            BlobsHash::from_vec(&blobs.iter().map(|b| b.blob.clone()).collect::<Vec<_>>()),
+            blobs_hash
+        );
 
         // If we're behind other pending transactions, we can't settle yet.
         should_try_and_settle = self.unsettled_transactions.add(UnsettledBlobTransaction {
             identity: tx.identity.clone(),
+            additional_identities: tx.additional_identities.clone(),
             hash: blob_tx_hash.clone(),
             tx_context,
             blobs_hash,
@@ -261,7 +502,7 @@ impl NodeState {
 
         // Update timeouts
         self.timeouts
-            .set(blob_tx_hash.clone(), self.current_height + 100); // TODO: Timeout after 100 blocks, make it configurable !
+            .schedule(blob_tx_hash.clone(), self.current_height);
 
         if should_try_and_settle {
             Ok(Some(blob_tx_hash))
@@ -336,14 +577,48 @@ impl NodeState {
         })
     }
 
+    /// Applies the proofs that were received before their blob transaction.
+    /// Returns the transactions that may now be settled.
+    fn handle_orphan_proofs(
+        &mut self,
+        blob_tx_hash: &TxHash,
+        blob_proof_outputs: &mut Vec<HandledBlobProofOutput>,
+    ) -> BTreeSet<TxHash> {
+        self.orphan_proofs
+            .take(blob_tx_hash)
+            .into_iter()
+            .filter_map(|(proof_tx_hash, blob_proof_data)| {
+                match self.handle_blob_proof(proof_tx_hash, blob_proof_outputs, &blob_proof_data) {
+                    Ok(maybe_tx_hash) => maybe_tx_hash,
+                    Err(err) => {
+                        info!(
+                            "Failed to handle buffered proof for blob #{} of {}: {err}",
+                            blob_proof_data.hyle_output.index, blob_tx_hash,
+                        );
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Settles as many txs as possible, starting from the given ones.
+    ///
+    /// The order in which txs settle changes the content of the block, so it must be the same
+    /// on every node: txs are always tried in the order they were added to the unsettled
+    /// transactions, that is by block then by index within the block, ties broken by hash.
+    /// Nothing here may depend on the iteration order of a `HashMap` or `HashSet`.
     fn settle_txs_until_done(
         &mut self,
         block_under_construction: &mut Block,
-        mut blob_tx_to_try_and_settle: BTreeSet<TxHash>,
+        blob_tx_to_try_and_settle: BTreeSet<TxHash>,
     ) {
+        let mut blob_tx_to_try_and_settle = blob_tx_to_try_and_settle
+            .into_iter()
+            .map(|bth| (self.unsettled_transactions.position(&bth), bth))
+            .collect::<BTreeSet<_>>();
         loop {
-            // TODO: investigate most performant order;
-            let Some(bth) = blob_tx_to_try_and_settle.pop_first() else {
+            let Some((_, bth)) = blob_tx_to_try_and_settle.pop_first() else {
                 break;
             };
 
@@ -355,14 +630,19 @@ impl NodeState {
                     success,
                 }) => {
                     // Settle the TX and add any new TXs to try and settle next.
-                    blob_tx_to_try_and_settle.append(&mut self.on_settled_blob_tx(
+                    let next_txs = self.on_settled_blob_tx(
                         block_under_construction,
                         bth,
                         settled_tx,
                         blob_proof_output_indices,
                         tx_updated_contracts,
                         success,
-                    ));
+                    );
+                    blob_tx_to_try_and_settle.extend(
+                        next_txs
+                            .into_iter()
+                            .map(|bth| (self.unsettled_transactions.position(&bth), bth)),
+                    );
                 }
                 Err(e) => debug!("Tx {:?} not ready to settle: {:?}", &bth, e),
             }
@@ -399,6 +679,7 @@ impl NodeState {
                 updated_contracts,
                 unsettled_tx.blobs.iter(),
                 vec![],
+                BTreeSet::new(),
             ) {
                 Some(res) => res,
                 None => {
@@ -406,6 +687,13 @@ impl NodeState {
                 }
             };
 
+        // Each identity must be proven with the signature scheme required by its suffix.
+        let success = success
+            && std::iter::once(&unsettled_tx.identity)
+                .chain(&unsettled_tx.additional_identities)
+                .all(|identity| self.validate_identity_scheme(identity, &updated_contracts))
+            && Self::all_identities_proven(unsettled_tx, &blob_proof_output_indices);
+
         // We are OK to settle now.
 
         #[allow(clippy::unwrap_used, reason = "must exist because of above checks")]
@@ -422,16 +710,40 @@ impl NodeState {
         })
     }
 
+    /// `failed_blobs` are the earlier blobs of the transaction that failed or were skipped,
+    /// without failing the transaction, because later blobs depend on them.
     fn settle_blobs_recursively<'a>(
         contracts: &HashMap<ContractName, Contract>,
         current_contracts: BTreeMap<ContractName, Contract>,
         mut blob_iter: impl Iterator<Item = &'a UnsettledBlobMetadata> + Clone,
-        mut blob_proof_output_indices: Vec<usize>,
-    ) -> Option<(BTreeMap<ContractName, Contract>, Vec<usize>, bool)> {
+        mut blob_proof_output_indices: Vec<Option<usize>>,
+        mut failed_blobs: BTreeSet<usize>,
+    ) -> Option<(BTreeMap<ContractName, Contract>, Vec<Option<usize>>, bool)> {
         // Recursion end-case: we succesfully settled all prior blobs, so success.
         let Some(current_blob) = blob_iter.next() else {
             return Some((current_contracts, blob_proof_output_indices, true));
         };
+        let blob_index = blob_proof_output_indices.len();
+
+        // Conditional blob whose condition failed: skip it, no proof needed.
+        if current_blob
+            .depends_on
+            .is_some_and(|depends_on| failed_blobs.contains(&depends_on.0))
+        {
+            debug!(
+                "Skipping blob {} as the blob it depends on failed",
+                blob_index
+            );
+            blob_proof_output_indices.push(None);
+            failed_blobs.insert(blob_index);
+            return Self::settle_blobs_recursively(
+                contracts,
+                current_contracts,
+                blob_iter,
+                blob_proof_output_indices,
+                failed_blobs,
+            );
+        }
 
         let contract_name = &current_blob.blob.contract_name;
         #[allow(
@@ -446,7 +758,7 @@ impl NodeState {
         // so we really can't do this before we've settled the earlier blobs.
         if contract_name.0 == "hyle" {
             // Have to push something here or the rest of the logic breaks
-            blob_proof_output_indices.push(0);
+            blob_proof_output_indices.push(Some(0));
             return match Self::handle_blob_for_hyle_tld(
                 contracts,
                 &current_contracts,
@@ -460,6 +772,7 @@ impl NodeState {
                         us,
                         blob_iter.clone(),
                         blob_proof_output_indices.clone(),
+                        failed_blobs.clone(),
                     )
                 }
                 Err(err) => {
@@ -469,6 +782,16 @@ impl NodeState {
                 }
             };
         }
+        // Checked before any proof, so that the staking contract never applies the rotation.
+        if !Self::is_key_rotation_authorized(&current_blob.blob, known_contract_state.blob_encoding)
+        {
+            debug!(
+                "Key rotation of blob {} is not signed by the old key",
+                blob_index
+            );
+            blob_proof_output_indices.push(None);
+            return Some((current_contracts, blob_proof_output_indices, false));
+        }
         // Regular case: go through each proof for this blob. If they settle, carry on recursively.
         for (i, proof_metadata) in current_blob.possible_proofs.iter().enumerate() {
             if !Self::validate_proof_metadata(proof_metadata, known_contract_state) {
@@ -485,27 +808,59 @@ impl NodeState {
                 continue;
             }
             if !proof_metadata.1.success {
-                // We have a valid proof of failure, we short-circuit.
-                debug!("Proven failure for blob {}", i);
-                return Some((current_contracts, blob_proof_output_indices, false));
+                if !blob_iter
+                    .clone()
+                    .any(|blob| blob.depends_on == Some(BlobIndex(blob_index)))
+                {
+                    // We have a valid proof of failure, we short-circuit.
+                    debug!("Proven failure for blob {}", i);
+                    return Some((current_contracts, blob_proof_output_indices, false));
+                }
+                // Later blobs are conditioned on this one: they get skipped, but the TX carries on.
+                let mut indices = blob_proof_output_indices.clone();
+                indices.push(Some(i));
+                let mut failed = failed_blobs.clone();
+                failed.insert(blob_index);
+                match Self::settle_blobs_recursively(
+                    contracts,
+                    current_contracts.clone(),
+                    blob_iter.clone(),
+                    indices,
+                    failed,
+                ) {
+                    Some(res) => return Some(res),
+                    _ => continue,
+                }
             }
+            // The current program may hand the contract over to a new one
+            let program_update = proof_metadata
+                .1
+                .registered_contracts
+                .iter()
+                .find(|effect| &effect.contract_name == contract_name);
             // TODO: ideally make this CoW
             let mut us = current_contracts.clone();
             us.insert(
                 contract_name.clone(),
                 Contract {
                     name: contract_name.clone(),
-                    program_id: proof_metadata.0.clone(),
+                    program_id: program_update
+                        .map_or(&proof_metadata.0, |effect| &effect.program_id)
+                        .clone(),
                     state: proof_metadata.1.next_state.clone(),
-                    verifier: known_contract_state.verifier.clone(),
+                    verifier: program_update
+                        .map_or(&known_contract_state.verifier, |effect| &effect.verifier)
+                        .clone(),
+                    blob_encoding: known_contract_state.blob_encoding,
                 },
             );
-            blob_proof_output_indices.push(i);
+            blob_proof_output_indices.push(Some(i));
             match Self::settle_blobs_recursively(
                 contracts,
                 us,
                 blob_iter.clone(),
                 blob_proof_output_indices.clone(),
+                failed_blobs.clone(),
             ) {
                 // If this proof settles, early return, otherwise try the next one (with continue for explicitness)
                 Some(res) => return Some(res),
@@ -516,6 +871,55 @@ impl NodeState {
         None
     }
 
+    /// Applies the staking action of a settled tx to the staking replica, as the contract did.
+    fn apply_staking_action(
+        staking: &mut Staking,
+        identity: &Identity,
+        action: &StakingAction,
+    ) -> Result<(), String> {
+        match action {
+            StakingAction::Stake { amount } => staking.stake(identity.clone(), *amount),
+            StakingAction::Delegate { validator } => {
+                staking.delegate_to(identity.clone(), validator.clone())
+            }
+            StakingAction::RotateKey {
+                old_pubkey,
+                new_pubkey,
+                nonce,
+                ..
+            } => staking.rotate_key(old_pubkey, new_pubkey.clone(), *nonce),
+            StakingAction::Distribute { .. } | StakingAction::Slash { .. } => Ok(String::new()),
+        }
+        .map(|_| ())
+    }
+
+    /// Slashes are decided by the consensus, not by a tx of the staking contract. Its on-chain
+    /// state is replaced by the digest of the slashed replica, as long as they were in sync.
+    /// Returns the new state of the contract.
+    fn slash_staking_contract(
+        &mut self,
+        validator: &ValidatorPublicKey,
+        amount: u128,
+    ) -> Option<StateDigest> {
+        let contract = self.contracts.get_mut(&ContractName::new("staking"))?;
+        if contract.state != self.staking.as_digest() {
+            error!(
+                "Staking contract state diverged from its replica, can't slash {}",
+                validator
+            );
+            return None;
+        }
+        if let Err(e) = self.staking.slash(validator, amount) {
+            error!(
+                "Failed to slash {} in the staking contract: {}",
+                validator, e
+            );
+            return None;
+        }
+        contract.state = self.staking.as_digest();
+        Some(contract.state.clone())
+    }
+
     /// Handle a settled blob transaction.
     /// Handles the multiple side-effects of settling.
     /// This returns the list of new TXs to try and settle next,
@@ -525,7 +929,7 @@ impl NodeState {
         block_under_construction: &mut Block,
         bth: TxHash,
         settled_tx: UnsettledBlobTransaction,
-        blob_proof_output_indices: Vec<usize>,
+        blob_proof_output_indices: Vec<Option<usize>>,
         tx_updated_contracts: BTreeMap<ContractName, Contract>,
         success: bool,
     ) -> BTreeSet<TxHash> {
@@ -546,7 +950,7 @@ impl NodeState {
                 block_under_construction.verified_blobs.push((
                     bth.clone(),
                     hyle_contract_sdk::BlobIndex(i),
-                    blob_proof_output_indices.get(i).cloned(),
+                    blob_proof_output_indices.get(i).copied().flatten(),
                 ));
 
                 self.unsettled_transactions
@@ -561,13 +965,38 @@ impl NodeState {
         } else {
             // Take note of staking and contract registration
             for (i, mut blob_metadata) in settled_tx.blobs.into_iter().enumerate() {
-                #[allow(clippy::indexing_slicing, reason = "all exist by construction")]
-                let settled_proof = blob_metadata
-                    .possible_proofs
-                    .remove(blob_proof_output_indices[i]);
+                // Skipped blobs have no proof, and failed conditions have no side-effects.
+                let Some(proof_index) = blob_proof_output_indices.get(i).copied().flatten() else {
+                    continue;
+                };
+                let settled_proof = blob_metadata.possible_proofs.remove(proof_index);
+                if !settled_proof.1.success {
+                    continue;
+                }
 
                 for rce in settled_proof.1.registered_contracts {
+                    // The contract replaced its own program, applied with its state below
+                    if rce.contract_name == blob_metadata.blob.contract_name {
+                        info!("🔧 Updating the program of contract {}", rce.contract_name);
+                        block_under_construction
+                            .updated_programs
+                            .push((bth.clone(), rce));
+                        continue;
+                    }
                     self.handle_register_contract_effect(&rce);
+                    // The encoding isn't part of the effect, only registrations through the
+                    // 'hyle' TLD declare it.
+                    if let (Some(registered), Some(contract)) = (
+                        tx_updated_contracts.get(&rce.contract_name),
+                        self.contracts.get_mut(&rce.contract_name),
+                    ) {
+                        contract.blob_encoding = registered.blob_encoding;
+                        if registered.blob_encoding != BlobEncoding::default() {
+                            block_under_construction
+                                .blob_encodings
+                                .insert(rce.contract_name.clone(), registered.blob_encoding);
+                        }
+                    }
                     block_under_construction
                         .registered_contracts
                         .push((bth.clone(), rce));
@@ -576,12 +1005,30 @@ impl NodeState {
                 let blob = blob_metadata.blob;
                 // Keep track of all stakers
                 if blob.contract_name.0 == "staking" {
-                    if let Some(structured_blob) = parse_structured_blob(&[blob], &BlobIndex(0)) {
-                        let staking_action: StakingAction = structured_blob.data.parameters;
-
-                        block_under_construction
-                            .staking_actions
-                            .push((settled_tx.identity.clone(), staking_action));
+                    let blob_encoding = self
+                        .contracts
+                        .get(&blob.contract_name)
+                        .map(|c| c.blob_encoding)
+                        .unwrap_or_default();
+                    if let Ok(structured_blob) =
+                        blob_encoding.decode::<StructuredBlobData<StakingAction>>(&blob.data)
+                    {
+                        let staking_action: StakingAction = structured_blob.parameters;
+
+                        if let StakingAction::Slash { .. } = staking_action {
+                            warn!("Ignoring Slash staking action of settled tx {}", bth);
+                        } else {
+                            if let Err(e) = Self::apply_staking_action(
+                                &mut self.staking,
+                                &settled_tx.identity,
+                                &staking_action,
+                            ) {
+                                error!("Staking replica diverged on tx {}: {}", bth, e);
+                            }
+                            block_under_construction
+                                .staking_actions
+                                .push((settled_tx.identity.clone(), staking_action));
+                        }
                     } else {
                         error!("Failed to parse StakingAction");
                     }
@@ -602,7 +1049,11 @@ impl NodeState {
                     "Update {} contract state: {:?}",
                     contract_name, next_state.state
                 );
-                self.contracts.get_mut(contract_name).unwrap().state = next_state.state.clone(); // unwrap, see above ^
+                let contract = self.contracts.get_mut(contract_name).unwrap(); // unwrap, see above ^
+                contract.state = next_state.state.clone();
+                // Only differ once the program was updated by the tx
+                contract.program_id = next_state.program_id.clone();
+                contract.verifier = next_state.verifier.clone();
 
                 // TODO: would be nice to have a drain-like API here.
                 block_under_construction
@@ -622,7 +1073,7 @@ impl NodeState {
         let Ok(reg) =
             StructuredBlobData::<RegisterContractAction>::try_from(current_blob.data.clone())
         else {
-            bail!("Blob is  not a RegisterContractAction");
+            bail!("Blob is not a RegisterContractAction");
         };
 
         // Check name, it's either a direct subdomain or a TLD
@@ -643,7 +1094,135 @@ impl NodeState {
             program_id: reg.parameters.program_id.clone(),
             state: reg.parameters.state_digest.clone(),
             verifier: reg.parameters.verifier.clone(),
+            blob_encoding: reg.parameters.blob_encoding,
+        })
+    }
+
+    fn validate_identity_scheme(
+        &self,
+        identity: &Identity,
+        updated_contracts: &BTreeMap<ContractName, Contract>,
+    ) -> bool {
+        let Some(scheme) = SignatureScheme::for_identity(identity) else {
+            return true;
+        };
+        let Some((_, identity_contract_name)) = identity.0.split_once('.') else {
+            return false;
+        };
+        let identity_contract_name = ContractName::new(identity_contract_name);
+        let Some(contract) = updated_contracts
+            .get(&identity_contract_name)
+            .or_else(|| self.contracts.get(&identity_contract_name))
+        else {
+            return false;
+        };
+        if !scheme.accepts(&contract.verifier, &contract.program_id) {
+            info!(
+                "Identity {} requires a {:?} proof, but contract {} runs program {} of verifier {}",
+                identity,
+                scheme,
+                identity_contract_name,
+                hex::encode(&contract.program_id.0),
+                contract.verifier
+            );
+            return false;
+        }
+        true
+    }
+
+    /// With several identities, each of them must be the identity of a settled proof
+    /// of a blob on its identity contract.
+    /// Single identity transactions are already covered by `verify_hyle_output`.
+    fn all_identities_proven(
+        unsettled_tx: &UnsettledBlobTransaction,
+        blob_proof_output_indices: &[Option<usize>],
+    ) -> bool {
+        if unsettled_tx.additional_identities.is_empty() {
+            return true;
+        }
+        std::iter::once(&unsettled_tx.identity)
+            .chain(&unsettled_tx.additional_identities)
+            .all(|identity| {
+                let proven = std::iter::zip(&unsettled_tx.blobs, blob_proof_output_indices)
+                    .filter_map(|(blob, index)| Some((blob, blob.possible_proofs.get((*index)?)?)))
+                    .any(|(blob, (_, hyle_output))| {
+                        &hyle_output.identity == identity
+                            && identity
+                                .0
+                                .split_once('.')
+                                .is_some_and(|(_, contract)| blob.blob.contract_name.0 == contract)
+                    });
+                if !proven {
+                    info!(
+                        "Identity {} of BlobTx {} is not proven by its identity contract",
+                        identity, unsettled_tx.hash
+                    );
+                }
+                proven
+            })
+    }
+
+    /// A key rotation has to be signed by the rotated key, other staking actions need no proof.
+    fn is_key_rotation_authorized(blob: &Blob, blob_encoding: BlobEncoding) -> bool {
+        if blob.contract_name.0 != "staking" {
+            return true;
+        }
+        let Ok(StructuredBlobData {
+            parameters:
+                StakingAction::RotateKey {
+                    old_pubkey,
+                    new_pubkey,
+                    nonce,
+                    proof,
+                },
+            ..
+        }) = blob_encoding.decode::<StructuredBlobData<StakingAction>>(&blob.data)
+        else {
+            return true;
+        };
+        BlstCrypto::verify(&SignedByValidator {
+            msg: KeyRotationMessage::new(old_pubkey.clone(), new_pubkey, nonce),
+            signature: ValidatorSignature {
+                signature: Signature(proof),
+                validator: old_pubkey,
+            },
         })
+        .unwrap_or(false)
+    }
+
+    /// Each blob proven by a recursive proof must be proven by the program of its contract.
+    /// Blobs of unknown or not yet registered contracts are only checked when settling.
+    fn check_recursive_program_ids(&self, proof_tx: &VerifiedProofTransaction) -> Result<()> {
+        for blob_proof_data in &proof_tx.proven_blobs {
+            let Some(unsettled_tx) = self
+                .unsettled_transactions
+                .get(&blob_proof_data.blob_tx_hash)
+            else {
+                continue;
+            };
+            let index = blob_proof_data.hyle_output.index;
+            let Some(blob) = unsettled_tx.blobs.get(index.0) else {
+                bail!(
+                    "blob at index {} not found in blob TX {}",
+                    index,
+                    blob_proof_data.blob_tx_hash
+                );
+            };
+            let Some(contract) = self.contracts.get(&blob.blob.contract_name) else {
+                continue;
+            };
+            if contract.program_id != blob_proof_data.program_id {
+                bail!(
+                    "Blob #{} of {} is proven by program {}, but contract {} runs program {}",
+                    index,
+                    blob_proof_data.blob_tx_hash,
+                    hex::encode(&blob_proof_data.program_id.0),
+                    contract.name,
+                    hex::encode(&contract.program_id.0)
+                );
+            }
+        }
+        Ok(())
     }
 
     // Assumes verify_hyle_output was already called
@@ -651,8 +1230,10 @@ impl NodeState {
         proof_metadata: &(ProgramId, HyleOutput),
         contract: &Contract,
     ) -> bool {
+        // Besides sub-contracts, a contract may only name itself, to update its program
         if proof_metadata.1.registered_contracts.iter().any(|effect| {
-            validate_contract_registration(&contract.name, &effect.contract_name).is_err()
+            effect.contract_name != contract.name
+                && validate_contract_registration(&contract.name, &effect.contract_name).is_err()
         }) {
             return false;
         }
@@ -665,7 +1246,11 @@ impl NodeState {
         hyle_output: &HyleOutput,
     ) -> Result<(), Error> {
         // Identity verification
-        if unsettled_tx.identity != hyle_output.identity {
+        if unsettled_tx.identity != hyle_output.identity
+            && !unsettled_tx
+                .additional_identities
+                .contains(&hyle_output.identity)
+        {
             bail!(
                 "Proof identity '{:?}' does not correspond to BlobTx identity '{:?}'.",
                 hyle_output.identity,
@@ -706,8 +1291,21 @@ impl NodeState {
     }
 
     fn clear_timeouts(&mut self, block_under_construction: &mut Block) {
-        let mut txs_at_timeout = self.timeouts.drop(&block_under_construction.block_height);
+        let block_height = block_under_construction.block_height;
+        let mut txs_at_timeout = self.timeouts.drop(&block_height);
         txs_at_timeout.retain(|tx| {
+            if self.unsettled_transactions.get(tx).is_none() {
+                self.timeouts.end_grace_period(tx);
+                return false;
+            }
+            if self.timeouts.start_grace_period(tx, block_height) {
+                info!(
+                    "⏳ Blob tx {} reached its timeout, accepting late proofs for {} more blocks",
+                    tx,
+                    self.timeouts.grace_period()
+                );
+                return false;
+            }
             if let Some(mut tx) = self.unsettled_transactions.remove(tx) {
                 info!("⏰ Blob tx timed out: {}", &tx.hash);
 
@@ -741,8 +1339,10 @@ pub mod test {
     mod native_verifiers;
 
     use super::*;
+    use crate::model::verifiers::BlstSignatureBlob;
     use assertables::assert_err;
     use hyle_contract_sdk::flatten_blobs;
+    use snapshot::SNAPSHOT_VERSION;
     use utils::get_current_timestamp_ms;
 
     async fn new_node_state() -> NodeState {
@@ -764,8 +1364,13 @@ pub mod test {
                 program_id: ProgramId(vec![]),
                 state_digest: StateDigest(vec![0, 1, 2, 3]),
                 contract_name: name,
+                blob_encoding: BlobEncoding::default(),
             }
             .as_blob("hyle".into(), None, None)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         }
     }
 
@@ -910,6 +1515,10 @@ pub mod test {
         let blob_tx = BlobTransaction {
             identity: identity.clone(),
             blobs: vec![new_blob("c1")],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
 
         let ctx = bogus_tx_context();
@@ -936,6 +1545,184 @@ pub mod test {
         assert_eq!(state.contracts.get(&c1).unwrap().state.0, vec![4, 5, 6]);
     }
 
+    async fn settle_identity_with_verifier(identity_contract: &str, verifier: &str) -> Block {
+        let mut state = new_node_state().await;
+        let contract_name = ContractName::new(identity_contract);
+        state.handle_register_contract_effect(&RegisterContractEffect {
+            verifier: verifier.into(),
+            ..make_register_contract_effect(contract_name.clone())
+        });
+
+        let blob_tx = BlobTransaction {
+            identity: Identity::new(format!("bob.{identity_contract}")),
+            blobs: vec![new_blob(identity_contract)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let hyle_output = make_hyle_output(blob_tx.clone(), BlobIndex(0));
+        let verified_proof = new_proof_tx(&contract_name, &hyle_output, &blob_tx.hash());
+
+        state.handle_signed_block(&craft_signed_block(
+            1,
+            vec![blob_tx.into(), verified_proof.into()],
+        ))
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn identity_proven_with_expected_scheme() {
+        let mut state = new_node_state().await;
+        state.handle_register_contract_effect(&RegisterContractEffect {
+            verifier: "blst".into(),
+            program_id: NativeVerifiers::Blst.into(),
+            state_digest: StateDigest::default(),
+            contract_name: "blst".into(),
+        });
+
+        let identity = Identity::new("bob.blst");
+        let crypto = BlstCrypto::new_random().unwrap();
+        let data = vec![1, 2, 3];
+        let signature = crypto
+            .sign([data.clone(), identity.0.as_bytes().to_vec()].concat())
+            .unwrap();
+        let blob = BlstSignatureBlob {
+            identity: identity.clone(),
+            data,
+            signature: signature.signature.signature.0,
+            public_key: crypto.validator_pubkey().0.clone(),
+        };
+        let blob_tx = BlobTransaction {
+            identity,
+            blobs: vec![blob.as_blob()],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+
+        let block = state.handle_signed_block(&craft_signed_block(1, vec![blob_tx.into()]));
+        assert_eq!(block.successful_txs.len(), 1);
+        assert_eq!(block.failed_txs.len(), 0);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn identity_proven_with_wrong_scheme_is_rejected() {
+        let block = settle_identity_with_verifier("blst", "test").await;
+        assert_eq!(block.successful_txs.len(), 0);
+        assert_eq!(block.failed_txs.len(), 1);
+
+        let block = settle_identity_with_verifier("ecdsa_secp256r1", "secp256k1").await;
+        assert_eq!(block.successful_txs.len(), 0);
+        assert_eq!(block.failed_txs.len(), 1);
+
+        // Any statement can be proven with Noir, not only a signature of the scheme
+        let block = settle_identity_with_verifier("ecdsa_secp256k1", "noir").await;
+        assert_eq!(block.successful_txs.len(), 0);
+        assert_eq!(block.failed_txs.len(), 1);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn identity_without_scheme_is_not_restricted() {
+        let block = settle_identity_with_verifier("c1", "test").await;
+        assert_eq!(block.successful_txs.len(), 1);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn enumerate_contracts_with_digests() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        let c2 = ContractName::new("c2");
+        state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+        state.handle_register_contract_effect(&RegisterContractEffect {
+            state_digest: StateDigest(vec![9]),
+            ..make_register_contract_effect(c2.clone())
+        });
+
+        let mut names = state
+            .contracts()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        names.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(names, vec![c1.clone(), c2.clone(), "hyle".into()]);
+
+        assert_eq!(
+            state.contract_digests(),
+            BTreeMap::from([
+                (c1, StateDigest(vec![0, 1, 2, 3])),
+                (c2, StateDigest(vec![9])),
+                ("hyle".into(), StateDigest(vec![0])),
+            ])
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn contract_digest_is_proven_against_block_state_root() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        let c2 = ContractName::new("c2");
+        let block = state.handle_signed_block(&craft_signed_block(
+            1,
+            vec![
+                make_register_contract_tx(c1.clone()).into(),
+                make_register_contract_tx(c2.clone()).into(),
+            ],
+        ));
+
+        assert_eq!(
+            block.state_root,
+            StateRoot::compute(&state.contract_digests())
+        );
+
+        let digest = state.contracts.get(&c1).unwrap().state.clone();
+        let proof = state.state_inclusion_proof(&c1).unwrap();
+        assert!(proof.verify(&block.state_root, &c1, &digest));
+        assert!(!proof.verify(&block.state_root, &c1, &StateDigest(vec![42])));
+        assert!(!proof.verify(&block.state_root, &c2, &digest));
+        assert!(state
+            .state_inclusion_proof(&ContractName::new("unknown"))
+            .is_none());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn node_state_snapshot_roundtrip() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        let c2 = ContractName::new("c2");
+        state.handle_signed_block(&craft_signed_block(
+            1,
+            vec![
+                make_register_contract_tx(c1.clone()).into(),
+                make_register_contract_tx(c2.clone()).into(),
+            ],
+        ));
+
+        let snapshot = state.export_snapshot().unwrap();
+        let imported = NodeState::import_snapshot(&snapshot).unwrap();
+        assert_eq!(imported.contract_digests(), state.contract_digests());
+        assert_eq!(
+            imported.state_inclusion_proof(&c2),
+            state.state_inclusion_proof(&c2)
+        );
+        assert_eq!(
+            imported.contracts.get(&c1).unwrap().program_id,
+            state.contracts.get(&c1).unwrap().program_id
+        );
+        assert_eq!(imported.current_height, state.current_height);
+
+        // Unknown version
+        let mut other_version = snapshot.clone();
+        *other_version.first_mut().unwrap() = SNAPSHOT_VERSION as u8 + 1;
+        assert_err!(NodeState::import_snapshot(&other_version));
+
+        // A contract registered under another name
+        let c3 = state.contracts.get(&c1).unwrap().clone();
+        state.contracts.insert("c3".into(), c3);
+        assert_err!(NodeState::import_snapshot(
+            &state.export_snapshot().unwrap()
+        ));
+    }
+
     #[test_log::test(tokio::test)]
     async fn blob_tx_without_blobs() {
         let mut state = new_node_state().await;
@@ -944,6 +1731,10 @@ pub mod test {
         let blob_tx = BlobTransaction {
             identity: identity.clone(),
             blobs: vec![],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
 
         assert_err!(state.handle_blob_tx(&blob_tx, bogus_tx_context()));
@@ -957,11 +1748,33 @@ pub mod test {
         let blob_tx = BlobTransaction {
             identity: identity.clone(),
             blobs: vec![new_blob("test")],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
 
         assert_err!(state.handle_blob_tx(&blob_tx, bogus_tx_context()));
     }
 
+    #[test_log::test(tokio::test)]
+    async fn tx_versions_are_activated_by_height() {
+        let mut state = new_node_state().await;
+        let v2 = |name: &str| {
+            let tx: Transaction = make_register_contract_tx(ContractName::new(name)).into();
+            Transaction { version: 2, ..tx }
+        };
+
+        let early = v2("c1");
+        let block = state.handle_signed_block(&craft_signed_block(9, vec![early.clone()]));
+        assert_eq!(block.failed_txs, vec![early.hash()]);
+        assert!(!state.contracts.contains_key(&"c1".into()));
+
+        let block = state.handle_signed_block(&craft_signed_block(10, vec![v2("c2")]));
+        assert!(block.failed_txs.is_empty());
+        assert!(state.contracts.contains_key(&"c2".into()));
+    }
+
     #[test_log::test(tokio::test)]
     async fn two_proof_for_one_blob_tx() {
         let mut state = new_node_state().await;
@@ -975,6 +1788,10 @@ pub mod test {
         let blob_tx = BlobTransaction {
             identity: identity.clone(),
             blobs: vec![new_blob(&c1.0), new_blob(&c2.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let blob_tx_hash = blob_tx.hash();
 
@@ -997,6 +1814,75 @@ pub mod test {
         assert_eq!(state.contracts.get(&c2).unwrap().state.0, vec![4, 5, 6]);
     }
 
+    #[test_log::test(tokio::test)]
+    async fn two_identities_need_both_proofs_to_settle() {
+        let c1 = ContractName::new("c1");
+        let c2 = ContractName::new("c2");
+        let alice = Identity::new("alice.c1");
+        let bob = Identity::new("bob.c2");
+
+        let blob_tx = BlobTransaction {
+            identity: alice.clone(),
+            blobs: vec![new_blob(&c1.0), new_blob(&c2.0)],
+            dependencies: vec![],
+            additional_identities: vec![bob.clone()],
+            nonce: None,
+            fee: 0,
+        };
+        let blob_tx_hash = blob_tx.hash();
+
+        // Each identity needs a blob on its identity contract
+        let mut state = new_node_state().await;
+        assert_err!(state.handle_blob_tx(
+            &BlobTransaction {
+                blobs: vec![new_blob(&c1.0)],
+                ..blob_tx.clone()
+            },
+            bogus_tx_context()
+        ));
+
+        let settle = |c2_prover: &Identity| {
+            let c2_prover = c2_prover.clone();
+            let blob_tx = blob_tx.clone();
+            let (c1, c2, blob_tx_hash) = (c1.clone(), c2.clone(), blob_tx_hash.clone());
+            async move {
+                let mut state = new_node_state().await;
+                state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+                state.handle_register_contract_effect(&make_register_contract_effect(c2.clone()));
+                state.handle_blob_tx(&blob_tx, bogus_tx_context()).unwrap();
+
+                let hyle_output_c1 = make_hyle_output(blob_tx.clone(), BlobIndex(0));
+                let hyle_output_c2 = HyleOutput {
+                    identity: c2_prover,
+                    ..make_hyle_output(blob_tx.clone(), BlobIndex(1))
+                };
+                handle_verify_proof_transaction(
+                    &mut state,
+                    &new_proof_tx(&c1, &hyle_output_c1, &blob_tx_hash),
+                )
+                .unwrap();
+                // Only one of the identities is proven, the TX waits
+                assert!(state.unsettled_transactions.get(&blob_tx_hash).is_some());
+                handle_verify_proof_transaction(
+                    &mut state,
+                    &new_proof_tx(&c2, &hyle_output_c2, &blob_tx_hash),
+                )
+                .unwrap();
+                assert!(state.unsettled_transactions.get(&blob_tx_hash).is_none());
+                state
+            }
+        };
+
+        let state = settle(&bob).await;
+        assert_eq!(state.contracts.get(&c1).unwrap().state.0, vec![4, 5, 6]);
+        assert_eq!(state.contracts.get(&c2).unwrap().state.0, vec![4, 5, 6]);
+
+        // Bob's identity contract proved alice instead: bob never agreed, the TX fails
+        let state = settle(&alice).await;
+        assert_eq!(state.contracts.get(&c1).unwrap().state.0, vec![0, 1, 2, 3]);
+        assert_eq!(state.contracts.get(&c2).unwrap().state.0, vec![0, 1, 2, 3]);
+    }
+
     #[test_log::test(tokio::test)]
     async fn wrong_blob_index_for_contract() {
         let mut state = new_node_state().await;
@@ -1009,6 +1895,10 @@ pub mod test {
         let blob_tx_1 = BlobTransaction {
             identity: Identity::new("test.c1"),
             blobs: vec![new_blob(&c1.0), new_blob(&c2.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let blob_tx_hash_1 = blob_tx_1.hash();
 
@@ -1033,17 +1923,536 @@ pub mod test {
     }
 
     #[test_log::test(tokio::test)]
-    async fn two_proof_for_same_blob() {
+    async fn proof_of_another_blob_tx_is_rejected() {
         let mut state = new_node_state().await;
         let c1 = ContractName::new("c1");
-        let c2 = ContractName::new("c2");
-
-        let register_c1 = make_register_contract_effect(c1.clone());
-        let register_c2 = make_register_contract_effect(c2.clone());
+        state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
 
-        let blob_tx = BlobTransaction {
+        // Same identity and blobs, only the nonce tells them apart
+        let blob_tx = |nonce| BlobTransaction {
             identity: Identity::new("test.c1"),
-            blobs: vec![new_blob(&c1.0), new_blob(&c2.0)],
+            blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: Some(nonce),
+            fee: 0,
+        };
+        let (blob_tx_a, blob_tx_b) = (blob_tx(1), blob_tx(2));
+        for tx in [&blob_tx_a, &blob_tx_b] {
+            state.handle_blob_tx(tx, bogus_tx_context()).unwrap();
+        }
+
+        // Proven for A, submitted for B
+        let hyle_output = make_hyle_output(blob_tx_a.clone(), BlobIndex(0));
+        let proof = new_proof_tx(&c1, &hyle_output, &blob_tx_b.hash());
+        let err = state
+            .handle_blob_proof(
+                TxHash::new(""),
+                &mut vec![],
+                proof.proven_blobs.first().unwrap(),
+            )
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("does not correspond to BlobTx hash"));
+
+        assert!(state
+            .unsettled_transactions
+            .get(&blob_tx_b.hash())
+            .is_some());
+        assert_eq!(state.contracts.get(&c1).unwrap().state.0, vec![0, 1, 2, 3]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn contract_program_is_updated_by_its_current_program() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        let c2 = ContractName::new("c2");
+        state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+        state.handle_register_contract_effect(&make_register_contract_effect(c2.clone()));
+
+        let update = RegisterContractEffect {
+            contract_name: c1.clone(),
+            program_id: ProgramId(vec![7]),
+            verifier: "test".into(),
+            state_digest: StateDigest(vec![]),
+        };
+
+        // Another contract cannot update the program of c1
+        let unauthorized_tx = BlobTransaction {
+            identity: Identity::new("test.c2"),
+            blobs: vec![new_blob(&c2.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        state.handle_signed_block(&craft_signed_block(1, vec![unauthorized_tx.clone().into()]));
+        let mut hyle_output = make_hyle_output(unauthorized_tx.clone(), BlobIndex(0));
+        hyle_output.registered_contracts = vec![update.clone()];
+        let proof = new_proof_tx(&c2, &hyle_output, &unauthorized_tx.hash());
+        let block = state.handle_signed_block(&craft_signed_block(2, vec![proof.into()]));
+        assert!(block.successful_txs.is_empty());
+        assert_eq!(
+            state.contracts.get(&c1).unwrap().program_id,
+            ProgramId(vec![])
+        );
+
+        let update_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        state.handle_signed_block(&craft_signed_block(3, vec![update_tx.clone().into()]));
+
+        let mut hyle_output = make_hyle_output(update_tx.clone(), BlobIndex(0));
+        hyle_output.registered_contracts = vec![update.clone()];
+        let proof = new_proof_tx(&c1, &hyle_output, &update_tx.hash());
+        let block = state.handle_signed_block(&craft_signed_block(4, vec![proof.into()]));
+        assert_eq!(block.successful_txs, vec![update_tx.hash()]);
+        assert_eq!(block.updated_programs, vec![(update_tx.hash(), update)]);
+        assert!(block.registered_contracts.is_empty());
+
+        let contract = state.contracts.get(&c1).unwrap();
+        assert_eq!(contract.program_id, ProgramId(vec![7]));
+        assert_eq!(contract.state.0, vec![4, 5, 6]);
+
+        // Proofs of the old program are no longer accepted
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        state.handle_signed_block(&craft_signed_block(5, vec![blob_tx.clone().into()]));
+
+        let hyle_output =
+            make_hyle_output_with_state(blob_tx.clone(), BlobIndex(0), &[4, 5, 6], &[7, 8]);
+        let old_proof = new_proof_tx(&c1, &hyle_output, &blob_tx.hash());
+        let mut new_proof = old_proof.clone();
+        for proven_blob in new_proof.proven_blobs.iter_mut() {
+            proven_blob.program_id = ProgramId(vec![7]);
+        }
+
+        let block = state.handle_signed_block(&craft_signed_block(6, vec![old_proof.into()]));
+        assert!(block.successful_txs.is_empty());
+
+        let block = state.handle_signed_block(&craft_signed_block(7, vec![new_proof.into()]));
+        assert_eq!(block.successful_txs, vec![blob_tx.hash()]);
+        assert_eq!(state.contracts.get(&c1).unwrap().state.0, vec![7, 8]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn key_rotation_needs_a_proof_of_the_old_key() {
+        let mut state = new_node_state().await;
+        let staking = ContractName::new("staking");
+        state.handle_register_contract_effect(&make_register_contract_effect(staking.clone()));
+
+        let old_key = BlstCrypto::new_random().unwrap();
+        let new_pubkey = BlstCrypto::new_random().unwrap().validator_pubkey().clone();
+        let rotate_tx = |proof| BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![StakingAction::RotateKey {
+                old_pubkey: old_key.validator_pubkey().clone(),
+                new_pubkey: new_pubkey.clone(),
+                nonce: 0,
+                proof,
+            }
+            .as_blob(staking.clone(), None, None)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+
+        // Signed without the domain, old key and nonce
+        let replayable_tx = rotate_tx(
+            old_key
+                .sign(new_pubkey.clone())
+                .unwrap()
+                .signature
+                .signature
+                .0,
+        );
+        state.handle_signed_block(&craft_signed_block(1, vec![replayable_tx.clone().into()]));
+        // The staking contract would apply it, the node fails the tx
+        let hyle_output = make_hyle_output(replayable_tx.clone(), BlobIndex(0));
+        let proof = new_proof_tx(&staking, &hyle_output, &replayable_tx.hash());
+        let block = state.handle_signed_block(&craft_signed_block(2, vec![proof.into()]));
+        assert_eq!(block.failed_txs, vec![replayable_tx.hash()]);
+        assert_eq!(
+            state.contracts.get(&staking).unwrap().state.0,
+            vec![0, 1, 2, 3]
+        );
+
+        let message =
+            KeyRotationMessage::new(old_key.validator_pubkey().clone(), new_pubkey.clone(), 0);
+        let signed_tx = rotate_tx(old_key.sign(message).unwrap().signature.signature.0);
+        state.handle_signed_block(&craft_signed_block(3, vec![signed_tx.clone().into()]));
+        let hyle_output = make_hyle_output(signed_tx.clone(), BlobIndex(0));
+        let proof = new_proof_tx(&staking, &hyle_output, &signed_tx.hash());
+        let block = state.handle_signed_block(&craft_signed_block(4, vec![proof.into()]));
+        assert_eq!(block.successful_txs, vec![signed_tx.hash()]);
+        assert_eq!(block.staking_actions.len(), 1);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn consensus_slash_is_applied_to_the_staking_contract() {
+        let mut state = new_node_state().await;
+        let staking = ContractName::new("staking");
+        state.handle_register_contract_effect(&RegisterContractEffect {
+            state_digest: Staking::new().as_digest(),
+            ..make_register_contract_effect(staking.clone())
+        });
+
+        let crypto = BlstCrypto::new_random().unwrap();
+        let validator = crypto.validator_pubkey().clone();
+        let mut expected = Staking::new();
+        for (height, action) in [
+            StakingAction::Stake { amount: 100 },
+            StakingAction::Delegate {
+                validator: validator.clone(),
+            },
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let initial_state = expected.as_digest();
+            NodeState::apply_staking_action(&mut expected, &Identity::new("test.c1"), &action)
+                .unwrap();
+            let blob_tx = BlobTransaction {
+                identity: Identity::new("test.c1"),
+                blobs: vec![action.as_blob(staking.clone(), None, None)],
+                dependencies: vec![],
+                additional_identities: vec![],
+                nonce: None,
+                fee: 0,
+            };
+            let hyle_output = make_hyle_output_with_state(
+                blob_tx.clone(),
+                BlobIndex(0),
+                &initial_state.0,
+                &expected.as_digest().0,
+            );
+            let proof = new_proof_tx(&staking, &hyle_output, &blob_tx.hash());
+            let height = 2 * height as u64;
+            state.handle_signed_block(&craft_signed_block(height + 1, vec![blob_tx.into()]));
+            state.handle_signed_block(&craft_signed_block(height + 2, vec![proof.into()]));
+        }
+        assert_eq!(state.staking, expected);
+        assert_eq!(
+            state.contracts.get(&staking).unwrap().state,
+            expected.as_digest()
+        );
+
+        let evidence = crypto
+            .sign(ConsensusNetMessage::PrepareVote(ConsensusProposalHash(
+                "proposal".into(),
+            )))
+            .unwrap();
+        let mut signed_block = craft_signed_block(5, vec![]);
+        signed_block.consensus_proposal.staking_actions = vec![ConsensusStakingAction::Slash {
+            validator: validator.clone(),
+            amount: 100,
+            first: evidence.clone(),
+            second: evidence,
+        }];
+        let block = state.handle_signed_block(&signed_block);
+
+        expected.slash(&validator, 100).unwrap();
+        assert_eq!(state.staking.get_stake(&validator), Some(0));
+        assert_eq!(
+            state.contracts.get(&staking).unwrap().state,
+            expected.as_digest()
+        );
+        assert_eq!(
+            block.updated_states.get(&staking),
+            Some(&expected.as_digest())
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn settlement_order_is_deterministic() {
+        let c1 = ContractName::new("c1");
+        let c2 = ContractName::new("c2");
+        let blob_tx = |identity: &str, blobs| BlobTransaction {
+            identity: Identity::new(identity),
+            blobs,
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let tx1 = blob_tx("test.c1", vec![new_blob(&c1.0)]);
+        let tx2 = blob_tx("test.c2", vec![new_blob(&c2.0)]);
+        // Comes after both of them in the queues of c1 and c2
+        let tx3 = blob_tx("test.c1", vec![new_blob(&c1.0), new_blob(&c2.0)]);
+
+        // A single proof settles tx1 and tx2 at once, which then lets tx3 settle
+        let mut aggregated_proof = new_proof_tx(
+            &c1,
+            &make_hyle_output(tx1.clone(), BlobIndex(0)),
+            &tx1.hash(),
+        );
+        aggregated_proof.proven_blobs.extend(
+            new_proof_tx(
+                &c2,
+                &make_hyle_output(tx2.clone(), BlobIndex(0)),
+                &tx2.hash(),
+            )
+            .proven_blobs,
+        );
+        let tx3_proofs = [(&c1, 0, vec![7]), (&c2, 1, vec![8])].map(|(contract, index, next)| {
+            let hyle_output =
+                make_hyle_output_with_state(tx3.clone(), BlobIndex(index), &[4, 5, 6], &next);
+            new_proof_tx(contract, &hyle_output, &tx3.hash())
+        });
+
+        let signed_block = craft_signed_block(
+            1,
+            vec![
+                tx1.clone().into(),
+                tx2.clone().into(),
+                tx3.clone().into(),
+                tx3_proofs[0].clone().into(),
+                tx3_proofs[1].clone().into(),
+                aggregated_proof.into(),
+            ],
+        );
+
+        let mut blocks = vec![];
+        for _ in 0..2 {
+            let mut state = new_node_state().await;
+            state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+            state.handle_register_contract_effect(&make_register_contract_effect(c2.clone()));
+            blocks.push(state.handle_signed_block(&signed_block));
+        }
+        let (block_a, block_b) = (blocks.first().unwrap(), blocks.last().unwrap());
+
+        // In the order the txs were added, whatever their hashes
+        assert_eq!(
+            block_a.successful_txs,
+            vec![tx1.hash(), tx2.hash(), tx3.hash()]
+        );
+        assert_eq!(block_a.successful_txs, block_b.successful_txs);
+        assert_eq!(block_a.verified_blobs, block_b.verified_blobs);
+        assert_eq!(
+            block_a.updated_states,
+            BTreeMap::from([
+                (c1.clone(), StateDigest(vec![7])),
+                (c2.clone(), StateDigest(vec![8])),
+            ])
+        );
+        assert_eq!(block_a.updated_states, block_b.updated_states);
+        assert_eq!(block_a.state_root, block_b.state_root);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn recursive_proof_settles_blobs_of_two_contracts() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        let c2 = ContractName::new("c2");
+        for (contract_name, program_id) in [(&c1, vec![1]), (&c2, vec![2])] {
+            state.handle_register_contract_effect(&RegisterContractEffect {
+                program_id: ProgramId(program_id),
+                ..make_register_contract_effect(contract_name.clone())
+            });
+        }
+
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0), new_blob(&c2.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let recursive_proof = |program_ids: [Vec<u8>; 2]| {
+            let mut proof = new_proof_tx(
+                &ContractName::new("risc0-recursion"),
+                &make_hyle_output(blob_tx.clone(), BlobIndex(0)),
+                &blob_tx.hash(),
+            );
+            proof.proven_blobs.extend(
+                new_proof_tx(
+                    &c2,
+                    &make_hyle_output(blob_tx.clone(), BlobIndex(1)),
+                    &blob_tx.hash(),
+                )
+                .proven_blobs,
+            );
+            for (proven_blob, program_id) in proof.proven_blobs.iter_mut().zip(program_ids) {
+                proven_blob.program_id = ProgramId(program_id);
+            }
+            proof.is_recursive = true;
+            proof
+        };
+
+        // The program of c1 can't stand in for the one of c2
+        state.handle_signed_block(&craft_signed_block(1, vec![blob_tx.clone().into()]));
+        let block = state.handle_signed_block(&craft_signed_block(
+            2,
+            vec![recursive_proof([vec![1], vec![1]]).into()],
+        ));
+        assert!(block.blob_proof_outputs.is_empty());
+        assert!(state
+            .unsettled_transactions
+            .get(&blob_tx.hash())
+            .unwrap()
+            .blobs
+            .iter()
+            .all(|blob| blob.possible_proofs.is_empty()));
+
+        let block = state.handle_signed_block(&craft_signed_block(
+            3,
+            vec![recursive_proof([vec![1], vec![2]]).into()],
+        ));
+        assert_eq!(block.blob_proof_outputs.len(), 2);
+        assert_eq!(block.successful_txs, vec![blob_tx.hash()]);
+        assert_eq!(state.contracts.get(&c1).unwrap().state.0, vec![4, 5, 6]);
+        assert_eq!(state.contracts.get(&c2).unwrap().state.0, vec![4, 5, 6]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn blob_order_is_kept_from_block_to_proof() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        let c2 = ContractName::new("c2");
+
+        state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+        state.handle_register_contract_effect(&make_register_contract_effect(c2.clone()));
+
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0), new_blob(&c2.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let blob_tx_hash = blob_tx.hash();
+
+        // Blocks are stored and sent encoded, the order must survive it
+        let signed_block = craft_signed_block(1, vec![blob_tx.clone().into()]);
+        let (signed_block, _) = bincode::decode_from_slice::<SignedBlock, _>(
+            &bincode::encode_to_vec(&signed_block, bincode::config::standard()).unwrap(),
+            bincode::config::standard(),
+        )
+        .unwrap();
+        state.handle_signed_block(&signed_block);
+
+        let unsettled_tx = state.unsettled_transactions.get(&blob_tx_hash).unwrap();
+        assert_eq!(unsettled_tx.blobs_hash, blob_tx.blobs_hash());
+        assert_eq!(
+            unsettled_tx
+                .blobs
+                .iter()
+                .map(|b| b.blob.contract_name.clone())
+                .collect::<Vec<_>>(),
+            vec![c1.clone(), c2.clone()]
+        );
+
+        // A proof of the reordered blobs, at the index c1 has in the reorder
+        let reordered_tx = BlobTransaction {
+            blobs: vec![new_blob(&c2.0), new_blob(&c1.0)],
+            ..blob_tx.clone()
+        };
+        assert_ne!(reordered_tx.blobs_hash(), blob_tx.blobs_hash());
+        let reordered_output = HyleOutput {
+            tx_hash: blob_tx_hash.clone(),
+            ..make_hyle_output(reordered_tx, BlobIndex(1))
+        };
+        assert_err!(handle_verify_proof_transaction(
+            &mut state,
+            &new_proof_tx(&c1, &reordered_output, &blob_tx_hash)
+        ));
+
+        handle_verify_proof_transaction(
+            &mut state,
+            &new_proof_tx(
+                &c1,
+                &make_hyle_output(blob_tx.clone(), BlobIndex(0)),
+                &blob_tx_hash,
+            ),
+        )
+        .unwrap();
+        handle_verify_proof_transaction(
+            &mut state,
+            &new_proof_tx(
+                &c2,
+                &make_hyle_output(blob_tx.clone(), BlobIndex(1)),
+                &blob_tx_hash,
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(state.contracts.get(&c1).unwrap().state.0, vec![4, 5, 6]);
+        assert_eq!(state.contracts.get(&c2).unwrap().state.0, vec![4, 5, 6]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn proof_committing_to_other_blobs_is_rejected() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let blob_tx_hash = blob_tx.hash();
+        state.handle_blob_tx(&blob_tx, bogus_tx_context()).unwrap();
+
+        // Same contract and index, different blob data
+        let other_tx = BlobTransaction {
+            blobs: vec![Blob {
+                contract_name: c1.clone(),
+                data: BlobData(vec![6, 6, 6]),
+            }],
+            ..blob_tx.clone()
+        };
+        let other_output = HyleOutput {
+            tx_hash: blob_tx_hash.clone(),
+            ..make_hyle_output(other_tx, BlobIndex(0))
+        };
+        assert_err!(handle_verify_proof_transaction(
+            &mut state,
+            &new_proof_tx(&c1, &other_output, &blob_tx_hash)
+        ));
+
+        let unsettled_tx = state.unsettled_transactions.get(&blob_tx_hash).unwrap();
+        assert!(unsettled_tx
+            .blobs
+            .iter()
+            .all(|blob| blob.possible_proofs.is_empty()));
+        assert_eq!(state.contracts.get(&c1).unwrap().state.0, vec![0, 1, 2, 3]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn two_proof_for_same_blob() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        let c2 = ContractName::new("c2");
+
+        let register_c1 = make_register_contract_effect(c1.clone());
+        let register_c2 = make_register_contract_effect(c2.clone());
+
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0), new_blob(&c2.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let blob_tx_hash = blob_tx.hash();
 
@@ -1085,6 +2494,10 @@ pub mod test {
         let blob_tx = BlobTransaction {
             identity: Identity::new("test.c1"),
             blobs: vec![new_blob(&c1.0), new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let blob_tx_hash = blob_tx.hash();
 
@@ -1109,6 +2522,162 @@ pub mod test {
         assert_eq!(block.blob_proof_outputs.len(), 1);
     }
 
+    #[test_log::test(tokio::test)]
+    async fn corrected_proof_settles_after_invalid_ones() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        let register_c1 = make_register_contract_tx(c1.clone());
+
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let blob_tx_hash = blob_tx.hash();
+
+        state.handle_signed_block(&craft_signed_block(
+            1,
+            vec![register_c1.into(), blob_tx.clone().into()],
+        ));
+
+        // A proof for another identity is rejected outright
+        let mut wrong_identity_output = make_hyle_output(blob_tx.clone(), BlobIndex(0));
+        wrong_identity_output.identity = Identity::new("other.c1");
+        let wrong_identity_proof = new_proof_tx(&c1, &wrong_identity_output, &blob_tx_hash);
+
+        // A proof starting from the wrong state is kept, but can't settle
+        let wrong_state_output =
+            make_hyle_output_with_state(blob_tx.clone(), BlobIndex(0), &[9, 9, 9], &[4, 5, 6]);
+        let wrong_state_proof = new_proof_tx(&c1, &wrong_state_output, &blob_tx_hash);
+
+        let block = state.handle_signed_block(&craft_signed_block(
+            2,
+            vec![wrong_identity_proof.into(), wrong_state_proof.into()],
+        ));
+        assert!(block.successful_txs.is_empty());
+        assert!(state.unsettled_transactions.get(&blob_tx_hash).is_some());
+
+        // The corrected proof settles the blob TX, which doesn't need to be resubmitted
+        let hyle_output = make_hyle_output(blob_tx.clone(), BlobIndex(0));
+        let corrected_proof = new_proof_tx(&c1, &hyle_output, &blob_tx_hash);
+        let block = state.handle_signed_block(&craft_signed_block(3, vec![corrected_proof.into()]));
+        assert_eq!(block.successful_txs, vec![blob_tx_hash.clone()]);
+        assert!(state.unsettled_transactions.get(&blob_tx_hash).is_none());
+        assert_eq!(
+            state.contracts.get(&c1).unwrap().state,
+            hyle_output.next_state
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn crash_mid_block_does_not_double_apply_on_restart() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        let register_c1 = make_register_contract_tx(c1.clone());
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let blob_tx_hash = blob_tx.hash();
+        let hyle_output = make_hyle_output(blob_tx.clone(), BlobIndex(0));
+        let proof_tx = new_proof_tx(&c1, &hyle_output, &blob_tx_hash);
+
+        let block_1 = craft_signed_block(1, vec![register_c1.into()]);
+        let block_2 = craft_signed_block(2, vec![blob_tx.into(), proof_tx.into()]);
+        assert!(state.handle_new_signed_block(&block_1).is_some());
+        // What is on disk once block 1 is handled
+        let persisted = bincode::encode_to_vec(&state, bincode::config::standard()).unwrap();
+
+        // Crashing while handling block 2 loses all of it
+        let mut crashed = state.clone();
+        crashed.handle_new_signed_block(&block_2);
+        drop(crashed);
+
+        // On restart, blocks are delivered again from the last one handled
+        let (mut state, _): (NodeState, _) =
+            bincode::decode_from_slice(&persisted, bincode::config::standard()).unwrap();
+        assert!(state.handle_new_signed_block(&block_1).is_none());
+        let block = state.handle_new_signed_block(&block_2).unwrap();
+        assert_eq!(block.successful_txs, vec![blob_tx_hash.clone()]);
+        let settled = bincode::encode_to_vec(&state, bincode::config::standard()).unwrap();
+
+        // Delivering it again doesn't settle it twice
+        assert!(state.handle_new_signed_block(&block_2).is_none());
+        assert_eq!(
+            bincode::encode_to_vec(&state, bincode::config::standard()).unwrap(),
+            settled
+        );
+        assert_eq!(
+            state.contracts.get(&c1).unwrap().state,
+            hyle_output.next_state
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn verification_writes_one_audit_entry() {
+        let mut state = new_node_state().await;
+        state.verification_audit.enabled = true;
+        let c1 = ContractName::new("c1");
+        let register_c1 = make_register_contract_tx(c1.clone());
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let blob_tx_hash = blob_tx.hash();
+
+        state.handle_signed_block(&craft_signed_block(
+            1,
+            vec![register_c1.into(), blob_tx.clone().into()],
+        ));
+        assert!(state.verification_audit.drain().is_empty());
+
+        let mut wrong_identity_output = make_hyle_output(blob_tx.clone(), BlobIndex(0));
+        wrong_identity_output.identity = Identity::new("other.c1");
+        let wrong_proof = new_proof_tx(&c1, &wrong_identity_output, &blob_tx_hash);
+        state.handle_signed_block(&craft_signed_block(2, vec![wrong_proof.into()]));
+        let entries = state.verification_audit.drain();
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(
+            entries.first().map(|entry| &entry.result),
+            Some(VerificationResult::Invalid(_))
+        ));
+
+        let hyle_output = make_hyle_output(blob_tx.clone(), BlobIndex(0));
+        let verified_proof = new_proof_tx(&c1, &hyle_output, &blob_tx_hash);
+        let mut signed_block = craft_signed_block(3, vec![verified_proof.clone().into()]);
+        signed_block.consensus_proposal.timestamp = 1234;
+        let block = state.handle_signed_block(&signed_block);
+        assert_eq!(block.successful_txs, vec![blob_tx_hash.clone()]);
+
+        assert_eq!(
+            state.verification_audit.drain(),
+            vec![VerificationAuditEntry {
+                timestamp: 1234,
+                block_height: BlockHeight(3),
+                submitted_by: ValidatorPublicKey::default(),
+                proof_tx_hash: verified_proof.hash(),
+                proof_hash: verified_proof.proof_hash.clone(),
+                contract_name: c1.clone(),
+                verifier: Verifier("test".into()),
+                program_id: "".into(),
+                blob_tx_hash,
+                blob_index: BlobIndex(0),
+                result: VerificationResult::Valid,
+            }]
+        );
+    }
+
     #[test_log::test(tokio::test)]
     async fn change_same_contract_state_multiple_times_in_same_tx() {
         let mut state = new_node_state().await;
@@ -1123,6 +2692,10 @@ pub mod test {
         let blob_tx = BlobTransaction {
             identity: Identity::new("test.c1"),
             blobs: vec![first_blob, second_blob, third_blob],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let blob_tx_hash = blob_tx.hash();
 
@@ -1166,6 +2739,10 @@ pub mod test {
         let blob_tx = BlobTransaction {
             identity: Identity::new("test.c1"),
             blobs: vec![first_blob, second_blob, third_blob],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let blob_tx_hash = blob_tx.hash();
 
@@ -1209,6 +2786,152 @@ pub mod test {
         assert_eq!(state.contracts.get(&c1).unwrap().state.0, vec![5]);
     }
 
+    #[test_log::test(tokio::test)]
+    async fn conditional_blob_is_skipped_when_its_dependency_fails() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        let c2 = ContractName::new("c2");
+        let c3 = ContractName::new("c3");
+        for contract in [&c1, &c2, &c3] {
+            state.handle_register_contract_effect(&make_register_contract_effect(contract.clone()));
+        }
+
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0), new_blob(&c2.0), new_blob(&c3.0)],
+            dependencies: vec![BlobDependency {
+                blob_index: BlobIndex(2),
+                depends_on: BlobIndex(1),
+            }],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let blob_tx_hash = blob_tx.hash();
+
+        let identity_output = make_hyle_output(blob_tx.clone(), BlobIndex(0));
+        let identity_proof = new_proof_tx(&c1, &identity_output, &blob_tx_hash);
+        // A failed output is not applied, whatever its next state
+        let failed_output = HyleOutput {
+            success: false,
+            next_state: StateDigest(vec![9, 9]),
+            ..make_hyle_output(blob_tx.clone(), BlobIndex(1))
+        };
+        let failed_proof = new_proof_tx(&c2, &failed_output, &blob_tx_hash);
+
+        // No proof is needed for the third blob, which is skipped.
+        let block = state.handle_signed_block(&craft_signed_block(
+            1,
+            vec![blob_tx.into(), identity_proof.into(), failed_proof.into()],
+        ));
+
+        assert_eq!(block.successful_txs, vec![blob_tx_hash.clone()]);
+        assert!(block.failed_txs.is_empty());
+        assert_eq!(
+            block.verified_blobs,
+            vec![
+                (blob_tx_hash.clone(), BlobIndex(0), Some(0)),
+                (blob_tx_hash.clone(), BlobIndex(1), Some(0)),
+                (blob_tx_hash, BlobIndex(2), None)
+            ]
+        );
+        assert_eq!(state.contracts.get(&c1).unwrap().state.0, vec![4, 5, 6]);
+        assert_eq!(state.contracts.get(&c2).unwrap().state.0, vec![0, 1, 2, 3]);
+        assert_eq!(state.contracts.get(&c3).unwrap().state.0, vec![0, 1, 2, 3]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn failed_blob_without_dependents_fails_the_tx() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        let c2 = ContractName::new("c2");
+        state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+        state.handle_register_contract_effect(&make_register_contract_effect(c2.clone()));
+
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0), new_blob(&c2.0), new_blob(&c2.0)],
+            dependencies: vec![BlobDependency {
+                blob_index: BlobIndex(2),
+                depends_on: BlobIndex(1),
+            }],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let blob_tx_hash = blob_tx.hash();
+
+        let identity_output = make_hyle_output(blob_tx.clone(), BlobIndex(0));
+        let identity_proof = new_proof_tx(&c1, &identity_output, &blob_tx_hash);
+        let anchor_output =
+            make_hyle_output_with_state(blob_tx.clone(), BlobIndex(1), &[0, 1, 2, 3], &[7]);
+        let anchor_proof = new_proof_tx(&c2, &anchor_output, &blob_tx_hash);
+        // The conditional blob runs, as its dependency succeeded, and fails
+        let failed_output = HyleOutput {
+            success: false,
+            ..make_hyle_output_with_state(blob_tx.clone(), BlobIndex(2), &[7], &[8])
+        };
+        let failed_proof = new_proof_tx(&c2, &failed_output, &blob_tx_hash);
+
+        let block = state.handle_signed_block(&craft_signed_block(
+            1,
+            vec![
+                blob_tx.into(),
+                identity_proof.into(),
+                anchor_proof.into(),
+                failed_proof.into(),
+            ],
+        ));
+
+        assert_eq!(block.failed_txs, vec![blob_tx_hash]);
+        assert_eq!(state.contracts.get(&c1).unwrap().state.0, vec![0, 1, 2, 3]);
+        assert_eq!(state.contracts.get(&c2).unwrap().state.0, vec![0, 1, 2, 3]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn identity_blob_cannot_be_a_dependency() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        let c2 = ContractName::new("c2");
+        state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+        state.handle_register_contract_effect(&make_register_contract_effect(c2.clone()));
+
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0), new_blob(&c2.0)],
+            dependencies: vec![BlobDependency {
+                blob_index: BlobIndex(1),
+                depends_on: BlobIndex(0),
+            }],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+
+        assert_err!(state.handle_blob_tx(&blob_tx, bogus_tx_context()));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn blob_dependency_must_point_to_an_earlier_blob() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0), new_blob(&c1.0)],
+            dependencies: vec![BlobDependency {
+                blob_index: BlobIndex(0),
+                depends_on: BlobIndex(1),
+            }],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+
+        assert_err!(state.handle_blob_tx(&blob_tx, bogus_tx_context()));
+    }
+
     #[test_log::test(tokio::test)]
     async fn duplicate_proof_with_inconsistent_state_should_never_settle() {
         let mut state = new_node_state().await;
@@ -1222,6 +2945,10 @@ pub mod test {
         let blob_tx = BlobTransaction {
             identity: Identity::new("test.c1"),
             blobs: vec![first_blob, second_blob],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let blob_tx_hash = blob_tx.hash();
 
@@ -1277,6 +3004,10 @@ pub mod test {
         let blob_tx = BlobTransaction {
             identity: Identity::new("test.c1"),
             blobs: vec![first_blob, second_blob, third_blob],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let blob_tx_hash = blob_tx.hash();
 
@@ -1330,18 +3061,34 @@ pub mod test {
         let blocking_tx = BlobTransaction {
             identity: Identity::new("test.c1"),
             blobs: vec![new_blob(&c1.0), new_blob(&c2.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let ready_same_block = BlobTransaction {
             identity: Identity::new("test.c1"),
             blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let ready_later_block = BlobTransaction {
             identity: Identity::new("test.c2"),
             blobs: vec![new_blob(&c2.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let ready_last_block = BlobTransaction {
             identity: Identity::new("test2.c1"),
             blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let blocking_tx_hash = blocking_tx.hash();
         let hyle_output =
@@ -1410,6 +3157,123 @@ pub mod test {
         );
     }
 
+    #[test_log::test(tokio::test)]
+    async fn proof_before_blob_tx_settles_when_blob_arrives() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let blob_tx_hash = blob_tx.hash();
+        let hyle_output = make_hyle_output(blob_tx.clone(), BlobIndex(0));
+        let verified_proof = new_proof_tx(&c1, &hyle_output, &blob_tx_hash);
+
+        let block = state.handle_signed_block(&craft_signed_block(1, vec![verified_proof.into()]));
+        assert!(block.successful_txs.is_empty());
+        assert_eq!(state.orphan_proofs.len(), 1);
+
+        let block = state.handle_signed_block(&craft_signed_block(2, vec![blob_tx.into()]));
+        assert_eq!(block.successful_txs, vec![blob_tx_hash]);
+        assert_eq!(block.blob_proof_outputs.len(), 1);
+        assert!(state.orphan_proofs.is_empty());
+        assert_eq!(state.contracts.get(&c1).unwrap().state.0, vec![4, 5, 6]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn replayed_proof_for_settled_tx_is_not_buffered() {
+        let mut state = new_node_state().await;
+        let c1 = ContractName::new("c1");
+        state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let blob_tx_hash = blob_tx.hash();
+        let hyle_output = make_hyle_output(blob_tx.clone(), BlobIndex(0));
+        let verified_proof = new_proof_tx(&c1, &hyle_output, &blob_tx_hash);
+
+        let block = state.handle_signed_block(&craft_signed_block(
+            1,
+            vec![blob_tx.into(), verified_proof.clone().into()],
+        ));
+        assert_eq!(block.successful_txs, vec![blob_tx_hash]);
+
+        state.verification_audit.enabled = true;
+        state.handle_signed_block(&craft_signed_block(2, vec![verified_proof.into()]));
+        assert!(state.orphan_proofs.is_empty());
+        assert!(matches!(
+            state
+                .verification_audit
+                .drain()
+                .first()
+                .map(|entry| &entry.result),
+            Some(VerificationResult::Invalid(_))
+        ));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn orphan_proofs_beyond_cap_are_dropped() {
+        let mut state = new_node_state().await;
+        state.orphan_proofs.max_proofs = 1;
+        let c1 = ContractName::new("c1");
+        state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+
+        let first_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let second_tx = BlobTransaction {
+            identity: Identity::new("test2.c1"),
+            blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let first_proof = new_proof_tx(
+            &c1,
+            &make_hyle_output(first_tx.clone(), BlobIndex(0)),
+            &first_tx.hash(),
+        );
+        let second_proof = new_proof_tx(
+            &c1,
+            &make_hyle_output_with_state(second_tx.clone(), BlobIndex(0), &[0, 1, 2, 3], &[7]),
+            &second_tx.hash(),
+        );
+
+        state.handle_signed_block(&craft_signed_block(
+            1,
+            vec![first_proof.into(), second_proof.into()],
+        ));
+        assert_eq!(state.orphan_proofs.len(), 1);
+
+        // The proof for the first transaction was evicted, so it can't settle.
+        let block = state.handle_signed_block(&craft_signed_block(2, vec![first_tx.into()]));
+        assert!(block.successful_txs.is_empty());
+
+        // Blocked behind the first one, but its proof is applied.
+        let block = state.handle_signed_block(&craft_signed_block(3, vec![second_tx.into()]));
+        assert!(block.successful_txs.is_empty());
+        assert_eq!(block.blob_proof_outputs.len(), 1);
+        assert!(state.orphan_proofs.is_empty());
+    }
+
     #[test_log::test(tokio::test)]
     async fn test_tx_timeout_simple() {
         let mut state = new_node_state().await;
@@ -1420,6 +3284,10 @@ pub mod test {
         let blob_tx = BlobTransaction {
             identity: Identity::new("test.c1"),
             blobs: vec![new_blob(&c1.0), new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let blob_tx_hash = blob_tx.hash();
 
@@ -1428,16 +3296,62 @@ pub mod test {
             vec![register_c1.into(), blob_tx.into()],
         ));
 
-        // This should trigger the timeout
+        // This should trigger the timeout, once the grace period is over
         let timed_out_tx_hashes = state
             .handle_signed_block(&craft_signed_block(103, vec![]))
             .timed_out_txs;
+        assert!(timed_out_tx_hashes.is_empty());
+        let timed_out_tx_hashes = state
+            .handle_signed_block(&craft_signed_block(
+                103 + timeouts::BLOB_TIMEOUT_GRACE_PERIOD,
+                vec![],
+            ))
+            .timed_out_txs;
 
         // Check that the transaction has timed out
         assert!(timed_out_tx_hashes.contains(&blob_tx_hash));
         assert!(state.unsettled_transactions.get(&blob_tx_hash).is_none());
     }
 
+    #[test_log::test(tokio::test)]
+    async fn test_tx_timeout_delay() {
+        let mut state = new_node_state().await;
+        state.timeouts.timeout = 5;
+        state.timeouts.grace_period = 0;
+        let c1 = ContractName::new("c1");
+        state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+
+        let blob_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        state.handle_signed_block(&craft_signed_block(3, vec![blob_tx.clone().into()]));
+
+        // Never proven, it times out 5 blocks after being sequenced
+        let block = state.handle_signed_block(&craft_signed_block(7, vec![]));
+        assert!(block.timed_out_txs.is_empty());
+        assert!(state.unsettled_transactions.get(&blob_tx.hash()).is_some());
+
+        let block = state.handle_signed_block(&craft_signed_block(8, vec![]));
+        assert_eq!(block.timed_out_txs, vec![blob_tx.hash()]);
+        assert!(state.unsettled_transactions.get(&blob_tx.hash()).is_none());
+
+        // Without timeouts, it is kept until proven
+        state.timeouts.timeout = 0;
+        let blob_tx = BlobTransaction {
+            nonce: Some(1),
+            ..blob_tx
+        };
+        state.handle_signed_block(&craft_signed_block(9, vec![blob_tx.clone().into()]));
+        let block = state.handle_signed_block(&craft_signed_block(1000, vec![]));
+        assert!(block.timed_out_txs.is_empty());
+        assert!(state.unsettled_transactions.get(&blob_tx.hash()).is_some());
+    }
+
     #[test_log::test(tokio::test)]
     async fn test_tx_no_timeout_once_settled() {
         let mut state = new_node_state().await;
@@ -1448,6 +3362,10 @@ pub mod test {
         let blob_tx = BlobTransaction {
             identity: Identity::new("test.c1"),
             blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let blob_tx_hash = blob_tx.hash();
         state.handle_signed_block(&craft_signed_block(
@@ -1488,9 +3406,139 @@ pub mod test {
         assert_eq!(timeouts::tests::get(&state.timeouts, &blob_tx_hash), None);
     }
 
+    #[test_log::test(tokio::test)]
+    async fn test_tx_timeout_grace_period() {
+        let mut state = new_node_state().await;
+        let grace_end = 103 + timeouts::BLOB_TIMEOUT_GRACE_PERIOD;
+        let c1 = ContractName::new("c1");
+        let c2 = ContractName::new("c2");
+        let register_c1 = make_register_contract_tx(c1.clone());
+        let register_c2 = make_register_contract_tx(c2.clone());
+
+        let late_tx = BlobTransaction {
+            identity: Identity::new("test.c1"),
+            blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let late_tx_hash = late_tx.hash();
+        let unproven_tx = BlobTransaction {
+            identity: Identity::new("test.c2"),
+            blobs: vec![new_blob(&c2.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let unproven_tx_hash = unproven_tx.hash();
+
+        state.handle_signed_block(&craft_signed_block(
+            3,
+            vec![
+                register_c1.into(),
+                register_c2.into(),
+                late_tx.clone().into(),
+                unproven_tx.into(),
+            ],
+        ));
+
+        // The nominal timeout starts the grace period
+        let timed_out_tx_hashes = state
+            .handle_signed_block(&craft_signed_block(103, vec![]))
+            .timed_out_txs;
+        assert!(timed_out_tx_hashes.is_empty());
+        assert!(state.unsettled_transactions.get(&late_tx_hash).is_some());
+        assert_eq!(
+            timeouts::tests::get(&state.timeouts, &late_tx_hash),
+            Some(BlockHeight(grace_end))
+        );
+
+        // A late proof still settles the TX, up to the last block of the grace period
+        let hyle_output = make_hyle_output(late_tx, BlobIndex(0));
+        let late_proof = new_proof_tx(&c1, &hyle_output, &late_tx_hash);
+        assert_eq!(
+            state
+                .handle_signed_block(&craft_signed_block(grace_end - 1, vec![late_proof.into()]))
+                .successful_txs,
+            vec![late_tx_hash.clone()]
+        );
+
+        // The other TX times out at the end of the grace period
+        let timed_out_tx_hashes = state
+            .handle_signed_block(&craft_signed_block(grace_end, vec![]))
+            .timed_out_txs;
+        assert_eq!(timed_out_tx_hashes, vec![unproven_tx_hash.clone()]);
+        assert!(state
+            .unsettled_transactions
+            .get(&unproven_tx_hash)
+            .is_none());
+        assert!(!timeouts::tests::in_grace_period(
+            &state.timeouts,
+            &unproven_tx_hash
+        ));
+        assert!(!timeouts::tests::in_grace_period(
+            &state.timeouts,
+            &late_tx_hash
+        ));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn block_reports_each_settlement_outcome_once() {
+        let mut state = new_node_state().await;
+        let [c1, c2, c3] = ["c1", "c2", "c3"].map(ContractName::new);
+        let blob_tx = |contract: &ContractName| BlobTransaction {
+            identity: Identity::new(format!("test.{}", contract.0)),
+            blobs: vec![new_blob(&contract.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        let (settled_tx, failed_tx, timed_out_tx) = (blob_tx(&c1), blob_tx(&c2), blob_tx(&c3));
+        state.timeouts.grace_period = 0;
+
+        state.handle_signed_block(&craft_signed_block(
+            3,
+            vec![
+                make_register_contract_tx(c1.clone()).into(),
+                make_register_contract_tx(c2.clone()).into(),
+                make_register_contract_tx(c3.clone()).into(),
+                timed_out_tx.clone().into(),
+            ],
+        ));
+        state.handle_signed_block(&craft_signed_block(
+            4,
+            vec![settled_tx.clone().into(), failed_tx.clone().into()],
+        ));
+
+        let success_output = make_hyle_output(settled_tx.clone(), BlobIndex(0));
+        let mut failure_output = make_hyle_output(failed_tx.clone(), BlobIndex(0));
+        failure_output.success = false;
+
+        // The timeout of the first TX is reached in the same block
+        let block = state.handle_signed_block(&craft_signed_block(
+            103,
+            vec![
+                new_proof_tx(&c1, &success_output, &settled_tx.hash()).into(),
+                new_proof_tx(&c2, &failure_output, &failed_tx.hash()).into(),
+            ],
+        ));
+
+        assert_eq!(block.successful_txs, vec![settled_tx.hash()]);
+        assert_eq!(block.failed_txs, vec![failed_tx.hash()]);
+        assert_eq!(block.timed_out_txs, vec![timed_out_tx.hash()]);
+        assert_eq!(
+            block.updated_states,
+            BTreeMap::from([(c1.clone(), success_output.next_state.clone())])
+        );
+    }
+
     #[test_log::test(tokio::test)]
     async fn test_tx_on_timeout_settle_next_txs() {
         let mut state = new_node_state().await;
+        state.timeouts.grace_period = 0;
         let c1 = ContractName::new("c1");
         let c2 = ContractName::new("c2");
         let register_c1 = make_register_contract_tx(c1.clone());
@@ -1500,15 +3548,27 @@ pub mod test {
         let blocking_tx = BlobTransaction {
             identity: Identity::new("test.c1"),
             blobs: vec![new_blob(&c1.0), new_blob(&c2.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let blocking_tx_hash = blocking_tx.hash();
         let ready_same_block = BlobTransaction {
             identity: Identity::new("test.c1"),
             blobs: vec![new_blob(&c1.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let ready_later_block = BlobTransaction {
             identity: Identity::new("test.c2"),
             blobs: vec![new_blob(&c2.0)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let ready_same_block_hash = ready_same_block.hash();
         let hyle_output = make_hyle_output(ready_same_block.clone(), BlobIndex(0));
@@ -1568,8 +3628,13 @@ pub mod test {
                     program_id: ProgramId(vec![]),
                     state_digest: StateDigest(vec![0, 1, 2, 3]),
                     contract_name: name,
+                    blob_encoding: BlobEncoding::default(),
                 }
                 .as_blob(tld, None, None)],
+                dependencies: vec![],
+                additional_identities: vec![],
+                nonce: None,
+                fee: 0,
             }
         }
 
@@ -1603,6 +3668,72 @@ pub mod test {
             assert_eq!(state.contracts.len(), 4);
         }
 
+        #[test_log::test(tokio::test)]
+        async fn test_register_contract_with_blob_encoding() {
+            let mut state = new_node_state().await;
+            let register_c1 = make_tx("hyle.hyle".into(), "hyle".into(), "c1".into());
+            let mut register_c2 = make_tx("hyle.hyle".into(), "hyle".into(), "c2".into());
+            register_c2.blobs = vec![RegisterContractAction {
+                verifier: "test".into(),
+                program_id: ProgramId(vec![]),
+                state_digest: StateDigest(vec![0, 1, 2, 3]),
+                contract_name: "c2".into(),
+                blob_encoding: BlobEncoding::Json,
+            }
+            .as_blob("hyle".into(), None, None)];
+
+            let block = state.handle_signed_block(&craft_signed_block(
+                1,
+                vec![register_c1.into(), register_c2.into()],
+            ));
+
+            assert_eq!(
+                state.contracts.get(&"c1".into()).unwrap().blob_encoding,
+                BlobEncoding::BincodeStandard
+            );
+            // Only declared encodings are sent to the indexers
+            assert_eq!(
+                block.blob_encodings,
+                BTreeMap::from([("c2".into(), BlobEncoding::Json)])
+            );
+            let encoding = state.contracts.get(&"c2".into()).unwrap().blob_encoding;
+            assert_eq!(encoding, BlobEncoding::Json);
+            assert_eq!(
+                encoding
+                    .decode::<Vec<u32>>(&BlobData(b"[1,2]".to_vec()))
+                    .unwrap(),
+                vec![1, 2]
+            );
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn test_register_contract_encoded_without_blob_encoding() {
+            let mut state = new_node_state().await;
+            // The layout of a RegisterContractAction before blob_encoding was added
+            let old_action = (
+                Verifier::from("test"),
+                ProgramId(vec![]),
+                StateDigest(vec![0, 1, 2, 3]),
+                ContractName::from("c1"),
+            );
+            let mut register_c1 = make_tx("hyle.hyle".into(), "hyle".into(), "c1".into());
+            register_c1.blobs = vec![Blob {
+                contract_name: "hyle".into(),
+                data: BlobData::from(StructuredBlobData {
+                    caller: None,
+                    callees: None,
+                    parameters: old_action,
+                }),
+            }];
+
+            state.handle_signed_block(&craft_signed_block(1, vec![register_c1.into()]));
+
+            assert_eq!(
+                state.contracts.get(&"c1".into()).unwrap().blob_encoding,
+                BlobEncoding::BincodeStandard
+            );
+        }
+
         #[test_log::test(tokio::test)]
         async fn test_register_contract_failure() {
             let mut state = new_node_state().await;
@@ -1616,6 +3747,10 @@ pub mod test {
                     contract_name: "hyle".into(),
                     data: BlobData(vec![0, 1, 2, 3]),
                 }],
+                dependencies: vec![],
+                additional_identities: vec![],
+                nonce: None,
+                fee: 0,
             };
             let register_good = make_tx("hyle.hyle".into(), "hyle".into(), "c1.hyle".into());
 
@@ -1660,6 +3795,7 @@ pub mod test {
                         program_id: ProgramId(vec![]),
                         state_digest: StateDigest(vec![0, 1, 2, 3]),
                         contract_name: "c1".into(),
+                        blob_encoding: BlobEncoding::default(),
                     }
                     .as_blob("hyle".into(), None, None),
                     Blob {
@@ -1667,6 +3803,10 @@ pub mod test {
                         data: BlobData(vec![0, 1, 2, 3]),
                     },
                 ],
+                dependencies: vec![],
+                additional_identities: vec![],
+                nonce: None,
+                fee: 0,
             };
             // Try to register the same contract validly later.
             let mut compositing_register_good = compositing_register_willfail.clone();
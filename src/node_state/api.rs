@@ -6,7 +6,10 @@ use axum::{
     Json, Router,
 };
 use hyle_contract_sdk::ContractName;
-use hyle_model::UnsettledBlobTransaction;
+use hyle_model::{
+    api::{APIContractStateProof, APITransactionStatus},
+    UnsettledBlobTransaction,
+};
 use tracing::error;
 use utoipa::OpenApi;
 use utoipa_axum::{router::OpenApiRouter, routes};
@@ -18,15 +21,20 @@ use crate::{
         metrics::BusMetrics,
     },
     model::{BlockHeight, CommonRunContext, Contract},
-    node_state::module::{QueryBlockHeight, QueryUnsettledTx},
+    node_state::module::{
+        QueryBlockHeight, QueryContractStateProof, QueryContracts, QueryTxStatus, QueryUnsettledTx,
+    },
     rest::AppError,
 };
 
 bus_client! {
 struct RestBusClient {
-    sender(Query<ContractName, Contract>),
+    sender(Query<ContractName, Option<Contract>>),
     sender(Query<QueryBlockHeight, BlockHeight>),
     sender(Query<QueryUnsettledTx, UnsettledBlobTransaction>),
+    sender(Query<QueryContracts, Vec<Contract>>),
+    sender(Query<QueryContractStateProof, APIContractStateProof>),
+    sender(Query<QueryTxStatus, Option<APITransactionStatus>>),
 }
 }
 
@@ -46,8 +54,11 @@ pub async fn api(ctx: &CommonRunContext) -> Router<()> {
         .routes(routes!(get_block_height))
         // FIXME: we expose this endpoint for testing purposes. This should be removed or adapted
         .routes(routes!(get_contract))
+        .routes(routes!(get_contracts))
+        .routes(routes!(get_contract_state_proof))
         // TODO: figure out if we want to rely on the indexer instead
         .routes(routes!(get_unsettled_tx))
+        .routes(routes!(get_transaction_status))
         .split_for_parts();
 
     if let Ok(mut o) = ctx.openapi.lock() {
@@ -65,7 +76,8 @@ pub async fn api(ctx: &CommonRunContext) -> Router<()> {
     ),
     tag = "Node State",
     responses(
-        (status = OK, body = Contract)
+        (status = OK, body = Contract),
+        (status = NOT_FOUND, description = "Contract not registered")
     )
 )]
 pub async fn get_contract(
@@ -74,7 +86,11 @@ pub async fn get_contract(
 ) -> Result<impl IntoResponse, AppError> {
     let name_clone = name.clone();
     match state.bus.request(name).await {
-        Ok(contract) => Ok(Json(contract)),
+        Ok(Some(contract)) => Ok(Json(contract)),
+        Ok(None) => Err(AppError(
+            StatusCode::NOT_FOUND,
+            anyhow!("Contract {} not found", name_clone),
+        )),
         err => {
             error!("{:?}", err);
 
@@ -86,6 +102,59 @@ pub async fn get_contract(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/contracts",
+    tag = "Node State",
+    responses(
+        (status = OK, body = [Contract])
+    )
+)]
+pub async fn get_contracts(
+    State(mut state): State<RouterState>,
+) -> Result<impl IntoResponse, AppError> {
+    match state.bus.request(QueryContracts {}).await {
+        Ok(contracts) => Ok(Json(contracts)),
+        err => {
+            error!("{:?}", err);
+
+            Err(AppError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                anyhow!("Error while getting contracts"),
+            ))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/contract/{name}/state_proof",
+    params(
+        ("name" = String, Path, description = "Contract name")
+    ),
+    tag = "Node State",
+    responses(
+        (status = OK, body = APIContractStateProof)
+    )
+)]
+pub async fn get_contract_state_proof(
+    Path(name): Path<ContractName>,
+    State(mut state): State<RouterState>,
+) -> Result<impl IntoResponse, AppError> {
+    let name_clone = name.clone();
+    match state.bus.request(QueryContractStateProof(name)).await {
+        Ok(proof) => Ok(Json(proof)),
+        err => {
+            error!("{:?}", err);
+
+            Err(AppError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                anyhow!("Error while getting state proof of contract {}", name_clone),
+            ))
+        }
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/unsettled_tx/{blob_tx_hash}",
@@ -118,6 +187,43 @@ pub async fn get_unsettled_tx(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/transaction/status/{tx_hash}",
+    params(
+        ("tx_hash" = String, Path, description = "Transaction hash")
+    ),
+    tag = "Node State",
+    responses(
+        (status = OK, body = APITransactionStatus),
+        (status = NOT_FOUND, description = "Transaction unknown, or handled before the node started")
+    )
+)]
+pub async fn get_transaction_status(
+    Path(tx_hash): Path<String>,
+    State(mut state): State<RouterState>,
+) -> Result<impl IntoResponse, AppError> {
+    match state
+        .bus
+        .request(QueryTxStatus(hyle_model::TxHash(tx_hash.clone())))
+        .await
+    {
+        Ok(Some(status)) => Ok(Json(status)),
+        Ok(None) => Err(AppError(
+            StatusCode::NOT_FOUND,
+            anyhow!("Transaction {} not found", tx_hash),
+        )),
+        err => {
+            error!("{:?}", err);
+
+            Err(AppError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                anyhow!("Error while getting status of transaction {}", tx_hash),
+            ))
+        }
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/da/block/height",
@@ -148,7 +254,7 @@ impl Clone for RouterState {
         Self {
             bus: RestBusClient::new(
                 Pick::<BusMetrics>::get(&self.bus).clone(),
-                Pick::<tokio::sync::broadcast::Sender<Query<ContractName, Contract>>>::get(
+                Pick::<tokio::sync::broadcast::Sender<Query<ContractName, Option<Contract>>>>::get(
                     &self.bus,
                 )
                 .clone(),
@@ -162,6 +268,22 @@ impl Clone for RouterState {
                     >,
                 >::get(&self.bus)
                 .clone(),
+                Pick::<tokio::sync::broadcast::Sender<Query<QueryContracts, Vec<Contract>>>>::get(
+                    &self.bus,
+                )
+                .clone(),
+                Pick::<
+                    tokio::sync::broadcast::Sender<
+                        Query<QueryContractStateProof, APIContractStateProof>,
+                    >,
+                >::get(&self.bus)
+                .clone(),
+                Pick::<
+                    tokio::sync::broadcast::Sender<
+                        Query<QueryTxStatus, Option<APITransactionStatus>>,
+                    >,
+                >::get(&self.bus)
+                .clone(),
             ),
         }
     }
@@ -0,0 +1,199 @@
+//! Append-only audit trail of the proofs handled by the node state.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::model::{
+    BlobIndex, BlockHeight, ContractName, ProofDataHash, TxHash, ValidatorPublicKey, Verifier,
+};
+
+/// What happened to a proven blob when its proof reached the node state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationResult {
+    Valid,
+    Invalid(String),
+    /// The blob transaction is not known yet, the proof is kept until it arrives.
+    Buffered,
+}
+
+/// One line of the audit log. The proof itself is not kept, only its hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub struct VerificationAuditEntry {
+    /// Timestamp of the block the proof was included in
+    pub timestamp: u64,
+    pub block_height: BlockHeight,
+    /// Validator whose lane carried the proof transaction
+    pub submitted_by: ValidatorPublicKey,
+    pub proof_tx_hash: TxHash,
+    pub proof_hash: ProofDataHash,
+    pub contract_name: ContractName,
+    pub verifier: Verifier,
+    /// Hex-encoded
+    pub program_id: String,
+    pub blob_tx_hash: TxHash,
+    pub blob_index: BlobIndex,
+    pub result: VerificationResult,
+}
+
+/// Entries recorded while handling a block, written to disk by the node state module.
+#[derive(Debug, Clone, Default, Encode, Decode)]
+pub struct VerificationAudit {
+    pub enabled: bool,
+    entries: Vec<VerificationAuditEntry>,
+}
+
+impl VerificationAudit {
+    pub fn record(&mut self, entry: VerificationAuditEntry) {
+        if self.enabled {
+            self.entries.push(entry);
+        }
+    }
+
+    pub fn drain(&mut self) -> Vec<VerificationAuditEntry> {
+        std::mem::take(&mut self.entries)
+    }
+}
+
+/// JSON lines file, rotated once it grows over `max_file_size` bytes.
+/// Rotated files are suffixed `.1` (most recent) to `.<max_files>`, older ones are deleted.
+pub struct AuditLog {
+    path: PathBuf,
+    max_file_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl AuditLog {
+    pub fn open(path: &Path, max_file_size: u64, max_files: usize) -> Result<Self> {
+        let file = Self::open_file(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_file_size,
+            max_files,
+            file,
+            size,
+        })
+    }
+
+    fn open_file(path: &Path) -> Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Opening audit log {}", path.display()))
+    }
+
+    pub fn append(&mut self, entry: &VerificationAuditEntry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        if self.size > 0 && self.size + line.len() as u64 > self.max_file_size {
+            self.rotate()?;
+        }
+        self.file.write_all(&line)?;
+        self.file.flush()?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{n}"));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        if self.max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            for n in (1..self.max_files).rev() {
+                let from = self.rotated_path(n);
+                if from.exists() {
+                    std::fs::rename(from, self.rotated_path(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = Self::open_file(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(blob_index: usize) -> VerificationAuditEntry {
+        VerificationAuditEntry {
+            timestamp: 0,
+            block_height: BlockHeight(1),
+            submitted_by: ValidatorPublicKey::default(),
+            proof_tx_hash: TxHash::new("proof"),
+            proof_hash: ProofDataHash("hash".into()),
+            contract_name: "c1".into(),
+            verifier: Verifier("test".into()),
+            program_id: "00".into(),
+            blob_tx_hash: TxHash::new("blob"),
+            blob_index: BlobIndex(blob_index),
+            result: VerificationResult::Valid,
+        }
+    }
+
+    fn read_entries(path: &Path) -> Vec<VerificationAuditEntry> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn disabled_audit_records_nothing() {
+        let mut audit = VerificationAudit::default();
+        audit.record(entry(0));
+        assert!(audit.drain().is_empty());
+
+        audit.enabled = true;
+        audit.record(entry(0));
+        assert_eq!(audit.drain(), vec![entry(0)]);
+        assert!(audit.drain().is_empty());
+    }
+
+    #[test]
+    fn log_is_rotated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let line_len = serde_json::to_vec(&entry(0)).unwrap().len() as u64 + 1;
+        // Two entries per file, two rotated files
+        let mut log = AuditLog::open(&path, 2 * line_len, 2).unwrap();
+        for i in 0..7 {
+            log.append(&entry(i)).unwrap();
+        }
+
+        assert_eq!(read_entries(&path), vec![entry(6)]);
+        assert_eq!(
+            read_entries(&dir.path().join("audit.jsonl.1")),
+            vec![entry(4), entry(5)]
+        );
+        assert_eq!(
+            read_entries(&dir.path().join("audit.jsonl.2")),
+            vec![entry(2), entry(3)]
+        );
+        assert!(!dir.path().join("audit.jsonl.3").exists());
+
+        // Appends to the existing file when reopened
+        let mut log = AuditLog::open(&path, 2 * line_len, 2).unwrap();
+        log.append(&entry(7)).unwrap();
+        assert_eq!(read_entries(&path), vec![entry(6), entry(7)]);
+    }
+}
@@ -0,0 +1,36 @@
+use opentelemetry::{metrics::Counter, InstrumentationScope, KeyValue};
+
+use crate::model::Block;
+
+pub struct NodeStateMetrics {
+    handled_blob_tx: Counter<u64>,
+}
+
+impl NodeStateMetrics {
+    pub fn global(node_name: String) -> NodeStateMetrics {
+        let scope = InstrumentationScope::builder(node_name).build();
+        let my_meter = opentelemetry::global::meter_with_scope(scope);
+
+        let node_state = "node_state";
+
+        NodeStateMetrics {
+            handled_blob_tx: my_meter
+                .u64_counter(format!("{node_state}_handled_blob_tx"))
+                .build(),
+        }
+    }
+
+    /// Counts the blob txs of the block by outcome: settled, failed or timed out.
+    pub fn add_handled_blob_txs(&self, block: &Block) {
+        for (status, txs) in [
+            ("success", &block.successful_txs),
+            ("failure", &block.failed_txs),
+            ("timed_out", &block.timed_out_txs),
+        ] {
+            if !txs.is_empty() {
+                self.handled_blob_tx
+                    .add(txs.len() as u64, &[KeyValue::new("status", status)]);
+            }
+        }
+    }
+}
@@ -1,17 +1,21 @@
 //! State required for participation in consensus by the node.
 
+use super::audit::AuditLog;
+use super::metrics::NodeStateMetrics;
+use super::tx_statuses::TxStatuses;
 use super::NodeState;
 use crate::bus::{command_response::Query, BusClientSender, BusMessage};
 use crate::data_availability::DataEvent;
 use crate::model::Contract;
-use crate::model::{Block, BlockHeight, CommonRunContext, ContractName};
+use crate::model::{Block, BlockHeight, CommonRunContext, ContractName, SignedBlock};
 use crate::module_handle_messages;
 use crate::utils::conf::SharedConf;
 use crate::utils::logger::LogMe;
 use crate::utils::modules::{module_bus_client, Module};
-use anyhow::{Context, Result};
+use anyhow::Result;
 use bincode::{Decode, Encode};
-use hyle_model::{TxHash, UnsettledBlobTransaction};
+use hyle_model::api::{APIContractStateProof, APITransactionStatus, TransactionStatus};
+use hyle_model::{StateRoot, TxHash, UnsettledBlobTransaction};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::info;
@@ -24,6 +28,10 @@ pub struct NodeStateModule {
     config: SharedConf,
     bus: NodeStateBusClient,
     inner: NodeState,
+    audit_log: Option<AuditLog>,
+    metrics: NodeStateMetrics,
+    /// Not persisted: txs handled before a restart are unknown
+    tx_statuses: TxStatuses,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Encode, Decode)]
@@ -38,15 +46,57 @@ pub struct QueryBlockHeight {}
 #[derive(Clone)]
 pub struct QueryUnsettledTx(pub TxHash);
 
+#[derive(Clone)]
+pub struct QueryContracts {}
+
+#[derive(Clone)]
+pub struct QueryContractStateProof(pub ContractName);
+
+#[derive(Clone)]
+pub struct QueryTxStatus(pub TxHash);
+
 module_bus_client! {
 #[derive(Debug)]
 pub struct NodeStateBusClient {
     sender(NodeStateEvent),
     receiver(DataEvent),
-    receiver(Query<ContractName, Contract>),
+    receiver(Query<ContractName, Option<Contract>>),
     receiver(Query<QueryBlockHeight , BlockHeight>),
     receiver(Query<QueryUnsettledTx, UnsettledBlobTransaction>),
+    receiver(Query<QueryContracts, Vec<Contract>>),
+    receiver(Query<QueryContractStateProof, APIContractStateProof>),
+    receiver(Query<QueryTxStatus, Option<APITransactionStatus>>),
+}
 }
+
+impl NodeStateModule {
+    fn handle_signed_block(&mut self, block: &SignedBlock) {
+        let Some(node_state_block) = self.inner.handle_new_signed_block(block) else {
+            return;
+        };
+        self.metrics.add_handled_blob_txs(&node_state_block);
+        self.tx_statuses
+            .record_block(&node_state_block, self.config.max_tx_statuses);
+        let audit_entries = self.inner.verification_audit.drain();
+        if let Some(audit_log) = &mut self.audit_log {
+            for entry in &audit_entries {
+                _ = audit_log
+                    .append(entry)
+                    .log_error("Writing verification audit log");
+            }
+        }
+        // Sent before persisting: a restart resumes after the last block persisted, so a block
+        // is never skipped by the modules listening, at worst it is sent again.
+        _ = self
+            .bus
+            .send(NodeStateEvent::NewBlock(Box::new(node_state_block)))
+            .log_error("Sending DataEvent while processing SignedBlock");
+        _ = Self::save_on_disk::<NodeState>(
+            self.config.data_directory.join("node_state.bin").as_path(),
+            &self.inner,
+        )
+        .log_error("Saving node state");
+    }
 }
 
 impl Module for NodeStateModule {
@@ -62,18 +112,39 @@ impl Module for NodeStateModule {
             }
         }
 
-        let storage = Self::load_from_disk_or_default::<NodeState>(
+        let mut storage = Self::load_from_disk_or_default::<NodeState>(
             ctx.config.data_directory.join("node_state.bin").as_path(),
-        );
+        )?;
+
+        let audit_conf = &ctx.config.verification_audit;
+        storage.verification_audit.enabled = audit_conf.enabled;
+        let audit_log = match audit_conf.enabled {
+            true => Some(AuditLog::open(
+                ctx.config
+                    .data_directory
+                    .join("verification_audit.jsonl")
+                    .as_path(),
+                audit_conf.max_file_size,
+                audit_conf.max_files,
+            )?),
+            false => None,
+        };
 
-        for name in storage.contracts.keys() {
-            info!("📝 Loaded contract state for {}", name);
+        for (name, digest) in storage.contract_digests() {
+            info!(
+                "📝 Loaded contract state for {} ({})",
+                name,
+                hex::encode(digest.0)
+            );
         }
 
         Ok(Self {
             config: ctx.config.clone(),
             bus,
             inner: storage,
+            audit_log,
+            metrics: NodeStateMetrics::global(ctx.config.id.clone()),
+            tx_statuses: TxStatuses::default(),
         })
     }
 
@@ -83,8 +154,25 @@ impl Module for NodeStateModule {
             command_response<QueryBlockHeight, BlockHeight> _ => {
                 Ok(self.inner.current_height)
             }
-            command_response<ContractName, Contract> cmd => {
-                self.inner.contracts.get(cmd).cloned().context("Contract not found")
+            command_response<ContractName, Option<Contract>> cmd => {
+                Ok(self.inner.contracts.get(cmd).cloned())
+            }
+            command_response<QueryContracts, Vec<Contract>> _ => {
+                let mut contracts = self.inner.contracts().map(|(_, c)| c.clone()).collect::<Vec<_>>();
+                contracts.sort_by(|a, b| a.name.0.cmp(&b.name.0));
+                Ok(contracts)
+            }
+            command_response<QueryContractStateProof, APIContractStateProof> cmd => {
+                match (self.inner.contracts.get(&cmd.0), self.inner.state_inclusion_proof(&cmd.0)) {
+                    (Some(contract), Some(proof)) => Ok(APIContractStateProof {
+                        contract_name: cmd.0.clone(),
+                        block_height: self.inner.current_height,
+                        state_root: StateRoot::compute(&self.inner.contract_digests()),
+                        state_digest: contract.state.clone(),
+                        proof,
+                    }),
+                    _ => Err(anyhow::anyhow!("Contract not found")),
+                }
             }
             command_response<QueryUnsettledTx, UnsettledBlobTransaction> tx_hash => {
                 match self.inner.unsettled_transactions.get(&tx_hash.0) {
@@ -92,15 +180,28 @@ impl Module for NodeStateModule {
                     None => Err(anyhow::anyhow!("Transaction not found")),
                 }
             }
+            command_response<QueryTxStatus, Option<APITransactionStatus>> cmd => {
+                let tx_hash = cmd.0.clone();
+                Ok(match self.tx_statuses.get(&tx_hash) {
+                    Some((status, block_height)) => Some(APITransactionStatus {
+                        tx_hash,
+                        status: status.clone(),
+                        block_height: Some(*block_height),
+                    }),
+                    None => self
+                        .inner
+                        .unsettled_transactions
+                        .get(&tx_hash)
+                        .map(|_| APITransactionStatus {
+                            tx_hash,
+                            status: TransactionStatus::Sequenced,
+                            block_height: None,
+                        }),
+                })
+            }
             listen<DataEvent> block => {
                 match block {
-                    DataEvent::OrderedSignedBlock(block) => {
-                        let node_state_block = self.inner.handle_signed_block(&block);
-                        _ = self
-                            .bus
-                            .send(NodeStateEvent::NewBlock(Box::new(node_state_block)))
-                            .log_error("Sending DataEvent while processing SignedBlock");
-                    }
+                    DataEvent::OrderedSignedBlock(block) => self.handle_signed_block(&block),
                 }
             }
         };
@@ -114,3 +215,75 @@ impl Module for NodeStateModule {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use hyle_model::{AggregateSignature, ConsensusProposal};
+    use tokio::sync::broadcast;
+
+    use super::*;
+    use crate::bus::{dont_use_this::get_receiver, metrics::BusMetrics, SharedMessageBus};
+    use crate::utils::conf::Conf;
+
+    async fn build_module(
+        data_directory: &Path,
+    ) -> (NodeStateModule, broadcast::Receiver<NodeStateEvent>) {
+        let bus = SharedMessageBus::new(BusMetrics::global("global".to_string()));
+        let events = get_receiver::<NodeStateEvent>(&bus).await;
+        let ctx = Arc::new(CommonRunContext {
+            bus,
+            config: Arc::new(Conf {
+                data_directory: data_directory.to_path_buf(),
+                ..Conf::default()
+            }),
+            router: Default::default(),
+            openapi: Default::default(),
+        });
+        (NodeStateModule::build(ctx).await.unwrap(), events)
+    }
+
+    fn signed_block(slot: u64) -> SignedBlock {
+        SignedBlock {
+            certificate: AggregateSignature::default(),
+            consensus_proposal: ConsensusProposal {
+                slot,
+                ..ConsensusProposal::default()
+            },
+            data_proposals: vec![],
+        }
+    }
+
+    fn next_block_height(events: &mut broadcast::Receiver<NodeStateEvent>) -> BlockHeight {
+        let NodeStateEvent::NewBlock(block) = events.try_recv().unwrap();
+        block.block_height
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_block_not_persisted_is_sent_again_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_file = dir.path().join("node_state.bin");
+
+        let (mut module, mut events) = build_module(dir.path()).await;
+        module.handle_signed_block(&signed_block(0));
+        let persisted = std::fs::read(&state_file).unwrap();
+
+        // The node stops before persisting the next block: a directory is in the way
+        std::fs::remove_file(&state_file).unwrap();
+        std::fs::create_dir(&state_file).unwrap();
+        module.handle_signed_block(&signed_block(1));
+        assert_eq!(next_block_height(&mut events), BlockHeight(0));
+        assert_eq!(next_block_height(&mut events), BlockHeight(1));
+
+        std::fs::remove_dir(&state_file).unwrap();
+        std::fs::write(&state_file, persisted).unwrap();
+        let (mut module, mut events) = build_module(dir.path()).await;
+        module.handle_signed_block(&signed_block(0));
+        module.handle_signed_block(&signed_block(1));
+
+        // Only the block that wasn't persisted is handled and sent again
+        assert_eq!(next_block_height(&mut events), BlockHeight(1));
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -12,6 +12,9 @@ use std::collections::{HashMap, VecDeque};
 pub struct OrderedTxMap {
     map: HashMap<TxHash, UnsettledBlobTransaction>,
     tx_order: HashMap<ContractName, VecDeque<TxHash>>,
+    /// Order in which the txs were added, i.e. by block then by index within the block
+    positions: HashMap<TxHash, u64>,
+    next_position: u64,
 }
 
 impl OrderedTxMap {
@@ -20,6 +23,11 @@ impl OrderedTxMap {
         self.map.get(hash)
     }
 
+    /// Position of the tx among all the txs added to the map, the same on every node.
+    pub fn position(&self, hash: &TxHash) -> Option<u64> {
+        self.positions.get(hash).copied()
+    }
+
     pub fn get_next_unsettled_tx(&self, contract: &ContractName) -> Option<&TxHash> {
         self.tx_order.get(contract).and_then(|v| v.front())
     }
@@ -77,6 +85,8 @@ impl OrderedTxMap {
             } && is_next;
         }
 
+        self.positions.insert(tx.hash.clone(), self.next_position);
+        self.next_position += 1;
         self.map.insert(tx.hash.clone(), tx);
         is_next
     }
@@ -95,6 +105,7 @@ impl OrderedTxMap {
                     }
                 }
             }
+            self.positions.remove(hash);
             self.map.remove(hash)
         } else {
             None
@@ -116,6 +127,7 @@ mod tests {
     fn new_tx(hash: &str, contract: &str) -> UnsettledBlobTransaction {
         UnsettledBlobTransaction {
             identity: Identity::new("toto"),
+            additional_identities: vec![],
             hash: TxHash::new(hash),
             blobs_hash: BlobsHash::new("blobs_hash"),
             blobs: vec![UnsettledBlobMetadata {
@@ -124,6 +136,7 @@ mod tests {
                     data: BlobData::default(),
                 },
                 possible_proofs: vec![],
+                depends_on: None,
             }],
             tx_context: Arc::new(TxContext::default()),
         }
@@ -247,4 +260,25 @@ mod tests {
         assert_eq!(map.tx_order.len(), 2);
         assert_eq!(map.tx_order[&c1].len(), 0);
     }
+
+    #[test]
+    fn positions_follow_insertion_order() {
+        let mut map = OrderedTxMap::default();
+        let tx1 = TxHash::new("tx1");
+        let tx2 = TxHash::new("tx2");
+        let tx3 = TxHash::new("tx3");
+
+        map.add(new_tx("tx3", "c1"));
+        map.add(new_tx("tx1", "c2"));
+        map.add(new_tx("tx1", "c2"));
+        map.add(new_tx("tx2", "c1"));
+
+        assert_eq!(map.position(&tx3), Some(0));
+        assert_eq!(map.position(&tx1), Some(1));
+        assert_eq!(map.position(&tx2), Some(2));
+
+        map.remove(&tx3);
+        assert_eq!(map.position(&tx3), None);
+        assert_eq!(map.position(&tx2), Some(2));
+    }
 }
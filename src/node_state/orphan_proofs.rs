@@ -0,0 +1,263 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::model::{BlobProofOutput, BlockHeight, ValidatorPublicKey};
+use bincode::{Decode, Encode};
+use hyle_contract_sdk::TxHash;
+use tracing::{info, warn};
+
+/// Maximum number of proofs kept while waiting for their blob transaction.
+/// Settlement depends on it, so it is the same for the whole network.
+pub const MAX_ORPHAN_PROOFS: usize = 1000;
+/// Number of blocks a proof waits for its blob transaction before being dropped.
+pub const ORPHAN_PROOF_TTL: u64 = 100;
+/// Maximum number of proofs kept for the proof transactions of a single lane, so that one
+/// validator can't evict the proofs carried by the others.
+pub const MAX_ORPHAN_PROOFS_PER_LANE: usize = 100;
+/// Number of settled, failed or timed out blob transactions remembered, to refuse the proofs
+/// replayed for them.
+pub const MAX_FINISHED_TXS: usize = 10_000;
+
+/// Proofs received before the blob transaction they prove.
+/// The buffer is bounded in size and in time: when full, the oldest proof is dropped,
+/// and proofs whose blob transaction didn't show up within [`ORPHAN_PROOF_TTL`] blocks are
+/// discarded. Proofs for a blob transaction that already went through are never buffered.
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct OrphanProofs {
+    proofs: VecDeque<OrphanProof>,
+    finished: HashSet<TxHash>,
+    finished_order: VecDeque<TxHash>,
+    /// Overrides [`MAX_ORPHAN_PROOFS`] in tests
+    #[cfg(test)]
+    pub max_proofs: usize,
+    /// Overrides [`ORPHAN_PROOF_TTL`] in tests
+    #[cfg(test)]
+    pub ttl: u64,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+struct OrphanProof {
+    submitted_by: ValidatorPublicKey,
+    proof_tx_hash: TxHash,
+    blob_proof_output: BlobProofOutput,
+    expires_at: BlockHeight,
+}
+
+impl Default for OrphanProofs {
+    fn default() -> Self {
+        Self {
+            proofs: VecDeque::new(),
+            finished: HashSet::new(),
+            finished_order: VecDeque::new(),
+            #[cfg(test)]
+            max_proofs: MAX_ORPHAN_PROOFS,
+            #[cfg(test)]
+            ttl: ORPHAN_PROOF_TTL,
+        }
+    }
+}
+
+impl OrphanProofs {
+    fn max_proofs(&self) -> usize {
+        #[cfg(test)]
+        return self.max_proofs;
+        #[cfg(not(test))]
+        MAX_ORPHAN_PROOFS
+    }
+
+    fn ttl(&self) -> u64 {
+        #[cfg(test)]
+        return self.ttl;
+        #[cfg(not(test))]
+        ORPHAN_PROOF_TTL
+    }
+
+    /// Whether the blob transaction settled, failed or timed out, so its proofs are useless.
+    pub fn is_finished(&self, blob_tx_hash: &TxHash) -> bool {
+        self.finished.contains(blob_tx_hash)
+    }
+
+    /// Records a blob transaction that went through, dropping the proofs waiting for it.
+    pub fn on_finished(&mut self, blob_tx_hash: &TxHash) {
+        self.proofs
+            .retain(|orphan| &orphan.blob_proof_output.blob_tx_hash != blob_tx_hash);
+        if !self.finished.insert(blob_tx_hash.clone()) {
+            return;
+        }
+        self.finished_order.push_back(blob_tx_hash.clone());
+        while self.finished_order.len() > MAX_FINISHED_TXS {
+            if let Some(oldest) = self.finished_order.pop_front() {
+                self.finished.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn push(
+        &mut self,
+        submitted_by: &ValidatorPublicKey,
+        proof_tx_hash: TxHash,
+        blob_proof_output: BlobProofOutput,
+        current_height: BlockHeight,
+    ) {
+        let max_proofs = self.max_proofs();
+        if max_proofs == 0 {
+            return;
+        }
+        let from_lane = |orphan: &OrphanProof| &orphan.submitted_by == submitted_by;
+        if self.proofs.iter().filter(|o| from_lane(o)).count() >= MAX_ORPHAN_PROOFS_PER_LANE {
+            if let Some(position) = self.proofs.iter().position(from_lane) {
+                if let Some(evicted) = self.proofs.remove(position) {
+                    warn!(
+                        "Too many orphan proofs from lane {}, dropping proof {} for BlobTx {}",
+                        submitted_by, evicted.proof_tx_hash, evicted.blob_proof_output.blob_tx_hash
+                    );
+                }
+            }
+        }
+        while self.proofs.len() >= max_proofs {
+            if let Some(evicted) = self.proofs.pop_front() {
+                warn!(
+                    "Orphan proofs buffer is full, dropping proof {} for BlobTx {}",
+                    evicted.proof_tx_hash, evicted.blob_proof_output.blob_tx_hash
+                );
+            }
+        }
+        self.proofs.push_back(OrphanProof {
+            submitted_by: submitted_by.clone(),
+            proof_tx_hash,
+            blob_proof_output,
+            expires_at: current_height + self.ttl(),
+        });
+    }
+
+    /// Removes and returns the proofs waiting for this blob transaction, oldest first.
+    pub fn take(&mut self, blob_tx_hash: &TxHash) -> Vec<(TxHash, BlobProofOutput)> {
+        let mut taken = vec![];
+        self.proofs.retain(|orphan| {
+            if &orphan.blob_proof_output.blob_tx_hash == blob_tx_hash {
+                taken.push((
+                    orphan.proof_tx_hash.clone(),
+                    orphan.blob_proof_output.clone(),
+                ));
+                false
+            } else {
+                true
+            }
+        });
+        taken
+    }
+
+    pub fn drop_expired(&mut self, at: BlockHeight) {
+        self.proofs.retain(|orphan| {
+            if orphan.expires_at <= at {
+                info!(
+                    "⏰ Orphan proof {} for BlobTx {} expired",
+                    orphan.proof_tx_hash, orphan.blob_proof_output.blob_tx_hash
+                );
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn orphan(blob_tx_hash: &str) -> BlobProofOutput {
+        BlobProofOutput {
+            blob_tx_hash: TxHash::new(blob_tx_hash),
+            ..BlobProofOutput::default()
+        }
+    }
+
+    #[test]
+    fn evicts_oldest_beyond_cap() {
+        let lane = ValidatorPublicKey::default();
+        let mut proofs = OrphanProofs {
+            max_proofs: 2,
+            ..OrphanProofs::default()
+        };
+
+        proofs.push(&lane, TxHash::new("p1"), orphan("tx1"), BlockHeight(0));
+        proofs.push(&lane, TxHash::new("p2"), orphan("tx2"), BlockHeight(0));
+        proofs.push(&lane, TxHash::new("p3"), orphan("tx3"), BlockHeight(0));
+
+        assert_eq!(proofs.len(), 2);
+        assert!(proofs.take(&TxHash::new("tx1")).is_empty());
+        assert_eq!(proofs.take(&TxHash::new("tx2")).len(), 1);
+        assert_eq!(proofs.take(&TxHash::new("tx3")).len(), 1);
+        assert_eq!(proofs.len(), 0);
+    }
+
+    #[test]
+    fn expires_after_ttl() {
+        let lane = ValidatorPublicKey::default();
+        let mut proofs = OrphanProofs {
+            ttl: 10,
+            ..OrphanProofs::default()
+        };
+
+        proofs.push(&lane, TxHash::new("p1"), orphan("tx1"), BlockHeight(0));
+        proofs.push(&lane, TxHash::new("p2"), orphan("tx2"), BlockHeight(5));
+
+        proofs.drop_expired(BlockHeight(9));
+        assert_eq!(proofs.len(), 2);
+
+        proofs.drop_expired(BlockHeight(10));
+        assert_eq!(proofs.len(), 1);
+        assert_eq!(proofs.take(&TxHash::new("tx2")).len(), 1);
+    }
+
+    #[test]
+    fn evicts_oldest_of_a_lane_beyond_its_cap() {
+        let (lane, other_lane) = (
+            ValidatorPublicKey(b"lane".to_vec()),
+            ValidatorPublicKey(b"other".to_vec()),
+        );
+        let mut proofs = OrphanProofs::default();
+
+        proofs.push(
+            &other_lane,
+            TxHash::new("p0"),
+            orphan("tx0"),
+            BlockHeight(0),
+        );
+        for i in 1..=MAX_ORPHAN_PROOFS_PER_LANE + 1 {
+            let tx = format!("tx{i}");
+            proofs.push(
+                &lane,
+                TxHash::new(format!("p{i}")),
+                orphan(&tx),
+                BlockHeight(0),
+            );
+        }
+
+        assert_eq!(proofs.len(), MAX_ORPHAN_PROOFS_PER_LANE + 1);
+        assert!(proofs.take(&TxHash::new("tx1")).is_empty());
+        assert_eq!(proofs.take(&TxHash::new("tx0")).len(), 1);
+        assert_eq!(proofs.take(&TxHash::new("tx2")).len(), 1);
+    }
+
+    #[test]
+    fn forgets_proofs_of_finished_txs() {
+        let lane = ValidatorPublicKey::default();
+        let mut proofs = OrphanProofs::default();
+
+        proofs.push(&lane, TxHash::new("p1"), orphan("tx1"), BlockHeight(0));
+        proofs.on_finished(&TxHash::new("tx1"));
+
+        assert!(proofs.is_empty());
+        assert!(proofs.is_finished(&TxHash::new("tx1")));
+        assert!(!proofs.is_finished(&TxHash::new("tx2")));
+    }
+}
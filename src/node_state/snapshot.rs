@@ -0,0 +1,79 @@
+//! Export of the whole node state, to restore it into a fresh node.
+
+use anyhow::{bail, Context, Result};
+use bincode::{Decode, Encode};
+
+use super::{validate_contract_registration, NodeState};
+use crate::model::{ContractName, StateRoot};
+
+/// Bumped whenever the encoding of `NodeState` changes.
+pub const SNAPSHOT_VERSION: u32 = 6;
+
+#[derive(Encode, Decode)]
+struct NodeStateSnapshot {
+    version: u32,
+    /// State root of the contracts of the node state, checked on import
+    state_root: StateRoot,
+    node_state: NodeState,
+}
+
+impl NodeState {
+    pub fn export_snapshot(&self) -> Result<Vec<u8>> {
+        let snapshot = NodeStateSnapshot {
+            version: SNAPSHOT_VERSION,
+            state_root: StateRoot::compute(&self.contract_digests()),
+            node_state: self.clone(),
+        };
+        bincode::encode_to_vec(snapshot, bincode::config::standard())
+            .context("Encoding node state snapshot")
+    }
+
+    pub fn import_snapshot(bytes: &[u8]) -> Result<Self> {
+        let (snapshot, _): (NodeStateSnapshot, _) =
+            bincode::decode_from_slice(bytes, bincode::config::standard())
+                .context("Decoding node state snapshot")?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            bail!(
+                "Unsupported node state snapshot version {}, expected {}",
+                snapshot.version,
+                SNAPSHOT_VERSION
+            );
+        }
+        let node_state = snapshot.node_state;
+        let state_root = StateRoot::compute(&node_state.contract_digests());
+        if state_root != snapshot.state_root {
+            bail!(
+                "Node state snapshot state root {} does not match its contracts ({})",
+                snapshot.state_root.0,
+                state_root.0
+            );
+        }
+        node_state.check_registrations()?;
+        Ok(node_state)
+    }
+
+    /// Every contract has to be registered under its own name, by a registered owner.
+    fn check_registrations(&self) -> Result<()> {
+        for (name, contract) in self.contracts() {
+            if name != &contract.name {
+                bail!("Contract {} is registered as {}", contract.name, name);
+            }
+            if name.0 == "hyle" {
+                continue;
+            }
+            let owner: ContractName = match name.0.split_once(".") {
+                Some((_, tld)) => tld.into(),
+                None => "hyle".into(),
+            };
+            if !self.contracts.contains_key(&owner) {
+                bail!(
+                    "Contract {} is registered without its owner {}",
+                    name,
+                    owner
+                );
+            }
+            validate_contract_registration(&owner, name)?;
+        }
+        Ok(())
+    }
+}
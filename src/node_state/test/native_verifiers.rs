@@ -1,13 +1,15 @@
 use anyhow::Result;
 use assertables::assert_ok;
-use hyle_model::{Blob, BlobTransaction, ContractName, Hashable, Identity};
+use base64::Engine;
+use hyle_model::{Blob, BlobIndex, BlobTransaction, ContractName, Hashable, Identity};
+use p256::ecdsa::{signature::Signer, Signature, SigningKey};
 use sha3::Digest;
 use tracing::info;
 
 use crate::{
     bus::{bus_client, BusClientReceiver, BusClientSender},
-    mempool::api::RestApiMessage,
-    model::verifiers::{BlstSignatureBlob, ShaBlob},
+    mempool::{api::RestApiMessage, verifiers::verify_native},
+    model::verifiers::{BlstSignatureBlob, NativeVerifiers, ShaBlob, WebAuthnBlob},
     rest::RestApi,
     utils::{crypto::BlstCrypto, integration_test::NodeIntegrationCtxBuilder},
 };
@@ -66,6 +68,139 @@ async fn test_sha3_256_native_verifier() {
     assert_ok!(res);
 }
 
+fn passkey() -> SigningKey {
+    SigningKey::from_slice(&[7; 32]).expect("signing key")
+}
+
+fn passkey_identity() -> Identity {
+    WebAuthnBlob::identity(passkey().verifying_key().to_encoded_point(true).as_bytes())
+        .expect("passkey identity")
+}
+
+/// Signs a WebAuthn assertion for `client_data_json`, as a passkey would
+fn webauthn_blob(identity: &Identity, flags: u8, client_data_json: String) -> WebAuthnBlob {
+    let key = passkey();
+    // RP ID hash, flags, signature counter
+    let authenticator_data = [vec![0; 32], vec![flags], vec![0, 0, 0, 1]].concat();
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(client_data_json.as_bytes());
+    let signature: Signature =
+        key.sign(&[authenticator_data.as_slice(), &hasher.finalize()].concat());
+
+    WebAuthnBlob {
+        identity: identity.clone(),
+        authenticator_data,
+        client_data_json: client_data_json.into_bytes(),
+        signature: signature.to_der().as_bytes().to_vec(),
+        public_key: key
+            .verifying_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec(),
+    }
+}
+
+/// The transaction the blob is sent in by `scenario`, and the challenge to sign for it
+fn webauthn_challenge(identity: &Identity) -> String {
+    let tx = BlobTransaction {
+        identity: identity.clone(),
+        blobs: vec![WebAuthnBlob {
+            identity: identity.clone(),
+            authenticator_data: vec![],
+            client_data_json: vec![],
+            signature: vec![],
+            public_key: vec![],
+        }
+        .as_blob()],
+        dependencies: vec![],
+        additional_identities: vec![],
+        nonce: None,
+        fee: 0,
+    };
+    let challenge = WebAuthnBlob::challenge(&tx, BlobIndex(0));
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .encode(hex::decode(challenge.0).expect("hex challenge"))
+}
+
+fn client_data_json(kind: &str, challenge: &str) -> String {
+    format!(r#"{{"type":"{kind}","challenge":"{challenge}","origin":"https://wallet.hyle.eu"}}"#)
+}
+
+#[test_log::test(tokio::test)]
+async fn test_webauthn_native_verifier() {
+    let identity = passkey_identity();
+    let challenge = webauthn_challenge(&identity);
+    let blob = webauthn_blob(
+        &identity,
+        0x05,
+        client_data_json("webauthn.get", &challenge),
+    );
+
+    let res = scenario(identity, blob.as_blob()).await;
+    assert_ok!(res);
+}
+
+#[test_log::test]
+fn test_invalid_webauthn_assertions() {
+    let identity = passkey_identity();
+    let challenge = webauthn_challenge(&identity);
+    let verify = |blob: WebAuthnBlob| {
+        let tx = BlobTransaction {
+            identity: blob.identity.clone(),
+            blobs: vec![blob.as_blob()],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        };
+        verify_native(tx.hash(), BlobIndex(0), &tx, NativeVerifiers::WebAuthn).success
+    };
+
+    assert!(verify(webauthn_blob(
+        &identity,
+        0x05,
+        client_data_json("webauthn.get", &challenge)
+    )));
+
+    // Identity not derived from the passkey
+    let other: Identity = "bob.webauthn".into();
+    assert!(!verify(webauthn_blob(
+        &other,
+        0x05,
+        client_data_json("webauthn.get", &webauthn_challenge(&other))
+    )));
+
+    // Signed for another transaction
+    assert!(!verify(webauthn_blob(
+        &identity,
+        0x05,
+        client_data_json("webauthn.get", "b3RoZXI")
+    )));
+
+    // Not an assertion
+    assert!(!verify(webauthn_blob(
+        &identity,
+        0x05,
+        client_data_json("webauthn.create", &challenge)
+    )));
+
+    // Tampered signature
+    let mut blob = webauthn_blob(
+        &identity,
+        0x05,
+        client_data_json("webauthn.get", &challenge),
+    );
+    *blob.authenticator_data.last_mut().unwrap() = 2;
+    assert!(!verify(blob));
+
+    // No user presence
+    assert!(!verify(webauthn_blob(
+        &identity,
+        0x04,
+        client_data_json("webauthn.get", &challenge)
+    )));
+}
+
 async fn scenario(identity: Identity, blob: Blob) -> Result<()> {
     let mut node_modules = NodeIntegrationCtxBuilder::new().await;
     node_modules.conf.consensus.slot_duration = 200;
@@ -79,6 +214,10 @@ async fn scenario(identity: Identity, blob: Blob) -> Result<()> {
     let blob_tx = BlobTransaction {
         identity: identity.clone(),
         blobs: vec![blob.clone()],
+        dependencies: vec![],
+        additional_identities: vec![],
+        nonce: None,
+        fee: 0,
     };
     let blob_tx_hash = blob_tx.hash();
     node_client.send(RestApiMessage::NewTx(blob_tx.clone().into()))?;
@@ -1,19 +1,87 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::model::BlockHeight;
 use bincode::{Decode, Encode};
 use hyle_contract_sdk::TxHash;
 
-#[derive(Default, Debug, Clone, Encode, Decode)]
+/// Number of blocks after which a blob transaction that isn't fully proven times out.
+/// Settlement depends on it, so it is the same for the whole network.
+pub const BLOB_TIMEOUT: u64 = 100;
+
+/// Number of blocks a timed out blob transaction still accepts late proofs,
+/// before it is reported as timed out. Settlement depends on it, so it is the
+/// same for the whole network.
+pub const BLOB_TIMEOUT_GRACE_PERIOD: u64 = 20;
+
+#[derive(Debug, Clone, Encode, Decode)]
 pub struct Timeouts {
     by_block: HashMap<BlockHeight, Vec<TxHash>>,
+    in_grace_period: HashSet<TxHash>,
+    /// Overrides [`BLOB_TIMEOUT`] in tests. 0 disables timeouts.
+    #[cfg(test)]
+    pub timeout: u64,
+    /// Overrides [`BLOB_TIMEOUT_GRACE_PERIOD`] in tests
+    #[cfg(test)]
+    pub grace_period: u64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            by_block: HashMap::new(),
+            in_grace_period: HashSet::new(),
+            #[cfg(test)]
+            timeout: BLOB_TIMEOUT,
+            #[cfg(test)]
+            grace_period: BLOB_TIMEOUT_GRACE_PERIOD,
+        }
+    }
 }
 
 impl Timeouts {
+    fn timeout(&self) -> u64 {
+        #[cfg(test)]
+        return self.timeout;
+        #[cfg(not(test))]
+        BLOB_TIMEOUT
+    }
+
+    pub fn grace_period(&self) -> u64 {
+        #[cfg(test)]
+        return self.grace_period;
+        #[cfg(not(test))]
+        BLOB_TIMEOUT_GRACE_PERIOD
+    }
+
     pub fn drop(&mut self, at: &BlockHeight) -> Vec<TxHash> {
         self.by_block.remove(at).unwrap_or_default()
     }
 
+    /// Schedules the timeout of a tx sequenced at the given height.
+    pub fn schedule(&mut self, tx: TxHash, sequenced_at: BlockHeight) {
+        let timeout = self.timeout();
+        if timeout > 0 {
+            self.set(tx, sequenced_at + timeout);
+        }
+    }
+
+    /// Delays the timeout of a tx reaching it by the grace period.
+    /// Returns false if there is no grace period, or if the tx already went through it.
+    pub fn start_grace_period(&mut self, tx: &TxHash, at: BlockHeight) -> bool {
+        let grace_period = self.grace_period();
+        if grace_period == 0 || self.in_grace_period.remove(tx) {
+            return false;
+        }
+        self.in_grace_period.insert(tx.clone());
+        self.set(tx.clone(), at + grace_period);
+        true
+    }
+
+    /// Forgets the grace period of a tx that doesn't need it anymore.
+    pub fn end_grace_period(&mut self, tx: &TxHash) {
+        self.in_grace_period.remove(tx);
+    }
+
     /// Set timeout for a tx.
     /// This does not check if the TX is already set to timeout at a different (or same) block.
     pub fn set(&mut self, tx: TxHash, at: BlockHeight) {
@@ -46,6 +114,10 @@ pub mod tests {
         })
     }
 
+    pub fn in_grace_period(t: &Timeouts, tx: &TxHash) -> bool {
+        t.in_grace_period.contains(tx)
+    }
+
     #[test]
     fn timeout() {
         let mut t = Timeouts::default();
@@ -72,4 +144,43 @@ pub mod tests {
         assert_eq!(get(&t, &tx1), None);
         assert_eq!(list_timeouts(&t, &b2), None);
     }
+
+    #[test]
+    fn schedule() {
+        let mut t = Timeouts {
+            timeout: 10,
+            ..Timeouts::default()
+        };
+        let tx1 = TxHash::new("tx1");
+
+        t.schedule(tx1.clone(), BlockHeight(3));
+        assert_eq!(get(&t, &tx1), Some(BlockHeight(13)));
+
+        t.drop(&BlockHeight(13));
+        t.timeout = 0;
+        t.schedule(tx1.clone(), BlockHeight(3));
+        assert_eq!(get(&t, &tx1), None);
+    }
+
+    #[test]
+    fn grace_period() {
+        let mut t = Timeouts {
+            grace_period: 5,
+            ..Timeouts::default()
+        };
+        let tx1 = TxHash::new("tx1");
+
+        assert!(t.start_grace_period(&tx1, BlockHeight(10)));
+        assert!(in_grace_period(&t, &tx1));
+        assert_eq!(get(&t, &tx1), Some(BlockHeight(15)));
+
+        // The grace period is only given once
+        assert_eq!(t.drop(&BlockHeight(15)), vec![tx1.clone()]);
+        assert!(!t.start_grace_period(&tx1, BlockHeight(15)));
+        assert!(!in_grace_period(&t, &tx1));
+        assert_eq!(get(&t, &tx1), None);
+
+        t.grace_period = 0;
+        assert!(!t.start_grace_period(&tx1, BlockHeight(20)));
+    }
 }
@@ -0,0 +1,89 @@
+use std::collections::{HashMap, VecDeque};
+
+use hyle_model::{api::TransactionStatus, Block, BlockHeight, TxHash};
+
+/// Outcome of the txs settled, failed or timed out in the latest blocks, with the height
+/// of that block. Kept in memory and bounded in size, the oldest txs are forgotten first.
+#[derive(Debug, Default)]
+pub struct TxStatuses {
+    statuses: HashMap<TxHash, (TransactionStatus, BlockHeight)>,
+    order: VecDeque<TxHash>,
+}
+
+impl TxStatuses {
+    pub fn get(&self, tx_hash: &TxHash) -> Option<&(TransactionStatus, BlockHeight)> {
+        self.statuses.get(tx_hash)
+    }
+
+    /// A capacity of 0 disables the tracking.
+    pub fn record_block(&mut self, block: &Block, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        let outcomes = (block.successful_txs.iter())
+            .map(|tx| (tx, TransactionStatus::Success))
+            .chain(
+                block
+                    .failed_txs
+                    .iter()
+                    .map(|tx| (tx, TransactionStatus::Failure)),
+            )
+            .chain(
+                block
+                    .timed_out_txs
+                    .iter()
+                    .map(|tx| (tx, TransactionStatus::TimedOut)),
+            );
+        for (tx_hash, status) in outcomes {
+            if self
+                .statuses
+                .insert(tx_hash.clone(), (status, block.block_height))
+                .is_none()
+            {
+                self.order.push_back(tx_hash.clone());
+            }
+        }
+        while self.order.len() > capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.statuses.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oldest_statuses_are_forgotten() {
+        let mut statuses = TxStatuses::default();
+        statuses.record_block(
+            &Block {
+                block_height: BlockHeight(1),
+                successful_txs: vec![TxHash::new("a")],
+                failed_txs: vec![TxHash::new("b")],
+                ..Block::default()
+            },
+            2,
+        );
+        statuses.record_block(
+            &Block {
+                block_height: BlockHeight(2),
+                timed_out_txs: vec![TxHash::new("c")],
+                ..Block::default()
+            },
+            2,
+        );
+
+        assert!(statuses.get(&TxHash::new("a")).is_none());
+        assert_eq!(
+            statuses.get(&TxHash::new("b")),
+            Some(&(TransactionStatus::Failure, BlockHeight(1)))
+        );
+        assert_eq!(
+            statuses.get(&TxHash::new("c")),
+            Some(&(TransactionStatus::TimedOut, BlockHeight(2)))
+        );
+    }
+}
@@ -3,24 +3,31 @@
 use crate::utils::logger::LogMe;
 use crate::{
     bus::{BusMessage, SharedMessageBus},
+    consensus::ConsensusEvent,
     handle_messages,
     model::SharedRunContext,
     module_handle_messages,
     utils::{
-        conf::SharedConf,
+        conf::{P2pConf, SharedConf},
         crypto::SharedBlstCrypto,
         modules::{module_bus_client, Module},
     },
 };
 use anyhow::{Context, Result};
+use rand::Rng;
+use seen_messages::{SeenMessages, SharedSeenMessages};
+use staking::state::Staking;
 use std::{collections::HashSet, sync::Arc, time::Duration};
-use tokio::{net::TcpListener, time::sleep};
-use tracing::{error, info, trace, warn};
+use tokio::{net::TcpListener, task::JoinHandle, time::sleep};
+use tracing::{info, trace, warn};
+use validator_set::{SharedValidatorSet, ValidatorSet};
 
 mod fifo_filter;
 pub mod network;
 mod peer;
+mod seen_messages;
 pub mod stream;
+mod validator_set;
 
 #[derive(Debug, Clone)]
 pub enum P2PCommand {
@@ -31,6 +38,7 @@ impl BusMessage for P2PCommand {}
 module_bus_client! {
 struct P2PBusClient {
     receiver(P2PCommand),
+    receiver(ConsensusEvent),
 }
 }
 
@@ -41,6 +49,9 @@ pub struct P2P {
     crypto: SharedBlstCrypto,
     peer_id: u64,
     connected_peers: HashSet<String>,
+    outbound_tasks: Vec<JoinHandle<()>>,
+    seen_messages: SharedSeenMessages,
+    validator_set: SharedValidatorSet,
 }
 
 impl Module for P2P {
@@ -48,6 +59,24 @@ impl Module for P2P {
 
     async fn build(ctx: Self::Context) -> Result<Self> {
         let bus_client = P2PBusClient::new_from_bus(ctx.common.bus.new_handle()).await;
+        let p2p_conf = &ctx.common.config.p2p;
+        let seen_messages = SeenMessages::shared(
+            p2p_conf.seen_messages_capacity,
+            Duration::from_secs(p2p_conf.seen_messages_ttl),
+        );
+        let validator_set = ValidatorSet::shared(p2p_conf.peer_allowlist.clone());
+        // Seeded with the stakers known before a restart, so that it never falls back to
+        // accepting any key once the chain has started
+        if let Some(staking) = Self::load_from_disk::<Staking>(
+            ctx.common
+                .config
+                .data_directory
+                .join("p2p_validator_set.bin")
+                .as_path(),
+        )? {
+            #[allow(clippy::expect_used, reason = "not held across await")]
+            validator_set.write().expect("logic issue").update(staking);
+        }
         Ok(P2P {
             config: ctx.common.config.clone(),
             bus: ctx.common.bus.new_handle(),
@@ -55,6 +84,9 @@ impl Module for P2P {
             crypto: ctx.node.crypto.clone(),
             peer_id: 1u64,
             connected_peers: HashSet::default(),
+            outbound_tasks: Vec::new(),
+            seen_messages,
+            validator_set,
         })
     }
 
@@ -72,50 +104,27 @@ impl P2P {
         let config = self.config.clone();
         let bus = self.bus.new_handle();
         let crypto = self.crypto.clone();
+        let seen_messages = self.seen_messages.clone();
+        let validator_set = self.validator_set.clone();
         let id = self.peer_id;
         self.peer_id += 1;
         self.connected_peers.insert(peer_address.clone());
 
-        let _ = tokio::task::Builder::new()
+        if let Ok(task) = tokio::task::Builder::new()
             .name("connect-to-peer")
-            .spawn(async move {
-                let mut retry_count = 20;
-                while retry_count > 0 {
-                    info!("Connecting to peer #{}: {}", id, peer_address);
-                    match peer::Peer::connect(peer_address.as_str()).await {
-                        Ok(stream) => {
-                            let mut peer = peer::Peer::new(
-                                id,
-                                stream,
-                                bus.new_handle(),
-                                crypto.clone(),
-                                config.clone(),
-                            )
-                            .await;
-
-                            if let Err(e) = peer.handshake().await {
-                                warn!("Error in handshake: {}", e);
-                            }
-                            trace!("Handshake done !");
-                            match peer.start().await {
-                                Ok(_) => warn!("Peer #{} thread ended with success.", id),
-                                Err(_) => warn!(
-                                    "Peer #{}: {} disconnected ! Retry connection",
-                                    id, peer_address
-                                ),
-                            };
-                        }
-                        Err(e) => {
-                            warn!("Error while connecting to peer #{}: {}", id, e);
-                        }
-                    }
-
-                    retry_count -= 1;
-                    sleep(Duration::from_secs(2)).await;
-                }
-                error!("Can't reach peer #{}: {}.", id, peer_address);
-            })
-            .log_error("Failed to spawn peer thread");
+            .spawn(connect_to_peer(
+                id,
+                peer_address,
+                bus,
+                crypto,
+                config,
+                seen_messages,
+                validator_set,
+            ))
+            .log_error("Failed to spawn peer thread")
+        {
+            self.outbound_tasks.push(task);
+        }
     }
 
     fn handle_command(&mut self, cmd: P2PCommand) {
@@ -124,6 +133,24 @@ impl P2P {
         }
     }
 
+    fn handle_consensus_event(&mut self, event: ConsensusEvent) {
+        if let ConsensusEvent::CommitConsensusProposal(committed) = event {
+            _ = Self::save_on_disk(
+                self.config
+                    .data_directory
+                    .join("p2p_validator_set.bin")
+                    .as_path(),
+                &committed.staking,
+            )
+            .log_error("Saving the P2P validator set");
+            #[allow(clippy::expect_used, reason = "not held across await")]
+            self.validator_set
+                .write()
+                .expect("logic issue")
+                .update(committed.staking);
+        }
+    }
+
     pub async fn p2p_server(&mut self) -> Result<()> {
         // Wait all other threads to start correctly
         sleep(Duration::from_secs(1)).await;
@@ -137,6 +164,9 @@ impl P2P {
                 listen<P2PCommand> cmd => {
                      self.handle_command(cmd)
                 }
+                listen<ConsensusEvent> event => {
+                     self.handle_consensus_event(event)
+                }
             }
             // unreachable!();
         }
@@ -161,12 +191,18 @@ impl P2P {
                  self.handle_command(cmd)
             }
 
+            listen<ConsensusEvent> event => {
+                 self.handle_consensus_event(event)
+            }
+
             res = listener.accept() => {
                 let (socket, _) = res.context("Accepting connection in P2P server")?;
 
                 let conf = Arc::clone(&self.config);
                 let bus = self.bus.new_handle();
                 let crypto = self.crypto.clone();
+                let seen_messages = self.seen_messages.clone();
+                let validator_set = self.validator_set.clone();
                 let id = self.peer_id;
                 self.peer_id += 1;
                 tokio::task::Builder::new()
@@ -180,7 +216,7 @@ impl P2P {
                                 .map(|a| a.to_string())
                                 .unwrap_or("no address".to_string())
                             );
-                        let mut peer_server = peer::Peer::new(id, socket, bus, crypto, conf).await;
+                        let mut peer_server = peer::Peer::new(id, socket, bus, crypto, conf, seen_messages, validator_set).await;
                         _ = peer_server.handshake().await;
                         trace!("Handshake done !");
                         match peer_server.start().await {
@@ -191,6 +227,149 @@ impl P2P {
                     })?;
             }
         };
+
+        for task in self.outbound_tasks.drain(..) {
+            task.abort();
+        }
+        Ok(())
+    }
+}
+
+/// Delay before the given reconnection attempt: the base delay doubled after each failed attempt,
+/// capped to the max delay, with a random jitter of up to half of it.
+fn reconnect_delay(conf: &P2pConf, attempt: u32) -> Duration {
+    let max = Duration::from_secs(conf.reconnect_max_delay);
+    let delay = Duration::from_secs(conf.reconnect_base_delay)
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(max);
+    delay.mul_f64(rand::rng().random_range(0.5..=1.0))
+}
+
+/// Keeps a connection open to a peer, reconnecting with an exponential backoff when it drops.
+/// The backoff is reset once a handshake succeeds.
+async fn connect_to_peer(
+    id: u64,
+    peer_address: String,
+    bus: SharedMessageBus,
+    crypto: SharedBlstCrypto,
+    config: SharedConf,
+    seen_messages: SharedSeenMessages,
+    validator_set: SharedValidatorSet,
+) {
+    let mut attempt = 0;
+    loop {
+        info!("Connecting to peer #{}: {}", id, peer_address);
+        match peer::Peer::connect(
+            peer_address.as_str(),
+            Duration::from_secs(config.p2p.connect_timeout),
+        )
+        .await
+        {
+            Ok(stream) => {
+                let mut peer = peer::Peer::new(
+                    id,
+                    stream,
+                    bus.new_handle(),
+                    crypto.clone(),
+                    config.clone(),
+                    seen_messages.clone(),
+                    validator_set.clone(),
+                )
+                .await;
+
+                match peer.handshake().await {
+                    Ok(_) => {
+                        trace!("Handshake done !");
+                        attempt = 0;
+                        match peer.start().await {
+                            Ok(_) => {
+                                warn!("Peer #{}: {} thread ended, reconnecting", id, peer_address)
+                            }
+                            Err(_) => warn!(
+                                "Peer #{}: {} disconnected ! Retry connection",
+                                id, peer_address
+                            ),
+                        };
+                    }
+                    Err(e) => warn!("Error in handshake with peer #{}: {}", id, e),
+                }
+            }
+            Err(e) => {
+                warn!("Error while connecting to peer #{}: {}", id, e);
+            }
+        }
+
+        let delay = reconnect_delay(&config.p2p, attempt);
+        attempt = attempt.saturating_add(1);
+        info!(
+            "Reconnecting to peer #{}: {} in {:?}",
+            id, peer_address, delay
+        );
+        sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{conf::Conf, crypto::BlstCrypto};
+
+    fn p2p_conf() -> P2pConf {
+        P2pConf {
+            ping_interval: 10,
+            connect_timeout: 1,
+            read_timeout: 60,
+            reconnect_base_delay: 1,
+            reconnect_max_delay: 4,
+            max_frame_length: 1024 * 1024,
+            ..P2pConf::default()
+        }
+    }
+
+    #[test]
+    fn test_reconnect_delay_is_capped() {
+        let conf = p2p_conf();
+        for (attempt, max_secs) in [(0, 1), (1, 2), (2, 4), (3, 4), (40, 4)] {
+            let delay = reconnect_delay(&conf, attempt);
+            let max = Duration::from_secs(max_secs);
+            assert!(delay <= max, "attempt {attempt}: {delay:?}");
+            assert!(delay >= max / 2, "attempt {attempt}: {delay:?}");
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_reconnects_when_peer_is_back() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+
+        let conf = Arc::new(Conf {
+            p2p: p2p_conf(),
+            ..Conf::default()
+        });
+        let task = tokio::spawn(connect_to_peer(
+            1,
+            addr.to_string(),
+            SharedMessageBus::default(),
+            Arc::new(BlstCrypto::new("node".into())?),
+            conf,
+            SeenMessages::shared(0, Duration::ZERO),
+        ));
+
+        // Kill the peer once connected
+        let (socket, _) = tokio::time::timeout(Duration::from_secs(5), listener.accept())
+            .await
+            .context("First connection")??;
+        drop(socket);
+        drop(listener);
+        sleep(Duration::from_secs(1)).await;
+
+        // The peer is back
+        let listener = TcpListener::bind(addr).await?;
+        tokio::time::timeout(Duration::from_secs(10), listener.accept())
+            .await
+            .context("Did not reconnect")??;
+
+        task.abort();
         Ok(())
     }
 }
@@ -1,20 +1,61 @@
 use crate::bus::BusMessage;
 use crate::mempool::MempoolNetMessage;
 use crate::model::ValidatorPublicKey;
-use anyhow::Context;
+use anyhow::{bail, Context};
 use bincode::{Decode, Encode};
-use hyle_model::{ConsensusNetMessage, SignedByValidator};
+use hyle_model::{ConsensusNetMessage, SignedByValidator, ValidatorSignature};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fmt::{self, Display};
 use strum_macros::IntoStaticStr;
 
+/// Version of the wire format, sent in the handshake. Bump it on breaking changes.
+pub const PROTOCOL_VERSION: u16 = 6;
+/// Oldest protocol version still spoken with peers.
+pub const MIN_SUPPORTED_PROTOCOL_VERSION: u16 = 6;
+/// First byte of a compressed frame, followed by the zstd compressed bincode payload.
+/// Bincode never starts a message with it, uncompressed frames are left as is.
+pub const COMPRESSED_FRAME_FLAG: u8 = 0xff;
+/// Prefix of the challenge signed by a peer, so that the signature can't be used for anything else.
+const HANDSHAKE_CHALLENGE_DOMAIN: &[u8] = b"hyle-p2p-handshake:";
+
+/// What a peer signs with its validator key to answer the challenge of the other side's hello.
+/// It binds the answered challenge to the signer's own challenge and to both keys, so that
+/// the answer can't be relayed to authenticate another connection.
+pub fn handshake_challenge_message(
+    answered_challenge: &[u8],
+    own_challenge: &[u8],
+    signer: &ValidatorPublicKey,
+    verifier: &ValidatorPublicKey,
+) -> Vec<u8> {
+    let mut msg = HANDSHAKE_CHALLENGE_DOMAIN.to_vec();
+    for part in [answered_challenge, own_challenge, &signer.0, &verifier.0] {
+        msg.extend_from_slice(&(part.len() as u32).to_le_bytes());
+        msg.extend_from_slice(part);
+    }
+    msg
+}
+
+pub fn check_protocol_version(version: u16) -> anyhow::Result<()> {
+    if !(MIN_SUPPORTED_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) {
+        bail!(
+            "Unsupported protocol version {}, supported versions are {} to {}",
+            version,
+            MIN_SUPPORTED_PROTOCOL_VERSION,
+            PROTOCOL_VERSION
+        );
+    }
+    Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Encode, Decode, Eq, PartialEq)]
 pub struct Hello {
     pub version: u16,
     pub validator_pubkey: ValidatorPublicKey,
     pub name: String,
     pub da_address: String,
+    /// Random nonce the other side must sign to prove it owns its validator key
+    pub challenge: Vec<u8>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,7 +127,8 @@ pub enum NetMessage {
 #[derive(Debug, Serialize, Deserialize, Clone, Encode, Decode, Eq, PartialEq)]
 pub enum HandshakeNetMessage {
     Hello(Hello),
-    Verack,
+    /// Signature of the challenge of the hello received
+    Verack(ValidatorSignature),
     Ping,
     Pong,
 }
@@ -114,4 +156,28 @@ impl NetMessage {
         bincode::encode_to_vec(self, bincode::config::standard())
             .context("Could not serialize NetMessage")
     }
+
+    /// Same as `to_binary`, but compresses payloads larger than `threshold` bytes.
+    pub fn to_compressed_binary(&self, threshold: usize, level: i32) -> anyhow::Result<Vec<u8>> {
+        let binary = self.to_binary()?;
+        if binary.len() <= threshold {
+            return Ok(binary);
+        }
+        let compressed =
+            zstd::bulk::compress(&binary, level).context("Could not compress NetMessage")?;
+        Ok([vec![COMPRESSED_FRAME_FLAG], compressed].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_protocol_version() {
+        assert!(check_protocol_version(PROTOCOL_VERSION).is_ok());
+        assert!(check_protocol_version(MIN_SUPPORTED_PROTOCOL_VERSION).is_ok());
+        assert!(check_protocol_version(PROTOCOL_VERSION + 1).is_err());
+        assert!(check_protocol_version(MIN_SUPPORTED_PROTOCOL_VERSION - 1).is_err());
+    }
 }
@@ -5,7 +5,7 @@ use anyhow::Context;
 use anyhow::{Error, Result};
 use tokio::net::TcpStream;
 use tokio::sync::mpsc;
-use tokio::time::sleep;
+use tokio::time::{sleep, sleep_until, Instant};
 use tokio_util::codec::Framed;
 use tokio_util::codec::LengthDelimitedCodec;
 use tracing::{info, trace, warn};
@@ -14,21 +14,27 @@ use super::fifo_filter::FifoFilter;
 use super::network::HandshakeNetMessage;
 use super::network::OutboundMessage;
 use super::network::PeerEvent;
-use super::network::{Hello, NetMessage};
-use super::stream::send_net_message;
+use super::network::{
+    check_protocol_version, handshake_challenge_message, Hello, NetMessage, PROTOCOL_VERSION,
+};
+use super::seen_messages::SharedSeenMessages;
+use super::stream::{framed, send_net_message, send_net_message_compressed};
+use super::validator_set::SharedValidatorSet;
 use crate::bus::bus_client;
 use crate::bus::BusClientSender;
 use crate::bus::SharedMessageBus;
 use crate::mempool::MempoolNetMessage;
 use crate::model::ConsensusNetMessage;
+use crate::model::Signed;
 use crate::model::SignedByValidator;
 use crate::model::ValidatorPublicKey;
 use crate::module_handle_messages;
 use crate::p2p::stream::read_stream;
 use crate::utils::conf::SharedConf;
-use crate::utils::crypto::SharedBlstCrypto;
+use crate::utils::crypto::{BlstCrypto, SharedBlstCrypto};
 use crate::utils::logger::LogMe;
 use crate::utils::modules::signal::ShutdownModule;
+use crate::utils::rate_limiter::TokenBucket;
 
 bus_client! {
 struct PeerBusClient {
@@ -45,12 +51,25 @@ pub struct Peer {
     stream: Framed<TcpStream, LengthDelimitedCodec>,
     bus: PeerBusClient,
     last_pong: SystemTime,
+    last_read: Instant,
     conf: SharedConf,
     fifo_filter: FifoFilter<Vec<u8>>,
-    self_pubkey: ValidatorPublicKey,
+    seen_messages: SharedSeenMessages,
+    validator_set: SharedValidatorSet,
+    crypto: SharedBlstCrypto,
+    /// Sent in our hello, the peer signs it to authenticate
+    challenge: Vec<u8>,
+    /// Sent in the peer's hello, signed in our verack
+    peer_challenge: Option<Vec<u8>>,
+    /// Whether the peer proved it owns `peer_pubkey`
+    authenticated: bool,
     peer_pubkey: Option<ValidatorPublicKey>,
     peer_name: Option<String>,
     peer_da_address: Option<String>,
+    peer_version: Option<u16>,
+    mempool_rate_limiter: TokenBucket,
+    /// Mempool messages dropped for exceeding the rate limit
+    dropped_mempool_messages: u64,
 
     // peer internal channel
     internal_cmd_tx: mpsc::Sender<Cmd>,
@@ -68,27 +87,39 @@ impl Peer {
         bus: SharedMessageBus,
         crypto: SharedBlstCrypto,
         conf: SharedConf,
+        seen_messages: SharedSeenMessages,
+        validator_set: SharedValidatorSet,
     ) -> Self {
         let (cmd_tx, cmd_rx) = mpsc::channel::<Cmd>(100);
         let fifo_filter = FifoFilter::new(1000);
-        let self_validator = crypto.validator_pubkey().clone();
-        let mut codec = LengthDelimitedCodec::new();
-        codec.set_max_frame_length(1024 * 1024 * 1024); // Set max frame length to 1 GB
-        let framed = Framed::new(stream, codec);
+        let framed = framed(stream, conf.p2p.max_frame_length);
+        let mempool_rate_limiter = TokenBucket::new(
+            conf.p2p.mempool_messages_per_second,
+            conf.p2p.mempool_messages_burst,
+        );
 
         Peer {
             id,
             stream: framed,
             bus: PeerBusClient::new_from_bus(bus).await,
             last_pong: SystemTime::now(),
+            last_read: Instant::now(),
             conf,
             fifo_filter,
-            self_pubkey: self_validator,
+            seen_messages,
+            validator_set,
+            crypto,
+            challenge: rand::random::<[u8; 32]>().to_vec(),
+            peer_challenge: None,
+            authenticated: false,
             peer_pubkey: None,
             internal_cmd_tx: cmd_tx,
             internal_cmd_rx: cmd_rx,
             peer_name: None,
             peer_da_address: None,
+            peer_version: None,
+            mempool_rate_limiter,
+            dropped_mempool_messages: 0,
         }
     }
 
@@ -97,22 +128,44 @@ impl Peer {
         validator_id: ValidatorPublicKey,
         msg: NetMessage,
     ) -> Result<()> {
-        if let Some(peer_validator) = &self.peer_pubkey {
-            if *peer_validator == validator_id {
-                return send_net_message(&mut self.stream, msg).await;
+        match &self.peer_pubkey {
+            Some(peer_validator) if self.authenticated => {
+                if *peer_validator == validator_id {
+                    return self.send(msg).await;
+                }
             }
-        } else {
-            warn!("Peer validator not set. Ignoring message");
+            _ => warn!("Peer validator not authenticated. Ignoring message"),
         }
         Ok(())
     }
 
+    /// Sends a message, compressed if it's large and the peer completed its handshake.
+    /// Every supported protocol version reads compressed frames.
+    async fn send(&mut self, msg: NetMessage) -> Result<()> {
+        match self.conf.p2p.compression_threshold {
+            Some(threshold) if self.peer_version.is_some() => {
+                send_net_message_compressed(
+                    &mut self.stream,
+                    msg,
+                    threshold,
+                    self.conf.p2p.compression_level,
+                )
+                .await
+            }
+            _ => send_net_message(&mut self.stream, msg).await,
+        }
+    }
+
     async fn handle_broadcast_message(&mut self, msg: NetMessage) -> Result<()> {
+        if !self.authenticated {
+            trace!("Peer #{} not authenticated yet, not broadcasting", self.id);
+            return Ok(());
+        }
         let binary = msg.to_binary()?;
         if !self.fifo_filter.check(&binary) {
             self.fifo_filter.set(binary);
             trace!("Broadcast message to #{}: {}", self.id, msg);
-            send_net_message(&mut self.stream, msg).await
+            self.send(msg).await
         } else {
             trace!("Message to #{} already broadcasted", self.id);
             Ok(())
@@ -123,13 +176,26 @@ impl Peer {
         match msg {
             HandshakeNetMessage::Hello(v) => {
                 info!("👋 Got peer hello message {:?}", v);
+                let signed = self.crypto.sign(handshake_challenge_message(
+                    &v.challenge,
+                    &self.challenge,
+                    self.crypto.validator_pubkey(),
+                    &v.validator_pubkey,
+                ))?;
                 self.peer_pubkey = Some(v.validator_pubkey);
                 self.peer_name = Some(v.name);
                 self.peer_da_address = Some(v.da_address);
-                send_net_message(&mut self.stream, HandshakeNetMessage::Verack.into()).await
+                self.peer_version = Some(v.version);
+                self.peer_challenge = Some(v.challenge);
+                send_net_message(
+                    &mut self.stream,
+                    HandshakeNetMessage::Verack(signed.signature).into(),
+                )
+                .await
             }
-            HandshakeNetMessage::Verack => {
+            HandshakeNetMessage::Verack(_) => {
                 trace!("Got peer verack message");
+                self.authenticated = true;
                 if let Some(pubkey) = &self.peer_pubkey {
                     self.bus.send(PeerEvent::NewPeer {
                         name: self.peer_name.clone().unwrap_or("unknown".to_string()),
@@ -160,6 +226,30 @@ impl Peer {
                 trace!("Received new handshake net message {:?}", handshake_msg);
                 self.handle_handshake_message(handshake_msg).await?;
             }
+            _ if !self.authenticated => {
+                warn!(
+                    "Ignoring message from peer #{}, not authenticated yet: {}",
+                    self.id, msg
+                );
+            }
+            // Checked before marking the message as seen, so that a copy received from
+            // another peer is still forwarded when this one is dropped.
+            NetMessage::MempoolMessage(_) if !self.mempool_rate_limiter.try_acquire() => {
+                self.dropped_mempool_messages += 1;
+                // Logs the 1st, 2nd, 4th... drop to not flood the logs
+                if self.dropped_mempool_messages.is_power_of_two() {
+                    warn!(
+                        "Peer #{} is over the mempool rate limit, {} messages dropped",
+                        self.id, self.dropped_mempool_messages
+                    );
+                }
+            }
+            _ if !self.first_seen(&msg)? => {
+                trace!(
+                    "Message from #{} already received from another peer",
+                    self.id
+                );
+            }
             NetMessage::MempoolMessage(mempool_msg) => {
                 trace!("Received new mempool net message {}", mempool_msg);
                 self.bus
@@ -176,6 +266,17 @@ impl Peer {
         Ok(())
     }
 
+    /// Whether the message wasn't already received from another peer recently
+    fn first_seen(&self, msg: &NetMessage) -> Result<bool> {
+        let payload = msg.to_binary()?;
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        Ok(self
+            .seen_messages
+            .lock()
+            .expect("logic issue")
+            .should_forward(self.id, &payload))
+    }
+
     fn ping_pong(&self) {
         let tx = self.internal_cmd_tx.clone();
         let interval = self.conf.p2p.ping_interval;
@@ -186,7 +287,10 @@ impl Peer {
             .spawn(async move {
                 loop {
                     sleep(Duration::from_secs(interval)).await;
-                    let _ = tx.send(Cmd::Ping).await;
+                    // The peer is gone
+                    if tx.send(Cmd::Ping).await.is_err() {
+                        break;
+                    }
                 }
             });
     }
@@ -219,12 +323,25 @@ impl Peer {
 
             res = read_stream(&mut self.stream) => {
                 let message = res.log_warn("Reading tcp stream")?;
+                self.last_read = Instant::now();
+
+                if let NetMessage::HandshakeMessage(handshake_msg) = &message {
+                    if let Err(e) = self.check_handshake(handshake_msg) {
+                        warn!("Disconnecting peer #{}: {:#}", self.id, e);
+                        return Err(e);
+                    }
+                }
 
                 _ = self.handle_peer_stream_message(message)
                     .await
                     .log_warn("Handling peer stream message");
             },
 
+            _ = sleep_until(self.last_read + Duration::from_secs(self.conf.p2p.read_timeout)) => {
+                warn!("Peer #{} sent nothing for {}s. Disconnecting.", self.id, self.conf.p2p.read_timeout);
+                return Ok(())
+            }
+
             res =  self.internal_cmd_rx.recv() => {
                 if let Some(cmd) = res {
                     let cmd_res = match cmd {
@@ -247,9 +364,66 @@ impl Peer {
         Ok(())
     }
 
-    pub async fn connect(addr: &str) -> Result<TcpStream> {
-        let conn = TcpStream::connect(addr)
+    /// Checks the handshake messages a peer must be disconnected for: a second hello, an
+    /// unsupported protocol version, a key that's not a known validator, or a wrong challenge
+    /// signature.
+    fn check_handshake(&self, msg: &HandshakeNetMessage) -> Result<()> {
+        match msg {
+            HandshakeNetMessage::Hello(hello) => {
+                // The key the peer is routed by can't change once announced
+                if self.peer_pubkey.is_some() || self.authenticated {
+                    anyhow::bail!("Got a second hello from {}", hello.name);
+                }
+                check_protocol_version(hello.version)?;
+                #[allow(clippy::expect_used, reason = "not held across await")]
+                let known = self
+                    .validator_set
+                    .read()
+                    .expect("logic issue")
+                    .contains(&hello.validator_pubkey);
+                if !known {
+                    anyhow::bail!(
+                        "{} is not a known validator ({})",
+                        hello.name,
+                        hello.validator_pubkey
+                    );
+                }
+            }
+            HandshakeNetMessage::Verack(signature) => {
+                let (Some(peer_pubkey), Some(peer_challenge)) =
+                    (&self.peer_pubkey, &self.peer_challenge)
+                else {
+                    anyhow::bail!("Got a verack before the hello");
+                };
+                if &signature.validator != peer_pubkey {
+                    anyhow::bail!(
+                        "Challenge signed by {} instead of {}",
+                        signature.validator,
+                        peer_pubkey
+                    );
+                }
+                let signed = Signed {
+                    msg: handshake_challenge_message(
+                        &self.challenge,
+                        peer_challenge,
+                        peer_pubkey,
+                        self.crypto.validator_pubkey(),
+                    ),
+                    signature: signature.clone(),
+                };
+                if !BlstCrypto::verify(&signed)? {
+                    anyhow::bail!("Invalid challenge signature from {}", peer_pubkey);
+                }
+            }
+            HandshakeNetMessage::Ping | HandshakeNetMessage::Pong => {}
+        }
+        Ok(())
+    }
+
+    pub async fn connect(addr: &str, timeout: Duration) -> Result<TcpStream> {
+        let conn = tokio::time::timeout(timeout, TcpStream::connect(addr))
             .await
+            .context("Timed out connecting to peer")?
             .context("Connect to peer with TCP stream")?;
         info!("Connected to peer: {}", addr);
         Ok(conn)
@@ -259,13 +433,546 @@ impl Peer {
         send_net_message(
             &mut self.stream,
             HandshakeNetMessage::Hello(Hello {
-                version: 1,
-                validator_pubkey: self.self_pubkey.clone(),
+                version: PROTOCOL_VERSION,
+                validator_pubkey: self.crypto.validator_pubkey().clone(),
                 name: self.conf.id.clone(),
                 da_address: self.conf.da_address.clone(),
+                challenge: self.challenge.clone(),
             })
             .into(),
         )
         .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::bus::dont_use_this::get_receiver;
+    use crate::model::DataProposal;
+    use crate::p2p::seen_messages::SeenMessages;
+    use crate::p2p::validator_set::ValidatorSet;
+    use crate::utils::{
+        conf::{Conf, P2pConf},
+        crypto::BlstCrypto,
+    };
+    use staking::state::Staking;
+
+    #[test_log::test(tokio::test)]
+    async fn test_silent_peer_is_disconnected() -> Result<()> {
+        // Accepts the connection, but never says hello
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?.to_string();
+        let silent_peer = tokio::spawn(async move { listener.accept().await });
+
+        let conf = Arc::new(Conf {
+            p2p: P2pConf {
+                ping_interval: 10,
+                connect_timeout: 1,
+                read_timeout: 1,
+                max_frame_length: 1024,
+                ..P2pConf::default()
+            },
+            ..Conf::default()
+        });
+        let stream = Peer::connect(&addr, Duration::from_secs(conf.p2p.connect_timeout)).await?;
+        let mut peer = Peer::new(
+            0,
+            stream,
+            SharedMessageBus::default(),
+            Arc::new(BlstCrypto::new("node".into())?),
+            conf,
+            SeenMessages::shared(0, Duration::ZERO),
+            ValidatorSet::shared(vec![]),
+        )
+        .await;
+        peer.handshake().await?;
+
+        tokio::time::timeout(Duration::from_secs(5), peer.start())
+            .await
+            .context("Silent peer was not disconnected")??;
+        assert!(peer.peer_pubkey.is_none());
+        drop(silent_peer);
+        Ok(())
+    }
+
+    fn test_p2p_conf() -> P2pConf {
+        P2pConf {
+            ping_interval: 10,
+            connect_timeout: 1,
+            read_timeout: 1,
+            max_frame_length: 1024 * 1024,
+            ..P2pConf::default()
+        }
+    }
+
+    /// Connects a Peer to a remote end driven by the test
+    async fn connect_peer(
+        validator_set: SharedValidatorSet,
+    ) -> Result<(Peer, Framed<TcpStream, LengthDelimitedCodec>)> {
+        connect_peer_with(
+            0,
+            test_p2p_conf(),
+            SharedMessageBus::default(),
+            SeenMessages::shared(0, Duration::ZERO),
+            validator_set,
+        )
+        .await
+    }
+
+    async fn connect_peer_on_bus(
+        id: u64,
+        p2p: P2pConf,
+        bus: SharedMessageBus,
+        seen_messages: SharedSeenMessages,
+    ) -> Result<(Peer, Framed<TcpStream, LengthDelimitedCodec>)> {
+        connect_peer_with(id, p2p, bus, seen_messages, ValidatorSet::shared(vec![])).await
+    }
+
+    async fn connect_peer_with(
+        id: u64,
+        p2p: P2pConf,
+        bus: SharedMessageBus,
+        seen_messages: SharedSeenMessages,
+        validator_set: SharedValidatorSet,
+    ) -> Result<(Peer, Framed<TcpStream, LengthDelimitedCodec>)> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?.to_string();
+        let conf = Arc::new(Conf {
+            p2p,
+            ..Conf::default()
+        });
+        let stream = Peer::connect(&addr, Duration::from_secs(conf.p2p.connect_timeout)).await?;
+        let (remote, _) = listener.accept().await?;
+        let peer = Peer::new(
+            id,
+            stream,
+            bus,
+            Arc::new(BlstCrypto::new("node".into())?),
+            conf,
+            seen_messages,
+            validator_set,
+        )
+        .await;
+        Ok((peer, framed(remote, 1024 * 1024)))
+    }
+
+    const REMOTE_CHALLENGE: [u8; 32] = [7; 32];
+
+    fn remote_hello(crypto: &BlstCrypto, version: u16) -> NetMessage {
+        HandshakeNetMessage::Hello(Hello {
+            version,
+            validator_pubkey: crypto.validator_pubkey().clone(),
+            name: "remote".into(),
+            da_address: "unknown".into(),
+            challenge: REMOTE_CHALLENGE.to_vec(),
+        })
+        .into()
+    }
+
+    fn node_pubkey() -> ValidatorPublicKey {
+        BlstCrypto::new("node".into())
+            .unwrap()
+            .validator_pubkey()
+            .clone()
+    }
+
+    /// Reads the hello of the peer and signs its challenge with the remote key
+    async fn remote_verack(
+        remote: &mut Framed<TcpStream, LengthDelimitedCodec>,
+        crypto: &BlstCrypto,
+    ) -> Result<NetMessage> {
+        let NetMessage::HandshakeMessage(HandshakeNetMessage::Hello(hello)) =
+            read_stream::<NetMessage>(remote).await?
+        else {
+            anyhow::bail!("Expected a hello from the peer");
+        };
+        let signed = crypto.sign(handshake_challenge_message(
+            &hello.challenge,
+            &REMOTE_CHALLENGE,
+            crypto.validator_pubkey(),
+            &hello.validator_pubkey,
+        ))?;
+        Ok(HandshakeNetMessage::Verack(signed.signature).into())
+    }
+
+    /// Checks the peer answered the remote challenge with the node key
+    fn assert_signed_remote_challenge(verack: NetMessage, peer: &Peer, remote: &BlstCrypto) {
+        let NetMessage::HandshakeMessage(HandshakeNetMessage::Verack(signature)) = verack else {
+            panic!("Expected a verack, got {:?}", verack);
+        };
+        assert_eq!(signature.validator, node_pubkey());
+        assert!(BlstCrypto::verify(&Signed {
+            msg: handshake_challenge_message(
+                &REMOTE_CHALLENGE,
+                &peer.challenge,
+                &node_pubkey(),
+                remote.validator_pubkey(),
+            ),
+            signature,
+        })
+        .unwrap());
+    }
+
+    /// A staking where only the given key has a stake
+    fn staking_of(crypto: &BlstCrypto) -> Staking {
+        let mut staking = Staking::new();
+        staking.stake("staker".into(), 100).unwrap();
+        staking
+            .delegate_to("staker".into(), crypto.validator_pubkey().clone())
+            .unwrap();
+        staking
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_peer_with_supported_version_is_accepted() -> Result<()> {
+        let remote_crypto = BlstCrypto::new("remote".into())?;
+        let (mut peer, mut remote) = connect_peer(ValidatorSet::shared(vec![])).await?;
+        peer.handshake().await?;
+        let verack = remote_verack(&mut remote, &remote_crypto).await?;
+        send_net_message(&mut remote, remote_hello(&remote_crypto, PROTOCOL_VERSION)).await?;
+        send_net_message(&mut remote, verack).await?;
+
+        let (res, verack) = tokio::join!(
+            tokio::time::timeout(Duration::from_secs(5), peer.start()),
+            read_stream::<NetMessage>(&mut remote)
+        );
+        // The peer is only disconnected for being silent afterwards
+        res.context("Peer was not disconnected")??;
+        assert_signed_remote_challenge(verack?, &peer, &remote_crypto);
+        assert_eq!(
+            peer.peer_pubkey.as_ref(),
+            Some(remote_crypto.validator_pubkey())
+        );
+        assert!(peer.authenticated);
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_second_hello_is_rejected() -> Result<()> {
+        let remote_crypto = BlstCrypto::new("remote".into())?;
+        let other_crypto = BlstCrypto::new("other".into())?;
+        let (mut peer, mut remote) = connect_peer(ValidatorSet::shared(vec![])).await?;
+        peer.handshake().await?;
+        let verack = remote_verack(&mut remote, &remote_crypto).await?;
+        send_net_message(&mut remote, remote_hello(&remote_crypto, PROTOCOL_VERSION)).await?;
+        send_net_message(&mut remote, verack).await?;
+        send_net_message(&mut remote, remote_hello(&other_crypto, PROTOCOL_VERSION)).await?;
+
+        let (res, verack) = tokio::join!(
+            tokio::time::timeout(Duration::from_secs(5), peer.start()),
+            read_stream::<NetMessage>(&mut remote)
+        );
+        assert!(res.context("Peer was not disconnected")?.is_err());
+        assert_signed_remote_challenge(verack?, &peer, &remote_crypto);
+        // Still routed by the key it proved it owns
+        assert_eq!(
+            peer.peer_pubkey.as_ref(),
+            Some(remote_crypto.validator_pubkey())
+        );
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_unresponsive_peer_is_disconnected_after_handshake() -> Result<()> {
+        let remote_crypto = BlstCrypto::new("remote".into())?;
+        let (mut peer, mut remote) = connect_peer(ValidatorSet::shared(vec![])).await?;
+        peer.handshake().await?;
+        let verack = remote_verack(&mut remote, &remote_crypto).await?;
+        send_net_message(&mut remote, remote_hello(&remote_crypto, PROTOCOL_VERSION)).await?;
+        send_net_message(&mut remote, verack).await?;
+        send_net_message(&mut remote, HandshakeNetMessage::Ping.into()).await?;
+
+        // The remote answers nothing after this
+        let (res, replies) = tokio::join!(
+            tokio::time::timeout(Duration::from_secs(5), peer.start()),
+            async {
+                let verack = read_stream::<NetMessage>(&mut remote).await?;
+                let pong = read_stream::<NetMessage>(&mut remote).await?;
+                anyhow::Ok((verack, pong))
+            }
+        );
+        res.context("Unresponsive peer was not disconnected")??;
+        let (verack, pong) = replies?;
+        assert_signed_remote_challenge(verack, &peer, &remote_crypto);
+        assert_eq!(pong, HandshakeNetMessage::Pong.into());
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_peer_with_unsupported_version_is_rejected() -> Result<()> {
+        let remote_crypto = BlstCrypto::new("remote".into())?;
+        let (mut peer, mut remote) = connect_peer(ValidatorSet::shared(vec![])).await?;
+        send_net_message(
+            &mut remote,
+            remote_hello(&remote_crypto, PROTOCOL_VERSION + 1),
+        )
+        .await?;
+
+        let res = tokio::time::timeout(Duration::from_secs(5), peer.start())
+            .await
+            .context("Peer was not disconnected")?;
+        assert!(res.is_err());
+        assert!(peer.peer_pubkey.is_none());
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_staked_peer_is_accepted() -> Result<()> {
+        let remote_crypto = BlstCrypto::new("remote".into())?;
+        let validator_set = ValidatorSet::shared(vec![]);
+        validator_set
+            .write()
+            .unwrap()
+            .update(staking_of(&remote_crypto));
+        let (mut peer, mut remote) = connect_peer(validator_set).await?;
+        peer.handshake().await?;
+        let verack = remote_verack(&mut remote, &remote_crypto).await?;
+        send_net_message(&mut remote, remote_hello(&remote_crypto, PROTOCOL_VERSION)).await?;
+        send_net_message(&mut remote, verack).await?;
+
+        let res = tokio::time::timeout(Duration::from_secs(5), peer.start())
+            .await
+            .context("Peer was not disconnected")?;
+        res?;
+        assert!(peer.authenticated);
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_peer_not_in_staking_is_rejected() -> Result<()> {
+        let remote_crypto = BlstCrypto::new("remote".into())?;
+        let staker = BlstCrypto::new("staker".into())?;
+        let validator_set = ValidatorSet::shared(vec![]);
+        validator_set.write().unwrap().update(staking_of(&staker));
+        let (mut peer, mut remote) = connect_peer(validator_set).await?;
+        send_net_message(&mut remote, remote_hello(&remote_crypto, PROTOCOL_VERSION)).await?;
+
+        let res = tokio::time::timeout(Duration::from_secs(5), peer.start())
+            .await
+            .context("Peer was not disconnected")?;
+        assert!(res.is_err());
+        assert!(peer.peer_pubkey.is_none());
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_peer_not_in_allowlist_is_rejected() -> Result<()> {
+        let remote_crypto = BlstCrypto::new("remote".into())?;
+        let allowed = BlstCrypto::new("allowed".into())?;
+        let (mut peer, mut remote) = connect_peer(ValidatorSet::shared(vec![allowed
+            .validator_pubkey()
+            .clone()]))
+        .await?;
+        send_net_message(&mut remote, remote_hello(&remote_crypto, PROTOCOL_VERSION)).await?;
+
+        let res = tokio::time::timeout(Duration::from_secs(5), peer.start())
+            .await
+            .context("Peer was not disconnected")?;
+        assert!(res.is_err());
+        assert!(peer.peer_pubkey.is_none());
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_peer_with_forged_verack_is_rejected() -> Result<()> {
+        let remote_crypto = BlstCrypto::new("remote".into())?;
+        let (mut peer, mut remote) = connect_peer(ValidatorSet::shared(vec![])).await?;
+        peer.handshake().await?;
+        // Claims the remote key, but can only sign some other challenge with it
+        let _ = read_stream::<NetMessage>(&mut remote).await?;
+        let forged = remote_crypto.sign(handshake_challenge_message(
+            &[0; 32],
+            &REMOTE_CHALLENGE,
+            remote_crypto.validator_pubkey(),
+            &node_pubkey(),
+        ))?;
+        send_net_message(&mut remote, remote_hello(&remote_crypto, PROTOCOL_VERSION)).await?;
+        send_net_message(
+            &mut remote,
+            HandshakeNetMessage::Verack(forged.signature).into(),
+        )
+        .await?;
+
+        let res = tokio::time::timeout(Duration::from_secs(5), peer.start())
+            .await
+            .context("Peer was not disconnected")?;
+        assert!(res.is_err());
+        assert!(!peer.authenticated);
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_verack_meant_for_another_node_is_rejected() -> Result<()> {
+        let remote_crypto = BlstCrypto::new("remote".into())?;
+        let other = BlstCrypto::new("other".into())?;
+        let (mut peer, mut remote) = connect_peer(ValidatorSet::shared(vec![])).await?;
+        peer.handshake().await?;
+        // The right challenge, but answered to another node, as a relay would
+        let NetMessage::HandshakeMessage(HandshakeNetMessage::Hello(hello)) =
+            read_stream::<NetMessage>(&mut remote).await?
+        else {
+            anyhow::bail!("Expected a hello from the peer");
+        };
+        let relayed = remote_crypto.sign(handshake_challenge_message(
+            &hello.challenge,
+            &REMOTE_CHALLENGE,
+            remote_crypto.validator_pubkey(),
+            other.validator_pubkey(),
+        ))?;
+        send_net_message(&mut remote, remote_hello(&remote_crypto, PROTOCOL_VERSION)).await?;
+        send_net_message(
+            &mut remote,
+            HandshakeNetMessage::Verack(relayed.signature).into(),
+        )
+        .await?;
+
+        let res = tokio::time::timeout(Duration::from_secs(5), peer.start())
+            .await
+            .context("Peer was not disconnected")?;
+        assert!(res.is_err());
+        assert!(!peer.authenticated);
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_mempool_burst_over_rate_limit_is_dropped() -> Result<()> {
+        let bus = SharedMessageBus::default();
+        let mut forwarded = get_receiver::<SignedByValidator<MempoolNetMessage>>(&bus).await;
+        let p2p = P2pConf {
+            mempool_messages_per_second: 1,
+            mempool_messages_burst: 3,
+            ..test_p2p_conf()
+        };
+        let remote_crypto = BlstCrypto::new("remote".into())?;
+        let (mut peer, mut remote) =
+            connect_peer_on_bus(0, p2p, bus, SeenMessages::shared(0, Duration::ZERO)).await?;
+        peer.handshake().await?;
+        let verack = remote_verack(&mut remote, &remote_crypto).await?;
+        send_net_message(&mut remote, remote_hello(&remote_crypto, PROTOCOL_VERSION)).await?;
+        send_net_message(&mut remote, verack).await?;
+        for _ in 0..10 {
+            let msg = remote_crypto.sign(MempoolNetMessage::SyncRequest(None, None))?;
+            send_net_message(&mut remote, NetMessage::MempoolMessage(msg)).await?;
+        }
+        // The peer still answers once over the limit
+        send_net_message(&mut remote, HandshakeNetMessage::Ping.into()).await?;
+
+        let (res, pong) = tokio::join!(
+            tokio::time::timeout(Duration::from_secs(5), peer.start()),
+            async {
+                let _verack = read_stream::<NetMessage>(&mut remote).await?;
+                read_stream::<NetMessage>(&mut remote).await
+            }
+        );
+        res.context("Peer was not disconnected")??;
+        assert_eq!(pong?, HandshakeNetMessage::Pong.into());
+        assert_eq!(peer.dropped_mempool_messages, 7);
+
+        let mut received = 0;
+        while forwarded.try_recv().is_ok() {
+            received += 1;
+        }
+        assert_eq!(received, 3);
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_message_from_two_peers_is_forwarded_once() -> Result<()> {
+        let bus = SharedMessageBus::default();
+        let mut forwarded = get_receiver::<SignedByValidator<MempoolNetMessage>>(&bus).await;
+        let seen_messages = SeenMessages::shared(100, Duration::from_secs(60));
+        // Both peers relay the same data proposal of a third validator
+        let author = BlstCrypto::new("author".into())?;
+        let data_proposal = author.sign(MempoolNetMessage::DataProposal(DataProposal {
+            id: 0,
+            parent_data_proposal_hash: None,
+            txs: vec![],
+        }))?;
+
+        let mut peers = vec![];
+        for id in [1, 2] {
+            let remote_crypto = BlstCrypto::new(format!("remote-{id}"))?;
+            let (mut peer, mut remote) =
+                connect_peer_on_bus(id, test_p2p_conf(), bus.new_handle(), seen_messages.clone())
+                    .await?;
+            peer.handshake().await?;
+            let verack = remote_verack(&mut remote, &remote_crypto).await?;
+            send_net_message(&mut remote, remote_hello(&remote_crypto, PROTOCOL_VERSION)).await?;
+            send_net_message(&mut remote, verack).await?;
+            send_net_message(
+                &mut remote,
+                NetMessage::MempoolMessage(data_proposal.clone()),
+            )
+            .await?;
+            peers.push((peer, remote));
+        }
+
+        for (mut peer, _remote) in peers {
+            tokio::time::timeout(Duration::from_secs(5), peer.start())
+                .await
+                .context("Peer was not disconnected")??;
+        }
+
+        assert_eq!(forwarded.try_recv()?, data_proposal);
+        assert!(forwarded.try_recv().is_err());
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_message_dropped_by_rate_limit_is_forwarded_from_another_peer() -> Result<()> {
+        let bus = SharedMessageBus::default();
+        let mut forwarded = get_receiver::<SignedByValidator<MempoolNetMessage>>(&bus).await;
+        let seen_messages = SeenMessages::shared(100, Duration::from_secs(60));
+        let author = BlstCrypto::new("author".into())?;
+        let sync_request = author.sign(MempoolNetMessage::SyncRequest(None, None))?;
+        let data_proposal = author.sign(MempoolNetMessage::DataProposal(DataProposal {
+            id: 0,
+            parent_data_proposal_hash: None,
+            txs: vec![],
+        }))?;
+
+        // Peer 1 uses its only token on the sync request, its data proposal is dropped
+        let limited = P2pConf {
+            mempool_messages_per_second: 1,
+            mempool_messages_burst: 1,
+            ..test_p2p_conf()
+        };
+        let mut peers = vec![];
+        for (id, p2p, msgs) in [
+            (
+                1,
+                limited,
+                vec![sync_request.clone(), data_proposal.clone()],
+            ),
+            (2, test_p2p_conf(), vec![data_proposal.clone()]),
+        ] {
+            let remote_crypto = BlstCrypto::new(format!("remote-{id}"))?;
+            let (mut peer, mut remote) =
+                connect_peer_on_bus(id, p2p, bus.new_handle(), seen_messages.clone()).await?;
+            peer.handshake().await?;
+            let verack = remote_verack(&mut remote, &remote_crypto).await?;
+            send_net_message(&mut remote, remote_hello(&remote_crypto, PROTOCOL_VERSION)).await?;
+            send_net_message(&mut remote, verack).await?;
+            for msg in msgs {
+                send_net_message(&mut remote, NetMessage::MempoolMessage(msg)).await?;
+            }
+            peers.push((peer, remote));
+        }
+
+        for (mut peer, _remote) in peers {
+            tokio::time::timeout(Duration::from_secs(5), peer.start())
+                .await
+                .context("Peer was not disconnected")??;
+        }
+
+        assert_eq!(forwarded.try_recv()?, sync_request);
+        assert_eq!(forwarded.try_recv()?, data_proposal);
+        assert!(forwarded.try_recv().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use sha3::{Digest, Sha3_256};
+
+pub type SharedSeenMessages = Arc<Mutex<SeenMessages>>;
+
+/// Hashes of the messages recently received from peers, shared by all the peers of a node,
+/// so that a message delivered by several peers is only forwarded once.
+/// Bounded in size, the oldest hashes are dropped first, and in time by the ttl.
+pub struct SeenMessages {
+    capacity: usize,
+    ttl: Duration,
+    /// Peer the message was first received from, and when
+    seen: HashMap<[u8; 32], (u64, Instant)>,
+    order: VecDeque<[u8; 32]>,
+}
+
+impl SeenMessages {
+    /// A capacity of 0 disables deduplication.
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn shared(capacity: usize, ttl: Duration) -> SharedSeenMessages {
+        Arc::new(Mutex::new(Self::new(capacity, ttl)))
+    }
+
+    /// Whether a message received from this peer should be forwarded.
+    /// Copies of a message sent by other peers within the ttl are not, but a peer
+    /// sending the same message again is retrying, its copies are still forwarded.
+    pub fn should_forward(&mut self, peer_id: u64, payload: &[u8]) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        let now = Instant::now();
+        self.drop_expired(now);
+
+        let hash: [u8; 32] = Sha3_256::digest(payload).into();
+        if let Some((first_peer_id, _)) = self.seen.get(&hash) {
+            return *first_peer_id == peer_id;
+        }
+        if self.seen.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.seen.insert(hash, (peer_id, now));
+        self.order.push_back(hash);
+        true
+    }
+
+    fn drop_expired(&mut self, now: Instant) {
+        while let Some(oldest) = self.order.front() {
+            if self
+                .seen
+                .get(oldest)
+                .is_some_and(|(_, at)| now.duration_since(*at) < self.ttl)
+            {
+                break;
+            }
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copies_from_other_peers_are_not_forwarded() {
+        let mut seen = SeenMessages::new(10, Duration::from_secs(60));
+
+        assert!(seen.should_forward(1, b"tx"));
+        assert!(!seen.should_forward(2, b"tx"));
+        // Retried by the same peer
+        assert!(seen.should_forward(1, b"tx"));
+        assert!(seen.should_forward(2, b"other tx"));
+    }
+
+    #[test]
+    fn test_oldest_messages_are_forgotten() {
+        let mut seen = SeenMessages::new(2, Duration::from_millis(100));
+
+        seen.should_forward(1, b"a");
+        seen.should_forward(1, b"b");
+        seen.should_forward(1, b"c");
+        // Beyond the capacity
+        assert!(seen.should_forward(2, b"a"));
+        assert!(!seen.should_forward(2, b"c"));
+
+        // Beyond the ttl
+        std::thread::sleep(Duration::from_millis(150));
+        assert!(seen.should_forward(2, b"c"));
+    }
+
+    #[test]
+    fn test_no_capacity_forwards_everything() {
+        let mut seen = SeenMessages::new(0, Duration::from_secs(60));
+
+        assert!(seen.should_forward(1, b"tx"));
+        assert!(seen.should_forward(2, b"tx"));
+    }
+}
@@ -4,7 +4,22 @@ use tokio::net::TcpStream;
 use tokio_util::codec::{Framed, LengthDelimitedCodec};
 use tracing::trace;
 
-use super::network::NetMessage;
+use super::network::{NetMessage, COMPRESSED_FRAME_FLAG};
+
+/// Frames a peer connection: each message is its bincode payload prefixed by its length,
+/// as a 4-byte big-endian integer. Frames longer than `max_frame_length` are rejected
+/// before being allocated.
+pub fn framed(
+    stream: TcpStream,
+    max_frame_length: usize,
+) -> Framed<TcpStream, LengthDelimitedCodec> {
+    let codec = LengthDelimitedCodec::builder()
+        .length_field_length(4)
+        .big_endian()
+        .max_frame_length(max_frame_length)
+        .new_codec();
+    Framed::new(stream, codec)
+}
 
 pub async fn read_stream<T: bincode::Decode>(
     stream: &mut Framed<TcpStream, LengthDelimitedCodec>,
@@ -12,11 +27,7 @@ pub async fn read_stream<T: bincode::Decode>(
     trace!("Waiting for data");
     if let Some(result) = stream.next().await {
         match result {
-            Ok(data) => {
-                let (msg, _) = bincode::decode_from_slice(&data, bincode::config::standard())
-                    .map_err(|_| anyhow::anyhow!("Could not decode message"))?;
-                Ok(msg)
-            }
+            Ok(data) => decode_frame(&data, stream.codec().max_frame_length()),
             Err(e) => Err(anyhow!(e).context("Error while reading message")),
         }
     } else {
@@ -24,6 +35,37 @@ pub async fn read_stream<T: bincode::Decode>(
     }
 }
 
+/// Decodes a frame, decompressing it first if it's flagged as compressed.
+/// The decompressed payload can't be larger than `max_len`.
+fn decode_frame<T: bincode::Decode>(data: &[u8], max_len: usize) -> Result<T, Error> {
+    let decompressed;
+    let payload = match data.split_first() {
+        Some((&COMPRESSED_FRAME_FLAG, compressed)) => {
+            decompressed = zstd::bulk::decompress(compressed, max_len)
+                .context("Could not decompress message")?;
+            decompressed.as_slice()
+        }
+        _ => data,
+    };
+    let (msg, _) = bincode::decode_from_slice(payload, bincode::config::standard())
+        .map_err(|_| anyhow::anyhow!("Could not decode message"))?;
+    Ok(msg)
+}
+
+pub async fn send_net_message_compressed(
+    stream: &mut Framed<TcpStream, LengthDelimitedCodec>,
+    msg: NetMessage,
+    threshold: usize,
+    level: i32,
+) -> Result<(), Error> {
+    stream
+        .send(msg.to_compressed_binary(threshold, level)?.into())
+        .await
+        .context("Failed to send NetMessage")?;
+
+    Ok(())
+}
+
 pub async fn send_net_message(
     stream: &mut Framed<TcpStream, LengthDelimitedCodec>,
     msg: NetMessage,
@@ -35,3 +77,98 @@ pub async fn send_net_message(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::{
+        io::AsyncWriteExt,
+        net::{TcpListener, TcpStream},
+    };
+
+    use super::*;
+    use crate::{
+        mempool::MempoolNetMessage,
+        model::{DataProposal, ProofData, ProofTransaction},
+        p2p::network::HandshakeNetMessage,
+        utils::crypto::BlstCrypto,
+    };
+
+    async fn connected_pair(
+        max_frame_length: usize,
+    ) -> anyhow::Result<(TcpStream, Framed<TcpStream, LengthDelimitedCodec>)> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let client = TcpStream::connect(listener.local_addr()?).await?;
+        let (server, _) = listener.accept().await?;
+        Ok((client, framed(server, max_frame_length)))
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_frame_split_over_several_reads() -> anyhow::Result<()> {
+        let (mut client, mut server) = connected_pair(1024).await?;
+
+        let payload = NetMessage::HandshakeMessage(HandshakeNetMessage::Ping).to_binary()?;
+        let mut frame = u32::try_from(payload.len())?.to_be_bytes().to_vec();
+        frame.extend(payload);
+
+        let writer = tokio::spawn(async move {
+            for byte in frame {
+                client.write_all(&[byte]).await?;
+                client.flush().await?;
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            anyhow::Ok(client)
+        });
+
+        let msg: NetMessage = read_stream(&mut server).await?;
+        assert_eq!(msg, NetMessage::HandshakeMessage(HandshakeNetMessage::Ping));
+        writer.await??;
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_compressed_frame_round_trip() -> anyhow::Result<()> {
+        let (client, mut server) = connected_pair(1024 * 1024).await?;
+        let mut client = framed(client, 1024 * 1024);
+
+        let crypto = BlstCrypto::new("node".into())?;
+        let large_tx = ProofTransaction {
+            contract_name: "c1".into(),
+            proof: ProofData(vec![42; 256 * 1024]),
+        };
+        let msg: NetMessage = crypto
+            .sign(MempoolNetMessage::DataProposal(DataProposal {
+                id: 0,
+                parent_data_proposal_hash: None,
+                txs: vec![large_tx.into()],
+            }))?
+            .into();
+
+        let compressed = msg.to_compressed_binary(4096, 3)?;
+        assert_eq!(compressed.first(), Some(&COMPRESSED_FRAME_FLAG));
+        assert!(compressed.len() < msg.to_binary()?.len());
+
+        // Small messages are sent as is
+        let ping = NetMessage::HandshakeMessage(HandshakeNetMessage::Ping);
+        assert_eq!(ping.to_compressed_binary(4096, 3)?, ping.to_binary()?);
+
+        send_net_message_compressed(&mut client, msg.clone(), 4096, 3).await?;
+        send_net_message_compressed(&mut client, ping.clone(), 4096, 3).await?;
+        assert_eq!(read_stream::<NetMessage>(&mut server).await?, msg);
+        assert_eq!(read_stream::<NetMessage>(&mut server).await?, ping);
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_oversized_frame_is_rejected() -> anyhow::Result<()> {
+        let (mut client, mut server) = connected_pair(1024).await?;
+
+        // Only the header is sent: the frame must be rejected from its announced length
+        client.write_all(&u32::MAX.to_be_bytes()).await?;
+        client.flush().await?;
+
+        assert!(read_stream::<NetMessage>(&mut server).await.is_err());
+        Ok(())
+    }
+}
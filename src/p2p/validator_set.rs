@@ -0,0 +1,87 @@
+use std::sync::{Arc, RwLock};
+
+use staking::state::Staking;
+
+use crate::model::ValidatorPublicKey;
+
+pub type SharedValidatorSet = Arc<RwLock<ValidatorSet>>;
+
+/// The validators a node accepts as peers: the stakers of the last committed consensus
+/// proposal, and the keys of the configured allowlist.
+/// Until a proposal is committed, and while the allowlist is empty, any key is accepted,
+/// as the genesis learns the keys of its validators from their handshakes. A restarted node
+/// is seeded with the staking it persisted, so this only ever applies to a fresh chain.
+pub struct ValidatorSet {
+    allowlist: Vec<ValidatorPublicKey>,
+    staking: Option<Staking>,
+}
+
+impl ValidatorSet {
+    pub fn new(allowlist: Vec<ValidatorPublicKey>) -> Self {
+        Self {
+            allowlist,
+            staking: None,
+        }
+    }
+
+    pub fn shared(allowlist: Vec<ValidatorPublicKey>) -> SharedValidatorSet {
+        Arc::new(RwLock::new(Self::new(allowlist)))
+    }
+
+    /// Called with the staking of each committed consensus proposal
+    pub fn update(&mut self, staking: Staking) {
+        self.staking = Some(staking);
+    }
+
+    pub fn contains(&self, pubkey: &ValidatorPublicKey) -> bool {
+        let staker = self
+            .staking
+            .as_ref()
+            .is_some_and(|s| s.is_bonded(pubkey) || s.get_stake(pubkey).is_some());
+        if staker || self.allowlist.contains(pubkey) {
+            return true;
+        }
+        self.staking.is_none() && self.allowlist.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> ValidatorPublicKey {
+        ValidatorPublicKey(name.as_bytes().to_vec())
+    }
+
+    fn staking_of(name: &str) -> Staking {
+        let mut staking = Staking::new();
+        staking.stake(name.into(), 100).unwrap();
+        staking.delegate_to(name.into(), key(name)).unwrap();
+        staking
+    }
+
+    #[test]
+    fn test_any_key_is_accepted_before_the_first_commit() {
+        let set = ValidatorSet::new(vec![]);
+        assert!(set.contains(&key("a")));
+    }
+
+    #[test]
+    fn test_only_stakers_are_accepted_after_a_commit() {
+        let mut set = ValidatorSet::new(vec![]);
+        set.update(staking_of("a"));
+        assert!(set.contains(&key("a")));
+        assert!(!set.contains(&key("b")));
+    }
+
+    #[test]
+    fn test_allowlist_is_accepted_along_stakers() {
+        let mut set = ValidatorSet::new(vec![key("b")]);
+        assert!(set.contains(&key("b")));
+        assert!(!set.contains(&key("a")));
+        set.update(staking_of("a"));
+        assert!(set.contains(&key("a")));
+        assert!(set.contains(&key("b")));
+        assert!(!set.contains(&key("c")));
+    }
+}
@@ -148,6 +148,7 @@ impl RestApi {
                     .context("Starting rest server")?,
                 #[allow(clippy::expect_used, reason="incorrect setup logic")]
                 self.app.take().expect("app is not set")
+                    .into_make_service_with_connect_info::<std::net::SocketAddr>()
             ) => { }
         };
 
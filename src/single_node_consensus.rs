@@ -61,11 +61,11 @@ impl Module for SingleNodeConsensus {
             .clone()
             .join("consensus_single_node.bin");
 
-        let store: SingleNodeConsensusStore = Self::load_from_disk_or_default(file.as_path());
+        let store: SingleNodeConsensusStore = Self::load_from_disk_or_default(file.as_path())?;
 
         let bus = SingleNodeConsensusBusClient::new_from_bus(ctx.common.bus.new_handle()).await;
 
-        let api = super::consensus::api::api(&ctx.common).await;
+        let api = super::consensus::api::api(&ctx.common).await?;
         if let Ok(mut guard) = ctx.common.router.lock() {
             if let Some(router) = guard.take() {
                 guard.replace(router.nest("/v1/consensus", api));
@@ -273,6 +273,9 @@ mod tests {
                     consensus_proposal,
                     ..
                 }) => consensus_proposal.cut,
+                ConsensusEvent::ValidatorEquivocation(_) => {
+                    panic!("{err}: unexpected equivocation")
+                }
             }
         }
     }
@@ -210,6 +210,7 @@ use crate::bus::command_response::Query;
 use crate::bus::dont_use_this::get_receiver;
 use crate::bus::metrics::BusMetrics;
 use crate::bus::{bus_client, SharedMessageBus};
+use crate::consensus::equivocation::Equivocation;
 use crate::consensus::test::ConsensusTestCtx;
 use crate::consensus::ConsensusEvent;
 use crate::handle_messages;
@@ -975,3 +976,126 @@ async fn autobahn_rejoin_flow() {
     // We are caught up
     assert!(!joining_node.consensus_ctx.is_joining());
 }
+
+#[test_log::test(tokio::test)]
+async fn autobahn_stalled_leader_times_out() {
+    let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4).await;
+    for node in [&mut node1, &mut node2, &mut node3, &mut node4] {
+        node.consensus_ctx.set_round_timeout(500);
+    }
+
+    // Slot 1 - leader = node1
+    node1.start_round_with_cut_from_mempool().await;
+    simple_commit_round! {
+        leader: node1.consensus_ctx,
+        followers: [node2.consensus_ctx, node3.consensus_ctx, node4.consensus_ctx]
+    };
+
+    // Slot 2 - leader = node2, which never broadcasts its Prepare
+    node3.consensus_ctx.timeout_tick().await;
+    node3
+        .consensus_ctx
+        .assert_no_broadcast("Timeout before the round timeout");
+
+    tokio::time::sleep(std::time::Duration::from_millis(600)).await;
+    node3.consensus_ctx.timeout_tick().await;
+    node4.consensus_ctx.timeout_tick().await;
+
+    broadcast! {
+        description: "Follower - Timeout",
+        from: node3.consensus_ctx, to: [node1.consensus_ctx, node4.consensus_ctx],
+        message_matches: ConsensusNetMessage::Timeout(2, 0)
+    };
+    // node1 joins the mutiny
+    broadcast! {
+        description: "Follower - Timeout",
+        from: node4.consensus_ctx, to: [node1.consensus_ctx, node3.consensus_ctx],
+        message_matches: ConsensusNetMessage::Timeout(2, 0)
+    };
+    broadcast! {
+        description: "Follower - Timeout",
+        from: node1.consensus_ctx, to: [node3.consensus_ctx, node4.consensus_ctx],
+        message_matches: ConsensusNetMessage::Timeout(2, 0)
+    };
+
+    // node3 is the next leader, it uses the timeout certificate for its Prepare
+    node3
+        .consensus_ctx
+        .assert_no_broadcast("Timeout Certificate 3");
+    node1
+        .consensus_ctx
+        .assert_broadcast("Timeout Certificate 1");
+    node4
+        .consensus_ctx
+        .assert_broadcast("Timeout Certificate 4");
+
+    // Slot 2 view 1 - leader = node3
+    node3.start_round_with_cut_from_mempool().await;
+    let (cp, ticket) = simple_commit_round! {
+        leader: node3.consensus_ctx,
+        followers: [node1.consensus_ctx, node2.consensus_ctx, node4.consensus_ctx]
+    };
+
+    assert!(matches!(ticket, Ticket::TimeoutQC(_)));
+    assert_eq!(cp.slot, 2);
+    assert_eq!(cp.view, 1);
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_conflicting_votes_are_detected() {
+    let (mut node1, mut node2, mut node3) = build_nodes!(3).await;
+
+    node1.start_round_with_cut_from_mempool().await;
+    let prepare = broadcast! {
+        description: "Prepare",
+        from: node1.consensus_ctx, to: [node2.consensus_ctx, node3.consensus_ctx],
+        message_matches: ConsensusNetMessage::Prepare(_, _)
+    };
+    let ConsensusNetMessage::Prepare(cp, ticket) = prepare.msg.clone() else {
+        panic!("Expected a Prepare");
+    };
+
+    // node1 also proposes another block for the same round
+    let conflicting_cp = ConsensusProposal {
+        timestamp: cp.timestamp + 1,
+        ..cp.clone()
+    };
+    let conflicting_prepare = node1
+        .consensus_ctx
+        .sign(ConsensusNetMessage::Prepare(conflicting_cp.clone(), ticket));
+    node3.consensus_ctx.handle_msg_err(&conflicting_prepare);
+
+    // node2 votes for both proposals
+    let vote = node2
+        .consensus_ctx
+        .sign(ConsensusNetMessage::PrepareVote(cp.hash()));
+    let conflicting_vote = node2
+        .consensus_ctx
+        .sign(ConsensusNetMessage::PrepareVote(conflicting_cp.hash()));
+    node3.consensus_ctx.handle_msg_err(&vote);
+    assert!(node3
+        .consensus_ctx
+        .handle_msg_err(&conflicting_vote)
+        .to_string()
+        .contains("equivocated"));
+
+    let equivocations: Vec<Equivocation> =
+        std::iter::from_fn(|| node3.consensus_ctx._event_receiver.try_recv().ok())
+            .filter_map(|event| match event {
+                ConsensusEvent::ValidatorEquivocation(equivocation) => Some(equivocation),
+                _ => None,
+            })
+            .collect();
+    assert_eq!(equivocations.len(), 2);
+    let leader_equivocation = equivocations.first().unwrap();
+    assert_eq!(leader_equivocation.validator, node1.consensus_ctx.pubkey());
+    assert_eq!(leader_equivocation.second, conflicting_prepare);
+
+    // The two conflicting votes are kept as evidence
+    let vote_equivocation = equivocations.get(1).unwrap();
+    assert_eq!(vote_equivocation.validator, node2.consensus_ctx.pubkey());
+    assert_eq!(vote_equivocation.slot, cp.slot);
+    assert_eq!(vote_equivocation.view, cp.view);
+    assert_eq!(vote_equivocation.first, vote);
+    assert_eq!(vote_equivocation.second, conflicting_vote);
+}
@@ -1,7 +1,10 @@
+use std::time::{Duration, Instant};
+
 use client_sdk::rest_client::{IndexerApiHttpClient, NodeApiHttpClient};
 use hyle_model::{
-    api::APIRegisterContract, BlobTransaction, ContractAction, ContractName, Hashable, ProgramId,
-    ProofData, ProofTransaction, RegisterContractAction, RegisterContractEffect, StateDigest,
+    api::APIRegisterContract, BlobEncoding, BlobTransaction, ContractAction, ContractName,
+    Hashable, ProgramId, ProofData, ProofTransaction, RegisterContractAction,
+    RegisterContractEffect, StateDigest,
 };
 use testcontainers_modules::{
     postgres::Postgres,
@@ -30,8 +33,13 @@ fn make_register_blob_action(
             program_id: ProgramId(vec![1, 2, 3]),
             state_digest,
             contract_name,
+            blob_encoding: BlobEncoding::default(),
         }
         .as_blob("hyle".into(), None, None)],
+        dependencies: vec![],
+        additional_identities: vec![],
+        nonce: None,
+        fee: 0,
     }
 }
 
@@ -67,6 +75,7 @@ async fn test_full_settlement_flow() -> Result<()> {
             program_id: ProgramId(vec![1, 2, 3]),
             state_digest: StateDigest(vec![7, 7, 7]),
             contract_name: "c2.hyle".into(),
+            blob_encoding: BlobEncoding::default(),
         })
         .await
         .unwrap();
@@ -84,6 +93,10 @@ async fn test_full_settlement_flow() -> Result<()> {
                 data: BlobData(vec![0, 1, 2, 3]),
             },
         ],
+        dependencies: vec![],
+        additional_identities: vec![],
+        nonce: None,
+        fee: 0,
     };
     client.send_tx_blob(&tx).await.unwrap();
 
@@ -172,6 +185,10 @@ async fn test_contract_upgrade() -> Result<()> {
             contract_name: "c1.hyle".into(),
             data: BlobData(vec![1]),
         }],
+        dependencies: vec![],
+        additional_identities: vec![],
+        nonce: None,
+        fee: 0,
     };
     client.send_tx_blob(&b2).await.unwrap();
 
@@ -212,3 +229,92 @@ async fn test_contract_upgrade() -> Result<()> {
 
     Ok(())
 }
+
+#[test_log::test(tokio::test)]
+async fn test_register_contract_idempotent() -> Result<()> {
+    let builder = NodeIntegrationCtxBuilder::new().await;
+    let rest_url = builder.conf.rest.clone();
+    let mut hyle_node = builder.build().await?;
+
+    hyle_node.wait_for_genesis_event().await?;
+
+    let client = NodeApiHttpClient::new(format!("http://{rest_url}/")).unwrap();
+    hyle_node.wait_for_rest_api(&client).await?;
+
+    let register = APIRegisterContract {
+        verifier: "test".into(),
+        program_id: ProgramId(vec![1, 2, 3]),
+        state_digest: StateDigest(vec![7, 7, 7]),
+        contract_name: "c1.hyle".into(),
+        blob_encoding: BlobEncoding::default(),
+    };
+
+    // Unknown contracts are reported as absent, not as a server error
+    let status = reqwest::get(format!("http://{rest_url}/v1/contract/c1.hyle"))
+        .await?
+        .status();
+    assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+
+    info!("➡️  Registering contract c1.hyle");
+    let tx_hash = client
+        .register_contract_idempotent(&register)
+        .await?
+        .expect("contract should get registered");
+
+    hyle_node.wait_for_settled_tx(tx_hash).await?;
+    // Wait a block on top to make sure the state is updated.
+    hyle_node.wait_for_n_blocks(1).await?;
+
+    info!("➡️  Registering contract c1.hyle again");
+    assert_eq!(client.register_contract_idempotent(&register).await?, None);
+
+    let contract = client.get_contract(&"c1.hyle".into()).await?;
+    assert_eq!(contract.state.0, vec![7, 7, 7]);
+
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_register_contract_and_wait() -> Result<()> {
+    let builder = NodeIntegrationCtxBuilder::new().await;
+    let rest_url = builder.conf.rest.clone();
+    let mut hyle_node = builder.build().await?;
+
+    hyle_node.wait_for_genesis_event().await?;
+
+    let client = NodeApiHttpClient::new(format!("http://{rest_url}/")).unwrap();
+    hyle_node.wait_for_rest_api(&client).await?;
+
+    let register = |contract_name: &str| APIRegisterContract {
+        verifier: "test".into(),
+        program_id: ProgramId(vec![1, 2, 3]),
+        state_digest: StateDigest(vec![7, 7, 7]),
+        contract_name: contract_name.into(),
+        blob_encoding: BlobEncoding::default(),
+    };
+
+    info!("➡️  Registering contract c1.hyle");
+    let timeout = Duration::from_secs(30);
+    let started = Instant::now();
+    let contract = client
+        .register_contract_and_wait(&register("c1.hyle"), timeout, Duration::from_millis(50))
+        .await?;
+    assert_eq!(contract.name, ContractName::new("c1.hyle"));
+    assert_eq!(contract.state.0, vec![7, 7, 7]);
+    assert!(started.elapsed() < timeout);
+
+    info!("➡️  Registering contract c2.hyle without waiting for a block");
+    let err = client
+        .register_contract_and_wait(
+            &register("c2.hyle"),
+            Duration::from_millis(1),
+            Duration::from_millis(50),
+        )
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Timeout waiting for contract c2.hyle"));
+
+    Ok(())
+}
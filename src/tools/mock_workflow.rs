@@ -13,7 +13,7 @@ use crate::{
 use anyhow::Result;
 use client_sdk::rest_client::NodeApiHttpClient;
 use hyle_contract_sdk::{Identity, ProgramId, StateDigest};
-use hyle_model::{ContractAction, RegisterContractAction};
+use hyle_model::{BlobEncoding, ContractAction, RegisterContractAction};
 use serde::{Deserialize, Serialize};
 use tokio::time::sleep;
 use tracing::{error, info, warn};
@@ -143,6 +143,10 @@ impl MockWorkflowHandler {
                     contract_name: ContractName::new("test"),
                     data: BlobData(vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9]),
                 }],
+                dependencies: vec![],
+                additional_identities: vec![],
+                nonce: None,
+                fee: 0,
             }),
         });
         for _ in 0..500000 {
@@ -161,6 +165,10 @@ impl MockWorkflowHandler {
                 contract_name: ContractName::new("contract_name"),
                 data: BlobData(vec![0, 1, 2]),
             }],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         let tx_register_blob = BlobTransaction {
             identity: Identity::new("id"),
@@ -169,8 +177,13 @@ impl MockWorkflowHandler {
                 program_id: ProgramId(vec![]),
                 state_digest: StateDigest(vec![]),
                 contract_name: ContractName::new("contract"),
+                blob_encoding: BlobEncoding::default(),
             }
             .as_blob("hyle".into(), None, None)],
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
 
         let tx_proof = ProofTransaction::default();
@@ -1,7 +1,12 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use config::{Config, Environment, File};
+use hyle_verifiers::NoirVerifier;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fmt::Debug, path::PathBuf, sync::Arc};
+use std::{collections::HashMap, fmt::Debug, path::PathBuf, sync::Arc, time::Duration};
+
+use crate::genesis::GenesisSpec;
+use crate::mempool::verifiers::VerifierBackends;
+use crate::model::{ContractName, ValidatorPublicKey};
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct Storage {
@@ -11,12 +16,32 @@ pub struct Storage {
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct Consensus {
     pub slot_duration: u64,
+    pub round_timeout: u64,
     pub genesis_stakers: HashMap<String, u64>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct VerificationAuditConf {
+    pub enabled: bool,
+    pub max_file_size: u64,
+    pub max_files: usize,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct P2pConf {
     pub ping_interval: u64,
+    pub connect_timeout: u64,
+    pub read_timeout: u64,
+    pub reconnect_base_delay: u64,
+    pub reconnect_max_delay: u64,
+    pub max_frame_length: usize,
+    pub compression_threshold: Option<usize>,
+    pub compression_level: i32,
+    pub peer_allowlist: Vec<ValidatorPublicKey>,
+    pub mempool_messages_per_second: u32,
+    pub mempool_messages_burst: u32,
+    pub seen_messages_capacity: usize,
+    pub seen_messages_ttl: u64,
 }
 pub type SharedConf = Arc<Conf>;
 
@@ -39,6 +64,23 @@ pub struct Conf {
     pub tcp_server_address: Option<String>,
     pub log_format: String,
     pub single_node: Option<bool>,
+    pub verifiers: VerifierBackends,
+    pub blob_tx_allowlist: Vec<ValidatorPublicKey>,
+    pub paused_contracts: Vec<ContractName>,
+    pub verification_audit: VerificationAuditConf,
+    pub noir_verification_timeout: u64,
+    pub noir_verifier_path: Option<PathBuf>,
+    pub max_supported_tx_version: u32,
+    pub verify_api_max_proof_size: usize,
+    pub verify_api_rate_limit: u32,
+    pub indexer_reconciliation_interval: u64,
+    pub max_proof_age: u64,
+    pub max_clock_skew: u64,
+    pub pending_tx_ttl: u64,
+    pub max_seen_txs: usize,
+    pub indexer_checkpoint_interval: u64,
+    pub genesis_file: Option<PathBuf>,
+    pub max_tx_statuses: usize,
 }
 
 impl Conf {
@@ -68,6 +110,7 @@ impl Conf {
             .set_override_option("run_indexer", run_indexer)?
             .build()?
             .try_deserialize()?;
+        conf.apply_genesis_file()?;
         if let Some(true) = conf.single_node {
             conf.consensus.genesis_stakers.insert(
                 conf.id.clone(),
@@ -80,6 +123,28 @@ impl Conf {
         }
         Ok(conf)
     }
+
+    /// Takes the genesis stakers from the genesis file, if there is one.
+    pub fn apply_genesis_file(&mut self) -> Result<()> {
+        let Some(genesis_file) = &self.genesis_file else {
+            return Ok(());
+        };
+        let spec = GenesisSpec::load(genesis_file)?;
+        if !self.consensus.genesis_stakers.is_empty()
+            && self.consensus.genesis_stakers != spec.stakers
+        {
+            bail!("Genesis stakers differ between the configuration and the genesis file");
+        }
+        self.consensus.genesis_stakers = spec.stakers;
+        Ok(())
+    }
+
+    pub fn noir_verifier(&self) -> NoirVerifier {
+        NoirVerifier::new(
+            self.noir_verifier_path.clone(),
+            Duration::from_secs(self.noir_verification_timeout),
+        )
+    }
 }
 
 #[cfg(test)]
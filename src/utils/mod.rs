@@ -4,5 +4,6 @@ pub mod crypto;
 pub mod integration_test;
 pub mod logger;
 pub mod modules;
+pub mod rate_limiter;
 pub mod serde;
 pub mod static_type_map;
@@ -30,33 +30,42 @@ where
     fn build(ctx: Self::Context) -> impl futures::Future<Output = Result<Self>> + Send;
     fn run(&mut self) -> impl futures::Future<Output = Result<()>> + Send;
 
-    fn load_from_disk<S>(file: &Path) -> Option<S>
+    /// Returns None if the file doesn't exist. A file that can't be read or decoded is an error:
+    /// starting over from an empty state would silently drop everything it held.
+    fn load_from_disk<S>(file: &Path) -> Result<Option<S>>
     where
         S: bincode::Decode,
     {
         match fs::File::open(file) {
             Ok(mut reader) => {
+                let store = bincode::decode_from_std_read(&mut reader, bincode::config::standard())
+                    .with_context(|| {
+                        format!(
+                            "Decoding {} for module {}. Refusing to start from an empty state: restore the file or remove it to start over",
+                            file.to_string_lossy(),
+                            type_name::<S>(),
+                        )
+                    })?;
                 info!("Loaded data from disk {}", file.to_string_lossy());
-                bincode::decode_from_std_read(&mut reader, bincode::config::standard())
-                    .log_error(format!("Loading and decoding {}", file.to_string_lossy()))
-                    .ok()
+                Ok(Some(store))
             }
-            Err(_) => {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
                 info!(
                     "File {} not found for module {} (using default)",
                     file.to_string_lossy(),
                     type_name::<S>(),
                 );
-                None
+                Ok(None)
             }
+            Err(e) => Err(e).with_context(|| format!("Opening {}", file.to_string_lossy())),
         }
     }
 
-    fn load_from_disk_or_default<S>(file: &Path) -> S
+    fn load_from_disk_or_default<S>(file: &Path) -> Result<S>
     where
         S: bincode::Decode + Default,
     {
-        Self::load_from_disk(file).unwrap_or(S::default())
+        Ok(Self::load_from_disk(file)?.unwrap_or_default())
     }
 
     fn save_on_disk<S>(file: &Path, store: &S) -> Result<()>
@@ -85,6 +94,11 @@ where
             "Flushing Buffer writer for store {}",
             type_name::<S>()
         ))?;
+        // So that the renamed file is never a partially written one
+        buf_writer
+            .get_ref()
+            .sync_all()
+            .log_error(format!("Syncing store {} to disk", type_name::<S>()))?;
         debug!("Renaming {:?} to {:?}", &tmp, &file);
         fs::rename(tmp, file).log_error("Rename file")?;
         Ok(())
@@ -372,16 +386,34 @@ mod tests {
             .unwrap();
 
         // Load the struct from the file
-        let loaded_struct: TestStruct = TestModule::<usize>::load_from_disk_or_default(&file_path);
+        let loaded_struct: TestStruct =
+            TestModule::<usize>::load_from_disk_or_default(&file_path).unwrap();
         assert_eq!(loaded_struct.value, 42);
 
         // Load from a non-existent file
         let non_existent_path = dir.path().join("non_existent_file");
         let default_struct: TestStruct =
-            TestModule::<usize>::load_from_disk_or_default(&non_existent_path);
+            TestModule::<usize>::load_from_disk_or_default(&non_existent_path).unwrap();
         assert_eq!(default_struct.value, 0);
     }
 
+    #[test]
+    fn test_load_corrupt_file_does_not_reset_to_default() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test_file");
+        std::fs::write(&file_path, [0xff; 3]).unwrap();
+
+        let err =
+            TestModule::<usize>::load_from_disk_or_default::<TestStruct>(&file_path).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Refusing to start from an empty state"));
+        assert!(TestModule::<usize>::load_from_disk::<TestStruct>(&file_path).is_err());
+
+        // The corrupt file is left untouched for recovery
+        assert_eq!(std::fs::read(&file_path).unwrap(), vec![0xff; 3]);
+    }
+
     #[test_log::test]
     fn test_save_on_disk() {
         let dir = tempdir().unwrap();
@@ -391,7 +423,8 @@ mod tests {
         TestModule::<usize>::save_on_disk(&file_path, &test_struct).unwrap();
 
         // Load the struct from the file to verify it was saved correctly
-        let loaded_struct: TestStruct = TestModule::<usize>::load_from_disk_or_default(&file_path);
+        let loaded_struct: TestStruct =
+            TestModule::<usize>::load_from_disk_or_default(&file_path).unwrap();
         assert_eq!(loaded_struct.value, 42);
     }
 
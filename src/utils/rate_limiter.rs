@@ -0,0 +1,147 @@
+//! Rate limiters: a fixed-window one shared between the handlers of an endpoint,
+//! with a window per client, and a token bucket owned by a single connection.
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug)]
+pub struct RateLimiter<K> {
+    max_per_window: u32,
+    window: Duration,
+    state: Mutex<HashMap<K, (Instant, u32)>>,
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    pub fn new(max_per_window: u32, window: Duration) -> Self {
+        Self {
+            max_per_window,
+            window,
+            state: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a request of `key`, returns false if its current window is already full.
+    pub fn try_acquire(&self, key: K) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        if !state.contains_key(&key) {
+            // Forget the clients whose window is over, they start afresh anyway
+            state.retain(|_, (window_start, _)| window_start.elapsed() < self.window);
+        }
+        let (window_start, count) = state.entry(key).or_insert((Instant::now(), 0));
+        if window_start.elapsed() >= self.window {
+            *window_start = Instant::now();
+            *count = 0;
+        }
+        if *count >= self.max_per_window {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
+
+/// Allows `rate` events per second on average, and bursts of up to `burst` events.
+/// A rate of 0 disables the limit.
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: u32,
+    burst: u32,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: u32, burst: u32) -> Self {
+        Self {
+            rate,
+            burst,
+            tokens: burst as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Takes a token, returns false if there is none left.
+    pub fn try_acquire(&mut self) -> bool {
+        if self.rate == 0 {
+            return true;
+        }
+        let now = Instant::now();
+        let refill = now.duration_since(self.last_refill).as_secs_f64() * self.rate as f64;
+        self.tokens = (self.tokens + refill).min(self.burst as f64);
+        self.last_refill = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_beyond_limit_until_next_window() {
+        let limiter = RateLimiter::new(2, Duration::from_millis(100));
+
+        assert!(limiter.try_acquire(()));
+        assert!(limiter.try_acquire(()));
+        assert!(!limiter.try_acquire(()));
+
+        std::thread::sleep(Duration::from_millis(150));
+        assert!(limiter.try_acquire(()));
+    }
+
+    #[test]
+    fn counts_each_key_separately() {
+        let limiter = RateLimiter::new(1, Duration::from_millis(100));
+
+        assert!(limiter.try_acquire("a"));
+        assert!(!limiter.try_acquire("a"));
+        assert!(limiter.try_acquire("b"));
+        assert!(!limiter.try_acquire("b"));
+
+        std::thread::sleep(Duration::from_millis(150));
+        assert!(limiter.try_acquire("c"));
+        // Expired windows are dropped when a new client shows up
+        assert_eq!(limiter.state.lock().unwrap().len(), 1);
+        assert!(limiter.try_acquire("a"));
+    }
+
+    #[test]
+    fn token_bucket_allows_bursts_then_refills() {
+        let mut bucket = TokenBucket::new(20, 3);
+
+        assert!(bucket.try_acquire());
+        assert!(bucket.try_acquire());
+        assert!(bucket.try_acquire());
+        assert!(!bucket.try_acquire());
+
+        // One token every 50ms
+        std::thread::sleep(Duration::from_millis(75));
+        assert!(bucket.try_acquire());
+        assert!(!bucket.try_acquire());
+
+        // Never more than the burst
+        std::thread::sleep(Duration::from_millis(500));
+        for _ in 0..3 {
+            assert!(bucket.try_acquire());
+        }
+        assert!(!bucket.try_acquire());
+    }
+
+    #[test]
+    fn token_bucket_without_rate_is_unlimited() {
+        let mut bucket = TokenBucket::new(0, 0);
+        for _ in 0..1000 {
+            assert!(bucket.try_acquire());
+        }
+    }
+}
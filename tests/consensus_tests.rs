@@ -31,6 +31,41 @@ mod e2e_consensus {
         Ok(())
     }
 
+    #[test_log::test(tokio::test)]
+    async fn nodes_report_their_genesis() -> Result<()> {
+        let single = E2ECtx::new_single(500).await?;
+        let genesis = single.client().get_consensus_genesis().await?;
+        assert_eq!(
+            genesis.stakers,
+            vec![("single-node".to_string(), 100)].into_iter().collect()
+        );
+
+        let multi = E2ECtx::new_multi(2, 500).await?;
+        let multi_genesis = multi.client().get_consensus_genesis().await?;
+        assert_eq!(
+            multi_genesis.stakers,
+            vec![("node-1".to_string(), 100), ("node-2".to_string(), 100)]
+                .into_iter()
+                .collect()
+        );
+        assert_ne!(genesis.genesis_hash, multi_genesis.genesis_hash);
+
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn three_nodes_connect_to_all_peers() -> Result<()> {
+        // Each node dials all the nodes started before it and accepts the others,
+        // consensus would stall without a full mesh.
+        let ctx = E2ECtx::new_multi(3, 500).await?;
+        ctx.wait_height(3).await?;
+
+        let consensus = ctx.client().get_consensus_info().await?;
+        assert_eq!(consensus.validators.len(), 3, "expected 3 validators");
+
+        Ok(())
+    }
+
     #[ignore = "flakky"]
     #[test_log::test(tokio::test)]
     async fn can_run_lot_of_nodes() -> Result<()> {
@@ -244,33 +244,41 @@ impl E2ECtx {
             program_id: Contract::program_id(),
             state_digest: Contract::state_digest(),
             contract_name: name.into(),
+            blob_encoding: BlobEncoding::default(),
         }
         .as_blob("hyle".into(), None, None)];
 
         let tx = &BlobTransaction {
             identity: sender.clone(),
             blobs: blobs.clone(),
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
         };
         assert_ok!(self.client().send_tx_blob(tx).await);
 
-        tokio::time::timeout(Duration::from_secs(30), async {
-            loop {
-                let resp = self.client().get_contract(&name.into()).await;
-                if resp.is_err() || resp.is_err() {
-                    info!("⏰ Waiting for contract {name} state to be ready");
-                    tokio::time::sleep(Duration::from_millis(500)).await;
-                } else {
-                    return;
-                }
-            }
-        })
-        .await?;
+        info!("⏰ Waiting for contract {name} state to be ready");
+        self.client()
+            .wait_contract(
+                &name.into(),
+                Duration::from_secs(30),
+                Duration::from_millis(100),
+            )
+            .await?;
         Ok(())
     }
 
     pub async fn send_blob(&self, identity: Identity, blobs: Vec<Blob>) -> Result<TxHash> {
         self.client()
-            .send_tx_blob(&BlobTransaction { identity, blobs })
+            .send_tx_blob(&BlobTransaction {
+                identity,
+                blobs,
+                dependencies: vec![],
+                additional_identities: vec![],
+                nonce: None,
+                fee: 0,
+            })
             .await
     }
 
@@ -279,6 +287,10 @@ impl E2ECtx {
             .send_tx_blob(&BlobTransaction {
                 identity: tx.identity.clone(),
                 blobs: tx.blobs.clone(),
+                dependencies: vec![],
+                additional_identities: vec![],
+                nonce: None,
+                fee: 0,
             })
             .await
     }
@@ -55,6 +55,7 @@ impl Default for ConfMaker {
         info!("Default conf: {:?}", default);
         default.consensus = Consensus {
             slot_duration: 1,
+            round_timeout: 5000,
             genesis_stakers: {
                 let mut stakers = std::collections::HashMap::new();
                 stakers.insert("node-1".to_owned(), 100);
@@ -196,7 +197,14 @@ pub async fn send_transaction<S: StateUpdater>(
     let identity = transaction.identity.clone();
     let blobs = transaction.blobs.clone();
     let tx_hash = client
-        .send_tx_blob(&BlobTransaction { identity, blobs })
+        .send_tx_blob(&BlobTransaction {
+            identity,
+            blobs,
+            dependencies: vec![],
+            additional_identities: vec![],
+            nonce: None,
+            fee: 0,
+        })
         .await
         .unwrap();
 
@@ -7,18 +7,24 @@ mod fixtures;
 use anyhow::Result;
 
 mod e2e_hyllar {
+    use anyhow::Context;
     use client_sdk::{
         contract_states,
         helpers::risc0::Risc0Prover,
+        rest_client::StateDecodeError,
         transaction_builder::{ProvableBlobTx, TxExecutorBuilder},
     };
+    use fixtures::proofs::generate_recursive_proof;
     use hydentity::{
         client::{register_identity, verify_identity},
         Hydentity,
     };
-    use hyle_contract_sdk::{erc20::ERC20, ContractName};
+    use hyle::model::{ProofData, ProofTransaction};
+    use hyle_contract_sdk::{erc20::ERC20, ContractName, StateDigest, TxHash};
     use hyle_contracts::{HYDENTITY_ELF, HYLLAR_ELF};
+    use hyle_model::api::{APITransactionStatus, TransactionStatus};
     use hyllar::{client::transfer, HyllarToken};
+    use std::time::Duration;
 
     use super::*;
 
@@ -101,6 +107,176 @@ mod e2e_hyllar {
         Ok(())
     }
 
+    async fn wait_tx_status(
+        ctx: &E2ECtx,
+        tx_hash: &TxHash,
+        expected: TransactionStatus,
+    ) -> Result<APITransactionStatus> {
+        tokio::time::timeout(Duration::from_secs(30), async {
+            loop {
+                if let Ok(status) = ctx.client().get_transaction_status(tx_hash).await {
+                    if status.status == expected {
+                        return status;
+                    }
+                }
+                info!("⏰ Waiting for tx {} to be {:?}", tx_hash, expected);
+                tokio::time::sleep(Duration::from_millis(250)).await;
+            }
+        })
+        .await
+        .context("Timed out waiting for the tx status")
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn transaction_status_follows_settlement() -> Result<()> {
+        let ctx = E2ECtx::new_single(500).await?;
+
+        let contract = ctx.get_contract("hydentity").await?;
+        let hydentity: hydentity::Hydentity = contract.state.try_into()?;
+        let contract = ctx.get_contract("hyllar").await?;
+        let hyllar: HyllarToken = contract.state.try_into()?;
+        let mut executor = TxExecutorBuilder::new(States { hydentity, hyllar })
+            .with_prover("hydentity".into(), Risc0Prover::new(HYDENTITY_ELF))
+            .build();
+
+        info!("➡️  Sending blob to register bob identity");
+        let mut tx = ProvableBlobTx::new("bob.hydentity".into());
+        register_identity(&mut tx, "hydentity".into(), "password".to_string())?;
+        let tx_hash = ctx.send_provable_blob_tx(&tx).await?;
+
+        let status = wait_tx_status(&ctx, &tx_hash, TransactionStatus::Sequenced).await?;
+        assert_eq!(status.block_height, None);
+
+        info!("➡️  Sending proof for register");
+        let tx = executor.process(tx)?;
+        let proof = tx.iter_prove().next().unwrap().await?;
+        ctx.send_proof_single(proof).await?;
+
+        let status = wait_tx_status(&ctx, &tx_hash, TransactionStatus::Success).await?;
+        assert_eq!(status.tx_hash, tx_hash);
+        assert!(status.block_height.is_some());
+
+        Ok(())
+    }
+
+    /// Only decodes empty digests, to check how decoding failures are reported.
+    struct EmptyState;
+
+    impl TryFrom<StateDigest> for EmptyState {
+        type Error = anyhow::Error;
+
+        fn try_from(state: StateDigest) -> Result<Self, Self::Error> {
+            anyhow::ensure!(state.0.is_empty(), "digest is not empty");
+            Ok(EmptyState)
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn contract_state_is_decoded_by_the_client() -> Result<()> {
+        let ctx = E2ECtx::new_single(500).await?;
+        let hyllar_name: ContractName = "hyllar".into();
+
+        let state: HyllarToken = ctx.client().get_contract_state(&hyllar_name).await?;
+        let state = hyllar::HyllarTokenContract::init(state, "caller".into());
+        assert!(
+            state
+                .balance_of("faucet.hydentity")
+                .expect("faucet identity not found")
+                > 0
+        );
+
+        let err = ctx
+            .client()
+            .get_contract_state::<EmptyState>(&hyllar_name)
+            .await
+            .err()
+            .expect("hyllar state is not empty");
+        let err = err
+            .downcast_ref::<StateDecodeError>()
+            .expect("a state decoding error");
+        assert_eq!(err.contract_name, hyllar_name);
+
+        Ok(())
+    }
+
+    async fn scenario_hyllar_recursive(ctx: E2ECtx) -> Result<()> {
+        info!("➡️  Setting up the executor with the initial state");
+
+        let contract = ctx.get_contract("hydentity").await?;
+        let hydentity: hydentity::Hydentity = contract.state.try_into()?;
+        let contract = ctx.get_contract("hyllar").await?;
+        let hyllar: HyllarToken = contract.state.try_into()?;
+        let mut executor = TxExecutorBuilder::new(States { hydentity, hyllar })
+            // Replace prover binaries for non-reproducible mode.
+            .with_prover("hydentity".into(), Risc0Prover::new(HYDENTITY_ELF))
+            .with_prover("hyllar".into(), Risc0Prover::new(HYLLAR_ELF))
+            .build();
+
+        info!("➡️  Sending blob to register bob identity");
+
+        let mut tx = ProvableBlobTx::new("bob.hydentity".into());
+        register_identity(&mut tx, "hydentity".into(), "password".to_string())?;
+        ctx.send_provable_blob_tx(&tx).await?;
+
+        let tx = executor.process(tx)?;
+        let proof = tx.iter_prove().next().unwrap().await?;
+
+        info!("➡️  Sending proof for register");
+        ctx.send_proof_single(proof).await?;
+
+        info!("➡️  Waiting for height 2");
+        ctx.wait_height(2).await?;
+
+        info!("➡️  Sending blob to transfer 25 tokens from faucet to bob");
+
+        let mut tx = ProvableBlobTx::new("faucet.hydentity".into());
+
+        verify_identity(
+            &mut tx,
+            "hydentity".into(),
+            &executor.hydentity,
+            "password".to_string(),
+        )?;
+
+        transfer(&mut tx, "hyllar".into(), "bob.hydentity".to_string(), 25)?;
+
+        ctx.send_provable_blob_tx(&tx).await?;
+
+        let tx = executor.process(tx)?;
+        let mut proofs = tx.iter_prove();
+
+        let hydentity_proof = proofs.next().unwrap().await?.proof.0;
+        let hyllar_proof = proofs.next().unwrap().await?.proof.0;
+
+        let recursive_proof = generate_recursive_proof(
+            &[hyle_contracts::HYDENTITY_ID, hyle_contracts::HYLLAR_ID],
+            &[&hydentity_proof, &hyllar_proof],
+        )
+        .await;
+
+        info!("➡️  Sending recursive proof for hydentity and hyllar");
+        ctx.client()
+            .send_recursive_proof(&ProofTransaction {
+                contract_name: "risc0-recursion".into(),
+                proof: ProofData(recursive_proof),
+            })
+            .await?;
+
+        info!("➡️  Waiting for height 5");
+        ctx.wait_height(5).await?;
+
+        let contract = ctx.get_contract("hyllar").await?;
+        let state: hyllar::HyllarToken = contract.state.try_into()?;
+        let state = hyllar::HyllarTokenContract::init(state, "caller".into());
+        assert_eq!(
+            state
+                .balance_of("bob.hydentity")
+                .expect("bob identity not found"),
+            25
+        );
+        Ok(())
+    }
+
     #[test_log::test(tokio::test)]
     async fn hyllar_single_node() -> Result<()> {
         let ctx = E2ECtx::new_single(500).await?;
@@ -113,4 +289,10 @@ mod e2e_hyllar {
 
         scenario_hyllar(ctx).await
     }
+
+    #[test_log::test(tokio::test)]
+    async fn hyllar_recursive_single_node() -> Result<()> {
+        let ctx = E2ECtx::new_single(500).await?;
+        scenario_hyllar_recursive(ctx).await
+    }
 }
@@ -7,10 +7,30 @@ use anyhow::Result;
 mod fixtures;
 
 mod e2e_indexer {
-    use hyle_model::BlockHeight;
+    use client_sdk::{
+        contract_states,
+        helpers::risc0::Risc0Prover,
+        transaction_builder::{ProvableBlobTx, TxExecutorBuilder},
+    };
+    use fixtures::test_helpers::send_transaction;
+    use hydentity::{
+        client::{register_identity, verify_identity},
+        Hydentity,
+    };
+    use hyle::genesis::States as GenesisStates;
+    use hyle_contracts::{HYDENTITY_ELF, HYLLAR_ELF, STAKING_ELF};
+    use hyle_model::{api::TransactionStatus, BlockHeight, StakingAction};
+    use hyllar::{client::transfer, HyllarToken};
+    use staking::{client::stake, state::Staking};
 
     use super::*;
 
+    contract_states!(
+        struct States {
+            hydentity: Hydentity,
+        }
+    );
+
     async fn scenario_indexer(ctx: E2ECtx) -> Result<()> {
         ctx.wait_height(5).await?;
 
@@ -46,4 +66,233 @@ mod e2e_indexer {
         let ctx = E2ECtx::new_multi_with_indexer(2, 500).await?;
         scenario_indexer(ctx).await
     }
+
+    #[test_log::test(tokio::test)]
+    async fn indexer_pages_through_blocks() -> Result<()> {
+        let ctx = E2ECtx::new_multi_with_indexer(2, 500).await?;
+        ctx.wait_height(12).await?;
+
+        let mut heights = vec![];
+        let mut cursor = None;
+        loop {
+            let page = ctx
+                .indexer_client()
+                .get_blocks_page(cursor.as_deref(), 4)
+                .await?;
+            assert!(page.blocks.len() <= 4);
+            heights.extend(page.blocks.iter().map(|block| block.height));
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        // Every block down to the genesis, most recent first
+        assert!(heights.len() > 10);
+        assert_eq!(heights.last(), Some(&0));
+        assert!(heights
+            .windows(2)
+            .all(|w| w.first() == w.get(1).map(|h| h + 1)));
+
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn indexer_lists_transactions_by_identity() -> Result<()> {
+        let ctx = E2ECtx::new_multi_with_indexer(2, 500).await?;
+        ctx.wait_height(3).await?;
+
+        // The faucet funds the validators in the genesis block
+        let txs = ctx
+            .indexer_client()
+            .get_transactions_by_identity("faucet.hydentity", None)
+            .await?;
+        assert!(!txs.is_empty());
+        assert!(txs.iter().all(|tx| tx.identity == "faucet.hydentity"));
+
+        let settled = ctx
+            .indexer_client()
+            .get_transactions_by_identity("faucet.hydentity", Some(TransactionStatus::Success))
+            .await?;
+        assert!(settled
+            .iter()
+            .all(|tx| tx.transaction_status == TransactionStatus::Success));
+
+        let unknown = ctx
+            .indexer_client()
+            .get_transactions_by_identity("unknown.hydentity", None)
+            .await?;
+        assert!(unknown.is_empty());
+
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn indexer_serves_submitted_proofs() -> Result<()> {
+        // Proofs are only kept in the lane of the node that received them,
+        // a single validator is followed by the indexer.
+        let ctx = E2ECtx::new_multi_with_indexer(1, 500).await?;
+
+        let contract = ctx.get_contract("hydentity").await?;
+        let hydentity: Hydentity = contract.state.try_into()?;
+        let mut executor = TxExecutorBuilder::new(States { hydentity })
+            .with_prover("hydentity".into(), Risc0Prover::new(HYDENTITY_ELF))
+            .build();
+
+        let mut tx = ProvableBlobTx::new("bob.hydentity".into());
+        register_identity(&mut tx, "hydentity".into(), "password".to_string())?;
+        let blob_tx_hash = ctx.send_provable_blob_tx(&tx).await?;
+
+        let tx = executor.process(tx)?;
+        let proof = tx.iter_prove().next().unwrap().await?;
+        let proof_bytes = proof.proof.0.clone();
+        ctx.send_proof_single(proof).await?;
+
+        ctx.wait_height(2).await?;
+
+        let settled = ctx
+            .indexer_client()
+            .get_settled_blob_proofs(&blob_tx_hash)
+            .await?;
+        let proof_tx_hash = settled.first().unwrap().proof_tx_hash.clone();
+
+        let indexed = ctx.indexer_client().get_proof(&proof_tx_hash).await?;
+        assert_eq!(indexed.tx_hash, proof_tx_hash);
+        assert_eq!(indexed.contract_name, "hydentity");
+        assert_eq!(indexed.proof, proof_bytes);
+
+        let metadata = ctx
+            .indexer_client()
+            .get_proof_metadata(&proof_tx_hash)
+            .await?;
+        assert_eq!(metadata.contract_name, "hydentity");
+        assert_eq!(metadata.proof_len, proof_bytes.len() as u64);
+
+        assert!(ctx.indexer_client().get_proof(&blob_tx_hash).await.is_err());
+
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn indexer_lists_staking_actions() -> Result<()> {
+        let ctx = E2ECtx::new_multi_with_indexer(2, 500).await?;
+        ctx.wait_height(2).await?;
+
+        let hyllar: HyllarToken = ctx
+            .indexer_client()
+            .fetch_current_state(&"hyllar".into())
+            .await?;
+        let hydentity: Hydentity = ctx
+            .indexer_client()
+            .fetch_current_state(&"hydentity".into())
+            .await?;
+        let staking: Staking = ctx.client().get_consensus_staking_state().await?.into();
+        let mut executor = TxExecutorBuilder::new(GenesisStates {
+            hyllar,
+            hydentity,
+            staking,
+        })
+        .with_prover("hydentity".into(), Risc0Prover::new(HYDENTITY_ELF))
+        .with_prover("hyllar".into(), Risc0Prover::new(HYLLAR_ELF))
+        .with_prover("staking".into(), Risc0Prover::new(STAKING_ELF))
+        .build();
+
+        let mut tx = ProvableBlobTx::new("faucet.hydentity".into());
+        verify_identity(
+            &mut tx,
+            "hydentity".into(),
+            &executor.hydentity,
+            "password".to_string(),
+        )?;
+        stake(&mut tx, "staking".into(), 42)?;
+        transfer(&mut tx, "hyllar".into(), "staking".to_string(), 42)?;
+        send_transaction(ctx.client(), tx, &mut executor).await;
+
+        ctx.wait_height(2).await?;
+
+        let actions = ctx
+            .indexer_client()
+            .get_staking_actions("faucet.hydentity", None, 10)
+            .await?;
+        assert_eq!(actions.len(), 1);
+        let action = actions.first().unwrap();
+        assert_eq!(action.identity, "faucet.hydentity");
+        assert_eq!(action.action, StakingAction::Stake { amount: 42 });
+
+        // Validators stake and delegate in the genesis block
+        let validator = ctx.client().get_node_info().await?.pubkey.unwrap();
+        let genesis_actions = ctx
+            .indexer_client()
+            .get_staking_actions(&format!("{validator}.hydentity"), None, 10)
+            .await?;
+        assert!(genesis_actions
+            .iter()
+            .any(|action| matches!(action.action, StakingAction::Stake { .. })));
+        assert!(genesis_actions
+            .windows(2)
+            .all(|w| w.first().map(|a| a.block_height) >= w.get(1).map(|a| a.block_height)));
+
+        // Bounded by the page size and the highest block
+        let page = ctx
+            .indexer_client()
+            .get_staking_actions(&format!("{validator}.hydentity"), Some(BlockHeight(0)), 1)
+            .await?;
+        assert_eq!(page.len(), 1);
+        assert_eq!(page.first().unwrap().block_height, BlockHeight(0));
+
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn indexer_serves_contract_state_history() -> Result<()> {
+        let ctx = E2ECtx::new_multi_with_indexer(2, 500).await?;
+
+        let contract = ctx.get_contract("hydentity").await?;
+        let hydentity: Hydentity = contract.state.try_into()?;
+        let mut executor = TxExecutorBuilder::new(States { hydentity })
+            .with_prover("hydentity".into(), Risc0Prover::new(HYDENTITY_ELF))
+            .build();
+
+        // Two transitions, settled in different blocks
+        for identity in ["alice.hydentity", "bob.hydentity"] {
+            let mut tx = ProvableBlobTx::new(identity.into());
+            register_identity(&mut tx, "hydentity".into(), "password".to_string())?;
+            send_transaction(ctx.client(), tx, &mut executor).await;
+            ctx.wait_height(2).await?;
+        }
+
+        let history = ctx
+            .indexer_client()
+            .get_contract_state_history(&"hydentity".into(), Some(BlockHeight(1)), None, 10)
+            .await?;
+        assert_eq!(history.len(), 2);
+        assert!(history.first().unwrap().block_height < history.last().unwrap().block_height);
+        let current = ctx
+            .indexer_client()
+            .get_indexer_contract(&"hydentity".into())
+            .await?;
+        assert_eq!(history.last().unwrap().state_digest, current.state_digest);
+
+        // The registration in the genesis block
+        let genesis = ctx
+            .indexer_client()
+            .get_contract_state_history(&"hydentity".into(), None, Some(BlockHeight(0)), 10)
+            .await?;
+        assert_eq!(genesis.len(), 1);
+
+        // Paginated from the height after the last entry
+        let first_page = ctx
+            .indexer_client()
+            .get_contract_state_history(&"hydentity".into(), None, None, 1)
+            .await?;
+        assert_eq!(first_page, genesis);
+        let next_height = BlockHeight(genesis.last().unwrap().block_height.0 + 1);
+        let second_page = ctx
+            .indexer_client()
+            .get_contract_state_history(&"hydentity".into(), Some(next_height), None, 1)
+            .await?;
+        assert_eq!(second_page.first(), history.first());
+
+        Ok(())
+    }
 }
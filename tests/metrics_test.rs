@@ -7,6 +7,9 @@ use anyhow::Result;
 
 mod e2e_metrics {
 
+    use std::time::Duration;
+
+    use hyle::model::{ProofData, ProofTransaction};
     use tracing::info;
 
     use super::*;
@@ -27,4 +30,31 @@ mod e2e_metrics {
 
         poll_metrics(ctx).await
     }
+
+    #[test_log::test(tokio::test)]
+    async fn test_malformed_risc0_receipt_is_counted() -> Result<()> {
+        let ctx = E2ECtx::new_single(500).await?;
+
+        // hydentity is registered at genesis with the risc0 verifier
+        ctx.send_proof_single(ProofTransaction {
+            contract_name: "hydentity".into(),
+            proof: ProofData(vec![1, 2, 3]),
+        })
+        .await?;
+
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let metrics = ctx.metrics().await?;
+                if metrics.lines().any(|line| {
+                    line.starts_with("verifier_decode_failure")
+                        && line.contains("verifier=\"risc0\"")
+                }) {
+                    return Ok::<_, anyhow::Error>(());
+                }
+                info!("⏰ Waiting for the risc0 decode failure to be counted");
+                tokio::time::sleep(Duration::from_millis(500)).await;
+            }
+        })
+        .await?
+    }
 }
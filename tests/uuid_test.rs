@@ -6,11 +6,12 @@ use client_sdk::{
 };
 use fixtures::ctx::{E2EContract, E2ECtx};
 use hydentity::{client::register_identity, Hydentity};
-use hyle::mempool::verifiers::verify_proof;
+use hyle::mempool::verifiers::VerifierRegistry;
 use hyle_contract_sdk::{
     BlobTransaction, ContractName, Digestable, Hashable, ProgramId, StateDigest, Verifier,
 };
 use hyle_contracts::{HYDENTITY_ELF, UUID_TLD_ELF, UUID_TLD_ID};
+use hyle_verifiers::NoirVerifier;
 use uuid_tld::{RegisterUuidContract, UuidTldState};
 
 contract_states!(
@@ -80,6 +81,10 @@ async fn test_uuid_registration() {
     let blob_tx = BlobTransaction {
         identity: tx.identity.clone(),
         blobs: tx.blobs.clone(),
+        dependencies: vec![],
+        additional_identities: vec![],
+        nonce: None,
+        fee: 0,
     };
 
     let tx_context = loop {
@@ -101,12 +106,13 @@ async fn test_uuid_registration() {
     ctx.send_proof_single(first_proof.clone()).await.unwrap();
     ctx.send_proof_single(uuid_proof.clone()).await.unwrap();
 
-    let outputs = verify_proof(
-        &uuid_proof.proof,
-        &Verifier("risc0".into()),
-        &ProgramId(UUID_TLD_ID.to_vec()),
-    )
-    .expect("Must validate proof");
+    let outputs = VerifierRegistry::new(&NoirVerifier::default())
+        .verify(
+            &uuid_proof.proof,
+            &Verifier("risc0".into()),
+            &ProgramId(UUID_TLD_ID.to_vec()),
+        )
+        .expect("Must validate proof");
 
     assert_eq!(outputs, &[expected_output.clone()]);
 